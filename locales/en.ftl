@@ -0,0 +1,557 @@
+# Console messages
+launcher-banner = === Perfetto Launcher ===
+dist-directory = Dist directory: { $path }
+compat-api-mismatch = the UI expects trace_processor RPC API version { $ui } but { $tp } provides version { $api }
+compat-release-mismatch = the UI is { $ui } but trace_processor is { $tp }; mismatched releases may fail to load traces
+compat-warning = Warning: { $message }
+compat-forced = Warning: { $message } (continuing because of --force)
+compat-error = Error: { $message }
+compat-hint = Use matching UI and trace_processor releases, or pass --force to start anyway.
+tp-starting = Starting trace_processor_shell...
+tp-path = Path: { $path }
+tp-chmod-failed = Warning: could not make { $path } executable: { $error }
+tp-preset = Preset: { $name } ({ $flags })
+tp-port = HTTP port: { $port }
+trace-loading = Loading trace file: { $path }
+tp-spawn-failed = Error: Failed to start trace_processor_shell: { $error }
+tp-waiting = Waiting for trace_processor to start...
+tp-start-timeout = Error: trace_processor_shell did not answer on port { $port } within { $seconds } s. Loading a very large trace can take longer; raise the limit with --startup-timeout.
+server-starting = Starting HTTP server on port { $port }...
+server-failed = Error: Failed to start HTTP server: { $error }
+ready-banner = === Perfetto is ready! ===
+ready-ui = UI Server:            { $url }
+ready-network = Network:              { $url }
+ready-local-only = Only this machine can open the UI; start with --expose-lan to let others on the network in
+ready-basic-auth = Sign-in:              user { $user }, password { $password }
+ready-rpc = Trace Processor RPC:  { $url }
+ready-stop = Press Ctrl+C to stop.
+browser-failed = Warning: Failed to open browser: { $error }
+browser-manual = Please open { $url } manually.
+browser-not-opened = Open { $url } in a browser to start.
+goodbye = Goodbye!
+
+# Port conflicts
+port-conflict = Error: { $what } could not use port { $port }.
+port-owner = Port { $port } is in use by { $name } (PID { $pid }).
+port-owner-unknown = Could not determine which process is using port { $port }.
+port-conflict-hint = Stop that process, or simply run the launcher again to pick different ports.
+port-busy-retry = Port { $port } is still held by { $holder }; retrying in { $delay } ms...
+port-holder-unknown = another process
+port-moved = { $what } could not use port { $port }, held by { $holder }; using port { $chosen } instead.
+
+# Doctor
+doctor-title = === Perfetto Launcher Doctor ===
+doctor-summary = { $total } checks, { $failed } failed, { $warnings } warnings
+doctor-fix-dist-dir = Place the launcher inside the Perfetto UI dist directory.
+doctor-fix-dist-index = Extract a complete Perfetto UI build into the dist directory.
+doctor-fix-dist-wasm = The UI bundle looks incomplete; re-extract the full Perfetto UI build.
+doctor-fix-dist-version = Run `perfetto_launcher update-ui` to install the UI again, or `update-ui --rollback` after a broken update.
+doctor-fix-dist-damaged = Run `perfetto_launcher update-ui` to download the missing files again.
+doctor-fix-tp-missing = Download trace_processor_shell from https://get.perfetto.dev/trace_processor and place it next to index.html.
+doctor-fix-tp-run = Make sure the binary matches this OS/architecture and is executable; on Windows install the latest VC++ runtime.
+doctor-fix-compat = Use a trace_processor_shell from the same Perfetto release as the UI bundle.
+doctor-fix-ports = Close other applications holding many ports or check local security software.
+doctor-fix-loopback = Allow local connections for perfetto_launcher and trace_processor_shell in your firewall or security software.
+doctor-fix-data-dir = Check the permissions of the directory or free up disk space.
+doctor-fix-browser = Install a recent Chromium-based browser or Firefox; the Perfetto UI requires WebAssembly.
+preflight-failed = Preflight check "{ $check }" failed: { $detail }
+preflight-warning = Preflight check "{ $check }": { $detail }
+preflight-hint = Run `perfetto_launcher doctor` for a full report, or pass --skip-preflight to start anyway.
+
+# Configuration problems
+config-invalid = Error: the configuration is invalid:
+config-dist-missing = dist directory { $path } does not exist
+config-index-missing = index.html not found at { $path }
+config-tp-missing = trace_processor_shell not found at { $path } (--auto-download fetches one)
+config-ports-equal = --ui-port and --rpc-port are both { $port }; they must differ
+config-trace-missing = trace file { $path } does not exist
+config-mount-missing = Mount /{ $prefix }/: { $path } is not a directory
+config-trace-dir-missing = Trace directory { $name }: { $path } is not a directory
+config-trace-dir-duplicate = Trace directory name { $name } is used more than once
+config-bad-origin = { $origin } is not an origin such as https://ui.perfetto.dev; leave out the path and trailing slash
+config-file-unreadable = Error: cannot read { $path }: { $error }
+config-file-invalid = Error: { $path } is not a valid config file: { $error }
+config-file-bad-value = Error: { $path }: { $key } = { $value } is out of range; expected { $expected }
+
+# Dry run
+dry-run-title = === Dry run: nothing will be started ===
+dry-run-config-file = Config file:       { $path }
+dry-run-dist-dir = Dist directory:    { $path }
+dry-run-ui-version = UI version:        { $version }
+dry-run-trace-processor = trace_processor:   { $path }
+dry-run-trace = Trace file:        { $path }
+dry-run-preset = Preset:            { $name } ({ $flags })
+dry-run-ui-port = UI port:           { $port }
+dry-run-rpc-port = RPC port:          { $port }
+dry-run-host = Listening on:      { $host }
+dry-run-tls-files = TLS:               { $cert } / { $key }
+dry-run-tls-self-signed = TLS:               self-signed, { $path }
+dry-run-ui-url = UI URL:            { $url }
+dry-run-command = Command line:      { $command }
+dry-run-env = Environment:       { $name }={ $value }
+dry-run-download = Planned downloads: { $download }
+dry-run-downloads-none = Planned downloads: none
+dry-run-unknown = unknown
+dry-run-download-tp = trace_processor_shell { $version } (unless cached)
+dry-run-download-tp-latest = trace_processor_shell, latest release
+dry-run-no-problems = No problems found.
+dry-run-problems = Problems:
+
+# Version
+version-launcher = perfetto_launcher { $version }
+version-ui = Perfetto UI:      { $version } ({ $location })
+version-ui-unknown = Perfetto UI:      unknown ({ $location })
+version-ui-embedded = embedded
+version-tp = trace_processor:  { $version } ({ $path })
+version-tp-api = trace_processor:  { $version }, RPC API { $api } ({ $path })
+version-tp-missing = trace_processor:  not found ({ $path })
+version-tp-unknown = trace_processor:  unknown ({ $error })
+
+# Shutdown
+shutdown-draining = Shutting down: waiting up to { $seconds }s for { $count } in-flight response(s). Press Ctrl+C again to quit now.
+shutdown-drain-expired = Drain timeout expired; closing remaining connections.
+shutdown-forced = Forced shutdown.
+shutdown-handler-failed = Could not install the Ctrl+C handler: { $error }
+shutdown-requested = Shutdown requested over HTTP.
+shutdown-idle = No requests for { $duration }; shutting down.
+shutdown-bad-token = A valid shutdown token is needed (see shutdown_token in instance.json)
+
+# Resource monitoring
+monitor-memory-exceeded = Warning: trace_processor is using { $used } MB of memory (limit { $limit } MB)
+monitor-cpu-exceeded = Warning: trace_processor has been using { $used }% CPU (limit { $limit }%)
+monitor-restarting = Restarting trace_processor because it exceeded its resource limits...
+
+# trace_processor crashes
+tp-crashed = Error: trace_processor_shell stopped unexpectedly ({ $status }).
+tp-crash-last-lines = Last output:
+tp-restarting = Restarting trace_processor in { $seconds } s (attempt { $attempt } of { $max })...
+tp-restarted = trace_processor restarted (pid { $pid }). Open UI tabs reload once it answers.
+tp-spawned = Started trace_processor_shell (pid { $pid }): { $path }
+tp-stopped = Stopped trace_processor_shell (pid { $pid })
+tp-restart-gave-up = trace_processor will not be restarted again (--max-restarts { $count } reached).
+restart-requested = Restarting trace_processor on request...
+restart-admins-only = Only admins may restart trace_processor; every user shares it
+restart-exited = trace_processor stopped again after the restart ({ $status }); /launcher/tp-logs has its output
+restart-not-answering = trace_processor was restarted but did not answer within the startup timeout
+restart-no-launcher = No launcher is running
+restart-asking = Asking the running launcher (pid { $pid }) to restart trace_processor...
+restart-started = trace_processor is restarting (pid { $pid }).
+restart-failed = Restarting trace_processor failed: { $error }
+tp-explain-missing-dll = A required DLL is missing. Install the latest Microsoft Visual C++ Redistributable (x64) and try again.
+tp-explain-oom = trace_processor ran out of memory. The trace may be too large for this machine; close other programs or use a machine with more RAM.
+tp-explain-unsupported-trace = The trace could not be parsed. It may be corrupt, or was recorded with a newer Perfetto version than this trace_processor supports.
+tp-explain-port = trace_processor could not listen on its RPC port. Another program may be using it.
+tp-explain-missing-lib = A required system library is missing or too old for this trace_processor build.
+tp-explain-segfault = trace_processor crashed. This is usually caused by a corrupt trace or a trace_processor bug; try a newer release.
+tp-explain-unknown = The cause is unknown; see the output below for details.
+
+# Crash reports
+crash-report-written = perfetto_launcher crashed. A crash report was written to { $path }; please attach it to your bug report.
+crash-report-failed = perfetto_launcher crashed and the crash report could not be written: { $error }
+
+# Startup timings
+timings-title = Startup timings:
+timings-total = Total
+timings-trace-failed = Warning: Failed to write timings trace to { $path }: { $error }
+timings-concurrent = * ran concurrently with the phases above
+
+# Query instances
+instance-spawn-failed = Failed to start trace_processor_shell: { $error }
+instance-exited = trace_processor_shell exited while loading the trace ({ $status })
+instance-not-ready = trace_processor did not become ready: { $error }
+
+# bench
+bench-read-failed = Cannot read { $path }: { $error }
+bench-no-queries = No queries found in { $path }
+bench-loaded = Trace loaded in { $ms } ms
+bench-query-progress = Running query { $index }/{ $total }...
+bench-write-failed = Cannot write { $path }: { $error }
+bench-failed = Benchmark failed: { $error }
+
+# Landing page
+landing-title = Perfetto Launcher
+landing-drop-hint = Drop a trace file here or click to choose one
+landing-upload-failed = Upload failed.
+landing-upload-error = Could not save the uploaded trace: { $error }
+landing-trace-opened = Opened pushed trace { $name } in the browser
+landing-sessions = Sessions
+landing-no-trace = (no trace loaded)
+landing-session-running = trace_processor running, PID { $pid }
+landing-session-opened = opened { $time }
+landing-session-stopped = trace_processor stopped
+landing-recent = Recent traces
+landing-no-recent = No uploaded traces yet.
+landing-links = Quick links
+landing-link-ui = Open the Perfetto UI
+landing-link-status = Launcher status (JSON)
+landing-link-tp-logs = trace_processor output
+landing-link-recent = Recently opened traces
+landing-link-docs = Perfetto documentation
+
+# Recently opened
+recent-title = Recently opened traces
+recent-empty = No trace has been opened through the launcher yet.
+recent-name = Trace
+recent-size = Size
+recent-opened = Last opened
+recent-back = Back to the launcher
+
+# Live reload
+livereload-watching = Watching { $path } for changes; open UI tabs reload when it changes
+livereload-failed = Could not watch { $path } for live reload: { $error }
+livereload-watch-error = Watching the dist directory failed: { $error }
+livereload-changed = { $count } files changed in the dist directory; reloading the UI
+
+# HTTPS
+tls-unreadable = Cannot read { $path }: { $error }
+tls-no-certificate = { $path } holds no PEM certificate
+tls-unsupported-key = { $path } holds no PEM private key in PKCS#8 or RSA form; convert it with `openssl pkcs8 -topk8 -nocrypt`
+tls-generate-failed = Could not create a self-signed certificate: { $error }
+tls-generated = Created a self-signed certificate at { $path }; browsers warn about it until it is trusted
+
+# Remote access
+auth-denied = Sign in first: open the launch URL with its ?token=, or send the launcher's credentials
+auth-token-failed = Error: Cannot generate an access token: { $error }
+auth-users-instead = Warning: --auth is ignored in multi-user mode; users sign in with their own tokens
+lan-unprotected = Warning: Anyone on the network can open the UI and its traces; add --auth token or --users to require signing in
+
+# Query
+query-read-failed = Cannot read SQL from { $path }: { $error }
+query-empty = No SQL to run; give it as an argument, with --file or on stdin
+query-row-count = ({ $count } rows)
+query-failed = Query failed: { $error }
+query-api-empty = No SQL to run; send it as the request body, or as the sql field of a JSON body
+query-api-unreachable = trace_processor did not answer: { $error }
+query-api-session-required = In multi-user mode, name the session to query with ?session=<id>
+batch-no-sql = No SQL to run in { $path }
+batch-no-traces = No traces found to run on
+batch-running = Running on { $count } trace(s) with { $jobs } worker(s)...
+batch-bad-metrics = trace_processor printed metrics that are not JSON: { $error }
+batch-write-failed = Cannot write { $path }: { $error }
+batch-summary = { $succeeded } trace(s) succeeded, { $failed } failed; results are in { $path }
+batch-some-failed = { $count } trace(s) failed
+batch-failed = Batch run failed: { $error }
+
+# Recording
+record-unknown-template = No config template named { $name }; known: { $known }
+record-started = Recording to { $path }. Press Ctrl+C to stop early.
+record-progress = Recording... { $elapsed } of { $duration }
+record-progress-open = Recording... { $elapsed }; press Ctrl+C to stop
+record-stopping = Stopping the recording and saving what was recorded...
+record-stop-failed = Warning: Cannot stop the recorder: { $error }
+record-busy = A recording is already in progress
+record-none = No recording is in progress
+record-admins-only = Only admins may record traces
+record-failed = Recording failed: { $error }
+
+# Android
+android-title = Traces on Android devices
+android-failed = Android: { $error }
+android-adb-failed = adb failed: { $error }
+android-no-devices = No Android device is connected.
+android-several-devices = Several devices are connected ({ $serials }); pick one with --device.
+android-device-unknown = No device with serial { $serial } is connected.
+android-device-not-ready = Device { $serial } is { $state }; allow USB debugging on it first.
+android-list-failed = Cannot list the traces on { $serial }: { $error }
+android-no-traces = No traces in { $path }.
+android-bad-name = Not a trace file name: { $name }
+android-pulling = Pulling { $path } from { $serial }...
+android-pull-failed = Cannot pull { $path }: { $error }
+android-admins-only = Only admins may pull traces from devices
+android-modified = Modified
+android-open = Pull and open
+android-pulling-button = Pulling...
+
+# Error pages
+error-not-found = There is nothing at this address.
+error-not-found-hint = Check the link for typos. A trace link stops working once the trace is removed from the catalog; the launcher page lists the traces it knows. If the UI itself is missing, run `perfetto_launcher doctor` to check the dist directory.
+error-forbidden = This address may not be opened.
+error-forbidden-hint = The launcher only serves files inside the UI's dist directory, uploads, captures and --mount or --trace-dir directories. With --auth or --users, open the link you were given again to sign in.
+error-method = This address does not answer this kind of request.
+error-method-detail = { $method } is not supported here; allowed: { $allow }
+error-method-hint = A script or extension may be calling the wrong endpoint; see the allowed methods above.
+error-server = Something went wrong inside the launcher.
+error-server-hint = The launcher status shows whether trace_processor is running and why it last stopped; the launcher's console or log file has the details, and `perfetto_launcher doctor` checks the installation.
+
+# Catalog
+catalog-corrupt = Warning: Ignoring unreadable trace catalog { $path }: { $error }
+catalog-unknown-id = No trace with id { $id } in the catalog.
+catalog-pinned = Pinned { $name } ({ $id })
+catalog-unpinned = Unpinned { $name } ({ $id })
+catalog-add-failed = Warning: Could not add { $path } to the trace catalog: { $error }
+catalog-save-failed = Warning: Could not save the trace catalog { $path }: { $error }
+
+# Ingest
+ingest-indexed = Indexed { $count } slice names in { $name }
+ingest-failed = Warning: Could not process { $name } for the catalog: { $error }
+metadata-not-extracted = Metadata for trace { $id } has not been extracted. Start the launcher with --index-traces.
+
+# catalog import
+import-not-dir = { $path } is not a directory
+import-ingesting = Processing { $count } trace(s) with { $jobs } worker(s)...
+import-summary-title = Import summary:
+import-summary-found = Traces found:       { $count }
+import-summary-added = Newly added:        { $count }
+import-summary-known = Already in catalog: { $count }
+import-summary-ingested = Processed:          { $count }
+import-summary-failed = Failed:             { $count }
+import-failed = Import failed: { $error }
+
+# Scheduled captures
+capture-config-unreadable = Cannot read trace config { $path }: { $error }
+capture-no-recorder = Neither tracebox nor perfetto was found next to trace_processor or on PATH.
+capture-no-adb = adb was not found on PATH or in the Android SDK (ANDROID_HOME).
+capture-scheduled = Recording { $duration } every { $every }. Press Ctrl+C to stop.
+capture-recording = Recording { $path }...
+capture-saved = Saved { $path } as catalog trace { $id }
+capture-failed = Capture failed: { $error }
+capture-deleted = Deleted old capture { $path }
+
+# Catalog archives
+archive-write-failed = Cannot write { $path }: { $error }
+archive-read-failed = Cannot read { $path }: { $error }
+archive-exported = Exported { $count } trace(s) to { $path }
+archive-imported = Imported { $added } trace(s); { $skipped } were already in the catalog
+archive-traces-missing = Warning: { $count } trace file(s) are not available on this machine
+archive-export-failed = Export failed: { $error }
+
+# Webhooks
+webhooks-invalid = Ignoring webhook configuration { $path }: { $error }
+webhook-failed = Webhook { $url } failed: { $error }
+webhook-trace-ingested = New trace { $name }: { $link }
+webhook-session-opened = Perfetto session opened at { $url }
+webhook-session-closed = Perfetto session at { $url } closed
+webhook-check-failed = Check failed: { $check }: { $message }
+
+# Fetch
+fetch-bad-url = Invalid URL { $url }: { $error }
+fetch-failed = Download of { $url } failed: { $error }
+fetch-http-error = Download of { $url } failed: HTTP { $status } { $reason }
+fetch-too-many-redirects = Too many redirects fetching { $url }
+fetch-config-invalid = Invalid fetch configuration { $path }: { $error }
+fetch-downloading = Downloading { $url } to { $path }
+fetch-forwarded = Asking the running launcher (pid { $pid }) to download { $url }
+fetch-http-only = only http and https URLs can be downloaded
+fetch-admins-only = Only admins may have the launcher download traces
+fetch-zip-failed = Could not read zip archive { $path }: { $error }
+fetch-zip-empty = Zip archive { $path } contains no files
+fetch-extracted = Extracted trace { $path }
+
+# SQL modules
+sql-module-dir-missing = SQL module directory { $path } does not exist
+sql-module-dir-empty = SQL module directory { $path } contains no .sql files
+sql-module-failed = SQL module { $module } failed to load: { $error }
+sql-modules-loaded = Loaded { $loaded } of { $total } SQL modules
+
+# Metrics
+metrics-extension-invalid = { $path } is not a metric extension directory (missing sql/)
+metrics-builtin = built-in
+metrics-tp-failed = trace_processor exited with { $status }
+metrics-write-failed = Cannot write { $path }: { $error }
+metrics-failed = Metrics failed: { $error }
+
+# Presets
+presets-invalid = Invalid presets in { $path }: { $error }
+presets-unknown = Unknown trace_processor preset '{ $name }' (defined in { $path }: { $known })
+
+# Symbolization
+symbol-dir-missing = Symbol directory { $path } does not exist
+symbolize-no-dirs = Give at least one --symbol-dir to symbolize with
+symbolize-no-traceconv = traceconv not found next to trace_processor or on PATH
+symbolize-no-llvm-symbolizer = llvm-symbolizer not found next to trace_processor or on PATH; native frames will stay unsymbolized
+symbolize-read-failed = Could not read { $path }: { $error }
+symbolize-write-failed = Could not write { $path }: { $error }
+symbolize-running = Symbolizing { $path }...
+symbolize-traceconv-failed = traceconv exited with { $status }
+symbolize-nothing = No symbols found for the trace's native frames
+symbolize-written = Wrote { $path } ({ $bytes } bytes of symbols added)
+symbolize-failed = Symbolization failed: { $error }
+convert-running = Converting { $input } ({ $from }) to { $output } ({ $to })...
+convert-progress = { $size } MB written
+convert-progress-percent = { $percent }% read
+convert-done = Wrote { $path } ({ $size } MB)
+convert-failed = Conversion failed: { $error }
+clean-would-delete = Would delete { $path } ({ $size } MB)
+clean-deleted = Deleted { $path } ({ $size } MB)
+clean-done = Deleted { $count } file(s), { $size } MB; { $remaining } MB of traces remain
+clean-would-free = Would delete { $count } file(s), { $size } MB; { $remaining } MB of traces would remain
+clean-failed = Cleaning failed: { $error }
+clean-launcher-running = A launcher is running (pid { $pid }); stop it before cleaning
+workspace-quota-cleaned = Deleted { $count } least recently used trace(s), { $size } MB, to stay within --workspace-quota-mb
+convert-unknown-format = Cannot tell the format to write from { $path }; pass --to proto, json, systrace, ctrace or text
+convert-same-format = { $path } is already a { $format } trace
+convert-unsupported = Cannot convert { $from } to { $to }: only Perfetto protobuf traces can be converted to other formats, besides ctrace and systrace into each other
+convert-read-failed = Could not read { $path }: { $error }
+convert-write-failed = Could not write { $path }: { $error }
+convert-no-traceconv = traceconv was not found next to trace_processor or on PATH; pass --auto-download to download it
+convert-traceconv-spawn-failed = Could not run { $path }: { $error }
+convert-traceconv-failed = traceconv exited with { $status }
+
+# Deobfuscation
+deobfuscate-map-missing = Proguard map { $path } does not exist
+deobfuscate-no-trace = --proguard-map has no effect without a trace
+
+# Symbol servers
+symbol-server-checking = Checking { $count } symbol server(s) for missing PDBs...
+symbol-server-downloaded = Downloaded { $pdb }
+symbol-server-summary = Downloaded { $count } PDB(s) into the symbol cache
+symbol-server-failed = Symbol server { $server } failed for { $pdb }: { $error }
+symbol-server-skipped = Skipping symbol download: { $error }
+
+# Enrichment
+enrich-no-traceconv = traceconv not found; Proguard maps are not applied
+enrich-cached = Using cached enriched trace { $path }
+enrich-pass = [{ $index }/{ $total }] Running { $pass } on { $path }...
+enrich-nothing = Nothing to symbolize or deobfuscate; loading the trace as is
+enrich-done = Enriched trace written to { $path } ({ $bytes } bytes added)
+enrich-failed = Symbolization/deobfuscation failed, loading the trace as is: { $error }
+
+# Request checks
+csrf-rejected = Forbidden: cross-site request
+host-rejected = Forbidden: { $host } is not this machine; open the launcher at localhost or one of its own addresses
+body-too-large = Request body exceeds the limit of { $limit } bytes
+
+# Plugins
+plugins-none = No plugins in { $path }
+plugins-entry = { $name } ({ $path })
+plugin-describe-failed = Ignoring plugin { $path }: { $error }
+plugin-failed = Plugin { $name } failed: { $error }
+plugin-bad-response = Plugin { $name } sent an invalid response: { $error }
+plugin-no-output = Plugin { $name } did not write { $path }
+plugin-converting = Converting { $path } with plugin { $name }...
+
+# Trace API
+api-bad-body = Invalid request body: { $error }
+api-empty-name = The trace name must not be empty
+
+# Extra sessions
+session-started = Started session { $id } for { $trace } (pid { $pid })
+session-stopped = Stopped session { $id } (pid { $pid })
+session-idle = Session { $id } was unused for too long and is stopped
+session-exited = trace_processor of session { $id } exited
+session-unknown = No session with id { $id }.
+session-forbidden = Session { $id } was started by another user
+session-limit = { $max } sessions are running already; stop one or raise --max-sessions.
+session-start-failed = Could not start a session: { $error }
+
+# Dashboards
+dashboards-title = Dashboards
+dashboards-none = No dashboards yet. Add JSON or YAML dashboard files to { $path }.
+dashboard-not-found = No dashboard named { $name }
+dashboard-invalid = Invalid dashboard { $path }: { $error }
+dashboard-updated = Updated
+dashboard-load-failed = Could not refresh:
+dashboard-no-rows = No rows
+
+# Reports
+reports-title = Reports
+reports-none = No reports yet. Configure them in { $path }.
+reports-invalid = Invalid reports in { $path }: { $error }
+reports-unknown = No report named { $name } in { $path }
+reports-ran = Report { $report }: { $count } written
+reports-some-failed = Some reports could not be generated
+reports-scheduled = Scheduled { $count } report(s)
+report-written = Report { $report } for { $name }: { $path }
+report-failed = Report { $report } for { $name } failed: { $error }
+report-no-output = The report command did not write { $path }
+report-summary-open = Open in the UI
+report-summary-file = File
+report-summary-size = Size (bytes)
+report-summary-duration = Duration (ns)
+report-summary-capture-time = Capture time (ns since epoch)
+report-summary-data-sources = Data sources
+report-summary-data-loss = Data loss
+report-summary-no-data-loss = No data loss recorded
+report-summary-top-slices = Most frequent slices
+
+# Timeline
+timeline-start = Trace start: { $wall } (ts { $ts })
+timeline-end = Trace end:   { $wall } (ts { $ts })
+timeline-drift = The wall clock was adjusted by up to { $ms } ms during the trace
+timeline-offset = { $offset } s from the trace start
+timeline-outside = { $offset } s from the trace start, outside the trace
+timeline-wall-to-trace = { $wall } -> ts { $ts } ({ $position })
+timeline-trace-to-wall = ts { $ts } -> { $wall } ({ $position })
+timeline-no-snapshots = { $path } has no REALTIME clock snapshots, so wall-clock times cannot be mapped
+timeline-invalid-time = Invalid time '{ $time }'; use YYYY-MM-DD HH:MM[:SS[.fraction]] with an optional Z or ±HH:MM offset
+timeline-failed = Timeline mapping failed: { $error }
+
+# Users
+users-invalid = Invalid user configuration { $path }: { $error }
+users-none = { $path } lists no users and no trust_header
+users-enabled = Multi-user mode: { $count } configured users
+users-unauthorized = Sign in with a token: open this page with ?token=<token> or send Authorization: Bearer <token>
+users-forbidden = Trace { $id } belongs to another user
+users-quota-exceeded = Upload quota exceeded: { $remaining } MB left
+
+# Logs
+logs-config-invalid = Invalid logging configuration { $path }: { $error }
+logs-open-failed = Could not open log { $path }: { $error }
+logs-compress-failed = Could not compress rotated log { $path }: { $error }
+
+# UI update
+ui-update-versions = Installed UI: { $current }, latest: { $latest }
+ui-update-up-to-date = UI { $version } is up to date
+ui-update-downloaded = Downloaded { $path }
+ui-update-transferred = { $reused } files unchanged, { $downloaded } downloaded ({ $mb } MB)
+ui-update-done = UI updated to { $version }
+ui-update-failed = UI update failed: { $error }
+ui-update-bad-index = Unreadable index.html: { $error }
+ui-update-no-version = No { $channel } version listed in index.html
+ui-update-bad-manifest = Invalid manifest.json: { $error }
+ui-update-hash-mismatch = { $path } does not match manifest.json (expected { $expected }, got { $actual })
+ui-update-available = Update available: { $version }. Run update-ui without --check to install it.
+ui-update-bad-version = Not a UI version: { $version } (expected e.g. v46.0-8a7e2f1c)
+ui-update-no-previous = No earlier UI version to go back to; it is kept only after an update that changed the version.
+ui-update-rolled-back = UI rolled back from { $from } to { $to }
+
+# Bundle
+bundle-unknown-platform = This platform has no bundle name; pass --platform
+bundle-need-launcher = Pass --launcher with a launcher binary built for { $platform }
+bundle-need-trace-processor = Pass --trace-processor with a trace_processor_shell built for { $platform }
+bundle-missing-file = File not found: { $path }
+bundle-no-ui = No UI version directory in { $path }; pass --ui-version to download one
+bundle-write-failed = Could not write { $path }: { $error }
+bundle-created = Created { $path } for { $platform } with UI { $version } ({ $mb } MB)
+bundle-failed = Bundle failed: { $error }
+
+# URL handler
+url-handler-expected = expected perfetto://open?trace=<id, path or URL>
+url-handler-bad-link = Invalid link { $link }: { $error }
+url-handler-forwarded = Opening in the running launcher: { $url }
+instance-forwarded = A launcher is already running (pid { $pid }); opening { $url } in it. Pass --new-instance to start another.
+url-handler-starting = Starting the launcher to open { $path }
+url-handler-spawn-failed = Could not start the launcher: { $error }
+url-handler-failed = Could not open the link: { $error }
+url-handler-register-failed = Could not register the perfetto:// handler: { $error }
+url-handler-installed = perfetto:// links now open with { $exe }
+url-handler-uninstalled = perfetto:// links are no longer handled by the launcher
+
+# trace_processor download
+tp-download-unsupported = No prebuilt { $tool } is published for { $platform }
+tp-download-no-prebuilt = { $url } lists no { $tool } for { $platform }
+tp-download-fetching = Downloading { $tool } { $version } from { $url }...
+tp-download-done = { $tool } saved to { $path }
+tp-download-hash-mismatch = Checksum mismatch for { $url }: expected { $expected }, got { $actual }
+tp-download-write-failed = Could not save { $path }: { $error }
+# RPC relay
+rpc-proxy-origin-rejected = Forbidden: { $origin } may not use trace_processor's RPC
+rpc-proxy-unreachable = trace_processor is not reachable on port { $port }: { $error }
+rpc-proxy-upgrade-refused = trace_processor refused the WebSocket connection ({ $status })
+rpc-proxy-shared-forbidden = The shared trace_processor is only for admins and the owner of its trace; use /rpc/<session id>/ with a session of your own
+# System tray
+tray-open-ui = Open UI
+tray-restart-tp = Restart trace_processor
+tray-copy-url = Copy URL
+tray-quit = Quit
+tray-tooltip = Perfetto launcher: { $url }
+tray-failed = Warning: no system tray icon: { $error }
+tray-copy-failed = Warning: could not copy the URL: { $error }
+tray-unsupported = Warning: the system tray icon is not supported on macOS
+# Background
+daemon-started = Running in the background as pid { $pid }; log: { $log }
+daemon-failed = Error: could not start in the background: { $error }
+daemon-exited = Error: the background launcher exited at once ({ $status }); see its log for why
+service-installed = Installed service { $name }, started at boot; log: { $log }. Start it now with `sc start { $name }`.
+service-uninstalled = Removed service { $name }.
+service-failed = Error: service control failed: { $error }
\ No newline at end of file
@@ -0,0 +1,557 @@
+# 控制台消息
+launcher-banner = === Perfetto 启动器 ===
+dist-directory = Dist 目录：{ $path }
+compat-api-mismatch = UI 需要 trace_processor RPC API 版本 { $ui }，但 { $tp } 提供的是版本 { $api }
+compat-release-mismatch = UI 版本为 { $ui }，但 trace_processor 版本为 { $tp }；版本不一致可能导致 trace 加载失败
+compat-warning = 警告：{ $message }
+compat-forced = 警告：{ $message }（因指定 --force 而继续运行）
+compat-error = 错误：{ $message }
+compat-hint = 请使用相同发行版本的 UI 与 trace_processor，或加上 --force 强制启动。
+tp-starting = 正在启动 trace_processor_shell...
+tp-path = 路径：{ $path }
+tp-chmod-failed = 警告：无法将 { $path } 设为可执行：{ $error }
+tp-preset = 预设：{ $name }（{ $flags }）
+tp-port = HTTP 端口：{ $port }
+trace-loading = 加载 trace 文件：{ $path }
+tp-spawn-failed = 错误：无法启动 trace_processor_shell：{ $error }
+tp-waiting = 等待 trace_processor 启动...
+tp-start-timeout = 错误：trace_processor_shell 在 { $seconds } 秒内未在端口 { $port } 上响应。加载非常大的跟踪文件可能需要更久；可用 --startup-timeout 提高上限。
+server-starting = 正在端口 { $port } 上启动 HTTP 服务器...
+server-failed = 错误：无法启动 HTTP 服务器：{ $error }
+ready-banner = === Perfetto 已就绪！ ===
+ready-ui = UI 服务器：           { $url }
+ready-network = 局域网：             { $url }
+ready-local-only = 只有本机可以打开 UI；使用 --expose-lan 启动可让网络上的其他计算机连接
+ready-basic-auth = 登录：               用户 { $user }，密码 { $password }
+ready-rpc = Trace Processor RPC：  { $url }
+ready-stop = 按 Ctrl+C 停止。
+browser-failed = 警告：无法打开浏览器：{ $error }
+browser-manual = 请手动打开 { $url }。
+browser-not-opened = 请在浏览器中打开 { $url } 开始使用。
+goodbye = 再见！
+
+# 端口冲突
+port-conflict = 错误：{ $what } 无法使用端口 { $port }。
+port-owner = 端口 { $port } 正被 { $name }（PID { $pid }）占用。
+port-owner-unknown = 无法确定是哪个进程占用了端口 { $port }。
+port-conflict-hint = 请结束该进程，或重新运行启动器以选择其他端口。
+port-busy-retry = 端口 { $port } 仍被 { $holder } 占用；{ $delay } 毫秒后重试...
+port-holder-unknown = 其他进程
+port-moved = { $what } 无法使用端口 { $port }（被 { $holder } 占用），改用端口 { $chosen }。
+
+# 诊断
+doctor-title = === Perfetto 启动器诊断 ===
+doctor-summary = 共 { $total } 项检查，{ $failed } 项失败，{ $warnings } 项警告
+doctor-fix-dist-dir = 请将启动器放在 Perfetto UI 的 dist 目录中。
+doctor-fix-dist-index = 请将完整的 Perfetto UI 构建解压到 dist 目录。
+doctor-fix-dist-wasm = UI 包似乎不完整，请重新解压完整的 Perfetto UI 构建。
+doctor-fix-dist-version = 运行 `perfetto_launcher update-ui` 重新安装 UI，或在更新失败后运行 `update-ui --rollback`。
+doctor-fix-dist-damaged = 运行 `perfetto_launcher update-ui` 重新下载缺少的文件。
+doctor-fix-tp-missing = 请从 https://get.perfetto.dev/trace_processor 下载 trace_processor_shell 并放在 index.html 旁边。
+doctor-fix-tp-run = 请确认可执行文件符合此操作系统与架构且可执行；在 Windows 上请安装最新的 VC++ 运行库。
+doctor-fix-compat = 请使用与 UI 包相同 Perfetto 发行版本的 trace_processor_shell。
+doctor-fix-ports = 请关闭占用大量端口的其他应用程序，或检查本机安全软件。
+doctor-fix-loopback = 请在防火墙或安全软件中允许 perfetto_launcher 与 trace_processor_shell 的本机连接。
+doctor-fix-data-dir = 请检查目录权限或释放磁盘空间。
+doctor-fix-browser = 请安装较新的 Chromium 内核浏览器或 Firefox；Perfetto UI 需要 WebAssembly。
+preflight-failed = 启动前检查“{ $check }”失败：{ $detail }
+preflight-warning = 启动前检查“{ $check }”：{ $detail }
+preflight-hint = 运行 `perfetto_launcher doctor` 获取完整报告，或加上 --skip-preflight 强制启动。
+
+# 配置问题
+config-invalid = 错误：配置无效：
+config-dist-missing = dist 目录 { $path } 不存在
+config-index-missing = 在 { $path } 找不到 index.html
+config-tp-missing = 在 { $path } 找不到 trace_processor_shell（--auto-download 可自动下载）
+config-ports-equal = --ui-port 与 --rpc-port 都是 { $port }，必须不同
+config-trace-missing = trace 文件 { $path } 不存在
+config-mount-missing = 挂载 /{ $prefix }/：{ $path } 不是目录
+config-trace-dir-missing = 跟踪文件目录 { $name }：{ $path } 不是目录
+config-trace-dir-duplicate = 跟踪文件目录名称 { $name } 重复使用
+config-bad-origin = { $origin } 不是 https://ui.perfetto.dev 这样的来源；请去掉路径和末尾的斜杠
+config-file-unreadable = 错误：无法读取 { $path }：{ $error }
+config-file-invalid = 错误：{ $path } 不是有效的配置文件：{ $error }
+config-file-bad-value = 错误：{ $path }：{ $key } = { $value } 超出范围，应为 { $expected }
+
+# 试运行
+dry-run-title = === 试运行：不会启动任何东西 ===
+dry-run-config-file = 配置文件：         { $path }
+dry-run-dist-dir = dist 目录：        { $path }
+dry-run-ui-version = UI 版本：          { $version }
+dry-run-trace-processor = trace_processor：  { $path }
+dry-run-trace = 跟踪文件：         { $path }
+dry-run-preset = 预设：             { $name }（{ $flags }）
+dry-run-ui-port = UI 端口：          { $port }
+dry-run-rpc-port = RPC 端口：         { $port }
+dry-run-host = 监听地址：         { $host }
+dry-run-tls-files = TLS：              { $cert } / { $key }
+dry-run-tls-self-signed = TLS：              自签名，{ $path }
+dry-run-ui-url = UI 网址：          { $url }
+dry-run-command = 命令行：           { $command }
+dry-run-env = 环境变量：         { $name }={ $value }
+dry-run-download = 计划下载：         { $download }
+dry-run-downloads-none = 计划下载：         无
+dry-run-unknown = 未知
+dry-run-download-tp = trace_processor_shell { $version }（若尚未缓存）
+dry-run-download-tp-latest = trace_processor_shell，最新版本
+dry-run-no-problems = 没有发现问题。
+dry-run-problems = 问题：
+
+# 版本
+version-launcher = perfetto_launcher { $version }
+version-ui = Perfetto UI：     { $version }（{ $location }）
+version-ui-unknown = Perfetto UI：     未知（{ $location }）
+version-ui-embedded = 内嵌
+version-tp = trace_processor： { $version }（{ $path }）
+version-tp-api = trace_processor： { $version }，RPC API { $api }（{ $path }）
+version-tp-missing = trace_processor： 未找到（{ $path }）
+version-tp-unknown = trace_processor： 未知（{ $error }）
+
+# 关闭
+shutdown-draining = 正在关闭：最多等待 { $seconds } 秒让 { $count } 个进行中的响应完成。再按一次 Ctrl+C 立即退出。
+shutdown-drain-expired = 等待超时，关闭剩余连接。
+shutdown-forced = 强制关闭。
+shutdown-handler-failed = 无法安装 Ctrl+C 处理程序：{ $error }
+shutdown-requested = 已通过 HTTP 请求关闭。
+shutdown-idle = 已有 { $duration } 没有请求，正在关闭。
+shutdown-bad-token = 需要有效的关闭令牌（见 instance.json 中的 shutdown_token）
+
+# 资源监控
+monitor-memory-exceeded = 警告：trace_processor 使用了 { $used } MB 内存（上限 { $limit } MB）
+monitor-cpu-exceeded = 警告：trace_processor 持续使用 { $used }% CPU（上限 { $limit }%）
+monitor-restarting = trace_processor 超出资源上限，正在重新启动...
+
+# trace_processor 崩溃
+tp-crashed = 错误：trace_processor_shell 意外停止（{ $status }）。
+tp-crash-last-lines = 最后的输出：
+tp-restarting = 将在 { $seconds } 秒后重新启动 trace_processor（第 { $attempt } 次，共 { $max } 次）...
+tp-restarted = trace_processor 已重新启动（pid { $pid }）。打开的 UI 标签页会在它响应后重新加载。
+tp-spawned = 已启动 trace_processor_shell（pid { $pid }）：{ $path }
+tp-stopped = 已停止 trace_processor_shell（pid { $pid }）
+tp-restart-gave-up = trace_processor 不会再重新启动（已达 --max-restarts { $count }）。
+restart-requested = 应要求重新启动 trace_processor...
+restart-admins-only = 只有管理员可以重新启动 trace_processor；所有用户共用它
+restart-exited = trace_processor 重新启动后又停止了（{ $status }）；输出请见 /launcher/tp-logs
+restart-not-answering = trace_processor 已重新启动，但在启动超时内没有响应
+restart-no-launcher = 没有运行中的启动器
+restart-asking = 请运行中的启动器（pid { $pid }）重新启动 trace_processor...
+restart-started = trace_processor 正在重新启动（pid { $pid }）。
+restart-failed = 重新启动 trace_processor 失败：{ $error }
+tp-explain-missing-dll = 缺少必要的 DLL。请安装最新的 Microsoft Visual C++ 运行库 (x64) 后重试。
+tp-explain-oom = trace_processor 内存不足。trace 可能太大；请关闭其他程序或换用内存更大的机器。
+tp-explain-unsupported-trace = 无法解析 trace。文件可能已损坏，或是用比此 trace_processor 更新的 Perfetto 版本录制的。
+tp-explain-port = trace_processor 无法监听其 RPC 端口，可能有其他程序正在使用。
+tp-explain-missing-lib = 缺少必要的系统库，或其版本对此 trace_processor 来说过旧。
+tp-explain-segfault = trace_processor 崩溃。通常是 trace 损坏或 trace_processor 的缺陷所致；请尝试更新的版本。
+tp-explain-unknown = 原因不明；详情请见下方输出。
+
+# 崩溃报告
+crash-report-written = perfetto_launcher 发生崩溃。崩溃报告已写入 { $path }，反馈问题时请附上此文件。
+crash-report-failed = perfetto_launcher 发生崩溃，且无法写入崩溃报告：{ $error }
+
+# 启动耗时
+timings-title = 启动各阶段耗时：
+timings-total = 总计
+timings-trace-failed = 警告：无法将耗时 trace 写入 { $path }：{ $error }
+timings-concurrent = * 与上方阶段同时进行
+
+# Query instances
+instance-spawn-failed = 无法启动 trace_processor_shell：{ $error }
+instance-exited = trace_processor_shell 在加载跟踪文件时退出（{ $status }）
+instance-not-ready = trace_processor 未能就绪：{ $error }
+
+# bench
+bench-read-failed = 无法读取 { $path }：{ $error }
+bench-no-queries = { $path } 中没有任何查询
+bench-loaded = 跟踪文件加载耗时 { $ms } 毫秒
+bench-query-progress = 正在执行查询 { $index }/{ $total }...
+bench-write-failed = 无法写入 { $path }：{ $error }
+bench-failed = 性能测试失败：{ $error }
+
+# Landing page
+landing-title = Perfetto 启动器
+landing-drop-hint = 将跟踪文件拖放到这里，或点击选择文件
+landing-upload-failed = 上传失败。
+landing-upload-error = 无法保存上传的跟踪文件：{ $error }
+landing-trace-opened = 已在浏览器中打开推送的跟踪文件 { $name }
+landing-sessions = 会话
+landing-no-trace = （未加载跟踪文件）
+landing-session-running = trace_processor 运行中，PID { $pid }
+landing-session-opened = 于 { $time } 打开
+landing-session-stopped = trace_processor 已停止
+landing-recent = 最近的跟踪文件
+landing-no-recent = 尚未上传任何跟踪文件。
+landing-links = 快速链接
+landing-link-ui = 打开 Perfetto UI
+landing-link-status = 启动器状态（JSON）
+landing-link-tp-logs = trace_processor 输出
+landing-link-recent = 最近打开的跟踪文件
+landing-link-docs = Perfetto 文档
+
+# Recently opened
+recent-title = 最近打开的跟踪文件
+recent-empty = 尚未通过启动器打开任何跟踪文件。
+recent-name = 跟踪文件
+recent-size = 大小
+recent-opened = 上次打开
+recent-back = 返回启动器
+
+# Live reload
+livereload-watching = 正在监视 { $path } 的变更；变更时已打开的 UI 标签页会重新加载
+livereload-failed = 无法监视 { $path } 以进行实时重新加载：{ $error }
+livereload-watch-error = 监视 dist 目录失败：{ $error }
+livereload-changed = dist 目录中有 { $count } 个文件变更；正在重新加载 UI
+
+# HTTPS
+tls-unreadable = 无法读取 { $path }：{ $error }
+tls-no-certificate = { $path } 中没有 PEM 证书
+tls-unsupported-key = { $path } 中没有 PKCS#8 或 RSA 格式的 PEM 私钥；请用 `openssl pkcs8 -topk8 -nocrypt` 转换
+tls-generate-failed = 无法创建自签名证书：{ $error }
+tls-generated = 已在 { $path } 创建自签名证书；在信任它之前浏览器会显示警告
+
+# Remote access
+auth-denied = 请先登录：使用带 ?token= 的启动网址打开，或发送启动器的用户名和密码
+auth-token-failed = 错误：无法生成访问令牌：{ $error }
+auth-users-instead = 警告：多用户模式下会忽略 --auth；用户使用各自的令牌登录
+lan-unprotected = 警告：网络上的任何人都能打开 UI 及其 trace；添加 --auth token 或 --users 以要求登录
+
+# Query
+query-read-failed = 无法从 { $path } 读取 SQL：{ $error }
+query-empty = 没有要执行的 SQL；请通过参数、--file 或标准输入提供
+query-row-count = （{ $count } 行）
+query-failed = 查询失败：{ $error }
+query-api-empty = 没有要运行的 SQL；请将其作为请求主体发送，或放在 JSON 主体的 sql 字段中
+query-api-unreachable = trace_processor 没有响应：{ $error }
+query-api-session-required = 多用户模式下，请用 ?session=<id> 指定要查询的会话
+batch-no-sql = { $path } 中没有要运行的 SQL
+batch-no-traces = 找不到要运行的跟踪文件
+batch-running = 正在以 { $jobs } 个工作线程对 { $count } 个跟踪文件运行...
+batch-bad-metrics = trace_processor 输出的指标不是 JSON：{ $error }
+batch-write-failed = 无法写入 { $path }：{ $error }
+batch-summary = { $succeeded } 个跟踪文件成功，{ $failed } 个失败；结果位于 { $path }
+batch-some-failed = { $count } 个跟踪文件失败
+batch-failed = 批量运行失败：{ $error }
+
+# Recording
+record-unknown-template = 没有名为 { $name } 的配置模板；可用的有：{ $known }
+record-started = 正在录制到 { $path }。按 Ctrl+C 可提前停止。
+record-progress = 录制中... { $elapsed }／{ $duration }
+record-progress-open = 录制中... { $elapsed }；按 Ctrl+C 停止
+record-stopping = 正在停止录制并保存已录制的内容...
+record-stop-failed = 警告：无法停止录制程序：{ $error }
+record-busy = 已有录制正在进行
+record-none = 当前没有正在进行的录制
+record-admins-only = 只有管理员可以录制跟踪
+record-failed = 录制失败：{ $error }
+
+# Android
+android-title = Android 设备上的跟踪文件
+android-failed = Android：{ $error }
+android-adb-failed = adb 执行失败：{ $error }
+android-no-devices = 没有已连接的 Android 设备。
+android-several-devices = 已连接多台设备（{ $serials }），请用 --device 指定其一。
+android-device-unknown = 没有序列号为 { $serial } 的已连接设备。
+android-device-not-ready = 设备 { $serial } 的状态为 { $state }，请先在设备上允许 USB 调试。
+android-list-failed = 无法列出 { $serial } 上的跟踪文件：{ $error }
+android-no-traces = { $path } 中没有跟踪文件。
+android-bad-name = 不是跟踪文件名：{ $name }
+android-pulling = 正在从 { $serial } 拉取 { $path }...
+android-pull-failed = 无法拉取 { $path }：{ $error }
+android-admins-only = 只有管理员可以从设备拉取跟踪文件
+android-modified = 修改时间
+android-open = 拉取并打开
+android-pulling-button = 拉取中...
+
+# Error pages
+error-not-found = 这个网址没有内容。
+error-not-found-hint = 请检查链接是否输错。跟踪文件从目录中移除后，它的链接就会失效；启动器页面列出了目前所有的跟踪文件。如果 UI 本身不见了，请运行 `perfetto_launcher doctor` 检查 dist 目录。
+error-forbidden = 不允许打开这个网址。
+error-forbidden-hint = 启动器只提供 UI 的 dist 目录、上传、捕获以及 --mount 或 --trace-dir 目录中的文件。使用 --auth 或 --users 时，请重新打开获得的链接以登录。
+error-method = 这个网址不接受此类请求。
+error-method-detail = 此处不支持 { $method }；允许：{ $allow }
+error-method-hint = 可能是脚本或扩展调用了错误的端点；请参考上方允许的方法。
+error-server = 启动器内部发生错误。
+error-server-hint = 启动器状态会显示 trace_processor 是否在运行以及上次停止的原因；详细信息请见启动器的控制台或日志文件，`perfetto_launcher doctor` 可检查安装。
+
+# Catalog
+catalog-corrupt = 警告：忽略无法读取的跟踪文件目录 { $path }：{ $error }
+catalog-unknown-id = 目录中没有 ID 为 { $id } 的跟踪文件。
+catalog-pinned = 已固定 { $name }（{ $id }）
+catalog-unpinned = 已取消固定 { $name }（{ $id }）
+catalog-add-failed = 警告：无法将 { $path } 加入跟踪文件目录：{ $error }
+catalog-save-failed = 警告：无法保存跟踪文件目录 { $path }：{ $error }
+
+# Ingest
+ingest-indexed = 已为 { $name } 建立 { $count } 个 slice 名称的索引
+ingest-failed = 警告：无法处理要加入目录的 { $name }：{ $error }
+metadata-not-extracted = 跟踪文件 { $id } 的元数据尚未提取。请使用 --index-traces 启动启动器。
+
+# catalog import
+import-not-dir = { $path } 不是目录
+import-ingesting = 正在以 { $jobs } 个工作线程处理 { $count } 个跟踪文件...
+import-summary-title = 导入摘要：
+import-summary-found = 找到的跟踪文件：{ $count }
+import-summary-added = 新加入：{ $count }
+import-summary-known = 已在目录中：{ $count }
+import-summary-ingested = 已处理：{ $count }
+import-summary-failed = 失败：{ $count }
+import-failed = 导入失败：{ $error }
+
+# Scheduled captures
+capture-config-unreadable = 无法读取跟踪配置 { $path }：{ $error }
+capture-no-recorder = 在 trace_processor 旁或 PATH 中找不到 tracebox 或 perfetto。
+capture-no-adb = 在 PATH 或 Android SDK（ANDROID_HOME）中找不到 adb。
+capture-scheduled = 每 { $every } 录制 { $duration }。按 Ctrl+C 停止。
+capture-recording = 正在录制 { $path }...
+capture-saved = 已保存 { $path }，目录 ID 为 { $id }
+capture-failed = 录制失败：{ $error }
+capture-deleted = 已删除旧的录制文件 { $path }
+
+# Catalog archives
+archive-write-failed = 无法写入 { $path }：{ $error }
+archive-read-failed = 无法读取 { $path }：{ $error }
+archive-exported = 已导出 { $count } 个跟踪文件至 { $path }
+archive-imported = 已导入 { $added } 个跟踪文件；{ $skipped } 个已在目录中
+archive-traces-missing = 警告：有 { $count } 个跟踪文件在这台电脑上不存在
+archive-export-failed = 导出失败：{ $error }
+
+# Webhooks
+webhooks-invalid = 忽略 Webhook 配置 { $path }：{ $error }
+webhook-failed = Webhook { $url } 失败：{ $error }
+webhook-trace-ingested = 新跟踪 { $name }：{ $link }
+webhook-session-opened = Perfetto 会话已在 { $url } 打开
+webhook-session-closed = 位于 { $url } 的 Perfetto 会话已关闭
+webhook-check-failed = 检查失败：{ $check }：{ $message }
+
+# Fetch
+fetch-bad-url = 无效的 URL { $url }：{ $error }
+fetch-failed = 下载 { $url } 失败：{ $error }
+fetch-http-error = 下载 { $url } 失败：HTTP { $status } { $reason }
+fetch-too-many-redirects = 下载 { $url } 时重定向次数过多
+fetch-config-invalid = 无效的下载配置 { $path }：{ $error }
+fetch-downloading = 正在下载 { $url } 到 { $path }
+fetch-forwarded = 请运行中的启动器（pid { $pid }）下载 { $url }
+fetch-http-only = 只能下载 http 和 https 网址
+fetch-admins-only = 只有管理员可以让启动器下载 trace
+fetch-zip-failed = 无法读取 zip 压缩包 { $path }：{ $error }
+fetch-zip-empty = zip 压缩包 { $path } 中没有文件
+fetch-extracted = 已解压跟踪文件 { $path }
+
+# SQL modules
+sql-module-dir-missing = SQL 模块目录 { $path } 不存在
+sql-module-dir-empty = SQL 模块目录 { $path } 中没有 .sql 文件
+sql-module-failed = SQL 模块 { $module } 加载失败：{ $error }
+sql-modules-loaded = 已加载 { $loaded } / { $total } 个 SQL 模块
+
+# Metrics
+metrics-extension-invalid = { $path } 不是指标扩展目录（缺少 sql/）
+metrics-builtin = 内置
+metrics-tp-failed = trace_processor 退出，状态 { $status }
+metrics-write-failed = 无法写入 { $path }：{ $error }
+metrics-failed = 指标计算失败：{ $error }
+
+# Presets
+presets-invalid = { $path } 中的预设无效：{ $error }
+presets-unknown = 未知的 trace_processor 预设“{ $name }”（{ $path } 中已定义：{ $known }）
+
+# Symbolization
+symbol-dir-missing = 符号目录 { $path } 不存在
+symbolize-no-dirs = 请至少指定一个 --symbol-dir 以进行符号化
+symbolize-no-traceconv = 在 trace_processor 旁或 PATH 中找不到 traceconv
+symbolize-no-llvm-symbolizer = 在 trace_processor 旁或 PATH 中找不到 llvm-symbolizer；原生帧将不会被符号化
+symbolize-read-failed = 无法读取 { $path }：{ $error }
+symbolize-write-failed = 无法写入 { $path }：{ $error }
+symbolize-running = 正在符号化 { $path }...
+symbolize-traceconv-failed = traceconv 退出，状态 { $status }
+symbolize-nothing = 未找到此跟踪原生帧的符号
+symbolize-written = 已写入 { $path }（添加了 { $bytes } 字节的符号）
+symbolize-failed = 符号化失败：{ $error }
+convert-running = 正在将 { $input }（{ $from }）转换为 { $output }（{ $to }）...
+convert-progress = 已写入 { $size } MB
+convert-progress-percent = 已读取 { $percent }%
+convert-done = 已写入 { $path }（{ $size } MB）
+convert-failed = 转换失败：{ $error }
+clean-would-delete = 将删除 { $path }（{ $size } MB）
+clean-deleted = 已删除 { $path }（{ $size } MB）
+clean-done = 已删除 { $count } 个文件，共 { $size } MB；剩余 { $remaining } MB 的 trace
+clean-would-free = 将删除 { $count } 个文件，共 { $size } MB；剩余 { $remaining } MB 的 trace
+clean-failed = 清理失败：{ $error }
+clean-launcher-running = 启动器正在运行（pid { $pid }）；请先停止再清理
+workspace-quota-cleaned = 已删除 { $count } 个最久未使用的 trace（{ $size } MB），以符合 --workspace-quota-mb
+convert-unknown-format = 无法从 { $path } 判断要写入的格式；请指定 --to proto、json、systrace、ctrace 或 text
+convert-same-format = { $path } 已经是 { $format } 格式的跟踪
+convert-unsupported = 无法将 { $from } 转换为 { $to }：只有 Perfetto protobuf 跟踪可以转换为其他格式，另外 ctrace 与 systrace 可以互转
+convert-read-failed = 无法读取 { $path }：{ $error }
+convert-write-failed = 无法写入 { $path }：{ $error }
+convert-no-traceconv = 在 trace_processor 旁边或 PATH 中找不到 traceconv；加上 --auto-download 即可下载
+convert-traceconv-spawn-failed = 无法运行 { $path }：{ $error }
+convert-traceconv-failed = traceconv 退出，状态 { $status }
+
+# Deobfuscation
+deobfuscate-map-missing = Proguard 映射文件 { $path } 不存在
+deobfuscate-no-trace = 没有跟踪文件时 --proguard-map 无效
+
+# Symbol servers
+symbol-server-checking = 正在向 { $count } 个符号服务器查询缺少的 PDB...
+symbol-server-downloaded = 已下载 { $pdb }
+symbol-server-summary = 已下载 { $count } 个 PDB 到符号缓存
+symbol-server-failed = 符号服务器 { $server } 获取 { $pdb } 失败：{ $error }
+symbol-server-skipped = 跳过符号下载：{ $error }
+
+# Enrichment
+enrich-no-traceconv = 找不到 traceconv；不会应用 Proguard 映射文件
+enrich-cached = 使用缓存的增强跟踪文件 { $path }
+enrich-pass = [{ $index }/{ $total }] 正在对 { $path } 运行 { $pass }...
+enrich-nothing = 没有需要符号化或反混淆的内容；直接加载跟踪文件
+enrich-done = 增强跟踪文件已写入 { $path }（添加了 { $bytes } 字节）
+enrich-failed = 符号化/反混淆失败，将直接加载跟踪文件：{ $error }
+
+# Request checks
+csrf-rejected = 禁止：跨站请求
+host-rejected = 禁止：{ $host } 不是本机；请通过 localhost 或本机自己的地址打开启动器
+body-too-large = 请求内容超过 { $limit } 字节的上限
+
+# Plugins
+plugins-none = { $path } 中没有插件
+plugins-entry = { $name }（{ $path }）
+plugin-describe-failed = 跳过插件 { $path }：{ $error }
+plugin-failed = 插件 { $name } 失败：{ $error }
+plugin-bad-response = 插件 { $name } 返回了无效的响应：{ $error }
+plugin-no-output = 插件 { $name } 没有写出 { $path }
+plugin-converting = 正在用插件 { $name } 转换 { $path }...
+
+# Trace API
+api-bad-body = 无效的请求内容：{ $error }
+api-empty-name = 跟踪名称不能为空
+
+# Extra sessions
+session-started = 已为 { $trace } 启动会话 { $id }（pid { $pid }）
+session-stopped = 已停止会话 { $id }（pid { $pid }）
+session-idle = 会话 { $id } 闲置过久，已停止
+session-exited = 会话 { $id } 的 trace_processor 已退出
+session-unknown = 没有 ID 为 { $id } 的会话。
+session-forbidden = 会话 { $id } 由其他用户启动
+session-limit = 已有 { $max } 个会话在运行；请停止其中一个或提高 --max-sessions。
+session-start-failed = 无法启动会话：{ $error }
+
+# Dashboards
+dashboards-title = 仪表板
+dashboards-none = 还没有仪表板。请将 JSON 或 YAML 仪表板文件添加到 { $path }。
+dashboard-not-found = 没有名为 { $name } 的仪表板
+dashboard-invalid = 无效的仪表板 { $path }：{ $error }
+dashboard-updated = 更新于
+dashboard-load-failed = 无法刷新：
+dashboard-no-rows = 没有数据行
+
+# Reports
+reports-title = 报告
+reports-none = 还没有报告。请在 { $path } 中配置。
+reports-invalid = { $path } 中的报告配置无效：{ $error }
+reports-unknown = { $path } 中没有名为 { $name } 的报告
+reports-ran = 报告 { $report }：已写出 { $count } 份
+reports-some-failed = 部分报告无法生成
+reports-scheduled = 已调度 { $count } 份报告
+report-written = { $name } 的报告 { $report }：{ $path }
+report-failed = { $name } 的报告 { $report } 失败：{ $error }
+report-no-output = 报告命令没有写出 { $path }
+report-summary-open = 在 UI 中打开
+report-summary-file = 文件
+report-summary-size = 大小（字节）
+report-summary-duration = 时长（纳秒）
+report-summary-capture-time = 捕获时间（自纪元起的纳秒）
+report-summary-data-sources = 数据源
+report-summary-data-loss = 数据丢失
+report-summary-no-data-loss = 没有记录到数据丢失
+report-summary-top-slices = 最常出现的切片
+
+# Timeline
+timeline-start = 跟踪开始：{ $wall }（ts { $ts }）
+timeline-end = 跟踪结束：{ $wall }（ts { $ts }）
+timeline-drift = 跟踪期间挂钟时间最多被调整了 { $ms } 毫秒
+timeline-offset = 距跟踪开始 { $offset } 秒
+timeline-outside = 距跟踪开始 { $offset } 秒，超出跟踪范围
+timeline-wall-to-trace = { $wall } -> ts { $ts }（{ $position }）
+timeline-trace-to-wall = ts { $ts } -> { $wall }（{ $position }）
+timeline-no-snapshots = { $path } 没有 REALTIME 时钟快照，无法映射挂钟时间
+timeline-invalid-time = 无效的时间“{ $time }”；请使用 YYYY-MM-DD HH:MM[:SS[.小数]]，可加上 Z 或 ±HH:MM 时差
+timeline-failed = 时间映射失败：{ $error }
+
+# Users
+users-invalid = 用户配置无效 { $path }：{ $error }
+users-none = { $path } 未列出任何用户，也未设置 trust_header
+users-enabled = 多用户模式：已配置 { $count } 个用户
+users-unauthorized = 请使用令牌登录：以 ?token=<token> 打开此页面，或发送 Authorization: Bearer <token>
+users-forbidden = 跟踪 { $id } 属于其他用户
+users-quota-exceeded = 已超出上传配额：剩余 { $remaining } MB
+
+# Logs
+logs-config-invalid = 日志配置无效 { $path }：{ $error }
+logs-open-failed = 无法打开日志 { $path }：{ $error }
+logs-compress-failed = 无法压缩轮转的日志 { $path }：{ $error }
+
+# UI update
+ui-update-versions = 已安装的 UI：{ $current }，最新版：{ $latest }
+ui-update-up-to-date = UI { $version } 已是最新版
+ui-update-downloaded = 已下载 { $path }
+ui-update-transferred = { $reused } 个文件未变更，已下载 { $downloaded } 个（{ $mb } MB）
+ui-update-done = UI 已更新至 { $version }
+ui-update-failed = UI 更新失败：{ $error }
+ui-update-bad-index = 无法读取 index.html：{ $error }
+ui-update-no-version = index.html 未列出 { $channel } 版本
+ui-update-bad-manifest = manifest.json 无效：{ $error }
+ui-update-hash-mismatch = { $path } 与 manifest.json 不符（预期 { $expected }，实际 { $actual }）
+ui-update-available = 有可用的更新：{ $version }。不加 --check 运行 update-ui 即可安装。
+ui-update-bad-version = 不是 UI 版本：{ $version }（例如 v46.0-8a7e2f1c）
+ui-update-no-previous = 没有可以回退的旧版 UI；只有在更新改变了版本后才会保留旧版。
+ui-update-rolled-back = UI 已从 { $from } 回退到 { $to }
+
+# Bundle
+bundle-unknown-platform = 此平台没有对应的包名称，请指定 --platform
+bundle-need-launcher = 请使用 --launcher 指定为 { $platform } 构建的启动器可执行文件
+bundle-need-trace-processor = 请使用 --trace-processor 指定为 { $platform } 构建的 trace_processor_shell
+bundle-missing-file = 找不到文件：{ $path }
+bundle-no-ui = { $path } 中没有 UI 版本目录；请使用 --ui-version 下载
+bundle-write-failed = 无法写入 { $path }：{ $error }
+bundle-created = 已创建 { $path }（{ $platform }，UI { $version }，{ $mb } MB）
+bundle-failed = 打包失败：{ $error }
+
+# URL handler
+url-handler-expected = 应为 perfetto://open?trace=<ID、路径或 URL>
+url-handler-bad-link = 无效的链接 { $link }：{ $error }
+url-handler-forwarded = 在运行中的启动器打开：{ $url }
+instance-forwarded = 已有启动器在运行（pid { $pid }），改在其中打开 { $url }。如需另外启动一个，请加上 --new-instance。
+url-handler-starting = 正在启动启动器以打开 { $path }
+url-handler-spawn-failed = 无法启动启动器：{ $error }
+url-handler-failed = 无法打开链接：{ $error }
+url-handler-register-failed = 无法注册 perfetto:// 处理程序：{ $error }
+url-handler-installed = perfetto:// 链接现在会用 { $exe } 打开
+url-handler-uninstalled = 启动器不再处理 perfetto:// 链接
+
+# trace_processor download
+tp-download-unsupported = 没有为 { $platform } 发布预构建的 { $tool }
+tp-download-no-prebuilt = { $url } 未列出 { $platform } 的 { $tool }
+tp-download-fetching = 正在从 { $url } 下载 { $tool } { $version }...
+tp-download-done = { $tool } 已保存到 { $path }
+tp-download-hash-mismatch = { $url } 的校验和不符：应为 { $expected }，实际为 { $actual }
+tp-download-write-failed = 无法保存 { $path }：{ $error }
+# RPC 转发
+rpc-proxy-origin-rejected = 禁止：{ $origin } 不可使用 trace_processor 的 RPC
+rpc-proxy-unreachable = 无法连接到端口 { $port } 上的 trace_processor：{ $error }
+rpc-proxy-upgrade-refused = trace_processor 拒绝了 WebSocket 连接（{ $status }）
+rpc-proxy-shared-forbidden = 共享的 trace_processor 仅供管理员和其跟踪的所有者使用；请通过 /rpc/<会话 ID>/ 使用自己的会话
+# 系统托盘
+tray-open-ui = 打开 UI
+tray-restart-tp = 重新启动 trace_processor
+tray-copy-url = 复制网址
+tray-quit = 退出
+tray-tooltip = Perfetto 启动器：{ $url }
+tray-failed = 警告：无法显示系统托盘图标：{ $error }
+tray-copy-failed = 警告：无法复制网址：{ $error }
+tray-unsupported = 警告：macOS 不支持系统托盘图标
+# 后台运行
+daemon-started = 已在后台运行，pid { $pid }；日志：{ $log }
+daemon-failed = 错误：无法在后台启动：{ $error }
+daemon-exited = 错误：后台启动器立即退出（{ $status }），原因请见其日志
+service-installed = 已安装服务 { $name }，将在开机时启动；日志：{ $log }。可用 `sc start { $name }` 立即启动。
+service-uninstalled = 已移除服务 { $name }。
+service-failed = 错误：服务控制失败：{ $error }
\ No newline at end of file
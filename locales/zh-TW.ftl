@@ -0,0 +1,557 @@
+# 主控台訊息
+launcher-banner = === Perfetto 啟動器 ===
+dist-directory = Dist 目錄：{ $path }
+compat-api-mismatch = UI 需要 trace_processor RPC API 版本 { $ui }，但 { $tp } 提供的是版本 { $api }
+compat-release-mismatch = UI 版本為 { $ui }，但 trace_processor 版本為 { $tp }；版本不一致可能導致追蹤檔載入失敗
+compat-warning = 警告：{ $message }
+compat-forced = 警告：{ $message }（因指定 --force 而繼續執行）
+compat-error = 錯誤：{ $message }
+compat-hint = 請使用相同發行版本的 UI 與 trace_processor，或加上 --force 強制啟動。
+tp-starting = 正在啟動 trace_processor_shell...
+tp-path = 路徑：{ $path }
+tp-chmod-failed = 警告：無法將 { $path } 設為可執行：{ $error }
+tp-preset = 預設：{ $name }（{ $flags }）
+tp-port = HTTP 連接埠：{ $port }
+trace-loading = 載入追蹤檔：{ $path }
+tp-spawn-failed = 錯誤：無法啟動 trace_processor_shell：{ $error }
+tp-waiting = 等待 trace_processor 啟動...
+tp-start-timeout = 錯誤：trace_processor_shell 在 { $seconds } 秒內未在連接埠 { $port } 上回應。載入非常大的追蹤檔可能需要更久；可用 --startup-timeout 提高上限。
+server-starting = 正在連接埠 { $port } 上啟動 HTTP 伺服器...
+server-failed = 錯誤：無法啟動 HTTP 伺服器：{ $error }
+ready-banner = === Perfetto 已就緒！ ===
+ready-ui = UI 伺服器：           { $url }
+ready-network = 區域網路：           { $url }
+ready-local-only = 只有本機可以開啟 UI；以 --expose-lan 啟動可讓網路上的其他電腦連線
+ready-basic-auth = 登入：               使用者 { $user }，密碼 { $password }
+ready-rpc = Trace Processor RPC：  { $url }
+ready-stop = 按 Ctrl+C 停止。
+browser-failed = 警告：無法開啟瀏覽器：{ $error }
+browser-manual = 請手動開啟 { $url }。
+browser-not-opened = 請在瀏覽器中開啟 { $url } 開始使用。
+goodbye = 再見！
+
+# 連接埠衝突
+port-conflict = 錯誤：{ $what } 無法使用連接埠 { $port }。
+port-owner = 連接埠 { $port } 正被 { $name }（PID { $pid }）使用。
+port-owner-unknown = 無法判斷是哪個程序佔用了連接埠 { $port }。
+port-conflict-hint = 請結束該程序，或重新執行啟動器以選擇其他連接埠。
+port-busy-retry = 連接埠 { $port } 仍被 { $holder } 佔用；{ $delay } 毫秒後重試...
+port-holder-unknown = 其他程序
+port-moved = { $what } 無法使用連接埠 { $port }（被 { $holder } 佔用），改用連接埠 { $chosen }。
+
+# 診斷
+doctor-title = === Perfetto 啟動器診斷 ===
+doctor-summary = 共 { $total } 項檢查，{ $failed } 項失敗，{ $warnings } 項警告
+doctor-fix-dist-dir = 請將啟動器放在 Perfetto UI 的 dist 目錄中。
+doctor-fix-dist-index = 請將完整的 Perfetto UI 建置解壓縮到 dist 目錄。
+doctor-fix-dist-wasm = UI 套件似乎不完整，請重新解壓縮完整的 Perfetto UI 建置。
+doctor-fix-dist-version = 執行 `perfetto_launcher update-ui` 重新安裝 UI，或在更新失敗後執行 `update-ui --rollback`。
+doctor-fix-dist-damaged = 執行 `perfetto_launcher update-ui` 重新下載缺少的檔案。
+doctor-fix-tp-missing = 請從 https://get.perfetto.dev/trace_processor 下載 trace_processor_shell 並放在 index.html 旁邊。
+doctor-fix-tp-run = 請確認執行檔符合此作業系統與架構且可執行；在 Windows 上請安裝最新的 VC++ 執行階段。
+doctor-fix-compat = 請使用與 UI 套件相同 Perfetto 發行版本的 trace_processor_shell。
+doctor-fix-ports = 請關閉佔用大量連接埠的其他應用程式，或檢查本機安全軟體。
+doctor-fix-loopback = 請在防火牆或安全軟體中允許 perfetto_launcher 與 trace_processor_shell 的本機連線。
+doctor-fix-data-dir = 請檢查目錄權限或釋放磁碟空間。
+doctor-fix-browser = 請安裝較新的 Chromium 系瀏覽器或 Firefox；Perfetto UI 需要 WebAssembly。
+preflight-failed = 啟動前檢查「{ $check }」失敗：{ $detail }
+preflight-warning = 啟動前檢查「{ $check }」：{ $detail }
+preflight-hint = 執行 `perfetto_launcher doctor` 取得完整報告，或加上 --skip-preflight 強制啟動。
+
+# 設定問題
+config-invalid = 錯誤：設定無效：
+config-dist-missing = dist 目錄 { $path } 不存在
+config-index-missing = 在 { $path } 找不到 index.html
+config-tp-missing = 在 { $path } 找不到 trace_processor_shell（--auto-download 可自動下載）
+config-ports-equal = --ui-port 與 --rpc-port 都是 { $port }，必須不同
+config-trace-missing = 追蹤檔 { $path } 不存在
+config-mount-missing = 掛載 /{ $prefix }/：{ $path } 不是目錄
+config-trace-dir-missing = 追蹤檔目錄 { $name }：{ $path } 不是目錄
+config-trace-dir-duplicate = 追蹤檔目錄名稱 { $name } 重複使用
+config-bad-origin = { $origin } 不是 https://ui.perfetto.dev 這樣的來源；請去掉路徑與結尾的斜線
+config-file-unreadable = 錯誤：無法讀取 { $path }：{ $error }
+config-file-invalid = 錯誤：{ $path } 不是有效的設定檔：{ $error }
+config-file-bad-value = 錯誤：{ $path }：{ $key } = { $value } 超出範圍，應為 { $expected }
+
+# 試執行
+dry-run-title = === 試執行：不會啟動任何東西 ===
+dry-run-config-file = 設定檔：           { $path }
+dry-run-dist-dir = dist 目錄：        { $path }
+dry-run-ui-version = UI 版本：          { $version }
+dry-run-trace-processor = trace_processor：  { $path }
+dry-run-trace = 追蹤檔：           { $path }
+dry-run-preset = 預設：             { $name }（{ $flags }）
+dry-run-ui-port = UI 連接埠：        { $port }
+dry-run-rpc-port = RPC 連接埠：       { $port }
+dry-run-host = 監聽位址：         { $host }
+dry-run-tls-files = TLS：              { $cert } / { $key }
+dry-run-tls-self-signed = TLS：              自我簽署，{ $path }
+dry-run-ui-url = UI 網址：          { $url }
+dry-run-command = 命令列：           { $command }
+dry-run-env = 環境變數：         { $name }={ $value }
+dry-run-download = 預計下載：         { $download }
+dry-run-downloads-none = 預計下載：         無
+dry-run-unknown = 未知
+dry-run-download-tp = trace_processor_shell { $version }（若尚未快取）
+dry-run-download-tp-latest = trace_processor_shell，最新版本
+dry-run-no-problems = 沒有發現問題。
+dry-run-problems = 問題：
+
+# 版本
+version-launcher = perfetto_launcher { $version }
+version-ui = Perfetto UI：     { $version }（{ $location }）
+version-ui-unknown = Perfetto UI：     未知（{ $location }）
+version-ui-embedded = 內嵌
+version-tp = trace_processor： { $version }（{ $path }）
+version-tp-api = trace_processor： { $version }，RPC API { $api }（{ $path }）
+version-tp-missing = trace_processor： 找不到（{ $path }）
+version-tp-unknown = trace_processor： 未知（{ $error }）
+
+# 關閉
+shutdown-draining = 正在關閉：最多等待 { $seconds } 秒讓 { $count } 個進行中的回應完成。再按一次 Ctrl+C 立即結束。
+shutdown-drain-expired = 等待逾時，關閉剩餘連線。
+shutdown-forced = 強制關閉。
+shutdown-handler-failed = 無法安裝 Ctrl+C 處理常式：{ $error }
+shutdown-requested = 已透過 HTTP 要求關閉。
+shutdown-idle = 已有 { $duration } 沒有請求，正在關閉。
+shutdown-bad-token = 需要有效的關閉權杖（見 instance.json 中的 shutdown_token）
+
+# 資源監控
+monitor-memory-exceeded = 警告：trace_processor 使用了 { $used } MB 記憶體（上限 { $limit } MB）
+monitor-cpu-exceeded = 警告：trace_processor 持續使用 { $used }% CPU（上限 { $limit }%）
+monitor-restarting = trace_processor 超出資源上限，正在重新啟動...
+
+# trace_processor 當機
+tp-crashed = 錯誤：trace_processor_shell 意外停止（{ $status }）。
+tp-crash-last-lines = 最後的輸出：
+tp-restarting = 將在 { $seconds } 秒後重新啟動 trace_processor（第 { $attempt } 次，共 { $max } 次）...
+tp-restarted = trace_processor 已重新啟動（pid { $pid }）。開啟中的 UI 分頁會在它回應後重新載入。
+tp-spawned = 已啟動 trace_processor_shell（pid { $pid }）：{ $path }
+tp-stopped = 已停止 trace_processor_shell（pid { $pid }）
+tp-restart-gave-up = trace_processor 不會再重新啟動（已達 --max-restarts { $count }）。
+restart-requested = 應要求重新啟動 trace_processor...
+restart-admins-only = 只有管理員可以重新啟動 trace_processor；所有使用者共用它
+restart-exited = trace_processor 重新啟動後又停止了（{ $status }）；輸出請見 /launcher/tp-logs
+restart-not-answering = trace_processor 已重新啟動，但在啟動逾時內沒有回應
+restart-no-launcher = 沒有執行中的啟動器
+restart-asking = 請執行中的啟動器（pid { $pid }）重新啟動 trace_processor...
+restart-started = trace_processor 正在重新啟動（pid { $pid }）。
+restart-failed = 重新啟動 trace_processor 失敗：{ $error }
+tp-explain-missing-dll = 缺少必要的 DLL。請安裝最新的 Microsoft Visual C++ 可轉散發套件 (x64) 後再試一次。
+tp-explain-oom = trace_processor 記憶體不足。追蹤檔可能太大；請關閉其他程式或改用記憶體較大的電腦。
+tp-explain-unsupported-trace = 無法解析追蹤檔。檔案可能已損毀，或是以比此 trace_processor 更新的 Perfetto 版本錄製。
+tp-explain-port = trace_processor 無法監聽其 RPC 連接埠，可能有其他程式正在使用。
+tp-explain-missing-lib = 缺少必要的系統函式庫，或其版本對此 trace_processor 而言過舊。
+tp-explain-segfault = trace_processor 當機。通常是追蹤檔損毀或 trace_processor 的錯誤所致；請嘗試較新的版本。
+tp-explain-unknown = 原因不明；詳情請見下方輸出。
+
+# 當機報告
+crash-report-written = perfetto_launcher 發生當機。當機報告已寫入 { $path }，回報問題時請附上此檔案。
+crash-report-failed = perfetto_launcher 發生當機，且無法寫入當機報告：{ $error }
+
+# 啟動時間
+timings-title = 啟動各階段耗時：
+timings-total = 總計
+timings-trace-failed = 警告：無法將耗時追蹤寫入 { $path }：{ $error }
+timings-concurrent = * 與上方階段同時進行
+
+# Query instances
+instance-spawn-failed = 無法啟動 trace_processor_shell：{ $error }
+instance-exited = trace_processor_shell 在載入追蹤檔時結束（{ $status }）
+instance-not-ready = trace_processor 未能就緒：{ $error }
+
+# bench
+bench-read-failed = 無法讀取 { $path }：{ $error }
+bench-no-queries = { $path } 中沒有任何查詢
+bench-loaded = 追蹤檔載入耗時 { $ms } 毫秒
+bench-query-progress = 正在執行查詢 { $index }/{ $total }...
+bench-write-failed = 無法寫入 { $path }：{ $error }
+bench-failed = 效能測試失敗：{ $error }
+
+# Landing page
+landing-title = Perfetto 啟動器
+landing-drop-hint = 將追蹤檔拖放到這裡，或點擊選擇檔案
+landing-upload-failed = 上傳失敗。
+landing-upload-error = 無法儲存上傳的追蹤檔：{ $error }
+landing-trace-opened = 已在瀏覽器中開啟推送的追蹤檔 { $name }
+landing-sessions = 工作階段
+landing-no-trace = （未載入追蹤檔）
+landing-session-running = trace_processor 執行中，PID { $pid }
+landing-session-opened = 於 { $time } 開啟
+landing-session-stopped = trace_processor 已停止
+landing-recent = 最近的追蹤檔
+landing-no-recent = 尚未上傳任何追蹤檔。
+landing-links = 快速連結
+landing-link-ui = 開啟 Perfetto UI
+landing-link-status = 啟動器狀態（JSON）
+landing-link-tp-logs = trace_processor 輸出
+landing-link-recent = 最近開啟的追蹤檔
+landing-link-docs = Perfetto 說明文件
+
+# Recently opened
+recent-title = 最近開啟的追蹤檔
+recent-empty = 尚未透過啟動器開啟任何追蹤檔。
+recent-name = 追蹤檔
+recent-size = 大小
+recent-opened = 上次開啟
+recent-back = 返回啟動器
+
+# Live reload
+livereload-watching = 正在監看 { $path } 的變更；變更時已開啟的 UI 分頁會重新載入
+livereload-failed = 無法監看 { $path } 以進行即時重新載入：{ $error }
+livereload-watch-error = 監看 dist 目錄失敗：{ $error }
+livereload-changed = dist 目錄中有 { $count } 個檔案變更；正在重新載入 UI
+
+# HTTPS
+tls-unreadable = 無法讀取 { $path }：{ $error }
+tls-no-certificate = { $path } 中沒有 PEM 憑證
+tls-unsupported-key = { $path } 中沒有 PKCS#8 或 RSA 格式的 PEM 私鑰；請用 `openssl pkcs8 -topk8 -nocrypt` 轉換
+tls-generate-failed = 無法建立自我簽署憑證：{ $error }
+tls-generated = 已在 { $path } 建立自我簽署憑證；在信任它之前瀏覽器會顯示警告
+
+# Remote access
+auth-denied = 請先登入：以含 ?token= 的啟動網址開啟，或傳送啟動器的帳號密碼
+auth-token-failed = 錯誤：無法產生存取權杖：{ $error }
+auth-users-instead = 警告：多使用者模式下會忽略 --auth；使用者以各自的權杖登入
+lan-unprotected = 警告：網路上的任何人都能開啟 UI 及其追蹤；加上 --auth token 或 --users 以要求登入
+
+# Query
+query-read-failed = 無法從 { $path } 讀取 SQL：{ $error }
+query-empty = 沒有要執行的 SQL；請以參數、--file 或標準輸入提供
+query-row-count = （{ $count } 列）
+query-failed = 查詢失敗：{ $error }
+query-api-empty = 沒有要執行的 SQL；請將其作為請求主體傳送，或放在 JSON 主體的 sql 欄位中
+query-api-unreachable = trace_processor 沒有回應：{ $error }
+query-api-session-required = 多使用者模式下，請以 ?session=<id> 指定要查詢的工作階段
+batch-no-sql = { $path } 中沒有要執行的 SQL
+batch-no-traces = 找不到要執行的追蹤檔
+batch-running = 正在以 { $jobs } 個工作執行緒對 { $count } 個追蹤檔執行...
+batch-bad-metrics = trace_processor 輸出的指標不是 JSON：{ $error }
+batch-write-failed = 無法寫入 { $path }：{ $error }
+batch-summary = { $succeeded } 個追蹤檔成功，{ $failed } 個失敗；結果位於 { $path }
+batch-some-failed = { $count } 個追蹤檔失敗
+batch-failed = 批次執行失敗：{ $error }
+
+# Recording
+record-unknown-template = 沒有名為 { $name } 的設定範本；可用的有：{ $known }
+record-started = 正在錄製至 { $path }。按 Ctrl+C 可提前停止。
+record-progress = 錄製中... { $elapsed }／{ $duration }
+record-progress-open = 錄製中... { $elapsed }；按 Ctrl+C 停止
+record-stopping = 正在停止錄製並儲存已錄製的內容...
+record-stop-failed = 警告：無法停止錄製程式：{ $error }
+record-busy = 已有錄製正在進行
+record-none = 目前沒有進行中的錄製
+record-admins-only = 只有管理員可以錄製追蹤
+record-failed = 錄製失敗：{ $error }
+
+# Android
+android-title = Android 裝置上的追蹤檔
+android-failed = Android：{ $error }
+android-adb-failed = adb 執行失敗：{ $error }
+android-no-devices = 沒有已連接的 Android 裝置。
+android-several-devices = 已連接多台裝置（{ $serials }），請用 --device 指定其一。
+android-device-unknown = 沒有序號為 { $serial } 的已連接裝置。
+android-device-not-ready = 裝置 { $serial } 的狀態為 { $state }，請先在裝置上允許 USB 偵錯。
+android-list-failed = 無法列出 { $serial } 上的追蹤檔：{ $error }
+android-no-traces = { $path } 中沒有追蹤檔。
+android-bad-name = 不是追蹤檔名稱：{ $name }
+android-pulling = 正在從 { $serial } 拉取 { $path }...
+android-pull-failed = 無法拉取 { $path }：{ $error }
+android-admins-only = 只有管理員可以從裝置拉取追蹤檔
+android-modified = 修改時間
+android-open = 拉取並開啟
+android-pulling-button = 拉取中...
+
+# Error pages
+error-not-found = 這個網址沒有內容。
+error-not-found-hint = 請檢查連結是否打錯。追蹤檔從目錄中移除後，它的連結就會失效；啟動器頁面列出了目前所有的追蹤檔。若是 UI 本身不見了，請執行 `perfetto_launcher doctor` 檢查 dist 目錄。
+error-forbidden = 不允許開啟這個網址。
+error-forbidden-hint = 啟動器只提供 UI 的 dist 目錄、上傳、擷取以及 --mount 或 --trace-dir 目錄中的檔案。使用 --auth 或 --users 時，請重新開啟取得的連結以登入。
+error-method = 這個網址不接受此類請求。
+error-method-detail = 此處不支援 { $method }；允許：{ $allow }
+error-method-hint = 可能是腳本或擴充功能呼叫了錯誤的端點；請參考上方允許的方法。
+error-server = 啟動器內部發生錯誤。
+error-server-hint = 啟動器狀態會顯示 trace_processor 是否在執行以及上次停止的原因；詳細資訊請見啟動器的主控台或記錄檔，`perfetto_launcher doctor` 可檢查安裝。
+
+# Catalog
+catalog-corrupt = 警告：忽略無法讀取的追蹤檔目錄 { $path }：{ $error }
+catalog-unknown-id = 目錄中沒有 ID 為 { $id } 的追蹤檔。
+catalog-pinned = 已釘選 { $name }（{ $id }）
+catalog-unpinned = 已取消釘選 { $name }（{ $id }）
+catalog-add-failed = 警告：無法將 { $path } 加入追蹤檔目錄：{ $error }
+catalog-save-failed = 警告：無法儲存追蹤檔目錄 { $path }：{ $error }
+
+# Ingest
+ingest-indexed = 已為 { $name } 建立 { $count } 個 slice 名稱的索引
+ingest-failed = 警告：無法處理要加入目錄的 { $name }：{ $error }
+metadata-not-extracted = 追蹤檔 { $id } 的中繼資料尚未擷取。請使用 --index-traces 啟動啟動器。
+
+# catalog import
+import-not-dir = { $path } 不是目錄
+import-ingesting = 正在以 { $jobs } 個工作執行緒處理 { $count } 個追蹤檔...
+import-summary-title = 匯入摘要：
+import-summary-found = 找到的追蹤檔：{ $count }
+import-summary-added = 新加入：{ $count }
+import-summary-known = 已在目錄中：{ $count }
+import-summary-ingested = 已處理：{ $count }
+import-summary-failed = 失敗：{ $count }
+import-failed = 匯入失敗：{ $error }
+
+# Scheduled captures
+capture-config-unreadable = 無法讀取追蹤設定 { $path }：{ $error }
+capture-no-recorder = 在 trace_processor 旁或 PATH 中找不到 tracebox 或 perfetto。
+capture-no-adb = 在 PATH 或 Android SDK（ANDROID_HOME）中找不到 adb。
+capture-scheduled = 每 { $every } 錄製 { $duration }。按 Ctrl+C 停止。
+capture-recording = 正在錄製 { $path }...
+capture-saved = 已儲存 { $path }，目錄 ID 為 { $id }
+capture-failed = 錄製失敗：{ $error }
+capture-deleted = 已刪除舊的錄製檔 { $path }
+
+# Catalog archives
+archive-write-failed = 無法寫入 { $path }：{ $error }
+archive-read-failed = 無法讀取 { $path }：{ $error }
+archive-exported = 已匯出 { $count } 個追蹤檔至 { $path }
+archive-imported = 已匯入 { $added } 個追蹤檔；{ $skipped } 個已在目錄中
+archive-traces-missing = 警告：有 { $count } 個追蹤檔在這台電腦上不存在
+archive-export-failed = 匯出失敗：{ $error }
+
+# Webhooks
+webhooks-invalid = 忽略 Webhook 設定 { $path }：{ $error }
+webhook-failed = Webhook { $url } 失敗：{ $error }
+webhook-trace-ingested = 新追蹤 { $name }：{ $link }
+webhook-session-opened = Perfetto 工作階段已開啟於 { $url }
+webhook-session-closed = 位於 { $url } 的 Perfetto 工作階段已關閉
+webhook-check-failed = 檢查失敗：{ $check }：{ $message }
+
+# Fetch
+fetch-bad-url = 無效的 URL { $url }：{ $error }
+fetch-failed = 下載 { $url } 失敗：{ $error }
+fetch-http-error = 下載 { $url } 失敗：HTTP { $status } { $reason }
+fetch-too-many-redirects = 下載 { $url } 時重新導向次數過多
+fetch-config-invalid = 無效的下載設定 { $path }：{ $error }
+fetch-downloading = 正在下載 { $url } 至 { $path }
+fetch-forwarded = 請執行中的啟動器（pid { $pid }）下載 { $url }
+fetch-http-only = 只能下載 http 與 https 網址
+fetch-admins-only = 只有管理員可以讓啟動器下載追蹤
+fetch-zip-failed = 無法讀取 zip 封存檔 { $path }：{ $error }
+fetch-zip-empty = zip 封存檔 { $path } 中沒有檔案
+fetch-extracted = 已解壓縮追蹤檔 { $path }
+
+# SQL modules
+sql-module-dir-missing = SQL 模組目錄 { $path } 不存在
+sql-module-dir-empty = SQL 模組目錄 { $path } 中沒有 .sql 檔案
+sql-module-failed = SQL 模組 { $module } 載入失敗：{ $error }
+sql-modules-loaded = 已載入 { $loaded } / { $total } 個 SQL 模組
+
+# Metrics
+metrics-extension-invalid = { $path } 不是指標擴充目錄（缺少 sql/）
+metrics-builtin = 內建
+metrics-tp-failed = trace_processor 結束，狀態 { $status }
+metrics-write-failed = 無法寫入 { $path }：{ $error }
+metrics-failed = 指標計算失敗：{ $error }
+
+# Presets
+presets-invalid = { $path } 中的預設無效：{ $error }
+presets-unknown = 未知的 trace_processor 預設「{ $name }」（{ $path } 中已定義：{ $known }）
+
+# Symbolization
+symbol-dir-missing = 符號目錄 { $path } 不存在
+symbolize-no-dirs = 請至少指定一個 --symbol-dir 以進行符號化
+symbolize-no-traceconv = 在 trace_processor 旁或 PATH 中找不到 traceconv
+symbolize-no-llvm-symbolizer = 在 trace_processor 旁或 PATH 中找不到 llvm-symbolizer；原生框架將不會被符號化
+symbolize-read-failed = 無法讀取 { $path }：{ $error }
+symbolize-write-failed = 無法寫入 { $path }：{ $error }
+symbolize-running = 正在符號化 { $path }...
+symbolize-traceconv-failed = traceconv 結束，狀態 { $status }
+symbolize-nothing = 找不到此追蹤原生框架的符號
+symbolize-written = 已寫入 { $path }（新增 { $bytes } 位元組的符號）
+symbolize-failed = 符號化失敗：{ $error }
+convert-running = 正在將 { $input }（{ $from }）轉換為 { $output }（{ $to }）...
+convert-progress = 已寫入 { $size } MB
+convert-progress-percent = 已讀取 { $percent }%
+convert-done = 已寫入 { $path }（{ $size } MB）
+convert-failed = 轉換失敗：{ $error }
+clean-would-delete = 將刪除 { $path }（{ $size } MB）
+clean-deleted = 已刪除 { $path }（{ $size } MB）
+clean-done = 已刪除 { $count } 個檔案，共 { $size } MB；剩餘 { $remaining } MB 的追蹤
+clean-would-free = 將刪除 { $count } 個檔案，共 { $size } MB；剩餘 { $remaining } MB 的追蹤
+clean-failed = 清理失敗：{ $error }
+clean-launcher-running = 啟動器正在執行（pid { $pid }）；請先停止再清理
+workspace-quota-cleaned = 已刪除 { $count } 個最久未使用的追蹤（{ $size } MB），以符合 --workspace-quota-mb
+convert-unknown-format = 無法從 { $path } 判斷要寫入的格式；請指定 --to proto、json、systrace、ctrace 或 text
+convert-same-format = { $path } 已經是 { $format } 格式的追蹤
+convert-unsupported = 無法將 { $from } 轉換為 { $to }：只有 Perfetto protobuf 追蹤可以轉換為其他格式，另外 ctrace 與 systrace 可以互轉
+convert-read-failed = 無法讀取 { $path }：{ $error }
+convert-write-failed = 無法寫入 { $path }：{ $error }
+convert-no-traceconv = 在 trace_processor 旁邊或 PATH 中找不到 traceconv；加上 --auto-download 即可下載
+convert-traceconv-spawn-failed = 無法執行 { $path }：{ $error }
+convert-traceconv-failed = traceconv 結束，狀態 { $status }
+
+# Deobfuscation
+deobfuscate-map-missing = Proguard 對照檔 { $path } 不存在
+deobfuscate-no-trace = 沒有追蹤檔時 --proguard-map 無效
+
+# Symbol servers
+symbol-server-checking = 正在向 { $count } 個符號伺服器查詢缺少的 PDB...
+symbol-server-downloaded = 已下載 { $pdb }
+symbol-server-summary = 已下載 { $count } 個 PDB 至符號快取
+symbol-server-failed = 符號伺服器 { $server } 取得 { $pdb } 失敗：{ $error }
+symbol-server-skipped = 略過符號下載：{ $error }
+
+# Enrichment
+enrich-no-traceconv = 找不到 traceconv；不會套用 Proguard 對照檔
+enrich-cached = 使用快取的增強追蹤檔 { $path }
+enrich-pass = [{ $index }/{ $total }] 正在對 { $path } 執行 { $pass }...
+enrich-nothing = 沒有需要符號化或反混淆的內容；直接載入追蹤檔
+enrich-done = 增強追蹤檔已寫入 { $path }（新增 { $bytes } 位元組）
+enrich-failed = 符號化/反混淆失敗，將直接載入追蹤檔：{ $error }
+
+# Request checks
+csrf-rejected = 禁止：跨站請求
+host-rejected = 禁止：{ $host } 不是本機；請透過 localhost 或本機自己的位址開啟啟動器
+body-too-large = 請求內容超過 { $limit } 位元組的上限
+
+# Plugins
+plugins-none = { $path } 中沒有外掛
+plugins-entry = { $name }（{ $path }）
+plugin-describe-failed = 略過外掛 { $path }：{ $error }
+plugin-failed = 外掛 { $name } 失敗：{ $error }
+plugin-bad-response = 外掛 { $name } 傳回無效的回應：{ $error }
+plugin-no-output = 外掛 { $name } 沒有寫出 { $path }
+plugin-converting = 正在以外掛 { $name } 轉換 { $path }...
+
+# Trace API
+api-bad-body = 無效的要求內容：{ $error }
+api-empty-name = 追蹤名稱不可為空
+
+# Extra sessions
+session-started = 已為 { $trace } 啟動工作階段 { $id }（pid { $pid }）
+session-stopped = 已停止工作階段 { $id }（pid { $pid }）
+session-idle = 工作階段 { $id } 閒置過久，已停止
+session-exited = 工作階段 { $id } 的 trace_processor 已結束
+session-unknown = 沒有 ID 為 { $id } 的工作階段。
+session-forbidden = 工作階段 { $id } 由其他使用者啟動
+session-limit = 已有 { $max } 個工作階段在執行；請停止其中一個或提高 --max-sessions。
+session-start-failed = 無法啟動工作階段：{ $error }
+
+# Dashboards
+dashboards-title = 儀表板
+dashboards-none = 尚無儀表板。請將 JSON 或 YAML 儀表板檔案加入 { $path }。
+dashboard-not-found = 沒有名為 { $name } 的儀表板
+dashboard-invalid = 無效的儀表板 { $path }：{ $error }
+dashboard-updated = 更新於
+dashboard-load-failed = 無法重新整理：
+dashboard-no-rows = 沒有資料列
+
+# Reports
+reports-title = 報告
+reports-none = 尚無報告。請在 { $path } 中設定。
+reports-invalid = { $path } 中的報告設定無效：{ $error }
+reports-unknown = { $path } 中沒有名為 { $name } 的報告
+reports-ran = 報告 { $report }：已寫出 { $count } 份
+reports-some-failed = 部分報告無法產生
+reports-scheduled = 已排程 { $count } 份報告
+report-written = { $name } 的報告 { $report }：{ $path }
+report-failed = { $name } 的報告 { $report } 失敗：{ $error }
+report-no-output = 報告命令沒有寫出 { $path }
+report-summary-open = 在 UI 中開啟
+report-summary-file = 檔案
+report-summary-size = 大小（位元組）
+report-summary-duration = 時長（奈秒）
+report-summary-capture-time = 擷取時間（自紀元起的奈秒）
+report-summary-data-sources = 資料來源
+report-summary-data-loss = 資料遺失
+report-summary-no-data-loss = 沒有記錄到資料遺失
+report-summary-top-slices = 最常出現的切片
+
+# Timeline
+timeline-start = 追蹤開始：{ $wall }（ts { $ts }）
+timeline-end = 追蹤結束：{ $wall }（ts { $ts }）
+timeline-drift = 追蹤期間牆鐘時間最多被調整了 { $ms } 毫秒
+timeline-offset = 距追蹤開始 { $offset } 秒
+timeline-outside = 距追蹤開始 { $offset } 秒，超出追蹤範圍
+timeline-wall-to-trace = { $wall } -> ts { $ts }（{ $position }）
+timeline-trace-to-wall = ts { $ts } -> { $wall }（{ $position }）
+timeline-no-snapshots = { $path } 沒有 REALTIME 時鐘快照，無法對應牆鐘時間
+timeline-invalid-time = 無效的時間「{ $time }」；請使用 YYYY-MM-DD HH:MM[:SS[.小數]]，可加上 Z 或 ±HH:MM 時差
+timeline-failed = 時間對應失敗：{ $error }
+
+# Users
+users-invalid = 使用者設定無效 { $path }：{ $error }
+users-none = { $path } 未列出任何使用者，也未設定 trust_header
+users-enabled = 多使用者模式：已設定 { $count } 位使用者
+users-unauthorized = 請以權杖登入：以 ?token=<token> 開啟此頁面，或傳送 Authorization: Bearer <token>
+users-forbidden = 追蹤 { $id } 屬於其他使用者
+users-quota-exceeded = 已超過上傳配額：剩餘 { $remaining } MB
+
+# Logs
+logs-config-invalid = 記錄設定無效 { $path }：{ $error }
+logs-open-failed = 無法開啟記錄 { $path }：{ $error }
+logs-compress-failed = 無法壓縮輪替的記錄 { $path }：{ $error }
+
+# UI update
+ui-update-versions = 已安裝的 UI：{ $current }，最新版：{ $latest }
+ui-update-up-to-date = UI { $version } 已是最新版
+ui-update-downloaded = 已下載 { $path }
+ui-update-transferred = { $reused } 個檔案未變更，已下載 { $downloaded } 個（{ $mb } MB）
+ui-update-done = UI 已更新至 { $version }
+ui-update-failed = UI 更新失敗：{ $error }
+ui-update-bad-index = 無法讀取 index.html：{ $error }
+ui-update-no-version = index.html 未列出 { $channel } 版本
+ui-update-bad-manifest = manifest.json 無效：{ $error }
+ui-update-hash-mismatch = { $path } 與 manifest.json 不符（預期 { $expected }，實際 { $actual }）
+ui-update-available = 有可用的更新：{ $version }。不加 --check 執行 update-ui 即可安裝。
+ui-update-bad-version = 不是 UI 版本：{ $version }（例如 v46.0-8a7e2f1c）
+ui-update-no-previous = 沒有可以退回的舊版 UI；只有在更新改變了版本後才會保留舊版。
+ui-update-rolled-back = UI 已從 { $from } 退回 { $to }
+
+# Bundle
+bundle-unknown-platform = 此平台沒有對應的套件名稱，請指定 --platform
+bundle-need-launcher = 請以 --launcher 指定為 { $platform } 建置的啟動器執行檔
+bundle-need-trace-processor = 請以 --trace-processor 指定為 { $platform } 建置的 trace_processor_shell
+bundle-missing-file = 找不到檔案：{ $path }
+bundle-no-ui = { $path } 中沒有 UI 版本目錄；請以 --ui-version 下載
+bundle-write-failed = 無法寫入 { $path }：{ $error }
+bundle-created = 已建立 { $path }（{ $platform }，UI { $version }，{ $mb } MB）
+bundle-failed = 打包失敗：{ $error }
+
+# URL handler
+url-handler-expected = 應為 perfetto://open?trace=<ID、路徑或 URL>
+url-handler-bad-link = 無效的連結 { $link }：{ $error }
+url-handler-forwarded = 在執行中的啟動器開啟：{ $url }
+instance-forwarded = 已有啟動器在執行（pid { $pid }），改在其中開啟 { $url }。若要另外啟動一個，請加上 --new-instance。
+url-handler-starting = 正在啟動啟動器以開啟 { $path }
+url-handler-spawn-failed = 無法啟動啟動器：{ $error }
+url-handler-failed = 無法開啟連結：{ $error }
+url-handler-register-failed = 無法註冊 perfetto:// 處理常式：{ $error }
+url-handler-installed = perfetto:// 連結現在會以 { $exe } 開啟
+url-handler-uninstalled = 啟動器不再處理 perfetto:// 連結
+
+# trace_processor download
+tp-download-unsupported = 沒有為 { $platform } 發布預先建置的 { $tool }
+tp-download-no-prebuilt = { $url } 未列出 { $platform } 的 { $tool }
+tp-download-fetching = 正在從 { $url } 下載 { $tool } { $version }...
+tp-download-done = { $tool } 已儲存至 { $path }
+tp-download-hash-mismatch = { $url } 的檢查碼不符：應為 { $expected }，實際為 { $actual }
+tp-download-write-failed = 無法儲存 { $path }：{ $error }
+# RPC 轉送
+rpc-proxy-origin-rejected = 禁止：{ $origin } 不可使用 trace_processor 的 RPC
+rpc-proxy-unreachable = 無法連線到連接埠 { $port } 上的 trace_processor：{ $error }
+rpc-proxy-upgrade-refused = trace_processor 拒絕了 WebSocket 連線（{ $status }）
+rpc-proxy-shared-forbidden = 共用的 trace_processor 僅供管理員與其追蹤的擁有者使用；請以 /rpc/<工作階段 ID>/ 使用自己的工作階段
+# 系統匣
+tray-open-ui = 開啟 UI
+tray-restart-tp = 重新啟動 trace_processor
+tray-copy-url = 複製網址
+tray-quit = 結束
+tray-tooltip = Perfetto 啟動器：{ $url }
+tray-failed = 警告：無法顯示系統匣圖示：{ $error }
+tray-copy-failed = 警告：無法複製網址：{ $error }
+tray-unsupported = 警告：macOS 不支援系統匣圖示
+# 背景執行
+daemon-started = 已在背景執行，pid { $pid }；日誌：{ $log }
+daemon-failed = 錯誤：無法在背景啟動：{ $error }
+daemon-exited = 錯誤：背景啟動器立即結束（{ $status }），原因請見其日誌
+service-installed = 已安裝服務 { $name }，將於開機時啟動；日誌：{ $log }。可用 `sc start { $name }` 立即啟動。
+service-uninstalled = 已移除服務 { $name }。
+service-failed = 錯誤：服務控制失敗：{ $error }
\ No newline at end of file
@@ -0,0 +1,130 @@
+//! Access control for browsers on other machines, set with `--auth`.
+//!
+//! With `--expose-lan`, or a `--host` other than a loopback address, anyone
+//! on the LAN can reach the UI server. With `--auth token` the launcher
+//! makes up a token for each run and adds it to the URLs it prints and opens
+//! as `?token=<token>`; the first request with it stores the token in a
+//! cookie, and later ones need the cookie or
+//! `Authorization: Bearer <token>`. With `--auth basic` browsers ask for a
+//! user name and password instead: `perfetto` and the token.
+//!
+//! Requests from this machine are not checked: the UI reaches its RPC on
+//! `127.0.0.1`, where the cookie is not sent, and local users can read the
+//! traces anyway. A reverse proxy on the same machine therefore needs
+//! authentication of its own. Multi-user mode has its own tokens and
+//! replaces `--auth`.
+
+use crate::server::query_param;
+use crate::stats;
+use crate::users::{self, constant_time_eq};
+use base64::Engine;
+use tiny_http::{Header, Request, Response};
+
+const COOKIE: &str = "perfetto_launcher_access";
+
+/// User name for `--auth basic`
+pub const BASIC_USER: &str = "perfetto";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum AuthMode {
+    /// Anyone who can reach the UI port may use it
+    None,
+    /// A token in the launch URL, kept in a cookie afterwards
+    Token,
+    /// HTTP Basic authentication with the token as password
+    Basic,
+}
+
+/// What to do with a request
+pub enum Check {
+    Allowed,
+    /// A valid `?token=`: store it in a cookie and reload without it
+    Login,
+    Denied,
+}
+
+/// 32 random hex digits
+pub fn random_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| t!("auth-token-failed", error = e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+pub struct Access {
+    pub mode: AuthMode,
+    pub token: String,
+}
+
+impl Access {
+    /// Access control with a new random token; None for `--auth none`
+    pub fn new(mode: AuthMode) -> Result<Option<Access>, String> {
+        if mode == AuthMode::None {
+            return Ok(None);
+        }
+        Ok(Some(Access { mode, token: random_token()? }))
+    }
+
+    /// `url` with the token for `--auth token`, for links that sign in
+    pub fn link(&self, url: &str) -> String {
+        match self.mode {
+            AuthMode::Token => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                format!("{}{}token={}", url, separator, self.token)
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    pub fn check(&self, request: &Request) -> Check {
+        if request.remote_addr().is_some_and(|addr| addr.ip().is_loopback()) {
+            return Check::Allowed;
+        }
+        let header = |name: &str| {
+            let header = request.headers().iter().find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name));
+            header.map(|h| h.value.as_str().to_string())
+        };
+        let valid = |token: &str| constant_time_eq(token, &self.token);
+        match self.mode {
+            AuthMode::None => Check::Allowed,
+            AuthMode::Token => {
+                if query_param(request.url(), "token").is_some_and(|token| valid(&token)) {
+                    return Check::Login;
+                }
+                let bearer =
+                    header("Authorization").and_then(|h| h.strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+                let cookie = header("Cookie").and_then(|cookies| {
+                    cookies.split(';').find_map(|c| c.trim().strip_prefix(&format!("{}=", COOKIE)).map(String::from))
+                });
+                match bearer.or(cookie) {
+                    Some(token) if valid(&token) => Check::Allowed,
+                    _ => Check::Denied,
+                }
+            }
+            AuthMode::Basic => {
+                let credentials = header("Authorization")
+                    .and_then(|h| h.strip_prefix("Basic ").map(|c| c.trim().to_string()))
+                    .and_then(|c| base64::engine::general_purpose::STANDARD.decode(c).ok())
+                    .and_then(|c| String::from_utf8(c).ok());
+                match credentials.as_deref().and_then(|c| c.split_once(':')) {
+                    Some((user, password)) if user == BASIC_USER && valid(password) => Check::Allowed,
+                    _ => Check::Denied,
+                }
+            }
+        }
+    }
+
+    pub fn respond_login(&self, request: Request) {
+        users::respond_with_cookie(request, COOKIE, &self.token);
+    }
+
+    pub fn respond_denied(&self, request: Request) {
+        let challenge = match self.mode {
+            AuthMode::Basic => "Basic realm=\"Perfetto Launcher\", charset=\"UTF-8\"",
+            _ => "Bearer",
+        };
+        let response = Response::from_string(t!("auth-denied"))
+            .with_status_code(401)
+            .with_header(Header::from_bytes("WWW-Authenticate", challenge).unwrap());
+        stats::respond(request, response);
+    }
+}
@@ -0,0 +1,376 @@
+//! Android devices over adb: pulling the traces recorded on them and opening
+//! them.
+//!
+//! `android devices` lists the connected devices, `android traces` the traces
+//! in a device's `/data/misc/perfetto-traces`, and `android pull` copies the
+//! newest one (or those named) into the data directory, adds it to the
+//! catalog and opens it. `/launcher/android` does the same from the browser:
+//! it lists every device with its traces, and `POST /launcher/android/pull`
+//! with `{"device": "<serial>", "name": "<file>"}` pulls one.
+//!
+//! adb is looked for on PATH, then in the Android SDK named by `ANDROID_HOME`
+//! or `ANDROID_SDK_ROOT`, then where Android Studio installs the SDK.
+
+use crate::catalog::{Catalog, Trace};
+use crate::landing::escape;
+use crate::paths::{find_in_path, home_dir};
+use crate::recent::format_time;
+use crate::server::{origin, query_param, respond_too_large, AppState, LimitedReader};
+use crate::stats;
+use crate::traces_api::{respond_error, respond_json};
+use crate::url_handler;
+use crate::users::Viewer;
+use crate::workspace::{self, Area};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use tiny_http::{Header, Request, Response};
+
+/// Where `perfetto` on a device writes traces that adb may read
+pub const TRACES_DIR: &str = "/data/misc/perfetto-traces";
+
+const TEMPLATE: &str = include_str!("../templates/android.html");
+
+/// adb on PATH or in the Android SDK
+pub fn find_adb() -> Option<PathBuf> {
+    let name = if cfg!(windows) { "adb.exe" } else { "adb" };
+    let default_sdk = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("Android").join("Sdk"))
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library").join("Android").join("sdk"))
+    } else {
+        home_dir().map(|home| home.join("Android").join("Sdk"))
+    };
+    let sdks = ["ANDROID_HOME", "ANDROID_SDK_ROOT"].into_iter().filter_map(env::var_os).map(PathBuf::from);
+    find_in_path("adb").or_else(|| {
+        sdks.chain(default_sdk).map(|sdk| sdk.join("platform-tools").join(name)).find(|adb| adb.is_file())
+    })
+}
+
+pub fn adb() -> Result<PathBuf, String> {
+    find_adb().ok_or_else(|| t!("capture-no-adb"))
+}
+
+/// A device as `adb devices -l` lists it
+#[derive(Serialize)]
+pub struct Device {
+    pub serial: String,
+    /// `device` when usable, otherwise e.g. `unauthorized` or `offline`
+    pub state: String,
+    pub model: Option<String>,
+}
+
+impl Device {
+    pub fn ready(&self) -> bool {
+        self.state == "device"
+    }
+}
+
+/// A trace file on a device
+#[derive(Serialize)]
+pub struct RemoteTrace {
+    pub name: String,
+    pub size: u64,
+    /// Unix time it was last written
+    pub modified: u64,
+}
+
+fn run(adb: &Path, args: &[&str]) -> Result<Output, String> {
+    Command::new(adb).args(args).stdin(Stdio::null()).output().map_err(|e| t!("android-adb-failed", error = e))
+}
+
+pub fn devices(adb: &Path) -> Result<Vec<Device>, String> {
+    let output = run(adb, &["devices", "-l"])?;
+    if !output.status.success() {
+        return Err(t!("android-adb-failed", error = String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    // "List of devices attached", then "<serial> <state> usb:1-1 product:x model:Pixel_7 ..."
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let serial = fields.next()?.to_string();
+            let state = fields.next()?.to_string();
+            let model = fields.find_map(|f| f.strip_prefix("model:")).map(|m| m.replace('_', " "));
+            Some(Device { serial, state, model })
+        })
+        .collect())
+}
+
+/// The device to use: `requested`, or the only one connected
+pub fn select_device(adb: &Path, requested: Option<&str>) -> Result<String, String> {
+    let devices = devices(adb)?;
+    let ready: Vec<&Device> = devices.iter().filter(|d| d.ready()).collect();
+    match requested {
+        Some(serial) => match devices.iter().find(|d| d.serial == serial) {
+            Some(device) if device.ready() => Ok(serial.to_string()),
+            Some(device) => Err(t!("android-device-not-ready", serial = serial, state = device.state.as_str())),
+            None => Err(t!("android-device-unknown", serial = serial)),
+        },
+        None if ready.len() == 1 => Ok(ready[0].serial.clone()),
+        None if ready.is_empty() => Err(t!("android-no-devices")),
+        None => {
+            let serials: Vec<&str> = ready.iter().map(|d| d.serial.as_str()).collect();
+            Err(t!("android-several-devices", serials = serials.join(", ")))
+        }
+    }
+}
+
+/// Traces on device `serial`, newest first
+pub fn traces(adb: &Path, serial: &str) -> Result<Vec<RemoteTrace>, String> {
+    let pattern = format!("{}/*", TRACES_DIR);
+    let output = run(adb, &["-s", serial, "shell", "stat", "-c", "'%s %Y %n'", &pattern])?;
+    // stat fails on the unexpanded pattern when the directory is empty
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && !stderr.contains(&pattern) {
+        return Err(t!("android-list-failed", serial = serial, error = stderr.trim()));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut traces: Vec<RemoteTrace> = text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, ' ');
+            let size = fields.next()?.parse().ok()?;
+            let modified = fields.next()?.parse().ok()?;
+            let name = fields.next()?.rsplit('/').next()?.to_string();
+            Some(RemoteTrace { name, size, modified })
+        })
+        .collect();
+    traces.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name)));
+    Ok(traces)
+}
+
+/// Copy trace `name` from device `serial` into the data directory
+pub fn pull(adb: &Path, serial: &str, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+        return Err(t!("android-bad-name", name = name));
+    }
+    let dir = workspace::dir(Area::Android).join(serial.replace([':', '/', '\\'], "_"));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let local = dir.join(name);
+    let remote = format!("{}/{}", TRACES_DIR, name);
+    say!("{}", t!("android-pulling", serial = serial, path = remote.as_str()));
+    let output = run(adb, &["-s", serial, "pull", &remote, &local.to_string_lossy()])?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(t!("android-pull-failed", path = remote.as_str(), error = error.trim()));
+    }
+    Ok(local)
+}
+
+fn add(catalog: &mut Catalog, path: &Path) -> Result<Trace, String> {
+    catalog.add(path).map_err(|e| t!("catalog-add-failed", path = path.display(), error = e))
+}
+
+/// `android devices`
+pub fn print_devices() -> Result<(), String> {
+    let devices = devices(&adb()?)?;
+    if devices.is_empty() {
+        say!("{}", t!("android-no-devices"));
+    }
+    for device in devices {
+        println!("{}\t{}\t{}", device.serial, device.state, device.model.as_deref().unwrap_or(""));
+    }
+    Ok(())
+}
+
+/// `android traces`
+pub fn print_traces(device: Option<&str>) -> Result<(), String> {
+    let adb = adb()?;
+    let serial = select_device(&adb, device)?;
+    let traces = traces(&adb, &serial)?;
+    if traces.is_empty() {
+        say!("{}", t!("android-no-traces", path = TRACES_DIR));
+    }
+    for trace in traces {
+        println!("{}\t{:.1} MB\t{}", trace.name, trace.size as f64 / (1024.0 * 1024.0), format_time(trace.modified));
+    }
+    Ok(())
+}
+
+/// `android pull`: pull `names`, every trace with `all`, or else the newest,
+/// and open the first in the launcher
+pub fn run_pull(
+    device: Option<&str>,
+    names: &[String],
+    all: bool,
+    open: bool,
+    browser: Option<&str>,
+) -> Result<(), String> {
+    let adb = adb()?;
+    let serial = select_device(&adb, device)?;
+    let names = if names.is_empty() {
+        let traces = traces(&adb, &serial)?;
+        if traces.is_empty() {
+            return Err(t!("android-no-traces", path = TRACES_DIR));
+        }
+        let take = if all { traces.len() } else { 1 };
+        traces.into_iter().take(take).map(|trace| trace.name).collect()
+    } else {
+        names.to_vec()
+    };
+    let mut catalog = Catalog::open();
+    let mut first = None;
+    for name in &names {
+        let path = pull(&adb, &serial, name)?;
+        let trace = add(&mut catalog, &path)?;
+        say!("{}", t!("capture-saved", path = path.display(), id = trace.id.as_str()));
+        first.get_or_insert(trace);
+    }
+    match first {
+        Some(trace) if open => url_handler::open_in_launcher(&format!("/t/{}", trace.id), browser),
+        _ => Ok(()),
+    }
+}
+
+/// Body of `POST /launcher/android/pull`
+#[derive(Deserialize)]
+struct PullRequest {
+    device: String,
+    name: String,
+}
+
+/// `GET /launcher/android[?format=json]`, with every device and its traces
+pub fn respond_page(request: Request) {
+    let wants_json = query_param(request.url(), "format").is_some_and(|f| f == "json")
+        || request
+            .headers()
+            .iter()
+            .any(|h| h.field.equiv("Accept") && h.value.as_str().trim_start().starts_with("application/json"));
+    let adb = find_adb();
+    let devices = match &adb {
+        Some(adb) => devices(adb),
+        None => Err(t!("capture-no-adb")),
+    };
+    let listed: Vec<(Device, Result<Vec<RemoteTrace>, String>)> = match devices {
+        Ok(devices) => devices
+            .into_iter()
+            .map(|device| {
+                let traces = match &adb {
+                    Some(adb) if device.ready() => traces(adb, &device.serial),
+                    _ => Ok(Vec::new()),
+                };
+                (device, traces)
+            })
+            .collect(),
+        Err(e) if wants_json => return respond_error(request, 503, e),
+        Err(e) => return respond_html(request, format!("<p class=\"error\">{}</p>", escape(&e))),
+    };
+
+    if wants_json {
+        let devices: Vec<_> = listed
+            .iter()
+            .map(|(device, traces)| {
+                let (traces, error) = match traces {
+                    Ok(traces) => (json!(traces), None),
+                    Err(e) => (json!([]), Some(e.as_str())),
+                };
+                json!({ "serial": device.serial, "state": device.state, "model": device.model,
+                        "traces": traces, "error": error })
+            })
+            .collect();
+        return respond_json(request, 200, json!({ "devices": devices }));
+    }
+
+    if listed.is_empty() {
+        return respond_html(request, format!("<p class=\"empty\">{}</p>", escape(&t!("android-no-devices"))));
+    }
+    let sections: String = listed
+        .iter()
+        .map(|(device, traces)| {
+            let title = match &device.model {
+                Some(model) => format!("{} <span class=\"serial\">{}</span>", escape(model), escape(&device.serial)),
+                None => escape(&device.serial),
+            };
+            let body = match traces {
+                _ if !device.ready() => {
+                    let (serial, state) = (device.serial.as_str(), device.state.as_str());
+                    let note = t!("android-device-not-ready", serial = serial, state = state);
+                    format!("<p class=\"empty\">{}</p>", escape(&note))
+                }
+                Err(e) => format!("<p class=\"error\">{}</p>", escape(e)),
+                Ok(traces) if traces.is_empty() => {
+                    format!("<p class=\"empty\">{}</p>", escape(&t!("android-no-traces", path = TRACES_DIR)))
+                }
+                Ok(traces) => {
+                    let rows: String = traces
+                        .iter()
+                        .map(|trace| {
+                            format!(
+                                "<tr><td>{}</td><td class=\"num\">{:.1} MB</td><td>{}</td>\
+                                 <td><button data-device=\"{}\" data-name=\"{}\">{}</button></td></tr>",
+                                escape(&trace.name),
+                                trace.size as f64 / (1024.0 * 1024.0),
+                                format_time(trace.modified),
+                                escape(&device.serial),
+                                escape(&trace.name),
+                                escape(&t!("android-open")),
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "<table><tr><th>{}</th><th class=\"num\">{}</th><th>{}</th><th></th></tr>{}</table>",
+                        escape(&t!("recent-name")),
+                        escape(&t!("recent-size")),
+                        escape(&t!("android-modified")),
+                        rows
+                    )
+                }
+            };
+            format!("<h2>{}</h2>{}", title, body)
+        })
+        .collect();
+    respond_html(request, sections)
+}
+
+fn respond_html(request: Request, devices: String) {
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&t!("android-title"))),
+        ("devices", devices),
+        ("pulling", escape(&t!("android-pulling-button"))),
+        ("back", escape(&t!("recent-back"))),
+    ];
+    let mut page = TEMPLATE.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
+
+/// `POST /launcher/android/pull`
+///
+/// In multi-user mode only admins may pull, as it runs adb on the
+/// launcher's machine.
+pub fn respond_pull(mut request: Request, state: &AppState, viewer: &Viewer) {
+    if matches!(viewer, Viewer::User { admin: false, .. }) {
+        return respond_error(request, 403, t!("android-admins-only"));
+    }
+    let limit = state.body_limits.api;
+    let mut body = Vec::new();
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        return respond_too_large(request, limit);
+    }
+    let pull_request: PullRequest = match serde_json::from_slice(&body) {
+        Ok(pull_request) => pull_request,
+        Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+    };
+    let result = adb()
+        .and_then(|adb| select_device(&adb, Some(&pull_request.device)).map(|serial| (adb, serial)))
+        .and_then(|(adb, serial)| pull(&adb, &serial, &pull_request.name))
+        .and_then(|path| add(&mut state.catalog.lock().unwrap(), &path));
+    match result {
+        Ok(trace) => {
+            let link = format!("{}/t/{}", origin(&request), trace.id);
+            respond_json(request, 201, json!({ "id": trace.id, "name": trace.name, "link": link }));
+        }
+        Err(e) => respond_error(request, 502, e),
+    }
+}
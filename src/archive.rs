@@ -0,0 +1,128 @@
+//! `catalog export` / archive `catalog import`: the catalog, its search
+//! indexes and metadata, and optionally the trace files, as a `.tar.zst`.
+//!
+//! Layout inside the archive:
+//!
+//! ```text
+//! catalog.json
+//! index/<id>.json
+//! metadata/<id>.json
+//! traces/<id>/<file name>     (only with --include-traces)
+//! ```
+
+use crate::catalog::{Catalog, Trace};
+use crate::metadata;
+use crate::search;
+use crate::workspace::{self, Area};
+use log::warn;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn export(output: &Path, include_traces: bool) -> Result<(), String> {
+    let catalog = Catalog::open();
+    let file = File::create(output).map_err(|e| t!("archive-write-failed", path = output.display(), error = e))?;
+    let encoder = zstd::Encoder::new(file, 0).map_err(|e| e.to_string())?;
+    let mut tar = tar::Builder::new(encoder);
+    let write = |e: io::Error| t!("archive-write-failed", path = output.display(), error = e);
+
+    let body = serde_json::to_vec_pretty(&json!({ "traces": catalog.traces() })).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(body.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()));
+    header.set_cksum();
+    tar.append_data(&mut header, "catalog.json", body.as_slice()).map_err(write)?;
+
+    let mut missing = 0;
+    for trace in catalog.traces() {
+        for (dir, path) in [("index", search::index_path(&trace.id)), ("metadata", metadata::metadata_path(&trace.id))] {
+            if path.is_file() {
+                tar.append_path_with_name(&path, format!("{}/{}.json", dir, trace.id)).map_err(write)?;
+            }
+        }
+        if include_traces {
+            if trace.path.is_file() {
+                let name = format!("traces/{}/{}", trace.id, trace.name);
+                tar.append_path_with_name(&trace.path, name).map_err(write)?;
+            } else {
+                missing += 1;
+            }
+        }
+    }
+    tar.into_inner().and_then(|encoder| encoder.finish()).map_err(write)?;
+
+    say!("{}", t!("archive-exported", count = catalog.traces().len(), path = output.display()));
+    if missing > 0 {
+        warn!("{}", t!("archive-traces-missing", count = missing));
+    }
+    Ok(())
+}
+
+/// Restore an export into the local catalog; traces already present (same id
+/// or path) are left untouched
+pub fn import(archive: &Path) -> Result<(), String> {
+    let read = |e: io::Error| t!("archive-read-failed", path = archive.display(), error = e);
+    let file = File::open(archive).map_err(read)?;
+    let decoder = zstd::Decoder::new(file).map_err(read)?;
+    let mut tar = tar::Archive::new(decoder);
+
+    let imported_dir = workspace::dir(Area::Imported);
+    let mut traces: Vec<Trace> = Vec::new();
+    let mut extracted: HashMap<String, PathBuf> = HashMap::new();
+    for entry in tar.entries().map_err(read)? {
+        let mut entry = entry.map_err(read)?;
+        let path = entry.path().map_err(read)?.into_owned();
+        let parts: Vec<&str> = path
+            .components()
+            .map(|c| match c {
+                Component::Normal(part) => part.to_str().unwrap_or(""),
+                _ => "",
+            })
+            .collect();
+        // Only ids and file names from the catalog are accepted as path parts
+        if parts.iter().any(|p| p.is_empty() || p.starts_with('.')) {
+            continue;
+        }
+        let destination = match parts.as_slice() {
+            ["catalog.json"] => {
+                let catalog: serde_json::Value = serde_json::from_reader(&mut entry).map_err(|e| e.to_string())?;
+                traces = serde_json::from_value(catalog["traces"].clone()).map_err(|e| e.to_string())?;
+                continue;
+            }
+            ["index", file] => file.strip_suffix(".json").map(search::index_path),
+            ["metadata", file] => file.strip_suffix(".json").map(metadata::metadata_path),
+            ["traces", id, name] => {
+                let destination = imported_dir.join(id).join(name);
+                extracted.insert(id.to_string(), destination.clone());
+                Some(destination)
+            }
+            _ => None,
+        };
+        let Some(destination) = destination else { continue };
+        if let Some(dir) = destination.parent() {
+            fs::create_dir_all(dir).map_err(read)?;
+        }
+        entry.unpack(&destination).map_err(read)?;
+    }
+
+    for trace in &mut traces {
+        if let Some(path) = extracted.remove(&trace.id) {
+            trace.path = path;
+        }
+    }
+    let missing = traces.iter().filter(|t| !t.path.is_file()).count();
+    let total = traces.len();
+    let mut catalog = Catalog::open();
+    let added = catalog
+        .merge(traces)
+        .map_err(|e| t!("catalog-save-failed", path = catalog.path().display(), error = e))?;
+    say!("{}", t!("archive-imported", added = added, skipped = total - added));
+    if missing > 0 {
+        warn!("{}", t!("archive-traces-missing", count = missing));
+    }
+    Ok(())
+}
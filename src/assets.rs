@@ -0,0 +1,65 @@
+//! Perfetto UI files compiled into the binary, for a single-file launcher.
+//!
+//! Built with `--features embedded-ui` and `PERFETTO_UI_DIR` pointing at a
+//! dist directory, the launcher serves the UI from these files and uses the
+//! dist directory on disk only for anything they lack. Without the feature
+//! there are no embedded files and everything comes from disk.
+
+use crate::dist::{is_version_string, version_key};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "embedded-ui")]
+static UI: include_dir::Dir<'static> = include_dir::include_dir!("$PERFETTO_UI_DIR");
+
+/// Contents of `path` (relative, `/`-separated) in the embedded UI
+#[cfg(feature = "embedded-ui")]
+pub fn get(path: &str) -> Option<&'static [u8]> {
+    UI.get_file(path).map(|file| file.contents())
+}
+
+#[cfg(not(feature = "embedded-ui"))]
+pub fn get(_path: &str) -> Option<&'static [u8]> {
+    None
+}
+
+/// Names of the top-level directories of the embedded UI
+#[cfg(feature = "embedded-ui")]
+fn top_level_dirs() -> Vec<&'static str> {
+    UI.dirs().filter_map(|dir| dir.path().to_str()).collect()
+}
+
+#[cfg(not(feature = "embedded-ui"))]
+fn top_level_dirs() -> Vec<&'static str> {
+    Vec::new()
+}
+
+/// Entity tag of an embedded file, from a hash of its contents worked out on
+/// first use
+pub fn etag(path: &str) -> Option<String> {
+    static TAGS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    let contents = get(path)?;
+    let mut tags = TAGS.get_or_init(Default::default).lock().unwrap();
+    let tag = tags.entry(path.to_string()).or_insert_with(|| {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        format!("\"{:x}-{:x}\"", contents.len(), hasher.finish())
+    });
+    Some(tag.clone())
+}
+
+/// Whether a complete UI is embedded
+pub fn has_ui() -> bool {
+    get("index.html").is_some()
+}
+
+/// Version of the embedded UI, e.g. "v46.0-8a7e2f1c"
+pub fn ui_version() -> Option<String> {
+    if !has_ui() {
+        return None;
+    }
+    let mut versions: Vec<&str> = top_level_dirs().into_iter().filter(|name| is_version_string(name)).collect();
+    versions.sort_by_key(|v| version_key(v));
+    versions.pop().map(String::from)
+}
@@ -0,0 +1,450 @@
+//! The trace_processor_shell child process serving the RPC backend.
+
+use crate::logs;
+use crate::rpc::RpcClient;
+use crate::shutdown;
+use crate::tp_logs::ChildOutput;
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Number of stderr lines kept for crash reports
+const STDERR_TAIL_LINES: usize = 50;
+
+/// First delay before restarting a crashed trace_processor; doubled for
+/// every further crash in a row
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// A restarted trace_processor that stays up this long starts the backoff over
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+pub struct Backend {
+    path: PathBuf,
+    rpc_port: u16,
+    /// How long a start may take to answer; `--startup-timeout`
+    startup_timeout: Duration,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    quiet: bool,
+    child: Mutex<Child>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    /// stdout and stderr of every child so far, for `/launcher/tp-logs`
+    output: Arc<ChildOutput>,
+    stopping: AtomicBool,
+    last_crash: Mutex<Option<Crash>>,
+    health: Mutex<Health>,
+    /// Restarts after crashes so far
+    restarts: AtomicU32,
+    /// When the current child was started
+    started: Mutex<Instant>,
+    starts: Mutex<Starts>,
+    starts_changed: Condvar,
+}
+
+/// Children started so far, numbered from 1, and how far the latest got
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Starts {
+    pub started: u64,
+    /// Last start whose wait for an answer finished, successfully or not
+    pub settled: u64,
+    /// Last start that answered
+    pub ready: u64,
+}
+
+/// Outcome of waiting for trace_processor to come up
+pub enum Startup {
+    Ready,
+    Exited(ExitStatus),
+    /// Shutdown was requested while waiting
+    Cancelled,
+    /// Still not answering when the startup timeout ran out
+    TimedOut,
+}
+
+/// What the supervisor last saw of trace_processor
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    Running,
+    /// Crashed; waiting to be started again
+    Restarting,
+    /// Crashed more often than `--max-restarts` allows; no longer restarted
+    Failed,
+}
+
+/// An unexpected exit of trace_processor
+#[derive(Clone)]
+pub struct Crash {
+    pub status: String,
+    pub exit_code: Option<i32>,
+    /// Plain-language explanation of the likely cause
+    pub explanation: String,
+    pub stderr_tail: Vec<String>,
+}
+
+impl Backend {
+    pub fn spawn(
+        path: PathBuf,
+        args: Vec<String>,
+        env: Vec<(String, String)>,
+        quiet: bool,
+        rpc_port: u16,
+        startup_timeout: Duration,
+    ) -> io::Result<Backend> {
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let output = Arc::new(ChildOutput::default());
+        let child = spawn_child(&path, &args, &env, quiet, &stderr_tail, &output)?;
+        Ok(Backend {
+            path,
+            rpc_port,
+            startup_timeout,
+            args,
+            env,
+            quiet,
+            child: Mutex::new(child),
+            stderr_tail,
+            output,
+            stopping: AtomicBool::new(false),
+            last_crash: Mutex::new(None),
+            health: Mutex::new(Health::Running),
+            restarts: AtomicU32::new(0),
+            started: Mutex::new(Instant::now()),
+            starts: Mutex::new(Starts { started: 1, settled: 0, ready: 0 }),
+            starts_changed: Condvar::new(),
+        })
+    }
+
+    /// Block until trace_processor answers `/status`, exits, shutdown is
+    /// requested or the startup timeout runs out, following restarts meanwhile
+    pub fn wait_until_ready(&self) -> Startup {
+        loop {
+            match self.wait_for_start(self.starts().started) {
+                Startup::Cancelled if !shutdown::requested() => continue,
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Block until start `start` answers `/status` on the RPC port, exits,
+    /// is replaced, shutdown is requested or the startup timeout runs out
+    ///
+    /// Polls start every 20 ms and back off to every 500 ms, so a fast start
+    /// is noticed at once without hammering a slow one.
+    fn wait_for_start(&self, start: u64) -> Startup {
+        let client = RpcClient::new(self.rpc_port);
+        let began = Instant::now();
+        let mut delay = Duration::from_millis(20);
+        let outcome = loop {
+            if let Some(status) = self.try_wait() {
+                break Startup::Exited(status);
+            }
+            if shutdown::requested() || self.starts().started != start {
+                break Startup::Cancelled;
+            }
+            if client.status().is_ok() {
+                break Startup::Ready;
+            }
+            if began.elapsed() >= self.startup_timeout {
+                break Startup::TimedOut;
+            }
+            thread::sleep(delay);
+            delay = (delay * 2).min(Duration::from_millis(500));
+        };
+        let mut starts = self.starts.lock().unwrap();
+        if starts.started == start {
+            starts.settled = start;
+            if matches!(outcome, Startup::Ready) {
+                starts.ready = start;
+            }
+        }
+        self.starts_changed.notify_all();
+        outcome
+    }
+
+    /// Wait up to `timeout` for the first start to finish; true if it has
+    pub fn wait_settled(&self, timeout: Duration) -> bool {
+        self.wait_starts(timeout, |starts| starts.settled == 0).settled > 0
+    }
+
+    pub fn starts(&self) -> Starts {
+        *self.starts.lock().unwrap()
+    }
+
+    /// Wait up to `timeout` for `pending` to turn false; the starts then
+    pub fn wait_starts(&self, timeout: Duration, pending: impl Fn(&Starts) -> bool) -> Starts {
+        let starts = self.starts.lock().unwrap();
+        let (starts, _) = self.starts_changed.wait_timeout_while(starts, timeout, |starts| pending(starts)).unwrap();
+        *starts
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+
+    /// Exit status if the child has exited, without blocking
+    pub fn try_wait(&self) -> Option<ExitStatus> {
+        self.child.lock().unwrap().try_wait().ok().flatten()
+    }
+
+    /// Kill the current child and start a fresh one with the same command line
+    pub fn restart(self: &Arc<Self>) -> io::Result<u64> {
+        self.restart_after(self.starts().started)
+    }
+
+    /// `restart`, unless another restart got in since start `seen`, so
+    /// requests that arrive together restart trace_processor once; the
+    /// start running afterwards
+    ///
+    /// A thread waits for the new child to answer, which `wait_starts`
+    /// callers such as the UI's event stream then learn of.
+    pub fn restart_after(self: &Arc<Self>, seen: u64) -> io::Result<u64> {
+        let mut child = self.child.lock().unwrap();
+        let current = self.starts().started;
+        if current != seen {
+            return Ok(current);
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        self.stderr_tail.lock().unwrap().clear();
+        *child = spawn_child(&self.path, &self.args, &self.env, self.quiet, &self.stderr_tail, &self.output)?;
+        *self.started.lock().unwrap() = Instant::now();
+        let start = {
+            let mut starts = self.starts.lock().unwrap();
+            starts.started += 1;
+            starts.started
+        };
+        self.starts_changed.notify_all();
+        drop(child);
+        let backend = self.clone();
+        thread::spawn(move || backend.wait_for_start(start));
+        Ok(start)
+    }
+
+    /// Kill the child and wait for it to exit
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+        info!("{}", t!("tp-stopped", pid = child.id()));
+    }
+
+    pub fn last_crash(&self) -> Option<Crash> {
+        self.last_crash.lock().unwrap().clone()
+    }
+
+    pub fn health(&self) -> Health {
+        *self.health.lock().unwrap()
+    }
+
+    /// Number of times the supervisor restarted trace_processor after a crash
+    pub fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::SeqCst)
+    }
+
+    pub fn output(&self) -> &Arc<ChildOutput> {
+        &self.output
+    }
+
+    /// How long the current child has been running
+    pub fn uptime(&self) -> Duration {
+        self.started.lock().unwrap().elapsed()
+    }
+
+    /// Build a crash report for `status` from the captured stderr
+    pub fn crash_report(&self, status: ExitStatus) -> Crash {
+        // Give the stderr reader a moment to drain the pipe
+        thread::sleep(Duration::from_millis(100));
+        let stderr_tail: Vec<String> = self.stderr_tail.lock().unwrap().iter().cloned().collect();
+        Crash {
+            status: status.to_string(),
+            exit_code: status.code(),
+            explanation: explain(status, &stderr_tail),
+            stderr_tail,
+        }
+    }
+
+    /// Supervise the child: report unexpected exits on the console, in the
+    /// trace_processor log and on the status page, and restart it with
+    /// exponential backoff up to `max_restarts` crashes in a row
+    pub fn watch(self: &Arc<Self>, max_restarts: u32) {
+        let backend = self.clone();
+        thread::spawn(move || {
+            let mut reported_pid = None;
+            let mut crashes_in_row = 0;
+            let mut started = Instant::now();
+            loop {
+                thread::sleep(Duration::from_millis(500));
+                if backend.stopping.load(Ordering::SeqCst) {
+                    return;
+                }
+                let crashed = backend.starts().started;
+                let pid = backend.pid();
+                let Some(status) = backend.try_wait() else {
+                    if started.elapsed() >= STABLE_AFTER {
+                        crashes_in_row = 0;
+                    }
+                    continue;
+                };
+                if backend.stopping.load(Ordering::SeqCst) || reported_pid == Some(pid) {
+                    continue;
+                }
+                reported_pid = Some(pid);
+                let crash = backend.crash_report(status);
+                print_crash(&crash);
+                logs::child(&t!("tp-crashed", status = crash.status.as_str()));
+                *backend.last_crash.lock().unwrap() = Some(crash);
+
+                if crashes_in_row >= max_restarts {
+                    *backend.health.lock().unwrap() = Health::Failed;
+                    error!("{}", t!("tp-restart-gave-up", count = max_restarts));
+                    continue;
+                }
+                *backend.health.lock().unwrap() = Health::Restarting;
+                let delay = RESTART_BACKOFF.saturating_mul(1 << crashes_in_row.min(16)).min(RESTART_BACKOFF_MAX);
+                crashes_in_row += 1;
+                warn!(
+                    "{}",
+                    t!("tp-restarting", seconds = delay.as_secs(), attempt = crashes_in_row, max = max_restarts)
+                );
+                if !backend.sleep_unless_stopping(delay) {
+                    return;
+                }
+                // Unless it was restarted on request meanwhile
+                match backend.restart_after(crashed) {
+                    Ok(_) => {
+                        backend.restarts.fetch_add(1, Ordering::SeqCst);
+                        *backend.health.lock().unwrap() = Health::Running;
+                        say!("{}", t!("tp-restarted", pid = backend.pid()));
+                        logs::child(&t!("tp-restarted", pid = backend.pid()));
+                    }
+                    // Counts as another crash on the next round
+                    Err(e) => error!("{}", t!("tp-spawn-failed", error = e)),
+                }
+                started = Instant::now();
+            }
+        });
+    }
+
+    /// Sleep for `duration` in short steps; false if shutdown began meanwhile
+    fn sleep_unless_stopping(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            if self.stopping.load(Ordering::SeqCst) || shutdown::requested() {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100).min(deadline - Instant::now()));
+        }
+        true
+    }
+}
+
+pub fn print_crash(crash: &Crash) {
+    error!("\n{}", t!("tp-crashed", status = crash.status));
+    error!("  {}", crash.explanation);
+    if !crash.stderr_tail.is_empty() {
+        error!("  {}", t!("tp-crash-last-lines"));
+        for line in crash.stderr_tail.iter().rev().take(10).rev() {
+            error!("    {}", line);
+        }
+    }
+}
+
+/// Map common failure signatures to a plain-language explanation
+fn explain(status: ExitStatus, stderr_tail: &[String]) -> String {
+    let stderr = stderr_tail.join("\n").to_ascii_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| stderr.contains(n));
+    // Windows NTSTATUS codes surface as negative i32 exit codes
+    let code = status.code().map(|c| c as u32);
+
+    if code == Some(0xC000_0135) || code == Some(0xC000_007B) || has(&["vcruntime", "msvcp", ".dll"]) {
+        t!("tp-explain-missing-dll")
+    } else if code == Some(0xC000_0017)
+        || has(&["bad_alloc", "out of memory", "cannot allocate memory"])
+        || killed_by_signal(status, 9)
+    {
+        t!("tp-explain-oom")
+    } else if has(&["unsupported", "unknown trace type", "failed to parse", "trace version"]) {
+        t!("tp-explain-unsupported-trace")
+    } else if has(&["address already in use", "failed to listen"]) {
+        t!("tp-explain-port")
+    } else if has(&["error while loading shared libraries", "glibc"]) || code == Some(127) {
+        t!("tp-explain-missing-lib")
+    } else if code == Some(0xC000_0005) || killed_by_signal(status, 11) {
+        t!("tp-explain-segfault")
+    } else {
+        t!("tp-explain-unknown")
+    }
+}
+
+#[cfg(unix)]
+fn killed_by_signal(status: ExitStatus, signal: i32) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal() == Some(signal)
+}
+
+#[cfg(not(unix))]
+fn killed_by_signal(_status: ExitStatus, _signal: i32) -> bool {
+    false
+}
+
+fn spawn_child(
+    path: &PathBuf,
+    args: &[String],
+    env: &[(String, String)],
+    quiet: bool,
+    stderr_tail: &Arc<Mutex<VecDeque<String>>>,
+    output: &Arc<ChildOutput>,
+) -> io::Result<Child> {
+    let mut child = Command::new(path)
+        .args(args)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let spawned = t!("tp-spawned", pid = child.id(), path = path.display());
+    info!("{}", spawned);
+    output.push("launcher", spawned);
+
+    // Forward both pipes to the console and the logs, keeping the stderr tail
+    // for crash reports
+    if let Some(stdout) = child.stdout.take() {
+        let output = output.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if !quiet {
+                    println!("{}", line);
+                }
+                logs::child(&line);
+                output.push("stdout", line);
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let (tail, output) = (stderr_tail.clone(), output.clone());
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if !quiet {
+                    eprintln!("{}", line);
+                }
+                logs::child(&line);
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line.clone());
+                drop(tail);
+                output.push("stderr", line);
+            }
+        });
+    }
+    Ok(child)
+}
@@ -0,0 +1,274 @@
+//! `batch` subcommand: run the same SQL or metrics on many traces, e.g. to
+//! compare a CI run's traces against the last one's.
+//!
+//! Every trace gets a trace_processor of its own, with a bounded number of
+//! them running at once. The output directory gets a file per trace, named
+//! after it, `results.csv` or `results.json` with every trace's result in
+//! one place, and `summary.json` with what happened to each trace. A trace
+//! that fails does not stop the others, but makes the run fail at the end.
+//!
+//! Directories given as traces are searched for trace files, and a `*` in a
+//! file name matches any run of characters, for shells that leave it as is.
+
+use crate::bench::OutputFormat;
+use crate::import;
+use crate::metrics::{self, MetricsFormat};
+use crate::path_headers;
+use crate::query;
+use crate::rpc::Rows;
+use crate::trace_processor::Instance;
+use log::error;
+use serde_json::{json, Map, Value};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What to run on each trace
+pub enum Job {
+    /// The SQL in a file; with several statements, the last one's result
+    Sql(PathBuf),
+    Metrics(Vec<String>),
+}
+
+pub struct BatchArgs<'a> {
+    pub trace_processor: &'a Path,
+    /// Flags for trace_processor besides the RPC port and the trace
+    pub tp_args: Vec<String>,
+    pub metric_extension_dirs: &'a [PathBuf],
+    pub traces: &'a [PathBuf],
+    pub job: Job,
+    pub out: &'a Path,
+    /// Format of SQL results; metrics are always JSON
+    pub format: OutputFormat,
+    /// Traces processed at once; half the CPU cores if None
+    pub jobs: Option<usize>,
+    pub timeout: Duration,
+}
+
+/// One trace's outcome
+struct Outcome {
+    trace: PathBuf,
+    /// File the result was written to, relative to the output directory
+    file: String,
+    result: Result<Output, String>,
+    seconds: f64,
+}
+
+enum Output {
+    Rows(Rows),
+    Metrics(Value),
+}
+
+pub fn run(args: BatchArgs) -> Result<(), String> {
+    let sql = match &args.job {
+        Job::Sql(path) => {
+            let sql = fs::read_to_string(path).map_err(|e| t!("query-read-failed", path = path.display(), error = e))?;
+            if sql.trim().is_empty() {
+                return Err(t!("batch-no-sql", path = path.display()));
+            }
+            sql
+        }
+        Job::Metrics(_) => String::new(),
+    };
+    let traces = expand(args.traces);
+    if traces.is_empty() {
+        return Err(t!("batch-no-traces"));
+    }
+    fs::create_dir_all(args.out).map_err(|e| t!("batch-write-failed", path = args.out.display(), error = e))?;
+    let extension = match (&args.job, args.format) {
+        (Job::Sql(_), OutputFormat::Csv) => "csv",
+        _ => "json",
+    };
+    let files = file_names(&traces, extension);
+
+    let jobs = args.jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get() / 2)).max(1);
+    say!("{}", t!("batch-running", count = traces.len(), jobs = jobs));
+    let total = traces.len();
+    let queue = Mutex::new(traces.into_iter().zip(files).collect::<VecDeque<_>>());
+    let done = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Outcome>> = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let Some((trace, file)) = queue.lock().unwrap().pop_front() else { return };
+                let started = Instant::now();
+                let result = run_one(&args, &sql, &trace).and_then(|output| {
+                    let path = args.out.join(&file);
+                    let text = match &output {
+                        Output::Rows(rows) => format_rows(rows, args.format),
+                        Output::Metrics(metrics) => format!("{:#}\n", metrics),
+                    };
+                    fs::write(&path, text).map_err(|e| t!("batch-write-failed", path = path.display(), error = e))?;
+                    Ok(output)
+                });
+                let n = done.fetch_add(1, Ordering::SeqCst) + 1;
+                match &result {
+                    Ok(_) => say!("[{}/{}] {}", n, total, trace.display()),
+                    Err(e) => error!("[{}/{}] {}: {}", n, total, trace.display(), e),
+                }
+                let seconds = started.elapsed().as_secs_f64();
+                outcomes.lock().unwrap().push(Outcome { trace, file, result, seconds });
+            });
+        }
+    });
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by(|a, b| a.trace.cmp(&b.trace));
+
+    let merged = args.out.join(format!("results.{}", extension));
+    let text = match args.job {
+        Job::Sql(_) => format_rows(&merge_rows(&outcomes), args.format),
+        Job::Metrics(_) => format!("{:#}\n", merge_metrics(&outcomes)),
+    };
+    fs::write(&merged, text).map_err(|e| t!("batch-write-failed", path = merged.display(), error = e))?;
+    let summary_path = args.out.join("summary.json");
+    let summary = format!("{:#}\n", summary(&outcomes));
+    fs::write(&summary_path, summary).map_err(|e| t!("batch-write-failed", path = summary_path.display(), error = e))?;
+
+    let failed = outcomes.iter().filter(|outcome| outcome.result.is_err()).count();
+    say!("\n{}", t!("batch-summary", succeeded = outcomes.len() - failed, failed = failed, path = args.out.display()));
+    if failed > 0 {
+        return Err(t!("batch-some-failed", count = failed));
+    }
+    Ok(())
+}
+
+fn run_one(args: &BatchArgs, sql: &str, trace: &Path) -> Result<Output, String> {
+    if !trace.is_file() {
+        return Err(t!("config-trace-missing", path = trace.display()));
+    }
+    match &args.job {
+        Job::Sql(_) => {
+            let instance = Instance::start_with(args.trace_processor, &args.tp_args, trace, args.timeout)?;
+            Ok(Output::Rows(instance.client.query_rows(sql)?))
+        }
+        Job::Metrics(names) => {
+            let output =
+                metrics::compute(args.trace_processor, trace, names, args.metric_extension_dirs, MetricsFormat::Json)?;
+            serde_json::from_str(&output).map(Output::Metrics).map_err(|e| t!("batch-bad-metrics", error = e))
+        }
+    }
+}
+
+fn format_rows(rows: &Rows, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Csv => query::format_csv(rows),
+        OutputFormat::Json => query::format_json(rows),
+    }
+}
+
+/// The traces `paths` name: files as they are, the trace files directly in
+/// directories, and the files matching a name with `*`, each once
+fn expand(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut traces = Vec::new();
+    for path in paths {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if path.is_dir() {
+            let mut found = Vec::new();
+            import::collect(path, false, &mut found);
+            found.sort();
+            traces.extend(found);
+        } else if name.contains('*') {
+            let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let mut found: Vec<PathBuf> = fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .filter(|entry| path_headers::matches(&name, &entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect();
+            found.sort();
+            traces.extend(found);
+        } else {
+            traces.push(path.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    traces.retain(|trace| seen.insert(trace.clone()));
+    traces
+}
+
+/// Result file names for `traces`: the trace's file name with `extension`
+/// added, numbered where two traces share a name
+fn file_names(traces: &[PathBuf], extension: &str) -> Vec<String> {
+    let mut taken = HashSet::new();
+    traces
+        .iter()
+        .map(|trace| {
+            let name = trace.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut file = format!("{}.{}", name, extension);
+            let mut n = 1;
+            while !taken.insert(file.clone()) {
+                n += 1;
+                file = format!("{}-{}.{}", name, n, extension);
+            }
+            file
+        })
+        .collect()
+}
+
+/// Every trace's rows under a leading `trace` column, with the columns of
+/// all the results in the order they first appear
+fn merge_rows(outcomes: &[Outcome]) -> Rows {
+    let mut merged = Rows { columns: vec!["trace".to_string()], rows: Vec::new() };
+    for outcome in outcomes {
+        let Ok(Output::Rows(rows)) = &outcome.result else { continue };
+        for column in &rows.columns {
+            if !merged.columns.contains(column) {
+                merged.columns.push(column.clone());
+            }
+        }
+        for row in &rows.rows {
+            let mut cells = vec![Value::Null; merged.columns.len()];
+            cells[0] = json!(outcome.trace.display().to_string());
+            for (column, value) in rows.columns.iter().zip(row) {
+                if let Some(at) = merged.columns.iter().position(|c| c == column) {
+                    cells[at] = value.clone();
+                }
+            }
+            merged.rows.push(cells);
+        }
+    }
+    // Rows merged before a later trace added a column are short of it
+    let width = merged.columns.len();
+    for row in &mut merged.rows {
+        row.resize(width, Value::Null);
+    }
+    merged
+}
+
+/// Every trace's metrics, by trace path
+fn merge_metrics(outcomes: &[Outcome]) -> Value {
+    let mut merged = Map::new();
+    for outcome in outcomes {
+        if let Ok(Output::Metrics(metrics)) = &outcome.result {
+            merged.insert(outcome.trace.display().to_string(), metrics.clone());
+        }
+    }
+    Value::Object(merged)
+}
+
+fn summary(outcomes: &[Outcome]) -> Value {
+    let traces: Vec<Value> = outcomes
+        .iter()
+        .map(|outcome| {
+            let rows = match &outcome.result {
+                Ok(Output::Rows(rows)) => json!(rows.rows.len()),
+                _ => Value::Null,
+            };
+            json!({
+                "trace": outcome.trace.display().to_string(),
+                "file": outcome.result.as_ref().ok().map(|_| &outcome.file),
+                "rows": rows,
+                "seconds": (outcome.seconds * 1000.0).round() / 1000.0,
+                "error": outcome.result.as_ref().err(),
+            })
+        })
+        .collect();
+    let failed = outcomes.iter().filter(|outcome| outcome.result.is_err()).count();
+    json!({ "succeeded": outcomes.len() - failed, "failed": failed, "traces": traces })
+}
@@ -0,0 +1,199 @@
+//! `bench` subcommand: trace load time and per-query latency distributions.
+
+use crate::trace_processor::Instance;
+use serde_json::json;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+pub struct BenchArgs<'a> {
+    pub trace_processor: &'a Path,
+    pub trace: &'a Path,
+    pub queries: &'a Path,
+    pub iterations: u32,
+    pub format: OutputFormat,
+    pub output: Option<&'a PathBuf>,
+}
+
+struct QueryStats {
+    sql: String,
+    samples_ms: Vec<f64>,
+    error: Option<String>,
+}
+
+pub fn run(args: BenchArgs) -> Result<(), String> {
+    let sql = fs::read_to_string(args.queries)
+        .map_err(|e| t!("bench-read-failed", path = args.queries.display(), error = e))?;
+    let queries = split_statements(&sql);
+    if queries.is_empty() {
+        return Err(t!("bench-no-queries", path = args.queries.display()));
+    }
+
+    // trace_processor parses the whole trace before it starts serving RPC,
+    // so time-to-ready is the load time.
+    let start = Instant::now();
+    let instance = Instance::start(args.trace_processor, args.trace, Duration::from_secs(600))?;
+    let client = &instance.client;
+    let load_time = start.elapsed();
+    eprintln!("{}", t!("bench-loaded", ms = format!("{:.1}", load_time.as_secs_f64() * 1000.0)));
+
+    let mut results = Vec::new();
+    for (i, sql) in queries.iter().enumerate() {
+        eprintln!("{}", t!("bench-query-progress", index = i + 1, total = queries.len()));
+        let mut stats = QueryStats { sql: sql.clone(), samples_ms: Vec::new(), error: None };
+        for _ in 0..args.iterations {
+            let started = Instant::now();
+            match client.query(sql) {
+                Ok(_) => stats.samples_ms.push(started.elapsed().as_secs_f64() * 1000.0),
+                Err(e) => {
+                    stats.error = Some(e);
+                    break;
+                }
+            }
+        }
+        results.push(stats);
+    }
+
+    let report = match args.format {
+        OutputFormat::Json => format_json(&args, load_time, &results),
+        OutputFormat::Csv => format_csv(&results),
+    };
+    match args.output {
+        Some(path) => fs::write(path, report).map_err(|e| t!("bench-write-failed", path = path.display(), error = e)),
+        None => {
+            println!("{}", report.trim_end());
+            Ok(())
+        }
+    }
+}
+
+/// Split a SQL file into statements on `;` outside of string literals and comments
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                current.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            None if c == '-' && chars.peek() == Some(&'-') => {
+                // Line comment: skip to end of line
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            None if c == ';' => {
+                statements.push(std::mem::take(&mut current));
+            }
+            None => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+struct Summary {
+    min: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    max: f64,
+    mean: f64,
+}
+
+fn summarize(samples: &[f64]) -> Option<Summary> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+    Some(Summary {
+        min: sorted[0],
+        p50: percentile(0.5),
+        p90: percentile(0.9),
+        p99: percentile(0.99),
+        max: sorted[sorted.len() - 1],
+        mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+    })
+}
+
+fn format_json(args: &BenchArgs, load_time: Duration, results: &[QueryStats]) -> String {
+    let queries: Vec<_> = results
+        .iter()
+        .map(|r| {
+            let summary = summarize(&r.samples_ms);
+            json!({
+                "sql": r.sql,
+                "error": r.error,
+                "samples_ms": r.samples_ms,
+                "min_ms": summary.as_ref().map(|s| s.min),
+                "p50_ms": summary.as_ref().map(|s| s.p50),
+                "p90_ms": summary.as_ref().map(|s| s.p90),
+                "p99_ms": summary.as_ref().map(|s| s.p99),
+                "max_ms": summary.as_ref().map(|s| s.max),
+                "mean_ms": summary.as_ref().map(|s| s.mean),
+            })
+        })
+        .collect();
+    let report = json!({
+        "trace": args.trace.display().to_string(),
+        "trace_processor": args.trace_processor.display().to_string(),
+        "iterations": args.iterations,
+        "load_time_ms": load_time.as_secs_f64() * 1000.0,
+        "queries": queries,
+    });
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+fn format_csv(results: &[QueryStats]) -> String {
+    let mut out = String::from("query,iterations,min_ms,p50_ms,p90_ms,p99_ms,max_ms,mean_ms,error\n");
+    for r in results {
+        let sql = r.sql.split_whitespace().collect::<Vec<_>>().join(" ").replace('"', "\"\"");
+        let error = r.error.clone().unwrap_or_default().replace('"', "\"\"");
+        match summarize(&r.samples_ms) {
+            Some(s) => {
+                let _ = writeln!(
+                    out,
+                    "\"{}\",{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},\"{}\"",
+                    sql,
+                    r.samples_ms.len(),
+                    s.min,
+                    s.p50,
+                    s.p90,
+                    s.p99,
+                    s.max,
+                    s.mean,
+                    error
+                );
+            }
+            None => {
+                let _ = writeln!(out, "\"{}\",0,,,,,,,\"{}\"", sql, error);
+            }
+        }
+    }
+    out
+}
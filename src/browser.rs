@@ -0,0 +1,57 @@
+//! Opening the UI in a browser, the system default or one chosen with
+//! `--browser`.
+
+use std::io;
+use std::net::{IpAddr, UdpSocket};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use sysinfo::Networks;
+
+/// Open `url` with `browser`, or with the system default if None
+///
+/// `browser` is either the path of an executable or a command line such as
+/// `firefox -P work`, to which the URL is appended. On macOS a bare name like
+/// `Firefox` is also looked up among the installed applications.
+pub fn open(url: &str, browser: Option<&str>) -> io::Result<()> {
+    let Some(browser) = browser.map(str::trim).filter(|b| !b.is_empty()) else {
+        return open::that(url);
+    };
+    let mut command = if Path::new(browser).is_file() {
+        Command::new(browser)
+    } else {
+        let mut words = browser.split_whitespace();
+        let program = words.next().unwrap_or(browser);
+        if cfg!(target_os = "macos") && !browser.contains(char::is_whitespace) {
+            return open::with(url, program);
+        }
+        let mut command = Command::new(program);
+        command.args(words);
+        command
+    };
+    command.arg(url).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map(|_| ())
+}
+
+/// Address other machines most likely reach this one at: that of the
+/// interface holding the default route. No packet is sent to find it.
+pub fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:9").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// IPv4 addresses of every network interface other machines may reach this
+/// one at, `lan_address` first
+pub fn lan_addresses() -> Vec<IpAddr> {
+    let mut addresses: Vec<IpAddr> = lan_address().into_iter().collect();
+    let networks = Networks::new_with_refreshed_list();
+    for network in networks.values() {
+        for ip in network.ip_networks().iter().map(|network| network.addr) {
+            let IpAddr::V4(v4) = ip else { continue };
+            if !v4.is_loopback() && !v4.is_link_local() && !v4.is_unspecified() && !addresses.contains(&ip) {
+                addresses.push(ip);
+            }
+        }
+    }
+    addresses
+}
@@ -0,0 +1,212 @@
+//! `bundle create`: a zip with everything the launcher needs on one
+//! platform, for machines without internet access.
+//!
+//! The archive holds a `perfetto_launcher/` directory laid out like a dist
+//! directory: the launcher, trace_processor, the UI (`index.html` and its
+//! version directory) and a start script that runs the launcher with the
+//! default arguments given at bundle time.
+
+use crate::dist::ui_version;
+use crate::trace_processor::{binary_name, trace_processor_path};
+use crate::ui_update;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Top-level directory inside the archive
+const ROOT: &str = "perfetto_launcher";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Platform {
+    WindowsX64,
+    WindowsArm64,
+    LinuxX64,
+    LinuxArm64,
+    MacX64,
+    MacArm64,
+}
+
+impl Platform {
+    /// The platform this launcher was built for, if it is one of the above
+    pub fn current() -> Option<Platform> {
+        match (env::consts::OS, env::consts::ARCH) {
+            ("windows", "x86_64") => Some(Platform::WindowsX64),
+            ("windows", "aarch64") => Some(Platform::WindowsArm64),
+            ("linux", "x86_64") => Some(Platform::LinuxX64),
+            ("linux", "aarch64") => Some(Platform::LinuxArm64),
+            ("macos", "x86_64") => Some(Platform::MacX64),
+            ("macos", "aarch64") => Some(Platform::MacArm64),
+            _ => None,
+        }
+    }
+
+    fn is_windows(self) -> bool {
+        matches!(self, Platform::WindowsX64 | Platform::WindowsArm64)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Platform::WindowsX64 => "windows-x64",
+            Platform::WindowsArm64 => "windows-arm64",
+            Platform::LinuxX64 => "linux-x64",
+            Platform::LinuxArm64 => "linux-arm64",
+            Platform::MacX64 => "mac-x64",
+            Platform::MacArm64 => "mac-arm64",
+        }
+    }
+}
+
+pub struct BundleArgs<'a> {
+    pub platform: Platform,
+    pub output: &'a Path,
+    /// Launcher binary for the platform; defaults to this one if it matches
+    pub launcher: Option<&'a Path>,
+    /// trace_processor_shell for the platform; defaults to the dist
+    /// directory's if the platform matches
+    pub trace_processor: Option<&'a Path>,
+    /// UI version to include; defaults to the one in the dist directory
+    pub ui_version: Option<&'a str>,
+    /// Site to download other UI versions from
+    pub ui_source: &'a str,
+    pub dist_dir: &'a Path,
+    /// Arguments the start script passes to the launcher
+    pub launcher_args: &'a [String],
+}
+
+pub fn create(args: BundleArgs) -> Result<(), String> {
+    let native = Platform::current() == Some(args.platform);
+    let launcher = match args.launcher {
+        Some(path) => path.to_path_buf(),
+        None if native => env::current_exe().map_err(|e| e.to_string())?,
+        None => return Err(t!("bundle-need-launcher", platform = args.platform.name())),
+    };
+    let trace_processor = match args.trace_processor {
+        Some(path) => path.to_path_buf(),
+        None if native => trace_processor_path(args.dist_dir),
+        None => return Err(t!("bundle-need-trace-processor", platform = args.platform.name())),
+    };
+    for path in [&launcher, &trace_processor] {
+        if !path.is_file() {
+            return Err(t!("bundle-missing-file", path = path.display()));
+        }
+    }
+
+    // A UI version other than the installed one is downloaded next to the output
+    let local_version = ui_version(args.dist_dir);
+    let download_dir = args.output.with_extension("ui.partial");
+    let (ui_dir, version) = match args.ui_version {
+        Some(version) if Some(version) != local_version.as_deref() => {
+            let _ = fs::remove_dir_all(&download_dir);
+            let result = ui_update::download(args.ui_source, version, args.dist_dir, &download_dir);
+            if let Err(e) = result {
+                let _ = fs::remove_dir_all(&download_dir);
+                return Err(e);
+            }
+            (download_dir.clone(), version.to_string())
+        }
+        _ => match local_version {
+            Some(version) if args.dist_dir.join(&version).is_dir() => (args.dist_dir.to_path_buf(), version),
+            _ => return Err(t!("bundle-no-ui", path = args.dist_dir.display())),
+        },
+    };
+
+    let result = write_archive(&args, &launcher, &trace_processor, &ui_dir, &version);
+    let _ = fs::remove_dir_all(&download_dir);
+    let size = result?;
+    say!(
+        "{}",
+        t!(
+            "bundle-created",
+            path = args.output.display(),
+            platform = args.platform.name(),
+            version = version,
+            mb = format!("{:.1}", size as f64 / (1024.0 * 1024.0))
+        )
+    );
+    Ok(())
+}
+
+/// Write the archive via a `.partial` file; returns its size
+fn write_archive(
+    args: &BundleArgs,
+    launcher: &Path,
+    trace_processor: &Path,
+    ui_dir: &Path,
+    version: &str,
+) -> Result<u64, String> {
+    let mut partial = args.output.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = (|| -> io::Result<()> {
+        let mut zip = ZipWriter::new(File::create(&partial)?);
+        let exe = |stem: &str| if args.platform.is_windows() { format!("{}.exe", stem) } else { stem.to_string() };
+        // The launcher looks for trace_processor under this name
+        let tp_name = binary_name(args.platform.is_windows());
+
+        add_file(&mut zip, launcher, &format!("{}/{}", ROOT, exe("perfetto_launcher")), true)?;
+        add_file(&mut zip, trace_processor, &format!("{}/{}", ROOT, tp_name), true)?;
+        for name in ["index.html", "service_worker.js"] {
+            let path = ui_dir.join(name);
+            if path.is_file() {
+                add_file(&mut zip, &path, &format!("{}/{}", ROOT, name), false)?;
+            }
+        }
+        add_dir(&mut zip, &ui_dir.join(version), &format!("{}/{}", ROOT, version))?;
+
+        let (script_name, script) = start_script(args.platform, &exe("perfetto_launcher"), args.launcher_args);
+        zip.start_file(format!("{}/{}", ROOT, script_name), options(true)).map_err(io::Error::other)?;
+        io::Write::write_all(&mut zip, script.as_bytes())?;
+        zip.finish().map_err(io::Error::other)?;
+        Ok(())
+    })();
+    if let Err(e) = result.and_then(|_| fs::rename(&partial, args.output)) {
+        let _ = fs::remove_file(&partial);
+        return Err(t!("bundle-write-failed", path = args.output.display(), error = e));
+    }
+    Ok(fs::metadata(args.output).map(|m| m.len()).unwrap_or(0))
+}
+
+fn options(executable: bool) -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(if executable { 0o755 } else { 0o644 })
+        .large_file(true)
+}
+
+fn add_file(zip: &mut ZipWriter<File>, path: &Path, name: &str, executable: bool) -> io::Result<()> {
+    zip.start_file(name, options(executable)).map_err(io::Error::other)?;
+    io::copy(&mut File::open(path)?, zip)?;
+    Ok(())
+}
+
+fn add_dir(zip: &mut ZipWriter<File>, dir: &Path, name: &str) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let child = format!("{}/{}", name, entry.file_name().to_string_lossy());
+        if path.is_dir() {
+            add_dir(zip, &path, &child)?;
+        } else {
+            add_file(zip, &path, &child, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// `start.cmd` or `start.sh` running the launcher from the bundle directory
+fn start_script(platform: Platform, launcher: &str, launcher_args: &[String]) -> (&'static str, String) {
+    if platform.is_windows() {
+        let args: String = launcher_args
+            .iter()
+            .map(|arg| if arg.contains([' ', '&', '^', '|']) { format!(" \"{}\"", arg) } else { format!(" {}", arg) })
+            .collect();
+        ("start.cmd", format!("@echo off\r\n\"%~dp0{}\"{} %*\r\n", launcher, args))
+    } else {
+        let args: String = launcher_args.iter().map(|arg| format!(" '{}'", arg.replace('\'', "'\\''"))).collect();
+        ("start.sh", format!("#!/bin/sh\nexec \"$(dirname \"$0\")/{}\"{} \"$@\"\n", launcher, args))
+    }
+}
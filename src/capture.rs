@@ -0,0 +1,239 @@
+//! Scheduled periodic trace captures with `perfetto`/`tracebox`, locally or on
+//! an adb-attached device, rotated into the catalog with retention.
+
+use crate::android;
+use crate::catalog::Catalog;
+use crate::dates::civil_from_days;
+use crate::ingest::{self, Ingest};
+use crate::paths::find_in_path;
+use crate::shutdown;
+use crate::workspace::{self, Area};
+use log::{error, warn};
+use std::cmp::Reverse;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub struct Schedule {
+    pub every: Duration,
+    pub duration: Duration,
+    /// Text-format trace config
+    pub config: PathBuf,
+    /// adb serial of the device to record on; records locally if unset
+    pub device: Option<String>,
+    /// Number of captures to keep
+    pub keep: usize,
+    /// Delete captures older than this
+    pub max_age: Option<Duration>,
+    /// Directory searched for tracebox before PATH
+    pub tools_dir: PathBuf,
+    pub ingest: Ingest,
+}
+
+/// Parse durations such as `500ms`, `30s`, `15m`, `1h` or `7d`
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("invalid duration '{}'", text))?;
+    let seconds = match unit {
+        "ms" => return Ok(Duration::from_millis(number)),
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("unknown unit '{}' in '{}' (use ms, s, m, h or d)", unit, text)),
+    };
+    Ok(Duration::from_secs(number * seconds))
+}
+
+/// Record every `schedule.every` until Ctrl+C
+pub fn run_schedule(schedule: Schedule) -> Result<(), String> {
+    let config = fs::read_to_string(&schedule.config)
+        .map_err(|e| t!("capture-config-unreadable", path = schedule.config.display(), error = e))?;
+    let config = with_duration(&config, schedule.duration);
+    let recorder = match &schedule.device {
+        Some(_) => android::adb()?,
+        None => find_recorder(&schedule.tools_dir).ok_or_else(|| t!("capture-no-recorder"))?,
+    };
+    fs::create_dir_all(workspace::dir(Area::Captures)).map_err(|e| e.to_string())?;
+
+    // Let a capture in progress finish; the recorder stops itself on Ctrl+C too
+    shutdown::install(schedule.duration + Duration::from_secs(30));
+    say!("{}", t!("capture-scheduled", every = format_duration(schedule.every), duration = format_duration(schedule.duration)));
+
+    while !shutdown::requested() {
+        let started = Instant::now();
+        let output = workspace::dir(Area::Captures).join(format!("capture-{}.pftrace", timestamp()));
+        say!("{}", t!("capture-recording", path = output.display()));
+        let result = spawn(&recorder, schedule.device.as_deref(), &config, &output)
+            .and_then(|child| finish(child, &recorder, schedule.device.as_deref(), &output));
+        match result {
+            Ok(()) => {
+                let added = Catalog::open().add(&output);
+                match added {
+                    Ok(trace) => {
+                        say!("{}", t!("capture-saved", path = output.display(), id = trace.id));
+                        schedule.ingest.submit(trace);
+                    }
+                    Err(e) => warn!("{}", t!("catalog-add-failed", path = output.display(), error = e)),
+                }
+            }
+            Err(e) => error!("{}", t!("capture-failed", error = e)),
+        }
+        apply_retention(schedule.keep, schedule.max_age);
+
+        let next = started + schedule.every;
+        while !shutdown::requested() && Instant::now() < next {
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+    Ok(())
+}
+
+/// tracebox next to trace_processor, or tracebox/perfetto on PATH
+pub fn find_recorder(tools_dir: &Path) -> Option<PathBuf> {
+    let bundled = tools_dir.join(if cfg!(windows) { "tracebox.exe" } else { "tracebox" });
+    if bundled.is_file() {
+        return Some(bundled);
+    }
+    find_in_path("tracebox").or_else(|| find_in_path("perfetto"))
+}
+
+/// Set the top-level `duration_ms` of a text-format config
+pub fn with_duration(config: &str, duration: Duration) -> String {
+    let mut depth = 0i32;
+    let mut lines: Vec<String> = Vec::new();
+    for line in config.lines() {
+        if !(depth == 0 && line.trim_start().starts_with("duration_ms:")) {
+            lines.push(line.to_string());
+        }
+        depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+    }
+    lines.push(format!("duration_ms: {}", duration.as_millis()));
+    lines.join("\n") + "\n"
+}
+
+/// The top-level `duration_ms` of a text-format config; None records until
+/// the recorder is stopped
+pub fn config_duration(config: &str) -> Option<Duration> {
+    let mut depth = 0i32;
+    let mut duration = None;
+    for line in config.lines() {
+        if depth == 0 {
+            if let Some(ms) = line.trim().strip_prefix("duration_ms:") {
+                duration = ms.trim().parse().ok().filter(|ms| *ms > 0).map(Duration::from_millis);
+            }
+        }
+        depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+    }
+    duration
+}
+
+/// Where on the device a capture for `output` is recorded
+pub fn device_path(output: &Path) -> String {
+    let name = output.file_name().unwrap_or_default().to_string_lossy();
+    format!("/data/misc/perfetto-traces/{}", name)
+}
+
+/// Start recording to `output` with `config`, locally with `recorder` or on
+/// the adb device `device` with `recorder` being adb
+pub fn spawn(recorder: &Path, device: Option<&str>, config: &str, output: &Path) -> Result<Child, String> {
+    let mut command = Command::new(recorder);
+    match device {
+        Some(serial) => {
+            let remote = device_path(output);
+            command.args(["-s", serial, "shell", "perfetto", "--txt", "-c", "-", "-o", &remote])
+        }
+        None => command.args(["--txt", "-c", "-", "-o"]).arg(output),
+    };
+    let mut child = command.stdin(Stdio::piped()).spawn().map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    Ok(child)
+}
+
+/// Wait for a recorder from `spawn` and, for a device, pull the trace to
+/// `output`
+pub fn finish(mut child: Child, recorder: &Path, device: Option<&str>, output: &Path) -> Result<(), String> {
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("recorder exited with {}", status));
+    }
+    let Some(serial) = device else { return Ok(()) };
+    let remote = device_path(output);
+    let pulled = Command::new(recorder)
+        .args(["-s", serial, "pull", &remote])
+        .arg(output)
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    let _ = Command::new(recorder).args(["-s", serial, "shell", "rm", "-f", &remote]).status();
+    if !pulled.success() {
+        return Err(format!("adb pull {} exited with {}", remote, pulled));
+    }
+    Ok(())
+}
+
+/// Delete the oldest captures beyond `keep` and any older than `max_age`,
+/// removing them from the catalog too; pinned captures are never deleted
+/// and do not count towards `keep`
+fn apply_retention(keep: usize, max_age: Option<Duration>) {
+    let mut catalog = Catalog::open();
+    let pinned: Vec<PathBuf> = catalog.traces().iter().filter(|t| t.pinned).map(|t| t.path.clone()).collect();
+    let mut captures: Vec<(SystemTime, PathBuf)> = fs::read_dir(workspace::dir(Area::Captures))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "pftrace"))
+        .filter(|path| !path.canonicalize().is_ok_and(|path| pinned.contains(&path)))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    captures.sort_by_key(|(modified, _)| Reverse(*modified));
+
+    let now = SystemTime::now();
+    for (index, (modified, path)) in captures.iter().enumerate() {
+        let expired = max_age.is_some_and(|age| now.duration_since(*modified).unwrap_or_default() > age);
+        if index < keep && !expired {
+            continue;
+        }
+        if let Some(trace) = catalog.remove_path(path) {
+            ingest::forget(&trace.id);
+        }
+        if fs::remove_file(path).is_ok() {
+            say!("{}", t!("capture-deleted", path = path.display()));
+        }
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => format!("{}ms", duration.as_millis()),
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// UTC `YYYYMMDD-HHMMSSZ`, so capture file names sort chronologically
+pub fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,240 @@
+//! Registry of known traces, persisted as `catalog.json` in the data directory.
+//!
+//! Every trace gets a short id on registration that never changes, so links
+//! such as `/t/<id>` stay valid for as long as the trace is in the catalog.
+//!
+//! Several processes may change the catalog at once, such as the server,
+//! `capture schedule` and `open-url`. Each change is made to the file as it
+//! is at the time, under a lock on `catalog.json.lock`, so none of them
+//! writes back a stale copy over what another added.
+
+use crate::paths::data_dir;
+use crate::webhooks;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File};
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters used for trace ids; lowercase only so ids survive case-folding
+const ID_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const ID_LEN: usize = 8;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Trace {
+    pub id: String,
+    /// Display name, usually the file name
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Unix time the trace was registered
+    pub added: u64,
+    /// Pinned traces are kept by retention and listed first
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// User who uploaded the trace in multi-user mode; None for shared traces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Unix time the trace was last opened through the launcher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opened: Option<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CatalogFile {
+    traces: Vec<Trace>,
+}
+
+pub struct Catalog {
+    file: PathBuf,
+    traces: Vec<Trace>,
+}
+
+impl Catalog {
+    /// Load the catalog from the data directory; a missing file is an empty catalog
+    pub fn open() -> Catalog {
+        let file = data_dir().join("catalog.json");
+        let traces = read(&file);
+        Catalog { file, traces }
+    }
+
+    /// Location of `catalog.json`
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn traces(&self) -> &[Trace] {
+        &self.traces
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Trace> {
+        self.traces.iter().find(|t| t.id == id)
+    }
+
+    /// Find a trace by id, or by path for traces given on the command line
+    pub fn find(&self, id_or_path: &str) -> Option<&Trace> {
+        self.get(id_or_path).or_else(|| {
+            let path = Path::new(id_or_path).canonicalize().ok()?;
+            self.traces.iter().find(|t| t.path == path)
+        })
+    }
+
+    pub fn set_pinned(&mut self, id: &str, pinned: bool) -> io::Result<Option<Trace>> {
+        self.update(id, |trace| trace.pinned = pinned)
+    }
+
+    /// Change the entry for `id` and save; None if there is no such trace
+    pub fn update(&mut self, id: &str, change: impl FnOnce(&mut Trace)) -> io::Result<Option<Trace>> {
+        self.modify(|catalog| {
+            let trace = catalog.traces.iter_mut().find(|t| t.id == id)?;
+            change(trace);
+            Some(trace.clone())
+        })
+    }
+
+    /// Note that `id` was opened just now
+    pub fn mark_opened(&mut self, id: &str) -> io::Result<Option<Trace>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.update(id, |trace| trace.opened = Some(now))
+    }
+
+    /// Unregister `id`, returning its entry if it was known
+    pub fn remove(&mut self, id: &str) -> io::Result<Option<Trace>> {
+        self.modify(|catalog| {
+            let index = catalog.traces.iter().position(|t| t.id == id)?;
+            Some(catalog.traces.remove(index))
+        })
+    }
+
+    pub fn contains_path(&self, path: &Path) -> bool {
+        path.canonicalize().is_ok_and(|path| self.traces.iter().any(|t| t.path == path))
+    }
+
+    /// Register `path`, or return the existing entry if it is already known
+    pub fn add(&mut self, path: &Path) -> io::Result<Trace> {
+        let path = path.canonicalize()?;
+        let size = fs::metadata(&path)?.len();
+        let added = self.modify(|catalog| {
+            if catalog.traces.iter().any(|t| t.path == path) {
+                return None;
+            }
+            let trace = Trace {
+                id: catalog.new_id(),
+                name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                size,
+                path: path.clone(),
+                added: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                pinned: false,
+                tags: Vec::new(),
+                owner: None,
+                opened: None,
+            };
+            catalog.traces.push(trace.clone());
+            Some(trace)
+        })?;
+        if let Some(trace) = &added {
+            webhooks::trace_ingested(trace);
+        }
+        // Otherwise known already, perhaps from another process
+        added
+            .or_else(|| self.traces.iter().find(|t| t.path == path).cloned())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    /// Add entries from another catalog whose id and path are both new;
+    /// returns how many were added
+    pub fn merge(&mut self, traces: Vec<Trace>) -> io::Result<usize> {
+        let added = self.modify(|catalog| {
+            let before = catalog.traces.len();
+            for trace in traces {
+                if catalog.get(&trace.id).is_none() && !catalog.traces.iter().any(|t| t.path == trace.path) {
+                    catalog.traces.push(trace);
+                }
+            }
+            let added = catalog.traces.len() - before;
+            (added > 0).then_some(added)
+        })?;
+        Ok(added.unwrap_or(0))
+    }
+
+    /// Unregister the trace at `path`, returning its entry if it was known
+    pub fn remove_path(&mut self, path: &Path) -> Option<Trace> {
+        let path = path.canonicalize().ok()?;
+        let removed = self.modify(|catalog| {
+            let index = catalog.traces.iter().position(|t| t.path == path)?;
+            Some(catalog.traces.remove(index))
+        });
+        removed.unwrap_or_else(|e| {
+            warn!("{}", t!("catalog-save-failed", path = self.file.display(), error = e));
+            None
+        })
+    }
+
+    fn new_id(&self) -> String {
+        loop {
+            let id = random_id();
+            if self.get(&id).is_none() {
+                return id;
+            }
+        }
+    }
+
+    /// Apply `change` to the catalog as it is on disk now, and save it unless
+    /// `change` returns None for nothing changed
+    fn modify<T>(&mut self, change: impl FnOnce(&mut Catalog) -> Option<T>) -> io::Result<Option<T>> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Released when the file is closed, also by a process that dies
+        let lock_file = self.file.with_extension("json.lock");
+        let lock = File::options().create(true).truncate(false).write(true).open(lock_file)?;
+        lock.lock()?;
+        self.traces = read(&self.file);
+        let changed = change(self);
+        if changed.is_some() {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// Write the catalog atomically so a crash never leaves it half-written
+    fn save(&self) -> io::Result<()> {
+        let file = CatalogFile { traces: self.traces.clone() };
+        let tmp = self.file.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&file)?)?;
+        fs::rename(&tmp, &self.file)
+    }
+}
+
+/// The traces in `file`; a missing file is an empty catalog
+fn read(file: &Path) -> Vec<Trace> {
+    match fs::read_to_string(file) {
+        Ok(text) => match serde_json::from_str::<CatalogFile>(&text) {
+            Ok(catalog) => catalog.traces,
+            Err(e) => {
+                warn!("{}", t!("catalog-corrupt", path = file.display(), error = e));
+                Vec::new()
+            }
+        },
+        Err(_) => Vec::new(),
+    }
+}
+
+/// A fresh id in the style of trace ids; callers check it is unused
+pub fn random_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos());
+    let mut hash = hasher.finish();
+    (0..ID_LEN)
+        .map(|_| {
+            let c = ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize];
+            hash /= ID_ALPHABET.len() as u64;
+            c as char
+        })
+        .collect()
+}
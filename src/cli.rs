@@ -0,0 +1,762 @@
+use crate::access::AuthMode;
+use crate::bench::OutputFormat;
+use crate::bundle::Platform;
+use crate::capture::parse_duration;
+use crate::config;
+use crate::convert::TraceFormat;
+use crate::deobfuscate::{parse_map, ProguardMap};
+use crate::i18n::Lang;
+use crate::metrics::MetricsFormat;
+use crate::monitor::LimitAction;
+use crate::path_headers::Rule;
+use crate::query::QueryFormat;
+use crate::ui_update::{Channel, DEFAULT_SOURCE};
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "perfetto_launcher", about = "Serve the Perfetto UI locally with trace_processor_shell")]
+#[command(disable_version_flag = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Print launcher, UI and trace_processor versions
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
+    /// Port for the UI server (random if not given)
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(1..))]
+    pub ui_port: Option<u16>,
+
+    /// Port for trace_processor's RPC server (random if not given)
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(1..))]
+    pub rpc_port: Option<u16>,
+
+    /// Address the UI server listens on; by default only this machine can
+    /// reach it. trace_processor stays on 127.0.0.1 behind the UI server
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1")]
+    pub host: IpAddr,
+
+    /// Listen on every interface so other machines on the network can open
+    /// the UI, and print the addresses they can use
+    #[arg(long, conflicts_with = "host")]
+    pub expose_lan: bool,
+
+    /// Serve the UI over HTTPS with this PEM certificate (chain)
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key (PKCS#8 or RSA) of --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Serve the UI over HTTPS with a self-signed certificate, generated on
+    /// first use and kept in the data directory
+    #[arg(long, conflicts_with = "tls_cert")]
+    pub self_signed: bool,
+
+    /// Directory with the Perfetto UI (defaults to the executable's directory)
+    #[arg(long, value_name = "DIR", global = true)]
+    pub dist_dir: Option<PathBuf>,
+
+    /// trace_processor_shell to run (defaults to the one in the dist directory)
+    #[arg(long = "trace-processor", value_name = "FILE")]
+    pub trace_processor_bin: Option<PathBuf>,
+
+    /// Download trace_processor_shell from the Perfetto releases if none is found
+    #[arg(long)]
+    pub auto_download: bool,
+
+    /// trace_processor_shell release to run, e.g. v47.0 (downloaded once and cached)
+    #[arg(long, value_name = "VERSION", conflicts_with = "trace_processor_bin")]
+    pub tp_version: Option<String>,
+
+    /// Start even if the UI and trace_processor versions are incompatible
+    #[arg(long)]
+    pub force: bool,
+
+    /// Start without the quick checks of the UI files, loopback connections
+    /// and the data directory
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    /// Print the resolved launch plan and exit without starting anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Seconds to wait for trace_processor to answer before giving up; loading
+    /// a large trace counts towards this
+    #[arg(long, value_name = "SECS", default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+    pub startup_timeout: u64,
+
+    /// Times in a row a crashed trace_processor is restarted before giving up;
+    /// 0 leaves it stopped
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    pub max_restarts: u32,
+
+    /// Gzip UI files that have no pre-compressed .br/.gz sibling when a browser
+    /// accepts it, keeping the results in memory
+    #[arg(long)]
+    pub compress: bool,
+
+    /// Answer 404 for unknown page paths instead of serving the UI's
+    /// index.html, which lets the UI's own routes work as deep links
+    #[arg(long = "no-spa-fallback", action = clap::ArgAction::SetFalse)]
+    pub spa_fallback: bool,
+
+    /// Watch the dist directory and reload open UI tabs when its files
+    /// change, for working on a UI build
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Seconds to let in-flight responses finish after Ctrl+C
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    pub drain_timeout: u64,
+
+    /// Exit, stopping trace_processor, after this long without requests, e.g. 30m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub idle_timeout: Option<Duration>,
+
+    /// Token POST /launcher/shutdown wants (defaults to a random one, saved
+    /// in instance.json in the data directory)
+    #[arg(long, value_name = "TOKEN")]
+    pub shutdown_token: Option<String>,
+
+    /// Threads answering UI server requests (defaults to twice the CPU count, 8 to 64)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..1025))]
+    pub workers: Option<u64>,
+
+    /// Maximum queued and in-flight requests before answering 503
+    #[arg(long, value_name = "N", default_value_t = 128)]
+    pub max_connections: usize,
+
+    /// Largest trace upload accepted, in MB
+    #[arg(long, value_name = "MB", default_value_t = 65536)]
+    pub max_upload_mb: u64,
+
+    /// Delete the least recently used stored traces whenever uploads,
+    /// downloads, captures and the like take more than this many MB
+    #[arg(long, value_name = "MB")]
+    pub workspace_quota_mb: Option<u64>,
+
+    /// Largest request body accepted by the JSON APIs, in KB
+    #[arg(long, value_name = "KB", default_value_t = 1024)]
+    pub max_api_body_kb: u64,
+
+    /// Soft memory limit for trace_processor in MB
+    #[arg(long, value_name = "MB")]
+    pub tp_memory_limit: Option<u64>,
+
+    /// Soft CPU limit for trace_processor in percent of one core, sustained
+    #[arg(long, value_name = "PERCENT")]
+    pub tp_cpu_limit: Option<f32>,
+
+    /// What to do when trace_processor exceeds a soft limit
+    #[arg(long, value_enum, default_value_t = LimitAction::Warn)]
+    pub tp_limit_action: LimitAction,
+
+    /// Most extra trace_processor sessions running at once, for traces
+    /// opened side by side through /api/sessions
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub max_sessions: usize,
+
+    /// Seconds an extra session may go unused before it is stopped; 0 keeps
+    /// sessions until they are stopped or the launcher exits
+    #[arg(long, value_name = "SECS", default_value_t = 900)]
+    pub session_idle_timeout: u64,
+
+    /// Named set of extra trace_processor flags from the config file's [presets.<name>] tables
+    #[arg(long, value_name = "NAME")]
+    pub tp_preset: Option<String>,
+
+    /// Extra argument for trace_processor_shell, after the preset's (repeatable)
+    #[arg(long, value_name = "ARG", allow_hyphen_values = true)]
+    pub tp_arg: Vec<String>,
+
+    /// Have trace_processor sort the whole trace before loading it, for traces
+    /// with events far out of order
+    #[arg(long)]
+    pub full_sort: bool,
+
+    /// Extra origin allowed to use trace_processor's RPC directly, such as
+    /// https://ui.perfetto.dev (repeatable)
+    #[arg(long, value_name = "ORIGIN")]
+    pub tp_cors_origin: Vec<String>,
+
+    /// PerfettoSQL module package to make available in trace_processor (repeatable)
+    #[arg(long, value_name = "DIR")]
+    pub sql_module_dir: Vec<PathBuf>,
+
+    /// Metric extension directory with protos/ and sql/ for trace_processor (repeatable)
+    #[arg(long, value_name = "DIR", global = true)]
+    pub metric_extension_dir: Vec<PathBuf>,
+
+    /// Directory with unstripped native binaries for symbolization (repeatable)
+    #[arg(long, value_name = "DIR", global = true)]
+    pub symbol_dir: Vec<PathBuf>,
+
+    /// Proguard/R8 mapping file for deobfuscating Java frames (repeatable)
+    #[arg(long, value_name = "[PACKAGE=]FILE", value_parser = parse_map)]
+    pub proguard_map: Vec<ProguardMap>,
+
+    /// Symbol server to download PDBs from, in addition to _NT_SYMBOL_PATH on Windows (repeatable)
+    #[arg(long, value_name = "URL", global = true)]
+    pub symbol_server: Vec<String>,
+
+    /// Also serve files from DIR at /PREFIX/ (repeatable)
+    #[arg(long, value_name = "PREFIX=DIR", value_parser = parse_mount)]
+    pub mount: Vec<(String, PathBuf)>,
+
+    /// Serve the traces in DIR read-only at /trace/NAME/, NAME defaulting to
+    /// the directory's name (repeatable)
+    #[arg(long, value_name = "[NAME=]DIR", value_parser = parse_trace_dir)]
+    pub trace_dir: Vec<(String, PathBuf)>,
+
+    /// Serve files with extension EXT as TYPE, e.g. mjs=text/javascript
+    /// (repeatable)
+    #[arg(long, value_name = "EXT=TYPE", value_parser = parse_mime_type)]
+    pub mime_type: Vec<(String, String)>,
+
+    /// Add header NAME to files whose URL path matches PATTERN, where * matches
+    /// anything; an empty VALUE leaves a default header out (repeatable)
+    #[arg(long, value_name = "PATTERN=NAME:VALUE", value_parser = Rule::parse)]
+    pub header: Vec<Rule>,
+
+    /// Let pages from ORIGIN, such as https://ui.perfetto.dev, read what the
+    /// launcher serves (repeatable)
+    #[arg(long, value_name = "ORIGIN", value_parser = parse_origin)]
+    pub cors_origin: Vec<String>,
+
+    /// Index slice names and extract metadata for traces added to the catalog
+    #[arg(long)]
+    pub index_traces: bool,
+
+    /// Print how long each startup phase took
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Write startup phase timings as a trace file viewable in Perfetto
+    #[arg(long, value_name = "FILE")]
+    pub timings_trace: Option<PathBuf>,
+
+    /// Suppress progress output and print only the UI URL once ready
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Log more: -v what the launcher does, -vv every request, -vvv everything
+    /// (RUST_LOG is used when not given)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Also write log messages, console output and trace_processor's stderr to
+    /// FILE, rotated at 10 MB or daily
+    #[arg(long, value_name = "FILE", global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Print a READY/ERROR JSON line on stdout when startup finishes
+    #[arg(long)]
+    pub machine_readable: bool,
+
+    /// Webhook configuration file, in place of the config file's [webhooks]
+    #[arg(long, value_name = "FILE", global = true)]
+    pub webhooks: Option<PathBuf>,
+
+    /// Users for multi-user mode, in place of the config file's [users]
+    #[arg(long, value_name = "FILE")]
+    pub users: Option<PathBuf>,
+
+    /// How browsers on other machines sign in
+    #[arg(long, value_enum, default_value_t = AuthMode::None)]
+    pub auth: AuthMode,
+
+    /// Language for console messages (defaults to the system locale)
+    #[arg(long, value_enum, global = true)]
+    pub lang: Option<Lang>,
+
+    /// Download the trace from URL (auth headers per host in the config file's [fetch])
+    #[arg(long, value_name = "URL", conflicts_with = "trace")]
+    pub fetch: Option<String>,
+
+    /// Do not open a browser; just print the UI's address
+    #[arg(long = "no-browser", action = clap::ArgAction::SetFalse, global = true)]
+    pub open_browser: bool,
+
+    /// Keep running in the background after the terminal closes, logging to
+    /// --log-file (launcher.log in the data directory by default)
+    #[cfg(unix)]
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Do not show the system tray icon
+    #[cfg(feature = "tray")]
+    #[arg(long = "no-tray", action = clap::ArgAction::SetFalse)]
+    pub tray: bool,
+
+    /// Browser to open the UI with, as a path or a command line such as
+    /// "firefox -P work" (defaults to the system's)
+    #[arg(long, value_name = "COMMAND", global = true)]
+    pub browser: Option<String>,
+
+    /// Start a launcher of its own even if one is already running, instead of
+    /// opening the trace in that one
+    #[arg(long)]
+    pub new_instance: bool,
+
+    /// Page to open in the browser instead of the UI, e.g. /t/<id>#<ui-state>
+    #[arg(long, value_name = "PATH", hide = true)]
+    pub open_path: Option<String>,
+
+    /// Trace file to load into trace_processor_shell
+    pub trace: Option<PathBuf>,
+
+    /// Config files read, lowest precedence first
+    #[arg(skip)]
+    pub config_files: Vec<PathBuf>,
+
+    /// Settings taken from a config file, by argument id, with the file
+    #[arg(skip)]
+    pub from_file: HashMap<&'static str, PathBuf>,
+}
+
+fn parse_mount(text: &str) -> Result<(String, PathBuf), String> {
+    match text.split_once('=') {
+        Some((prefix, dir)) if !prefix.trim_matches('/').is_empty() && !dir.is_empty() => {
+            Ok((prefix.trim_matches('/').to_string(), PathBuf::from(dir)))
+        }
+        _ => Err(format!("expected PREFIX=DIR, got '{}'", text)),
+    }
+}
+
+fn parse_mime_type(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((extension, mime)) if !extension.trim_start_matches('.').is_empty() && !mime.trim().is_empty() => {
+            Ok((extension.trim_start_matches('.').to_string(), mime.trim().to_string()))
+        }
+        _ => Err(format!("expected EXT=TYPE, got '{}'", text)),
+    }
+}
+
+fn parse_origin(text: &str) -> Result<String, String> {
+    if config::is_origin(text) {
+        Ok(text.to_string())
+    } else {
+        Err(format!("expected an origin such as https://ui.perfetto.dev, got '{}'", text))
+    }
+}
+
+fn parse_trace_dir(text: &str) -> Result<(String, PathBuf), String> {
+    let (name, dir) = match text.split_once('=') {
+        Some((name, dir)) => (name.to_string(), PathBuf::from(dir)),
+        None => {
+            let dir = PathBuf::from(text);
+            let name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            (name, dir)
+        }
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || dir.as_os_str().is_empty() {
+        return Err(format!("expected [NAME=]DIR with a NAME without slashes, got '{}'", text));
+    }
+    Ok((name, dir))
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Check that everything needed to run the launcher is in place
+    Doctor,
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Measure trace load time and per-query latency
+    Bench {
+        /// Trace file to load
+        #[arg(long)]
+        trace: PathBuf,
+        /// SQL file with `;`-separated queries to time
+        #[arg(long)]
+        queries: PathBuf,
+        /// Times each query is run
+        #[arg(long, default_value_t = 10)]
+        iterations: u32,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+        /// Write the report to FILE instead of stdout
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Record traces
+    Capture {
+        #[command(subcommand)]
+        command: CaptureCommand,
+    },
+    /// Record a trace with perfetto/tracebox and open it in the UI
+    Record {
+        /// Config template: android, cpu (the default), memory or one from
+        /// record_templates in the data directory
+        #[arg(long, conflicts_with = "config")]
+        template: Option<String>,
+        /// Text-format trace config
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
+        /// Length of the recording, e.g. 30s, instead of the config's
+        #[arg(long, value_parser = parse_duration)]
+        duration: Option<Duration>,
+        /// Record on the adb device with this serial instead of locally
+        #[arg(long, value_name = "SERIAL")]
+        device: Option<String>,
+        /// Trace file to write (defaults to a new one in the data directory)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Save the trace without opening it
+        #[arg(long)]
+        no_open: bool,
+        /// List the config templates and exit
+        #[arg(long)]
+        list_templates: bool,
+    },
+    /// Pull traces from Android devices over adb
+    Android {
+        #[command(subcommand)]
+        command: AndroidCommand,
+    },
+    /// Manage the trace catalog
+    Catalog {
+        #[command(subcommand)]
+        command: CatalogCommand,
+    },
+    /// Delete stored traces and leftover temporary files from the data directory
+    Clean {
+        /// Delete every stored trace that is not pinned
+        #[arg(long)]
+        all: bool,
+        /// Delete stored traces not opened for this long, e.g. 30d
+        #[arg(long, value_parser = parse_duration)]
+        older_than: Option<Duration>,
+        /// Delete the least recently used traces until the stored ones take at
+        /// most this many MB (defaults to --workspace-quota-mb)
+        #[arg(long, value_name = "MB")]
+        quota_mb: Option<u64>,
+        /// Only list what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write a copy of a trace with native symbols from --symbol-dir added
+    Symbolize {
+        /// Trace to symbolize
+        input: PathBuf,
+        /// Symbolized trace to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Convert a trace to another format, e.g. Perfetto protobuf to Chrome JSON
+    Convert {
+        /// Trace to convert
+        input: PathBuf,
+        /// Converted trace to write
+        output: PathBuf,
+        /// Output format; by default the one OUTPUT's extension names
+        #[arg(long, value_enum)]
+        to: Option<TraceFormat>,
+    },
+    /// Compute trace-based metrics, built-in or from --metric-extension-dir
+    Metrics {
+        /// Comma-separated names of metrics to compute
+        #[arg(long, value_delimiter = ',', required_unless_present = "list", requires = "trace")]
+        run: Vec<String>,
+        /// List available metrics, built-in and custom
+        #[arg(long)]
+        list: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = MetricsFormat::Text)]
+        format: MetricsFormat,
+        /// Write the result to FILE instead of stdout
+        #[arg(short, long, value_name = "FILE", conflicts_with = "list")]
+        output: Option<PathBuf>,
+        /// Trace file to compute the metrics on
+        trace: Option<PathBuf>,
+    },
+    /// Run SQL or metrics on many traces, writing each trace's result, all of
+    /// them merged and a summary to a directory
+    Batch {
+        /// Traces, directories of traces or file names with `*`
+        #[arg(required = true)]
+        traces: Vec<PathBuf>,
+        /// File with the SQL to run on each trace
+        #[arg(long, value_name = "FILE", required_unless_present = "metrics", conflicts_with = "metrics")]
+        sql: Option<PathBuf>,
+        /// Comma-separated names of metrics to compute on each trace instead
+        #[arg(long, value_delimiter = ',')]
+        metrics: Vec<String>,
+        /// Directory to write the results to
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+        /// Format of SQL results; metrics are written as JSON
+        #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+        format: OutputFormat,
+        /// Traces to process in parallel (defaults to half the CPU cores)
+        #[arg(long, value_name = "N")]
+        jobs: Option<usize>,
+    },
+    /// Run SQL on a trace and print the result, without the UI
+    Query {
+        /// Trace file to query
+        trace: PathBuf,
+        /// SQL to run (read from --file or stdin if left out)
+        #[arg(conflicts_with = "file")]
+        sql: Option<String>,
+        /// File with the SQL to run, `-` for stdin
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = QueryFormat::Table)]
+        format: QueryFormat,
+    },
+    /// List plugins found in the plugins directory
+    Plugins,
+    /// Generate the reports configured in the config file, or map wall-clock
+    /// times onto a trace
+    #[command(alias = "report")]
+    Reports {
+        #[command(subcommand)]
+        command: ReportsCommand,
+    },
+    /// Update the Perfetto UI in the dist directory, downloading only the
+    /// files that changed
+    UpdateUi {
+        /// Release channel to follow
+        #[arg(long, value_enum, default_value_t = Channel::Stable)]
+        channel: Channel,
+        /// Site to download the UI from
+        #[arg(long, value_name = "URL", default_value = DEFAULT_SOURCE)]
+        source: String,
+        /// Install this version, e.g. v46.0-8a7e2f1c, instead of the channel's latest
+        #[arg(long, value_name = "VERSION", conflicts_with = "channel")]
+        version: Option<String>,
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+        /// Go back to the version installed before the last update
+        #[arg(long, conflicts_with_all = ["channel", "version", "check"])]
+        rollback: bool,
+    },
+    /// Build redistributable archives for offline installs
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommand,
+    },
+    /// Download a trace and open it, in the running launcher if there is one,
+    /// like --fetch
+    Open {
+        /// Trace to download, with the headers and cookies in [fetch]
+        #[arg(long, value_name = "URL")]
+        url: String,
+    },
+    /// Open the trace a perfetto:// link points to
+    OpenUrl {
+        /// Link such as perfetto://open?trace=<id|path|url>&visStart=...
+        url: String,
+    },
+    /// Restart the running launcher's trace_processor, e.g. when a trace got
+    /// it stuck; open UI tabs reconnect by themselves
+    RestartBackend {
+        /// Return once it is started rather than once it answers
+        #[arg(long)]
+        no_wait: bool,
+    },
+    /// Register the launcher as the handler for perfetto:// links
+    UrlHandler {
+        #[command(subcommand)]
+        command: UrlHandlerCommand,
+    },
+    /// Run the launcher as a Windows service started at boot
+    #[cfg(windows)]
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommand,
+    },
+}
+
+#[cfg(windows)]
+#[derive(Subcommand)]
+pub enum ServiceCommand {
+    /// Register the service, e.g. `service install -- --ui-port 10000 --no-browser`
+    Install {
+        /// Arguments the service runs the launcher with, after `--`
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+    /// Stop the service and remove it
+    Uninstall,
+    /// Entry point for the service control manager
+    #[command(hide = true)]
+    Run {
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum UrlHandlerCommand {
+    /// Register with the desktop for this user
+    Install,
+    /// Remove the registration
+    Uninstall,
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommand {
+    /// Zip the launcher, trace_processor, the UI and a start script
+    Create {
+        /// Platform to bundle for (defaults to this one)
+        #[arg(long, value_enum)]
+        platform: Option<Platform>,
+        /// Archive to write, e.g. perfetto_offline.zip
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Launcher binary for the platform (defaults to this one when the platform matches)
+        #[arg(long, value_name = "FILE")]
+        launcher: Option<PathBuf>,
+        /// trace_processor_shell for the platform (defaults to the dist directory's when the platform matches)
+        #[arg(long, value_name = "FILE")]
+        trace_processor: Option<PathBuf>,
+        /// UI version to include, e.g. v47.0-1a2b3c4d (defaults to the installed one)
+        #[arg(long, value_name = "VERSION")]
+        ui_version: Option<String>,
+        /// Site to download --ui-version from
+        #[arg(long, value_name = "URL", default_value = DEFAULT_SOURCE)]
+        ui_source: String,
+        /// Argument the start script passes to the launcher (repeatable)
+        #[arg(long = "arg", value_name = "ARG", allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CaptureCommand {
+    /// Record a trace periodically and rotate captures into the catalog
+    Schedule {
+        /// Time between capture starts, e.g. 1h
+        #[arg(long, value_parser = parse_duration)]
+        every: Duration,
+        /// Length of each capture, e.g. 30s
+        #[arg(long, value_parser = parse_duration)]
+        duration: Duration,
+        /// Text-format trace config
+        #[arg(long)]
+        config: PathBuf,
+        /// Record on the adb device with this serial instead of locally
+        #[arg(long, value_name = "SERIAL")]
+        device: Option<String>,
+        /// Number of captures to keep
+        #[arg(long, default_value_t = 48)]
+        keep: usize,
+        /// Also delete captures older than this, e.g. 7d
+        #[arg(long, value_parser = parse_duration)]
+        max_age: Option<Duration>,
+        /// Index slice names and extract metadata for each capture
+        #[arg(long)]
+        index: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AndroidCommand {
+    /// List the connected devices
+    Devices,
+    /// List the traces in /data/misc/perfetto-traces on a device
+    Traces {
+        /// Device serial (needed when several are connected)
+        #[arg(long, value_name = "SERIAL")]
+        device: Option<String>,
+    },
+    /// Copy traces from a device into the data directory and open them
+    Pull {
+        /// File names in /data/misc/perfetto-traces (defaults to the newest)
+        names: Vec<String>,
+        /// Device serial (needed when several are connected)
+        #[arg(long, value_name = "SERIAL")]
+        device: Option<String>,
+        /// Pull every trace on the device
+        #[arg(long, conflicts_with = "names")]
+        all: bool,
+        /// Add the traces to the catalog without opening them
+        #[arg(long)]
+        no_open: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportsCommand {
+    /// Generate reports now for every trace still missing them
+    Run {
+        /// Report to generate (defaults to all)
+        name: Option<String>,
+        /// Generate for this trace only (catalog id or path), even if it
+        /// already has the report
+        #[arg(long)]
+        trace: Option<String>,
+    },
+    /// Map wall-clock times to trace timestamps and back, with UI links
+    Timeline {
+        /// Trace file to map
+        #[arg(long)]
+        trace: PathBuf,
+        /// Wall-clock time to find in the trace, e.g. "2024-05-01 12:33:05.250";
+        /// UTC unless it ends in Z or an offset like +08:00 (repeatable)
+        #[arg(long, value_name = "TIME")]
+        at: Vec<String>,
+        /// Trace timestamp in ns to convert to wall-clock time (repeatable)
+        #[arg(long, value_name = "NS", allow_negative_numbers = true)]
+        ts: Vec<i64>,
+        /// Width of the UI selection around each point, e.g. 10ms or 1s
+        #[arg(long, value_parser = parse_duration, default_value = "10ms")]
+        window: Duration,
+        /// Address of a running launcher to prefix links with,
+        /// e.g. http://localhost:10001
+        #[arg(long, value_name = "URL")]
+        base_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CatalogCommand {
+    /// Register every trace in a directory and extract its metadata, or
+    /// restore an archive written by `catalog export`
+    Import {
+        /// Directory to scan for traces, or a .tar.zst export
+        path: PathBuf,
+        /// Also scan subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+        /// Traces to process in parallel (defaults to half the CPU cores)
+        #[arg(long, value_name = "N")]
+        jobs: Option<usize>,
+    },
+    /// Write the catalog, search indexes and metadata to a .tar.zst archive
+    Export {
+        /// Archive to write, e.g. catalog.tar.zst
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Include the trace files themselves
+        #[arg(long)]
+        include_traces: bool,
+    },
+    /// Pin a trace so retention never deletes it and it is listed first
+    Pin {
+        /// Catalog id or path of the trace
+        trace: String,
+    },
+    /// Remove the pin from a trace
+    Unpin {
+        /// Catalog id or path of the trace
+        trace: String,
+    },
+}
@@ -0,0 +1,39 @@
+use crate::dist::{served_ui_version, ui_api_version, version_key};
+use crate::trace_processor::query_version;
+use std::path::Path;
+
+/// Outcome of comparing the UI bundle with the trace_processor binary
+pub enum Compat {
+    /// Versions match, or there was not enough information to tell
+    Ok,
+    /// Release versions differ but the RPC API is (or may be) compatible
+    Warning(String),
+    /// The RPC API versions differ; the UI will not work with this binary
+    Mismatch(String),
+}
+
+pub fn check(dist_dir: &Path, trace_processor_path: &Path) -> Compat {
+    let tp = match query_version(trace_processor_path) {
+        Ok(v) => v,
+        Err(_) => return Compat::Ok,
+    };
+
+    if let (Some(ui_api), Some(tp_api)) = (ui_api_version(dist_dir), tp.api_version) {
+        if ui_api != tp_api {
+            return Compat::Mismatch(t!(
+                "compat-api-mismatch",
+                ui = ui_api,
+                tp = tp.version,
+                api = tp_api
+            ));
+        }
+        return Compat::Ok;
+    }
+
+    match served_ui_version(dist_dir) {
+        Some(ui) if version_key(&ui).0 != version_key(&tp.version).0 => {
+            Compat::Warning(t!("compat-release-mismatch", ui = ui, tp = tp.version))
+        }
+        _ => Compat::Ok,
+    }
+}
@@ -0,0 +1,140 @@
+//! Compressed responses for static files, negotiated on `Accept-Encoding`.
+//!
+//! A client accepting `br` or `gzip` gets the `<file>.br` or `<file>.gz`
+//! sibling of a file if the dist directory (or the embedded UI) has one.
+//! With `--compress`, text and WebAssembly files without a sibling are
+//! gzipped on first request and kept in memory for later ones.
+
+use crate::dist::get_mime_type;
+use flate2::write::GzEncoder;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tiny_http::Request;
+
+/// Total size of the gzipped files kept in memory
+const CACHE_LIMIT: usize = 128 * 1024 * 1024;
+
+/// Files smaller than this gain too little from compression
+const MIN_SIZE: u64 = 1024;
+
+/// Files larger than this are not compressed on the fly
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// Name in `Accept-Encoding` and `Content-Encoding`
+    pub fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Extension of a pre-compressed sibling
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Encoding::Brotli => ".br",
+            Encoding::Gzip => ".gz",
+        }
+    }
+}
+
+/// Encodings `request` accepts, most preferred first
+pub fn accepted(request: &Request) -> Vec<Encoding> {
+    let Some(header) = request.headers().iter().find(|h| h.field.equiv("Accept-Encoding")) else {
+        return Vec::new();
+    };
+    let mut accepted = Vec::new();
+    for item in header.value.as_str().split(',') {
+        let mut parts = item.split(';');
+        let name = parts.next().unwrap_or_default().trim();
+        let refused = parts.any(|p| p.trim().strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0));
+        let encoding = match name.to_ascii_lowercase().as_str() {
+            "br" => Encoding::Brotli,
+            "gzip" => Encoding::Gzip,
+            _ => continue,
+        };
+        if !refused {
+            accepted.push(encoding);
+        }
+    }
+    // Brotli compresses the UI's JavaScript and WebAssembly noticeably better
+    accepted.sort_by_key(|e| *e != Encoding::Brotli);
+    accepted
+}
+
+/// Whether a file of `len` bytes at `path` is worth compressing on the fly
+pub fn compressible(path: &Path, len: u64) -> bool {
+    let mime = get_mime_type(path);
+    let textual = mime.starts_with("text/")
+        || mime.starts_with("application/javascript")
+        || mime.starts_with("application/json")
+        || mime == "application/wasm"
+        || mime == "image/svg+xml";
+    textual && (MIN_SIZE..=MAX_SIZE).contains(&len)
+}
+
+struct Entry {
+    /// Size and modification time of the file the entry was made from
+    version: String,
+    data: Arc<[u8]>,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    /// Keys from oldest to newest, for eviction
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+/// Files gzipped on the fly, if `--compress` is on
+pub struct Compression {
+    pub on_the_fly: bool,
+    cache: Mutex<Cache>,
+}
+
+impl Compression {
+    pub fn new(on_the_fly: bool) -> Compression {
+        Compression { on_the_fly, cache: Mutex::new(Cache::default()) }
+    }
+
+    /// Gzipped `contents` of the file `key` at `version`, from the cache or
+    /// compressed now
+    pub fn gzip<F>(&self, key: &str, version: &str, contents: F) -> io::Result<Arc<[u8]>>
+    where
+        F: FnOnce() -> io::Result<Vec<u8>>,
+    {
+        if let Some(entry) = self.cache.lock().unwrap().entries.get(key).filter(|e| e.version == version) {
+            return Ok(entry.data.clone());
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&contents()?)?;
+        let data: Arc<[u8]> = encoder.finish()?.into();
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(old) = cache.entries.remove(key) {
+            cache.bytes -= old.data.len();
+            cache.order.retain(|k| k != key);
+        }
+        while cache.bytes + data.len() > CACHE_LIMIT {
+            let Some(oldest) = cache.order.pop_front() else { break };
+            if let Some(evicted) = cache.entries.remove(&oldest) {
+                cache.bytes -= evicted.data.len();
+            }
+        }
+        if data.len() <= CACHE_LIMIT {
+            cache.bytes += data.len();
+            cache.order.push_back(key.to_string());
+            cache.entries.insert(key.to_string(), Entry { version: version.to_string(), data: data.clone() });
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,326 @@
+//! Resolved launcher configuration and its validation.
+
+use crate::assets;
+use crate::cli::Cli;
+use crate::deobfuscate::ProguardMap;
+use crate::dist::{get_dist_dir, served_ui_version};
+use crate::metrics;
+use crate::presets;
+use crate::sql_modules;
+use crate::tls::Tls;
+use crate::tp_download;
+use crate::trace_processor::trace_processor_path;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+
+/// Where a setting's value came from, so problems can point at it
+#[derive(Clone)]
+pub enum Origin {
+    Default,
+    Cli(&'static str),
+    File(PathBuf),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::Cli(flag) => write!(f, "command line {}", flag),
+            Origin::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A single validation failure
+pub struct Problem {
+    pub origin: Origin,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.origin, self.message)
+    }
+}
+
+pub struct Config {
+    pub dist_dir: PathBuf,
+    pub trace_processor: PathBuf,
+    /// Fixed UI server port; random if None
+    pub ui_port: Option<u16>,
+    /// Fixed trace_processor RPC port; random if None
+    pub rpc_port: Option<u16>,
+    pub trace: Option<PathBuf>,
+    /// URL to download the trace from before starting
+    pub fetch: Option<String>,
+    /// trace_processor release to download before starting, if any; None
+    /// inside means the latest
+    pub tp_download: Option<Option<String>>,
+    /// PerfettoSQL module packages for trace_processor
+    pub sql_module_dirs: Vec<PathBuf>,
+    /// Metric extensions for trace_processor
+    pub metric_extension_dirs: Vec<PathBuf>,
+    /// Directories searched for native symbols
+    pub symbol_dirs: Vec<PathBuf>,
+    /// Symbol servers given on the command line
+    pub symbol_servers: Vec<String>,
+    /// Mapping files for deobfuscating the trace before loading
+    pub proguard_maps: Vec<ProguardMap>,
+    /// Extra directories served by the UI server, by URL prefix
+    pub mounts: Vec<(String, PathBuf)>,
+    /// Trace directories served at `/trace/<name>/`
+    pub trace_dirs: Vec<(String, PathBuf)>,
+    /// Name of the trace_processor flag preset, if any
+    pub tp_preset: Option<String>,
+    /// Flags of that preset; empty if it is not defined
+    pub tp_preset_args: Vec<String>,
+    /// Flags from `--tp-arg` or the config file's `trace_processor_args`
+    pub tp_extra_args: Vec<String>,
+    /// Whether trace_processor sorts the whole trace before loading it
+    pub full_sort: bool,
+    /// Origins besides the launcher's allowed to call trace_processor
+    pub cors_origins: Vec<String>,
+    /// Config files the settings were read from
+    pub config_files: Vec<PathBuf>,
+    /// Certificate for serving the UI over HTTPS; plain HTTP if None
+    pub tls: Option<Tls>,
+    /// Address the UI server listens on; unspecified with `--expose-lan`
+    pub host: IpAddr,
+    origins: HashMap<&'static str, Origin>,
+}
+
+impl Config {
+    pub fn from_cli(cli: &Cli) -> Config {
+        let dist_dir = cli.dist_dir.clone().unwrap_or_else(get_dist_dir);
+        let mut origins = HashMap::new();
+        // Where argument `id` came from, if not the command line
+        let from = |id: &str, flag: &'static str| match cli.from_file.get(id) {
+            Some(path) => Origin::File(path.clone()),
+            None => Origin::Cli(flag),
+        };
+        if cli.dist_dir.is_some() {
+            origins.insert("dist_dir", from("dist_dir", "--dist-dir"));
+        }
+        if cli.tls_cert.is_some() {
+            origins.insert("tls", from("tls_cert", "--tls-cert"));
+        }
+        if cli.trace_processor_bin.is_some() {
+            origins.insert("trace_processor", from("trace_processor_bin", "--trace-processor"));
+        }
+        let trace_processor = cli.trace_processor_bin.clone().unwrap_or_else(|| trace_processor_path(&dist_dir));
+        let tp_download = match &cli.tp_version {
+            Some(version) => {
+                origins.insert("trace_processor", Origin::Cli("--tp-version"));
+                Some(Some(version.clone()))
+            }
+            None if cli.auto_download && !trace_processor.is_file() => {
+                origins.insert("trace_processor", from("auto_download", "--auto-download"));
+                // The release the UI was built with, e.g. v46.0 for v46.0-8a7e2f1c
+                Some(served_ui_version(&dist_dir).map(|v| v.split('-').next().unwrap_or_default().to_string()))
+            }
+            None => None,
+        };
+        if cli.ui_port.is_some() || cli.rpc_port.is_some() {
+            let id = if cli.from_file.contains_key("ui_port") { "ui_port" } else { "rpc_port" };
+            origins.insert("ports", from(id, "--ui-port/--rpc-port"));
+        }
+        if cli.trace.is_some() {
+            origins.insert("trace", Origin::Cli("[TRACE]"));
+        }
+        if !cli.sql_module_dir.is_empty() {
+            origins.insert("sql_module_dirs", from("sql_module_dir", "--sql-module-dir"));
+        }
+        if !cli.symbol_dir.is_empty() {
+            origins.insert("symbol_dirs", from("symbol_dir", "--symbol-dir"));
+        }
+        if !cli.proguard_map.is_empty() {
+            origins.insert("proguard_maps", Origin::Cli("--proguard-map"));
+        }
+        if !cli.mount.is_empty() {
+            origins.insert("mounts", from("mount", "--mount"));
+        }
+        if !cli.trace_dir.is_empty() {
+            origins.insert("trace_dirs", from("trace_dir", "--trace-dir"));
+        }
+        if cli.tp_preset.is_some() {
+            origins.insert("tp_preset", from("tp_preset", "--tp-preset"));
+        }
+        if !cli.metric_extension_dir.is_empty() {
+            origins.insert("metric_extension_dirs", from("metric_extension_dir", "--metric-extension-dir"));
+        }
+        if !cli.tp_cors_origin.is_empty() {
+            origins.insert("cors_origins", from("tp_cors_origin", "--tp-cors-origin"));
+        }
+        if cli.expose_lan {
+            origins.insert("host", from("expose_lan", "--expose-lan"));
+        } else if cli.from_file.contains_key("host") || cli.host != Ipv4Addr::LOCALHOST {
+            origins.insert("host", from("host", "--host"));
+        }
+        Config {
+            trace_processor,
+            tp_download,
+            dist_dir,
+            ui_port: cli.ui_port,
+            rpc_port: cli.rpc_port,
+            trace: cli.trace.clone(),
+            fetch: cli.fetch.clone(),
+            sql_module_dirs: cli.sql_module_dir.clone(),
+            metric_extension_dirs: cli.metric_extension_dir.clone(),
+            symbol_dirs: cli.symbol_dir.clone(),
+            symbol_servers: cli.symbol_server.clone(),
+            proguard_maps: cli.proguard_map.clone(),
+            mounts: cli.mount.clone(),
+            trace_dirs: cli.trace_dir.clone(),
+            tp_preset: cli.tp_preset.clone(),
+            tp_preset_args: cli.tp_preset.as_deref().and_then(|name| presets::resolve(name).ok()).unwrap_or_default(),
+            tp_extra_args: cli.tp_arg.clone(),
+            full_sort: cli.full_sort,
+            cors_origins: cli.tp_cors_origin.clone(),
+            config_files: cli.config_files.clone(),
+            tls: match (&cli.tls_cert, &cli.tls_key) {
+                (Some(cert), Some(key)) => Some(Tls::Files { cert: cert.clone(), key: key.clone() }),
+                _ if cli.self_signed => Some(Tls::SelfSigned),
+                _ => None,
+            },
+            host: if cli.expose_lan { Ipv4Addr::UNSPECIFIED.into() } else { cli.host },
+            origins,
+        }
+    }
+
+    /// Switch to a downloaded trace_processor for `--tp-version`, or for
+    /// `--auto-download` when there is none; the release matching the UI is
+    /// preferred
+    pub fn download_trace_processor(&mut self) -> Result<(), String> {
+        let Some(version) = &self.tp_download else {
+            return Ok(());
+        };
+        let pinned = matches!(self.origin("trace_processor"), Origin::Cli("--tp-version"));
+        self.trace_processor = match tp_download::ensure(version.as_deref()) {
+            Ok(path) => path,
+            // Offline, any cached release beats none unless one was asked for
+            Err(e) if !pinned => tp_download::newest_cached().ok_or(e)?,
+            Err(e) => return Err(e),
+        };
+        self.tp_download = None;
+        Ok(())
+    }
+
+    /// Use a downloaded file as the trace
+    pub fn set_fetched_trace(&mut self, path: PathBuf) {
+        self.trace = Some(path);
+        self.origins.insert("trace", Origin::Cli("--fetch"));
+    }
+
+    /// "https" when the UI is served over TLS, else "http"
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Whether machines other than this one can reach the UI server
+    pub fn exposed(&self) -> bool {
+        !self.host.is_loopback()
+    }
+
+    /// Host in the URLs the launcher prints and opens: `localhost`, unless
+    /// the UI server only listens on another address
+    pub fn url_host(&self) -> String {
+        match self.host {
+            host if host.is_loopback() || host.is_unspecified() => "localhost".to_string(),
+            IpAddr::V6(host) => format!("[{}]", host),
+            IpAddr::V4(host) => host.to_string(),
+        }
+    }
+
+    fn origin(&self, setting: &str) -> Origin {
+        self.origins.get(setting).cloned().unwrap_or(Origin::Default)
+    }
+
+    /// Check every setting and return all problems found, not just the first
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let mut problem = |setting: &str, message: String| {
+            problems.push(Problem { origin: self.origin(setting), message })
+        };
+
+        if assets::has_ui() {
+            // The UI is served from the binary; the dist directory is optional
+        } else if !self.dist_dir.is_dir() {
+            problem("dist_dir", t!("config-dist-missing", path = self.dist_dir.display()));
+        } else if !self.dist_dir.join("index.html").is_file() {
+            let index = self.dist_dir.join("index.html");
+            problem("dist_dir", t!("config-index-missing", path = index.display()));
+        }
+        if self.tp_download.is_none() && !self.trace_processor.is_file() {
+            problem("trace_processor", t!("config-tp-missing", path = self.trace_processor.display()));
+        }
+        if let (Some(ui), Some(rpc)) = (self.ui_port, self.rpc_port) {
+            if ui == rpc {
+                problem("ports", t!("config-ports-equal", port = ui));
+            }
+        }
+        if let Some(trace) = &self.trace {
+            if !trace.is_file() {
+                problem("trace", t!("config-trace-missing", path = trace.display()));
+            }
+        }
+        for dir in &self.sql_module_dirs {
+            if let Err(e) = sql_modules::scan(dir) {
+                problem("sql_module_dirs", e);
+            }
+        }
+        for dir in &self.symbol_dirs {
+            if !dir.is_dir() {
+                problem("symbol_dirs", t!("symbol-dir-missing", path = dir.display()));
+            }
+        }
+        for map in &self.proguard_maps {
+            if !map.path.is_file() {
+                problem("proguard_maps", t!("deobfuscate-map-missing", path = map.path.display()));
+            }
+        }
+        for (prefix, dir) in &self.mounts {
+            if !dir.is_dir() {
+                problem("mounts", t!("config-mount-missing", prefix = prefix, path = dir.display()));
+            }
+        }
+        for (i, (name, dir)) in self.trace_dirs.iter().enumerate() {
+            if self.trace_dirs[..i].iter().any(|(other, _)| other == name) {
+                problem("trace_dirs", t!("config-trace-dir-duplicate", name = name.as_str()));
+            } else if !dir.is_dir() {
+                problem("trace_dirs", t!("config-trace-dir-missing", name = name.as_str(), path = dir.display()));
+            }
+        }
+        if let Some(Err(e)) = self.tp_preset.as_deref().map(presets::resolve) {
+            problem("tp_preset", e);
+        }
+        for dir in &self.metric_extension_dirs {
+            if let Err(e) = metrics::check_extension_dir(dir) {
+                problem("metric_extension_dirs", e);
+            }
+        }
+        for origin in &self.cors_origins {
+            if !is_origin(origin) {
+                problem("cors_origins", t!("config-bad-origin", origin = origin.as_str()));
+            }
+        }
+        // A self-signed certificate is only generated once the launcher starts
+        if let Some(Err(e)) = self.tls.as_ref().filter(|tls| matches!(tls, Tls::Files { .. })).map(Tls::ssl_config) {
+            problem("tls", e);
+        }
+        problems
+    }
+}
+
+/// Whether `text` is a web origin such as `https://ui.perfetto.dev`, which
+/// has no path
+pub fn is_origin(text: &str) -> bool {
+    url::Url::parse(text).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https") && url.has_host() && url.origin().ascii_serialization() == text
+    })
+}
@@ -0,0 +1,453 @@
+//! `perfetto_launcher.toml`: settings for every run, so they need not be
+//! repeated on the command line.
+//!
+//! The file is read from the user's config directory and from next to the
+//! executable. Where both set something the one next to the executable wins,
+//! so a portable install keeps its own settings, and flags given on the
+//! command line override both. Relative paths are taken from the file's
+//! directory. Every key is optional:
+//!
+//! ```toml
+//! ui_port = 10000
+//! rpc_port = 10001
+//! host = "127.0.0.1"
+//! expose_lan = false
+//! tls_cert = "tls/cert.pem"
+//! tls_key = "tls/key.pem"
+//! dist_dir = "ui"
+//! trace_processor = "/opt/perfetto/trace_processor_shell"
+//! trace_processor_args = ["--dev"]
+//! tp_preset = "android"
+//! auto_download = true
+//! open_browser = false
+//! browser = "firefox -P perfetto"
+//! quiet = true
+//! verbose = 1
+//! log_file = "logs/launcher.log"
+//! lang = "zh-TW"
+//! startup_timeout = 600
+//! max_restarts = 5
+//! workers = 16
+//! workspace_quota_mb = 20000
+//! compress = true
+//! spa_fallback = false
+//! sql_module_dirs = ["sql/android"]
+//! symbol_dirs = ["/srv/symbols"]
+//!
+//! [mounts]
+//! shared = "/mnt/traces"
+//!
+//! [trace_dirs]
+//! device-lab = "/mnt/device-lab/traces"
+//!
+//! [mime_types]
+//! trace = "application/octet-stream"
+//!
+//! [headers."/reports/*"]
+//! Cache-Control = "no-store"
+//! ```
+//!
+//! `cors_origins` at the top level are the origins allowed to read what the
+//! launcher serves, like `--cors-origin`; the one in the `trace_processor`
+//! section below is for trace_processor's RPC.
+//!
+//! trace_processor's settings can be grouped in a section of their own
+//! instead, in place of the `trace_processor` and `trace_processor_args`
+//! keys:
+//!
+//! ```toml
+//! [trace_processor]
+//! path = "/opt/perfetto/trace_processor_shell"
+//! extra_args = ["--dev"]
+//! full_sort = true
+//! cors_origins = ["https://ui.perfetto.dev"]
+//! metric_extension_dirs = ["metrics/android"]
+//! ```
+//!
+//! Features with settings of their own read them from tables named after
+//! them, such as `[logging]`; see each feature's module. A table in the file
+//! next to the executable replaces the same table in the user's one whole.
+
+use crate::cli::Cli;
+use crate::config;
+use crate::i18n::Lang;
+use crate::path_headers::Rule;
+use crate::paths::config_dir;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const FILE_NAME: &str = "perfetto_launcher.toml";
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    ui_port: Option<u16>,
+    rpc_port: Option<u16>,
+    host: Option<IpAddr>,
+    expose_lan: Option<bool>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    self_signed: Option<bool>,
+    dist_dir: Option<PathBuf>,
+    trace_processor: Option<TraceProcessorKey>,
+    trace_processor_args: Option<Vec<String>>,
+    tp_preset: Option<String>,
+    auto_download: Option<bool>,
+    open_browser: Option<bool>,
+    browser: Option<String>,
+    quiet: Option<bool>,
+    verbose: Option<u8>,
+    log_file: Option<PathBuf>,
+    lang: Option<String>,
+    startup_timeout: Option<u64>,
+    max_restarts: Option<u32>,
+    workers: Option<u64>,
+    workspace_quota_mb: Option<u64>,
+    compress: Option<bool>,
+    spa_fallback: Option<bool>,
+    sql_module_dirs: Option<Vec<PathBuf>>,
+    symbol_dirs: Option<Vec<PathBuf>>,
+    mounts: Option<BTreeMap<String, PathBuf>>,
+    trace_dirs: Option<BTreeMap<String, PathBuf>>,
+    /// Content types by extension, like `--mime-type`
+    mime_types: Option<BTreeMap<String, String>>,
+    /// Headers by URL path pattern, like `--header`
+    headers: Option<BTreeMap<String, BTreeMap<String, String>>>,
+    cors_origins: Option<Vec<String>>,
+    /// Read by `logs`
+    logging: Option<toml::Table>,
+    /// Read by `presets`
+    presets: Option<toml::Table>,
+    /// Read by `webhooks`
+    webhooks: Option<toml::Table>,
+    /// `[[reports]]`, read by `reports`
+    reports: Option<Vec<toml::Value>>,
+    /// Read by `fetch`
+    fetch: Option<toml::Table>,
+    /// Read by `users`
+    users: Option<toml::Table>,
+}
+
+/// Feature tables by name, with the file each came from
+static SECTIONS: OnceLock<BTreeMap<&'static str, (PathBuf, toml::Value)>> = OnceLock::new();
+
+/// `trace_processor`: the binary's path, or a section of its settings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TraceProcessorKey {
+    Path(PathBuf),
+    Section(TraceProcessorSection),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TraceProcessorSection {
+    path: Option<PathBuf>,
+    /// Forwarded verbatim, like `--tp-arg`
+    extra_args: Option<Vec<String>>,
+    full_sort: Option<bool>,
+    cors_origins: Option<Vec<String>>,
+    metric_extension_dirs: Option<Vec<PathBuf>>,
+}
+
+/// A config file that could not be used
+pub enum FileError {
+    Unreadable(PathBuf, io::Error),
+    /// Not valid TOML, or a key or type the launcher does not know
+    Invalid(PathBuf, String),
+    /// A known key with a value out of its range
+    BadValue { path: PathBuf, key: &'static str, value: String, expected: &'static str },
+}
+
+impl FileError {
+    pub fn message(&self) -> String {
+        match self {
+            FileError::Unreadable(path, e) => t!("config-file-unreadable", path = path.display(), error = e),
+            FileError::Invalid(path, e) => t!("config-file-invalid", path = path.display(), error = e.trim()),
+            FileError::BadValue { path, key, value, expected } => t!(
+                "config-file-bad-value",
+                path = path.display(),
+                key = *key,
+                value = value.as_str(),
+                expected = *expected
+            ),
+        }
+    }
+}
+
+/// Candidate files, lowest precedence first
+pub fn paths() -> Vec<PathBuf> {
+    let mut paths = vec![config_dir().join(FILE_NAME)];
+    let exe_dir = env::current_exe().ok().and_then(|exe| exe.canonicalize().ok()).and_then(|exe| {
+        exe.parent().map(Path::to_path_buf)
+    });
+    if let Some(dir) = exe_dir {
+        paths.push(dir.join(FILE_NAME));
+    }
+    paths
+}
+
+/// Fill in the settings not given on the command line from the config files
+///
+/// Runs before the message language is known, so problems are returned for
+/// printing later rather than printed here.
+pub fn apply(cli: &mut Cli, matches: &ArgMatches) -> Vec<FileError> {
+    let mut errors = Vec::new();
+    let mut sections = BTreeMap::new();
+    for path in paths() {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                errors.push(FileError::Unreadable(path, e));
+                continue;
+            }
+        };
+        let result = toml::from_str::<ConfigFile>(&text)
+            .map_err(|e| FileError::Invalid(path.clone(), e.to_string()))
+            .and_then(|file| file.check(&path).map(|_| file));
+        match result {
+            Ok(file) => {
+                file.apply(cli, matches, &path, &mut sections);
+                cli.config_files.push(path);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let _ = SECTIONS.set(sections);
+    errors
+}
+
+/// Table `name` of the config files and the file it is from, if any sets it
+pub fn section<T: DeserializeOwned>(name: &str) -> Option<(PathBuf, Result<T, String>)> {
+    let (path, value) = SECTIONS.get()?.get(name)?;
+    Some((path.clone(), value.clone().try_into().map_err(|e: toml::de::Error| e.message().to_string())))
+}
+
+impl ConfigFile {
+    /// Range checks the types alone do not cover, matching the command line's
+    fn check(&self, path: &Path) -> Result<(), FileError> {
+        let bad = |key, value: String, expected| FileError::BadValue { path: path.to_path_buf(), key, value, expected };
+        for (key, port) in [("ui_port", self.ui_port), ("rpc_port", self.rpc_port)] {
+            if port == Some(0) {
+                return Err(bad(key, "0".to_string(), "a port from 1 to 65535"));
+            }
+        }
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(_), None) => return Err(bad("tls_key", "nothing".to_string(), "the key for tls_cert")),
+            (None, Some(_)) => return Err(bad("tls_cert", "nothing".to_string(), "the certificate for tls_key")),
+            _ => {}
+        }
+        if let Some(TraceProcessorKey::Section(section)) = &self.trace_processor {
+            if section.extra_args.is_some() && self.trace_processor_args.is_some() {
+                let expected = "nothing, as [trace_processor] has extra_args";
+                return Err(bad("trace_processor_args", "a list".to_string(), expected));
+            }
+        }
+        if self.startup_timeout == Some(0) {
+            return Err(bad("startup_timeout", "0".to_string(), "at least 1 second"));
+        }
+        if let Some(workers) = self.workers.filter(|w| !(1..=1024).contains(w)) {
+            return Err(bad("workers", workers.to_string(), "1 to 1024"));
+        }
+        if let Some(lang) = self.lang.as_deref().filter(|lang| Lang::from_str(lang, true).is_err()) {
+            let expected = "one of en, zh-TW, zh-CN";
+            return Err(bad("lang", lang.to_string(), expected));
+        }
+        for prefix in self.mounts.iter().flat_map(|mounts| mounts.keys()) {
+            if prefix.trim_matches('/').is_empty() {
+                return Err(bad("mounts", format!("\"{}\"", prefix), "a non-empty URL prefix"));
+            }
+        }
+        for name in self.trace_dirs.iter().flat_map(|dirs| dirs.keys()) {
+            if name.is_empty() || name.contains(['/', '\\']) {
+                return Err(bad("trace_dirs", format!("\"{}\"", name), "a non-empty name without slashes"));
+            }
+        }
+        for (extension, mime) in self.mime_types.iter().flatten() {
+            if extension.trim_start_matches('.').is_empty() || mime.trim().is_empty() {
+                let value = format!("{} = \"{}\"", extension, mime);
+                return Err(bad("mime_types", value, "a file extension and a content type"));
+            }
+        }
+        for (pattern, headers) in self.headers.iter().flatten() {
+            for (name, value) in headers {
+                if Rule::new(pattern, name, value).is_err() {
+                    let value = format!("\"{}\": {} = \"{}\"", pattern, name, value);
+                    return Err(bad("headers", value, "a URL path pattern starting with / and a valid header"));
+                }
+            }
+        }
+        for origin in self.cors_origins.iter().flatten() {
+            if !config::is_origin(origin) {
+                return Err(bad("cors_origins", format!("\"{}\"", origin), "an origin such as https://ui.perfetto.dev"));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(
+        self,
+        cli: &mut Cli,
+        matches: &ArgMatches,
+        path: &Path,
+        sections: &mut BTreeMap<&'static str, (PathBuf, toml::Value)>,
+    ) {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let (tp_path, section) = match self.trace_processor {
+            Some(TraceProcessorKey::Path(path)) => (Some(path), TraceProcessorSection::default()),
+            Some(TraceProcessorKey::Section(section)) => (section.path.clone(), section),
+            None => (None, TraceProcessorSection::default()),
+        };
+        let resolve = |p: PathBuf| if p.is_absolute() { p } else { dir.join(p) };
+        let mut applied = Vec::new();
+        // Whether argument `id` should take the file's value, noting it if so
+        let mut take = |id: &'static str, present: bool| {
+            // Settings without a flag are not known to clap and never given
+            let given = matches.try_get_raw(id).is_ok() && matches.value_source(id) == Some(ValueSource::CommandLine);
+            let take = present && !given;
+            if take {
+                applied.push(id);
+            }
+            take
+        };
+
+        if take("ui_port", self.ui_port.is_some()) {
+            cli.ui_port = self.ui_port;
+        }
+        if take("rpc_port", self.rpc_port.is_some()) {
+            cli.rpc_port = self.rpc_port;
+        }
+        // `--host` and `--expose-lan` on the command line both override either key
+        let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let address_given = given("host") || given("expose_lan");
+        if let Some(host) = self.host.filter(|_| !address_given && take("host", true)) {
+            cli.host = host;
+        }
+        if let Some(expose_lan) = self.expose_lan.filter(|_| !address_given && take("expose_lan", true)) {
+            cli.expose_lan = expose_lan;
+        }
+        // Both halves come from the same place, so a file never pairs its key
+        // with a certificate given on the command line
+        if take("tls_cert", self.tls_cert.is_some()) && take("tls_key", true) {
+            cli.tls_cert = self.tls_cert.map(&resolve);
+            cli.tls_key = self.tls_key.map(&resolve);
+        }
+        if let Some(self_signed) = self.self_signed.filter(|_| take("self_signed", true)) {
+            cli.self_signed = self_signed;
+        }
+        if take("dist_dir", self.dist_dir.is_some()) {
+            cli.dist_dir = self.dist_dir.map(resolve);
+        }
+        // A release given on the command line stands in for the path
+        let tp_version_given = matches.value_source("tp_version") == Some(ValueSource::CommandLine);
+        if take("trace_processor_bin", tp_path.is_some() && !tp_version_given) {
+            cli.trace_processor_bin = tp_path.map(resolve);
+        }
+        if let Some(args) = section.extra_args.or(self.trace_processor_args).filter(|_| take("tp_arg", true)) {
+            cli.tp_arg = args;
+        }
+        if let Some(full_sort) = section.full_sort.filter(|_| take("full_sort", true)) {
+            cli.full_sort = full_sort;
+        }
+        if let Some(origins) = section.cors_origins.filter(|_| take("tp_cors_origin", true)) {
+            cli.tp_cors_origin = origins;
+        }
+        if let Some(dirs) = section.metric_extension_dirs.filter(|_| take("metric_extension_dir", true)) {
+            cli.metric_extension_dir = dirs.into_iter().map(resolve).collect();
+        }
+        if take("tp_preset", self.tp_preset.is_some()) {
+            cli.tp_preset = self.tp_preset;
+        }
+        if let Some(auto_download) = self.auto_download.filter(|_| take("auto_download", true)) {
+            cli.auto_download = auto_download;
+        }
+        if let Some(open_browser) = self.open_browser.filter(|_| take("open_browser", true)) {
+            cli.open_browser = open_browser;
+        }
+        if take("browser", self.browser.is_some()) {
+            cli.browser = self.browser;
+        }
+        if let Some(quiet) = self.quiet.filter(|_| take("quiet", true)) {
+            cli.quiet = quiet;
+        }
+        if let Some(verbose) = self.verbose.filter(|_| take("verbose", true)) {
+            cli.verbose = verbose;
+        }
+        if take("log_file", self.log_file.is_some()) {
+            cli.log_file = self.log_file.map(resolve);
+        }
+        if let Some(lang) = self.lang.filter(|_| take("lang", true)) {
+            cli.lang = Lang::from_str(&lang, true).ok();
+        }
+        if let Some(timeout) = self.startup_timeout.filter(|_| take("startup_timeout", true)) {
+            cli.startup_timeout = timeout;
+        }
+        if let Some(max_restarts) = self.max_restarts.filter(|_| take("max_restarts", true)) {
+            cli.max_restarts = max_restarts;
+        }
+        if take("workers", self.workers.is_some()) {
+            cli.workers = self.workers;
+        }
+        if take("workspace_quota_mb", self.workspace_quota_mb.is_some()) {
+            cli.workspace_quota_mb = self.workspace_quota_mb;
+        }
+        if let Some(compress) = self.compress.filter(|_| take("compress", true)) {
+            cli.compress = compress;
+        }
+        if let Some(spa_fallback) = self.spa_fallback.filter(|_| take("spa_fallback", true)) {
+            cli.spa_fallback = spa_fallback;
+        }
+        if let Some(dirs) = self.sql_module_dirs.filter(|_| take("sql_module_dir", true)) {
+            cli.sql_module_dir = dirs.into_iter().map(resolve).collect();
+        }
+        if let Some(dirs) = self.symbol_dirs.filter(|_| take("symbol_dir", true)) {
+            cli.symbol_dir = dirs.into_iter().map(resolve).collect();
+        }
+        if let Some(mounts) = self.mounts.filter(|_| take("mount", true)) {
+            let mounts = mounts.into_iter().map(|(prefix, dir)| (prefix.trim_matches('/').to_string(), resolve(dir)));
+            cli.mount = mounts.collect();
+        }
+        if let Some(dirs) = self.trace_dirs.filter(|_| take("trace_dir", true)) {
+            cli.trace_dir = dirs.into_iter().map(|(name, dir)| (name, resolve(dir))).collect();
+        }
+        if let Some(types) = self.mime_types.filter(|_| take("mime_type", true)) {
+            let types = types.into_iter().map(|(ext, mime)| (ext.trim_start_matches('.').to_string(), mime));
+            cli.mime_type = types.collect();
+        }
+        if let Some(headers) = self.headers.filter(|_| take("header", true)) {
+            let rules = headers.iter().flat_map(|(pattern, headers)| {
+                headers.iter().filter_map(move |(name, value)| Rule::new(pattern, name, value).ok())
+            });
+            cli.header = rules.collect();
+        }
+        if let Some(origins) = self.cors_origins.filter(|_| take("cors_origin", true)) {
+            cli.cors_origin = origins;
+        }
+        for id in applied {
+            cli.from_file.insert(id, path.to_path_buf());
+        }
+        let tables = [
+            ("logging", self.logging.map(toml::Value::Table)),
+            ("presets", self.presets.map(toml::Value::Table)),
+            ("webhooks", self.webhooks.map(toml::Value::Table)),
+            ("reports", self.reports.map(toml::Value::Array)),
+            ("fetch", self.fetch.map(toml::Value::Table)),
+            ("users", self.users.map(toml::Value::Table)),
+        ];
+        for (name, value) in tables {
+            if let Some(value) = value {
+                sections.insert(name, (path.to_path_buf(), value));
+            }
+        }
+    }
+}
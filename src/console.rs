@@ -0,0 +1,44 @@
+//! Human-oriented console output that `--quiet` can silence.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Number of recent console lines kept for crash reports
+const RECENT_LINES: usize = 100;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Remember a line of output for crash reports
+pub fn record(line: String) {
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+pub fn recent_lines() -> Vec<String> {
+    RECENT.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+}
+
+/// `println!` for banners and progress chatter; suppressed by `--quiet`
+macro_rules! say {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        if !$crate::console::is_quiet() {
+            println!("{}", line);
+        }
+        $crate::logs::said(&line);
+        $crate::console::record(line);
+    }};
+}
@@ -0,0 +1,238 @@
+//! `convert` subcommand: write a trace in another format.
+//!
+//! Perfetto protobuf traces are converted by traceconv, which is found next
+//! to trace_processor or on PATH, or with `--auto-download` fetched from the
+//! release trace_processor comes from. ctrace is systrace text compressed
+//! with zlib behind a `TRACE:` line, so those two are converted into each
+//! other here. Nothing converts JSON or systrace back to protobuf.
+//!
+//! The output is written next to its final path and renamed into place once
+//! complete, so a failed conversion leaves no half-written trace behind.
+
+use crate::symbols::find_traceconv;
+use crate::tp_download;
+use crate::trace_processor::query_version;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval of the progress lines
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// First line of ctrace and of atrace's uncompressed output
+const TRACE_HEADER: &[u8] = b"TRACE:\n";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum TraceFormat {
+    /// Perfetto protobuf trace
+    Proto,
+    /// Chrome JSON trace
+    Json,
+    /// systrace / ftrace text
+    Systrace,
+    /// zlib-compressed systrace
+    Ctrace,
+    /// Perfetto protobuf in text form
+    Text,
+}
+
+impl TraceFormat {
+    fn name(self) -> &'static str {
+        match self {
+            TraceFormat::Proto => "proto",
+            TraceFormat::Json => "json",
+            TraceFormat::Systrace => "systrace",
+            TraceFormat::Ctrace => "ctrace",
+            TraceFormat::Text => "text",
+        }
+    }
+
+    /// Format an output file name asks for
+    fn from_extension(path: &Path) -> Option<TraceFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "pftrace" | "perfetto-trace" | "pb" | "proto" => TraceFormat::Proto,
+            "json" => TraceFormat::Json,
+            "systrace" | "txt" => TraceFormat::Systrace,
+            "ctrace" => TraceFormat::Ctrace,
+            "pbtxt" | "textproto" => TraceFormat::Text,
+            _ => return None,
+        })
+    }
+}
+
+/// Where to get traceconv from
+pub struct Tools {
+    /// Directory searched before PATH
+    pub tools_dir: PathBuf,
+    /// trace_processor whose release a downloaded traceconv matches
+    pub trace_processor: PathBuf,
+    pub auto_download: bool,
+}
+
+/// Convert `input` to `output`, in format `to` or else the one its extension names
+pub fn run(tools: &Tools, input: &Path, output: &Path, to: Option<TraceFormat>) -> Result<(), String> {
+    let to = to
+        .or_else(|| TraceFormat::from_extension(output))
+        .ok_or_else(|| t!("convert-unknown-format", path = output.display()))?;
+    let from = sniff(input).map_err(|e| t!("convert-read-failed", path = input.display(), error = e))?;
+    if from == to {
+        return Err(t!("convert-same-format", path = input.display(), format = to.name()));
+    }
+    let (ctrace, systrace) = (TraceFormat::Ctrace, TraceFormat::Systrace);
+    if from != TraceFormat::Proto && (from, to) != (ctrace, systrace) && (from, to) != (systrace, ctrace) {
+        return Err(t!("convert-unsupported", from = from.name(), to = to.name()));
+    }
+    let (input_name, output_name) = (input.display(), output.display());
+    say!("{}", t!("convert-running", input = input_name, from = from.name(), output = output_name, to = to.name()));
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = match (from, to) {
+        (TraceFormat::Ctrace, _) => rewrite(input, &partial, inflate),
+        (TraceFormat::Systrace, _) => rewrite(input, &partial, deflate),
+        _ => traceconv(tools, to, input, &partial),
+    };
+    if let Err(e) = result.and_then(|_| fs::rename(&partial, output).map_err(|e| e.to_string())) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    let size = fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+    say!("{}", t!("convert-done", path = output.display(), size = megabytes(size)));
+    Ok(())
+}
+
+/// Format of the trace at `path`, from its first bytes
+///
+/// Anything that is not recognizably JSON or systrace is taken to be a
+/// protobuf trace, possibly gzipped, and left for traceconv to judge.
+fn sniff(path: &Path) -> io::Result<TraceFormat> {
+    let mut head = Vec::with_capacity(512);
+    File::open(path)?.take(512).read_to_end(&mut head)?;
+    let text = head.iter().position(|b| !b.is_ascii_whitespace()).map_or(&head[..0], |start| &head[start..]);
+    Ok(if text.starts_with(b"{") || text.starts_with(b"[") {
+        TraceFormat::Json
+    } else if let Some(body) = text.strip_prefix(TRACE_HEADER) {
+        // A zlib stream starts with 0x78 for the default window size
+        if body.first() == Some(&0x78) { TraceFormat::Ctrace } else { TraceFormat::Systrace }
+    } else if text.starts_with(b"# tracer:") {
+        TraceFormat::Systrace
+    } else {
+        TraceFormat::Proto
+    })
+}
+
+/// Run `traceconv <format> input output`, reporting the output's size as it grows
+fn traceconv(tools: &Tools, to: TraceFormat, input: &Path, output: &Path) -> Result<(), String> {
+    let traceconv = find(tools)?;
+    let mut child = Command::new(&traceconv)
+        .arg(to.name())
+        .arg(input)
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| t!("convert-traceconv-spawn-failed", path = traceconv.display(), error = e))?;
+    let mut next_progress = Instant::now() + PROGRESS_INTERVAL;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        thread::sleep(Duration::from_millis(100));
+        if Instant::now() >= next_progress {
+            next_progress += PROGRESS_INTERVAL;
+            let written = fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+            say!("{}", t!("convert-progress", size = megabytes(written)));
+        }
+    };
+    if !status.success() {
+        return Err(t!("convert-traceconv-failed", status = status));
+    }
+    Ok(())
+}
+
+/// traceconv from the tools directory, PATH or the download cache, or else
+/// downloaded with `--auto-download`
+fn find(tools: &Tools) -> Result<PathBuf, String> {
+    if let Some(found) = find_traceconv(&tools.tools_dir).or_else(tp_download::newest_cached_traceconv) {
+        return Ok(found);
+    }
+    if !tools.auto_download {
+        return Err(t!("convert-no-traceconv"));
+    }
+    // The release trace_processor is from, e.g. v46.0 for v46.0-8a7e2f1c
+    let release = query_version(&tools.trace_processor)
+        .ok()
+        .map(|v| v.version.split('-').next().unwrap_or_default().to_string());
+    tp_download::ensure_traceconv(release.as_deref())
+}
+
+/// Stream `input` through `convert` into `output`, reporting progress
+fn rewrite(
+    input: &Path,
+    output: &Path,
+    convert: fn(&mut dyn Read, &mut dyn Write) -> io::Result<()>,
+) -> Result<(), String> {
+    let total = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+    let result = (|| -> io::Result<()> {
+        let inner = BufReader::new(File::open(input)?);
+        let mut reader = Progress { inner, read: 0, total, next: Instant::now() + PROGRESS_INTERVAL };
+        let mut writer = BufWriter::new(File::create(output)?);
+        convert(&mut reader, &mut writer)?;
+        writer.flush()
+    })();
+    result.map_err(|e| t!("convert-write-failed", path = output.display(), error = e))
+}
+
+/// ctrace to systrace: the `TRACE:` line, then the decompressed text
+fn inflate(input: &mut dyn Read, output: &mut dyn Write) -> io::Result<()> {
+    let mut header = [0; TRACE_HEADER.len()];
+    input.read_exact(&mut header)?;
+    output.write_all(TRACE_HEADER)?;
+    io::copy(&mut ZlibDecoder::new(input), output)?;
+    Ok(())
+}
+
+/// systrace to ctrace: the `TRACE:` line, then the text without its own one, compressed
+fn deflate(input: &mut dyn Read, output: &mut dyn Write) -> io::Result<()> {
+    let mut head = Vec::with_capacity(TRACE_HEADER.len());
+    input.take(TRACE_HEADER.len() as u64).read_to_end(&mut head)?;
+    output.write_all(TRACE_HEADER)?;
+    let mut encoder = ZlibEncoder::new(output, Compression::default());
+    if head != TRACE_HEADER {
+        encoder.write_all(&head)?;
+    }
+    io::copy(input, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Reader that reports how much of the input has been read so far
+struct Progress<R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    next: Instant,
+}
+
+impl<R: Read> Read for Progress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if Instant::now() >= self.next && self.total > 0 {
+            self.next = Instant::now() + PROGRESS_INTERVAL;
+            say!("{}", t!("convert-progress-percent", percent = self.read * 100 / self.total));
+        }
+        Ok(n)
+    }
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1}", bytes as f64 / (1024.0 * 1024.0))
+}
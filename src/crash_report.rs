@@ -0,0 +1,72 @@
+//! Panic hook that writes a crash report to the data directory.
+
+use crate::console;
+use crate::paths::data_dir;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Key/value facts (config, component versions) included in crash reports
+static CONTEXT: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Record a fact to include in any later crash report
+pub fn set_context(key: &str, value: impl Into<String>) {
+    let mut context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    let value = value.into();
+    match context.iter_mut().find(|(k, _)| k == key) {
+        Some(entry) => entry.1 = value,
+        None => context.push((key.to_string(), value)),
+    }
+}
+
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = build_report(&info.to_string());
+        match write_report(&report) {
+            Ok(path) => eprintln!("\n{}", t!("crash-report-written", path = path.display())),
+            Err(e) => eprintln!("\n{}", t!("crash-report-failed", error = e)),
+        }
+    }));
+}
+
+fn build_report(panic_message: &str) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "perfetto_launcher crash report");
+    let _ = writeln!(report, "==============================\n");
+    let _ = writeln!(report, "Launcher version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "Platform:         {}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let _ = writeln!(report, "Command line:     {:?}\n", std::env::args().collect::<Vec<_>>());
+
+    let _ = writeln!(report, "Panic:\n  {}\n", panic_message);
+
+    let _ = writeln!(report, "Context:");
+    for (key, value) in CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        let _ = writeln!(report, "  {}: {}", key, value);
+    }
+
+    let _ = writeln!(report, "\nRecent output:");
+    for line in console::recent_lines() {
+        let _ = writeln!(report, "  {}", line);
+    }
+
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+    report
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let dir = data_dir().join("crashes");
+    fs::create_dir_all(&dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("crash-{}-{}.txt", timestamp, std::process::id()));
+    fs::write(&path, report)?;
+    Ok(path)
+}
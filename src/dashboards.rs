@@ -0,0 +1,204 @@
+//! Live query dashboards at `/dashboards/<name>`.
+//!
+//! A dashboard is a JSON or YAML file in `dashboards/` in the data directory,
+//! named by its file stem:
+//!
+//! ```yaml
+//! title: Frame health
+//! refresh: 10          # seconds between refreshes; 0 turns it off
+//! panels:
+//!   - title: Janky frames
+//!     query: select count(*) from actual_frame_timeline_slice where jank_type != 'None'
+//!     chart: number    # table (default), number, bar or line
+//!   - title: Slowest slices
+//!     query: select name, max(dur) as dur from slice group by name order by dur desc limit 10
+//!     chart: bar
+//!     x: name
+//!     y: dur
+//! ```
+//!
+//! The page polls `/dashboards/<name>/data`, which runs every query against
+//! the session's trace_processor. Definitions are read on every request, so
+//! edits show up on the next refresh.
+
+use crate::http_errors;
+use crate::landing::escape;
+use crate::paths::data_dir;
+use crate::rpc::RpcClient;
+use crate::server::AppState;
+use crate::stats;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Request, Response};
+
+const PAGE_TEMPLATE: &str = include_str!("../templates/dashboard.html");
+const INDEX_TEMPLATE: &str = include_str!("../templates/dashboards.html");
+
+const EXTENSIONS: &[&str] = &["json", "yaml", "yml"];
+
+#[derive(Deserialize)]
+struct Dashboard {
+    title: Option<String>,
+    #[serde(default = "default_refresh")]
+    refresh: u64,
+    panels: Vec<Panel>,
+}
+
+fn default_refresh() -> u64 {
+    30
+}
+
+#[derive(Deserialize)]
+struct Panel {
+    title: String,
+    query: String,
+    #[serde(default)]
+    chart: Chart,
+    /// Column for labels (bar) or the horizontal axis (line)
+    x: Option<String>,
+    /// Column for values
+    y: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Chart {
+    #[default]
+    Table,
+    Number,
+    Bar,
+    Line,
+}
+
+pub fn dashboards_dir() -> PathBuf {
+    data_dir().join("dashboards")
+}
+
+/// Names of the dashboards in the dashboards directory, sorted
+fn names() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dashboards_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| has_dashboard_extension(path))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn has_dashboard_extension(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|ext| EXTENSIONS.contains(&ext))
+}
+
+/// Load the dashboard called `name`; None if there is no such file
+fn load(name: &str) -> Option<Result<Dashboard, String>> {
+    if name.is_empty() || name.contains(['/', '\\', '.']) {
+        return None;
+    }
+    let path = EXTENSIONS.iter().map(|ext| dashboards_dir().join(format!("{}.{}", name, ext))).find(|p| p.is_file())?;
+    let parsed = fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| {
+        if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&text).map_err(|e| e.to_string())
+        }
+    });
+    Some(parsed.map_err(|e| t!("dashboard-invalid", path = path.display(), error = e)))
+}
+
+/// `/dashboards`, `/dashboards/<name>` and `/dashboards/<name>/data`
+pub fn respond(request: Request, state: &AppState, rest: &str) {
+    let rest = rest.trim_end_matches('/');
+    if rest.is_empty() {
+        return respond_index(request);
+    }
+    let (name, data) = match rest.strip_suffix("/data") {
+        Some(name) => (name, true),
+        None => (rest, false),
+    };
+    match load(name) {
+        None => http_errors::respond(request, 404, &t!("dashboard-not-found", name = name)),
+        Some(Err(e)) => http_errors::respond(request, 500, &e),
+        Some(Ok(dashboard)) if data => respond_data(request, state, &dashboard),
+        Some(Ok(dashboard)) => respond_page(request, name, &dashboard),
+    }
+}
+
+fn respond_html(request: Request, page: String) {
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
+
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut page = template.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    page
+}
+
+fn respond_index(request: Request) {
+    let names = names();
+    let list = if names.is_empty() {
+        format!("<p class=\"empty\">{}</p>", escape(&t!("dashboards-none", path = dashboards_dir().display())))
+    } else {
+        let items: String = names
+            .iter()
+            .map(|name| format!("<li><a href=\"/dashboards/{}\">{}</a></li>", escape(name), escape(name)))
+            .collect();
+        format!("<ul>{}</ul>", items)
+    };
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&t!("dashboards-title"))),
+        ("list", list),
+    ];
+    respond_html(request, fill(INDEX_TEMPLATE, &values));
+}
+
+fn respond_page(request: Request, name: &str, dashboard: &Dashboard) {
+    let title = dashboard.title.clone().unwrap_or_else(|| name.to_string());
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&title)),
+        ("name", escape(name)),
+        ("refresh", dashboard.refresh.to_string()),
+        ("updated", escape(&t!("dashboard-updated"))),
+        ("failed", escape(&t!("dashboard-load-failed"))),
+        ("no_rows", escape(&t!("dashboard-no-rows"))),
+    ];
+    respond_html(request, fill(PAGE_TEMPLATE, &values));
+}
+
+/// Run every panel's query; a failing query only fails its own panel
+fn respond_data(request: Request, state: &AppState, dashboard: &Dashboard) {
+    let client = RpcClient::new(state.rpc_port);
+    let panels: Vec<_> = dashboard
+        .panels
+        .iter()
+        .map(|panel| {
+            let mut result = json!({
+                "title": panel.title,
+                "chart": panel.chart,
+                "x": panel.x,
+                "y": panel.y,
+            });
+            match client.query_rows(&panel.query) {
+                Ok(rows) => {
+                    result["columns"] = json!(rows.columns);
+                    result["rows"] = json!(rows.rows);
+                }
+                Err(e) => result["error"] = json!(e),
+            }
+            result
+        })
+        .collect();
+    let body = json!({ "refresh": dashboard.refresh, "panels": panels }).to_string();
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(body).with_header(content_type));
+}
@@ -0,0 +1,38 @@
+//! Proguard/R8 deobfuscation of Java frames with `--proguard-map`.
+//!
+//! `traceconv deobfuscate` reads the maps from `PERFETTO_PROGUARD_MAP`
+//! (`package=mapping.txt` entries); see `enrich` for the pass that runs it.
+
+use std::path::PathBuf;
+
+/// A mapping file, optionally restricted to one package
+#[derive(Clone)]
+pub struct ProguardMap {
+    pub package: Option<String>,
+    pub path: PathBuf,
+}
+
+/// Parse `[PACKAGE=]FILE`
+pub fn parse_map(text: &str) -> Result<ProguardMap, String> {
+    let (package, path) = match text.split_once('=') {
+        Some((package, path)) => (Some(package.to_string()), path),
+        None => (None, text),
+    };
+    if path.is_empty() {
+        return Err(format!("missing mapping file in '{}'", text));
+    }
+    Ok(ProguardMap { package, path: PathBuf::from(path) })
+}
+
+/// Environment for traceconv naming the mapping files
+pub fn env(maps: &[ProguardMap]) -> Vec<(String, String)> {
+    let value = maps
+        .iter()
+        .map(|map| match &map.package {
+            Some(package) => format!("{}={}", package, map.path.display()),
+            None => map.path.display().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(":");
+    vec![("PERFETTO_PROGUARD_MAP".to_string(), value)]
+}
@@ -0,0 +1,190 @@
+use crate::assets;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Content types from `--mime-type` and the config file's `[mime_types]`,
+/// by lowercase extension
+static MIME_TYPES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Get the dist directory path: the first of these with an index.html, or
+/// else the executable's directory
+///
+/// - the executable's directory
+/// - `Contents/Resources/dist` when inside a macOS app bundle
+/// - `<prefix>/share/perfetto_launcher` for Unix installs to `<prefix>/bin`
+/// - `dist` above `dist/perfetto_launcher/target/{debug,release}` in development
+pub fn get_dist_dir() -> PathBuf {
+    let exe_path = env::current_exe().expect("Failed to get executable path");
+    // Follow symlinks such as /usr/local/bin/perfetto_launcher -> /opt/...
+    let exe_path = exe_path.canonicalize().unwrap_or(exe_path);
+    let exe_dir = exe_path.parent().expect("Failed to get executable directory");
+
+    let mut candidates = vec![exe_dir.to_path_buf()];
+    if exe_dir.ends_with("Contents/MacOS") {
+        if let Some(contents) = exe_dir.parent() {
+            candidates.push(contents.join("Resources").join("dist"));
+        }
+    }
+    if exe_dir.ends_with("bin") {
+        if let Some(prefix) = exe_dir.parent() {
+            candidates.push(prefix.join("share").join("perfetto_launcher"));
+        }
+    }
+    if exe_dir.ends_with("release") || exe_dir.ends_with("debug") {
+        let dist = exe_dir
+            .parent() // target
+            .and_then(|p| p.parent()) // perfetto_launcher
+            .and_then(|p| p.parent()); // dist
+        candidates.extend(dist.map(Path::to_path_buf));
+    }
+    candidates
+        .into_iter()
+        .find(|dir| dir.join("index.html").is_file())
+        .unwrap_or_else(|| exe_dir.to_path_buf())
+}
+
+/// Add to or override the built-in content types; called once at startup
+pub fn set_mime_types(types: &[(String, String)]) {
+    let types = types.iter().map(|(extension, mime)| (extension.to_ascii_lowercase(), mime.clone()));
+    let _ = MIME_TYPES.set(types.collect());
+}
+
+/// Get MIME type based on file extension, configured types first
+pub fn get_mime_type(path: &Path) -> &'static str {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase) else {
+        return "application/octet-stream";
+    };
+    if let Some(mime) = MIME_TYPES.get().and_then(|types| types.get(&extension)) {
+        return mime;
+    }
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" | "cjs" => "application/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json; charset=utf-8",
+        "webmanifest" => "application/manifest+json; charset=utf-8",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml; charset=utf-8",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "map" => "application/json",
+        "gz" => "application/gzip",
+        "zip" => "application/zip",
+        // Traces and the UI's binary data files
+        "pb" | "proto" | "pftrace" | "perfetto-trace" | "data" => "application/octet-stream",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Attribute of an official index.html naming each channel's version
+const VERSION_ATTRIBUTE: &str = "data-perfetto_version='";
+
+/// Version of the Perfetto UI bundle in dist, e.g. "v46.0-8a7e2f1c"
+///
+/// Official UI builds keep their assets in a `v<version>` subdirectory. The
+/// stable version named in index.html is the one the UI loads; failing that
+/// the newest directory is taken, and without any, index.html is scanned for
+/// a version string.
+pub fn ui_version(dist_dir: &Path) -> Option<String> {
+    let index = fs::read_to_string(dist_dir.join("index.html")).ok();
+    let pinned = index.as_deref().and_then(channel_versions).and_then(|mut versions| versions.remove("stable"));
+    if let Some(pinned) = pinned.filter(|v| is_version_string(v) && dist_dir.join(v).is_dir()) {
+        return Some(pinned);
+    }
+    let mut versions: Vec<String> = fs::read_dir(dist_dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| is_version_string(name))
+        .collect();
+    versions.sort_by_key(|v| version_key(v));
+    if let Some(latest) = versions.pop() {
+        return Some(latest);
+    }
+    index?
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+        .find(|w| is_version_string(w))
+        .map(|w| w.to_string())
+}
+
+/// Version directory of an official UI build, which keeps `index.html` and
+/// `service_worker.js` at the top and everything else under `v<version>/`;
+/// None for a flat layout such as a development build
+pub fn versioned_layout(dist_dir: &Path) -> Option<String> {
+    let version = served_ui_version(dist_dir)?;
+    let versioned = assets::ui_version().is_some()
+        || dist_dir.join(&version).is_dir()
+        || dist_dir.join("service_worker.js").is_file();
+    versioned.then_some(version)
+}
+
+/// Version of each channel in an official index.html, e.g.
+/// `data-perfetto_version='{"stable":"v47.0-…","canary":"v48.0-…"}'`
+pub fn channel_versions(index: &str) -> Option<BTreeMap<String, String>> {
+    let start = index.find(VERSION_ATTRIBUTE)? + VERSION_ATTRIBUTE.len();
+    let end = start + index[start..].find('\'')?;
+    serde_json::from_str(&index[start..end]).ok()
+}
+
+/// Version of the UI the launcher serves: the embedded one if the binary has
+/// it, else the one in `dist_dir`
+pub fn served_ui_version(dist_dir: &Path) -> Option<String> {
+    assets::ui_version().or_else(|| ui_version(dist_dir))
+}
+
+pub fn is_version_string(s: &str) -> bool {
+    s.strip_prefix('v')
+        .and_then(|rest| rest.split(['.', '-']).next())
+        .is_some_and(|major| !major.is_empty() && major.chars().all(|c| c.is_ascii_digit()))
+        && s.contains('.')
+}
+
+/// Sort key for "v<major>.<minor>-<hash>" strings
+pub fn version_key(v: &str) -> (u32, u32) {
+    let numeric = v.trim_start_matches('v').split('-').next().unwrap_or("");
+    let mut parts = numeric.split('.').map(|p| p.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
+
+/// The trace_processor RPC API version the UI bundle was built against
+///
+/// The protos bundle compiled into frontend_bundle.js contains the
+/// `TraceProcessorApiVersion` enum, from which the current value is read.
+pub fn ui_api_version(dist_dir: &Path) -> Option<u32> {
+    const MARKER: &str = "\"TRACE_PROCESSOR_CURRENT_API_VERSION\"";
+    let embedded = assets::ui_version().and_then(|v| assets::get(&format!("{}/frontend_bundle.js", v)));
+    let bundle = match embedded {
+        Some(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        None => {
+            let bundle_dir = ui_version(dist_dir)
+                .map(|v| dist_dir.join(v))
+                .filter(|d| d.is_dir())
+                .unwrap_or_else(|| dist_dir.to_path_buf());
+            fs::read_to_string(bundle_dir.join("frontend_bundle.js")).ok()?
+        }
+    };
+    let pos = bundle.find(MARKER)?;
+    // protobufjs emits: values[valuesById[11] = "TRACE_PROCESSOR_CURRENT_API_VERSION"] = 11
+    let after = &bundle[pos + MARKER.len()..];
+    let digits: String = after
+        .trim_start_matches(|c: char| c == ']' || c == '=' || c == ':' || c.is_whitespace())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
@@ -0,0 +1,297 @@
+use crate::assets;
+use crate::compat::{self, Compat};
+use crate::config::Config;
+use crate::dist::{channel_versions, ui_version};
+use crate::paths::{data_dir, find_in_path};
+use crate::ports::get_available_port_with_offset;
+use crate::trace_processor::query_version;
+use crate::ui_update::damaged_files;
+use crate::webhooks;
+use log::{error, warn};
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    remedy: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Check { name, status: Status::Pass, detail: detail.into(), remedy: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, remedy: impl Into<String>) -> Self {
+        Check { name, status: Status::Warn, detail: detail.into(), remedy: Some(remedy.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, remedy: impl Into<String>) -> Self {
+        Check { name, status: Status::Fail, detail: detail.into(), remedy: Some(remedy.into()) }
+    }
+}
+
+/// Run all checks, print a report and return whether every check passed
+pub fn run(config: &Config) -> bool {
+    println!("{}\n", t!("doctor-title"));
+
+    let dist_dir = &config.dist_dir;
+    let tp_path = &config.trace_processor;
+    let checks = vec![
+        check_dist(dist_dir, true),
+        check_trace_processor(tp_path),
+        check_compat(dist_dir, tp_path),
+        check_ports(),
+        check_loopback(),
+        check_data_dir(),
+        check_browser(),
+    ];
+
+    for check in &checks {
+        let tag = match check.status {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        };
+        println!("[{}] {}: {}", tag, check.name, check.detail);
+        if check.status == Status::Fail {
+            webhooks::check_failed(check.name, &check.detail);
+        }
+        if let Some(remedy) = &check.remedy {
+            println!("       -> {}", remedy);
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warned = checks.iter().filter(|c| c.status == Status::Warn).count();
+    println!(
+        "\n{}",
+        t!("doctor-summary", total = checks.len(), failed = failed, warnings = warned)
+    );
+    failed == 0
+}
+
+/// The quick checks, run before every launch unless `--skip-preflight`:
+/// print what is wrong and return whether nothing failed
+pub fn preflight(config: &Config) -> bool {
+    let checks = [check_dist(&config.dist_dir, false), check_loopback(), check_data_dir()];
+    for check in checks.iter().filter(|check| check.status != Status::Pass) {
+        let (name, detail) = (check.name, check.detail.as_str());
+        match check.status {
+            Status::Fail => error!("{}", t!("preflight-failed", check = name, detail = detail)),
+            _ => warn!("{}", t!("preflight-warning", check = name, detail = detail)),
+        }
+        if let Some(remedy) = &check.remedy {
+            warn!("  -> {}", remedy);
+        }
+    }
+    checks.iter().all(|check| check.status != Status::Fail)
+}
+
+/// With `hashes`, every file of the UI version is checked against its
+/// manifest; otherwise only that it is there
+fn check_dist(dist_dir: &Path, hashes: bool) -> Check {
+    const NAME: &str = "UI dist";
+    if let Some(version) = assets::has_ui().then(assets::ui_version) {
+        return Check::pass(NAME, format!("embedded UI {}", version.as_deref().unwrap_or("(unknown version)")));
+    }
+    if !dist_dir.is_dir() {
+        return Check::fail(
+            NAME,
+            format!("{} is not a directory", dist_dir.display()),
+            t!("doctor-fix-dist-dir"),
+        );
+    }
+    if !dist_dir.join("index.html").is_file() {
+        return Check::fail(
+            NAME,
+            format!("index.html not found in {}", dist_dir.display()),
+            t!("doctor-fix-dist-index"),
+        );
+    }
+    let index = fs::read_to_string(dist_dir.join("index.html")).unwrap_or_default();
+    if let Some(pinned) = channel_versions(&index).and_then(|mut versions| versions.remove("stable")) {
+        if !dist_dir.join(&pinned).is_dir() {
+            return Check::fail(
+                NAME,
+                format!("index.html loads {}, which is not in {}", pinned, dist_dir.display()),
+                t!("doctor-fix-dist-version"),
+            );
+        }
+    }
+    let version = ui_version(dist_dir);
+    let checked = version.as_ref().and_then(|version| Some((version, damaged_files(&dist_dir.join(version), hashes)?)));
+    match checked {
+        Some((version, damaged)) if !damaged.is_empty() => Check::fail(
+            NAME,
+            format!("{} files of {} are missing or damaged, e.g. {}", damaged.len(), version, damaged[0]),
+            t!("doctor-fix-dist-damaged"),
+        ),
+        Some((version, _)) => {
+            let how = if hashes { "verified against" } else { "listed in" };
+            Check::pass(NAME, format!("{} ({}, all files {} manifest.json)", dist_dir.display(), version, how))
+        }
+        None if find_wasm(dist_dir).is_none() => Check::warn(
+            NAME,
+            format!("no .wasm files found under {}", dist_dir.display()),
+            t!("doctor-fix-dist-wasm"),
+        ),
+        None => Check::pass(NAME, dist_dir.display().to_string()),
+    }
+}
+
+/// Look for a .wasm file in dist or one of its version subdirectories
+fn find_wasm(dist_dir: &Path) -> Option<PathBuf> {
+    let is_wasm = |p: &Path| p.extension().is_some_and(|e| e == "wasm");
+    for entry in fs::read_dir(dist_dir).ok()?.flatten() {
+        let path = entry.path();
+        if is_wasm(&path) {
+            return Some(path);
+        }
+        if path.is_dir() {
+            if let Some(found) = fs::read_dir(&path)
+                .ok()
+                .and_then(|d| d.flatten().map(|e| e.path()).find(|p| is_wasm(p)))
+            {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+fn check_trace_processor(path: &Path) -> Check {
+    const NAME: &str = "trace_processor";
+    if !path.is_file() {
+        return Check::fail(
+            NAME,
+            format!("not found at {}", path.display()),
+            t!("doctor-fix-tp-missing"),
+        );
+    }
+    match query_version(path) {
+        Ok(v) => {
+            let api = v.api_version.map(|a| format!(", RPC API {}", a)).unwrap_or_default();
+            Check::pass(NAME, format!("{}{} ({})", v.version, api, path.display()))
+        }
+        Err(e) => Check::fail(
+            NAME,
+            e,
+            t!("doctor-fix-tp-run"),
+        ),
+    }
+}
+
+fn check_compat(dist_dir: &Path, tp_path: &Path) -> Check {
+    const NAME: &str = "Compatibility";
+    let remedy = t!("doctor-fix-compat");
+    match compat::check(dist_dir, tp_path) {
+        Compat::Ok => Check::pass(NAME, "UI and trace_processor versions are compatible"),
+        Compat::Warning(msg) => Check::warn(NAME, msg, remedy),
+        Compat::Mismatch(msg) => Check::fail(NAME, msg, remedy),
+    }
+}
+
+fn check_ports() -> Check {
+    const NAME: &str = "Ports";
+    let port = get_available_port_with_offset(10000);
+    match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Check::pass(NAME, format!("able to bind 127.0.0.1:{}", port)),
+        Err(e) => Check::fail(
+            NAME,
+            format!("cannot bind a local port: {}", e),
+            t!("doctor-fix-ports"),
+        ),
+    }
+}
+
+fn check_loopback() -> Check {
+    const NAME: &str = "Loopback";
+    let remedy = t!("doctor-fix-loopback");
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(l) => l,
+        Err(e) => return Check::fail(NAME, format!("cannot listen on 127.0.0.1: {}", e), remedy),
+    };
+    let addr = match listener.local_addr() {
+        Ok(a) => a,
+        Err(e) => return Check::fail(NAME, e.to_string(), remedy),
+    };
+    match TcpStream::connect_timeout(&addr, Duration::from_secs(2)) {
+        Ok(_) => Check::pass(NAME, format!("connected to {}", addr)),
+        Err(e) => Check::fail(NAME, format!("connection to {} failed: {}", addr, e), remedy),
+    }
+}
+
+fn check_data_dir() -> Check {
+    const NAME: &str = "Data directory";
+    let dir = data_dir();
+    let probe = dir.join(".doctor_write_test");
+    let result = fs::create_dir_all(&dir).and_then(|_| fs::write(&probe, b"ok"));
+    let _ = fs::remove_file(&probe);
+    match result {
+        Ok(()) => Check::pass(NAME, format!("{} is writable", dir.display())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("cannot write to {}: {}", dir.display(), e),
+            t!("doctor-fix-data-dir"),
+        ),
+    }
+}
+
+fn check_browser() -> Check {
+    const NAME: &str = "Browser";
+    match find_browser() {
+        Some(path) => Check::pass(NAME, path.display().to_string()),
+        None => Check::warn(
+            NAME,
+            "no Chrome, Edge, Chromium or Firefox installation found",
+            t!("doctor-fix-browser"),
+        ),
+    }
+}
+
+/// Find a known WASM-capable browser installation
+fn find_browser() -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if cfg!(windows) {
+        for var in ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"] {
+            if let Some(base) = std::env::var_os(var).map(PathBuf::from) {
+                candidates.push(base.join(r"Google\Chrome\Application\chrome.exe"));
+                candidates.push(base.join(r"Microsoft\Edge\Application\msedge.exe"));
+                candidates.push(base.join(r"Mozilla Firefox\firefox.exe"));
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        for app in [
+            "Google Chrome.app/Contents/MacOS/Google Chrome",
+            "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+            "Chromium.app/Contents/MacOS/Chromium",
+            "Firefox.app/Contents/MacOS/firefox",
+        ] {
+            candidates.push(Path::new("/Applications").join(app));
+        }
+    }
+    if let Some(found) = candidates.into_iter().find(|p| p.is_file()) {
+        return Some(found);
+    }
+    [
+        "google-chrome",
+        "google-chrome-stable",
+        "chromium",
+        "chromium-browser",
+        "microsoft-edge",
+        "firefox",
+    ]
+    .iter()
+    .find_map(|name| find_in_path(name))
+}
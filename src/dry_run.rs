@@ -0,0 +1,93 @@
+use crate::config::Config;
+use crate::dist::served_ui_version;
+use crate::ports::allocate_ports;
+use crate::tls::{self_signed_paths, Tls};
+use crate::trace_processor::{trace_processor_args, trace_processor_env};
+use std::path::Path;
+
+/// Print everything the launcher would do and return whether it could start
+pub fn print_plan(config: &Config) -> bool {
+    println!("{}\n", t!("dry-run-title"));
+
+    for file in &config.config_files {
+        println!("{}", t!("dry-run-config-file", path = file.display()));
+    }
+    println!("{}", t!("dry-run-dist-dir", path = config.dist_dir.display()));
+    let version = served_ui_version(&config.dist_dir).unwrap_or_else(|| t!("dry-run-unknown"));
+    println!("{}", t!("dry-run-ui-version", version = version));
+    println!("{}", t!("dry-run-trace-processor", path = config.trace_processor.display()));
+    if let Some(trace) = &config.trace {
+        println!("{}", t!("dry-run-trace", path = trace.display()));
+    }
+    if let Some(preset) = &config.tp_preset {
+        println!("{}", t!("dry-run-preset", name = preset, flags = config.tp_preset_args.join(" ")));
+    }
+
+    let (http_port, rpc_port) = allocate_ports(config.ui_port, config.rpc_port);
+    println!("{}", t!("dry-run-ui-port", port = http_port));
+    println!("{}", t!("dry-run-rpc-port", port = rpc_port));
+    println!("{}", t!("dry-run-host", host = config.host));
+    let host = config.url_host();
+    match &config.tls {
+        // The UI's RPC goes to a plain loopback port picked at startup
+        Some(tls) => {
+            match tls {
+                Tls::Files { cert, key } => {
+                    println!("{}", t!("dry-run-tls-files", cert = cert.display(), key = key.display()))
+                }
+                Tls::SelfSigned => {
+                    println!("{}", t!("dry-run-tls-self-signed", path = self_signed_paths().0.display()))
+                }
+            }
+            println!("{}", t!("dry-run-ui-url", url = format!("https://{}:{}/", host, http_port)));
+        }
+        None => {
+            let url = format!("http://{}:{}/?rpc_port={}", host, http_port, http_port);
+            println!("{}", t!("dry-run-ui-url", url = url));
+        }
+    }
+
+    let args = trace_processor_args(rpc_port, config);
+    println!("{}", t!("dry-run-command", command = shell_quote(&config.trace_processor, &args)));
+    for (name, value) in trace_processor_env(config) {
+        println!("{}", t!("dry-run-env", name = name, value = value));
+    }
+    let mut downloads: Vec<String> = config.fetch.iter().cloned().collect();
+    match &config.tp_download {
+        Some(Some(version)) => downloads.push(t!("dry-run-download-tp", version = version)),
+        Some(None) => downloads.push(t!("dry-run-download-tp-latest")),
+        None => {}
+    }
+    if downloads.is_empty() {
+        println!("{}", t!("dry-run-downloads-none"));
+    }
+    for download in downloads {
+        println!("{}", t!("dry-run-download", download = download));
+    }
+
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("\n{}", t!("dry-run-no-problems"));
+        return true;
+    }
+    println!("\n{}", t!("dry-run-problems"));
+    for problem in &problems {
+        println!("  - {}", problem);
+    }
+    false
+}
+
+/// Render a command line that can be copied into a shell
+fn shell_quote(program: &Path, args: &[String]) -> String {
+    let quote = |s: &str| {
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:,=\\".contains(c)) {
+            s.to_string()
+        } else {
+            format!("\"{}\"", s.replace('"', "\\\""))
+        }
+    };
+    std::iter::once(quote(&program.display().to_string()))
+        .chain(args.iter().map(|a| quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,105 @@
+//! Pre-load enrichment: symbolization and deobfuscation passes that turn a
+//! trace into a copy with symbol and deobfuscation packets appended.
+//!
+//! Outputs are cached in `enriched/` in the data directory, keyed by the
+//! trace file and the symbol sources, so reopening the same trace skips the
+//! passes. A trace that turned out to need nothing is remembered too.
+
+use crate::deobfuscate::{self, ProguardMap};
+use crate::symbols::{self, augment, find_traceconv};
+use crate::workspace::{self, Area};
+use log::warn;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub struct Sources<'a> {
+    pub symbol_dirs: &'a [PathBuf],
+    pub proguard_maps: &'a [ProguardMap],
+}
+
+impl Sources<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.symbol_dirs.is_empty() && self.proguard_maps.is_empty()
+    }
+}
+
+/// Path of the trace to load: an enriched copy of `trace` if any pass added
+/// something, `trace` itself otherwise
+pub fn enrich(tools_dir: &Path, trace: &Path, sources: &Sources) -> Result<PathBuf, String> {
+    let Some(traceconv) = find_traceconv(tools_dir) else {
+        // trace_processor still symbolizes during load from PERFETTO_BINARY_PATH
+        if !sources.proguard_maps.is_empty() {
+            warn!("{}", t!("enrich-no-traceconv"));
+        }
+        return Ok(trace.to_path_buf());
+    };
+
+    let dir = workspace::dir(Area::Enriched);
+    let stem = trace.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let key = cache_key(trace, sources);
+    let output = dir.join(format!("{}-{}.pftrace", stem, key));
+    let unchanged_marker = dir.join(format!("{}-{}.unchanged", stem, key));
+    if output.is_file() {
+        say!("{}", t!("enrich-cached", path = output.display()));
+        return Ok(output);
+    }
+    if unchanged_marker.is_file() {
+        return Ok(trace.to_path_buf());
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let mut passes: Vec<(&str, Vec<(String, String)>)> = Vec::new();
+    if !sources.symbol_dirs.is_empty() {
+        passes.push(("symbolize", symbols::env(tools_dir, sources.symbol_dirs)));
+    }
+    if !sources.proguard_maps.is_empty() {
+        passes.push(("deobfuscate", deobfuscate::env(sources.proguard_maps)));
+    }
+
+    // Each pass reads the previous pass's output so packets accumulate
+    let partial = output.with_extension("pftrace.partial");
+    let mut input = trace.to_path_buf();
+    let mut added = 0;
+    for (index, (command, env)) in passes.iter().enumerate() {
+        say!("{}", t!("enrich-pass", index = index + 1, total = passes.len(), pass = command, path = trace.display()));
+        added += augment(&traceconv, command, env, &input, &partial)?;
+        input = partial.clone();
+    }
+
+    if added == 0 {
+        let _ = fs::remove_file(&partial);
+        let _ = fs::write(&unchanged_marker, b"");
+        say!("{}", t!("enrich-nothing"));
+        return Ok(trace.to_path_buf());
+    }
+    fs::rename(&partial, &output).map_err(|e| e.to_string())?;
+    say!("{}", t!("enrich-done", path = output.display(), bytes = added));
+    Ok(output)
+}
+
+/// Hash of everything the enriched output depends on
+fn cache_key(trace: &Path, sources: &Sources) -> String {
+    let mut hasher = DefaultHasher::new();
+    let mut file = |path: &Path| {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf()).hash(&mut hasher);
+        if let Ok(metadata) = fs::metadata(path) {
+            metadata.len().hash(&mut hasher);
+            let modified = metadata.modified().ok().and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+            modified.hash(&mut hasher);
+        }
+    };
+    file(trace);
+    for dir in sources.symbol_dirs {
+        file(dir);
+    }
+    for map in sources.proguard_maps {
+        file(&map.path);
+    }
+    for map in sources.proguard_maps {
+        map.package.hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
@@ -0,0 +1,260 @@
+//! `--fetch <URL>` and `open --url <URL>`: download a trace before starting,
+//! including CI artifact endpoints that need auth headers and wrap the trace
+//! in a zip. `POST /launcher/open-url` does the same for a running launcher.
+//!
+//! Headers and cookies are configured per host in the config file's `[fetch]`
+//! table (see `config_file`). `${VAR}` in a value is replaced with the
+//! environment variable, so tokens need not be written to disk:
+//!
+//! ```toml
+//! [fetch.hosts."ci.example.com"]
+//! Authorization = "Bearer ${CI_TOKEN}"
+//!
+//! [fetch.hosts."api.github.com"]
+//! Authorization = "token ${GITHUB_TOKEN}"
+//!
+//! [fetch.cookies."jenkins.example.com"]
+//! JSESSIONID = "${JENKINS_SESSION}"
+//! ```
+//!
+//! Without the table, the same settings are read from `fetch.json` in the
+//! data directory, where earlier versions kept them.
+//!
+//! Redirects are followed by hand so that each hop only gets the headers of
+//! its own host; a token for the CI server is never sent to the storage
+//! service it redirects to.
+
+use crate::config_file;
+use crate::import;
+use crate::landing;
+use crate::paths::data_dir;
+use crate::server::{query_param, respond_too_large, AppState, LimitedReader};
+use crate::traces_api::respond_error;
+use crate::users::Viewer;
+use crate::workspace::{self, Area};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tiny_http::Request;
+use url::Url;
+
+const MAX_REDIRECTS: usize = 10;
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+#[derive(Deserialize)]
+struct OpenUrlRequest {
+    url: String,
+}
+
+#[derive(Default, Deserialize)]
+struct FetchFile {
+    /// Request headers by host
+    #[serde(default)]
+    hosts: HashMap<String, HashMap<String, String>>,
+    /// Cookies by host, sent as one `Cookie` header
+    #[serde(default)]
+    cookies: HashMap<String, HashMap<String, String>>,
+}
+
+pub fn config_path() -> PathBuf {
+    data_dir().join("fetch.json")
+}
+
+/// Download `url` into the downloads area and return the trace path,
+/// extracted from the archive if the download is a zip
+pub fn fetch(url: &str) -> Result<PathBuf, String> {
+    let config = load_config()?;
+    let mut url = parse_url(url)?;
+    let agent = ureq::AgentBuilder::new().redirects(0).timeout_connect(Duration::from_secs(30)).build();
+
+    let mut redirects = 0;
+    let response = loop {
+        let mut request = agent.get(url.as_str());
+        let host = url.host_str().unwrap_or_default();
+        for (name, value) in config.hosts.get(host).into_iter().flatten() {
+            request = request.set(name, &expand_env(value));
+        }
+        if let Some(cookies) = config.cookies.get(host).filter(|cookies| !cookies.is_empty()) {
+            let pairs: Vec<String> =
+                cookies.iter().map(|(name, value)| format!("{}={}", name, expand_env(value))).collect();
+            request = request.set("Cookie", &pairs.join("; "));
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                return Err(t!("fetch-http-error", url = url, status = status, reason = response.status_text()));
+            }
+            Err(e) => return Err(t!("fetch-failed", url = url, error = e)),
+        };
+        if !(300..400).contains(&response.status()) {
+            break response;
+        }
+        redirects += 1;
+        let location = response.header("Location").unwrap_or_default();
+        if redirects > MAX_REDIRECTS || location.is_empty() {
+            return Err(t!("fetch-too-many-redirects", url = url));
+        }
+        url = url.join(location).map_err(|e| t!("fetch-bad-url", url = location, error = e))?;
+    };
+
+    let name = file_name(&response, &url);
+    let dir = workspace::dir(Area::Downloads);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(&name);
+    say!("{}", t!("fetch-downloading", url = url, path = path.display()));
+    // Into the scratch directory first, so a failed download leaves nothing behind
+    let partial = workspace::temp_path(&name).map_err(|e| e.to_string())?;
+    let mut file = File::create(&partial).map_err(|e| e.to_string())?;
+    if let Err(e) = io::copy(&mut response.into_reader(), &mut file) {
+        drop(file);
+        let _ = fs::remove_file(&partial);
+        return Err(t!("fetch-failed", url = url, error = e));
+    }
+    drop(file);
+
+    let mut magic = [0u8; 4];
+    let is_zip = File::open(&partial).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == ZIP_MAGIC;
+    if is_zip {
+        let extracted = extract_trace(&partial, &path);
+        let _ = fs::remove_file(&partial);
+        return extracted;
+    }
+    fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// `url` if it is one to download from: HTTP or HTTPS
+fn parse_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url).map_err(|e| t!("fetch-bad-url", url = url, error = e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(t!("fetch-bad-url", url = url, error = t!("fetch-http-only")));
+    }
+    Ok(parsed)
+}
+
+/// `POST /launcher/open-url` with `{"url": "<trace URL>"}` or `?url=`:
+/// download the trace into the workspace, register it and open it like
+/// `/launcher/open-trace`
+///
+/// The download uses the credentials in `[fetch]`, so in multi-user mode
+/// only admins may ask for one.
+pub fn respond_open_url(mut request: Request, state: &AppState, viewer: &Viewer) {
+    if matches!(viewer, Viewer::User { admin: false, .. }) {
+        return respond_error(request, 403, t!("fetch-admins-only"));
+    }
+    let url = match query_param(request.url(), "url") {
+        Some(url) => url,
+        None => {
+            let limit = state.body_limits.api;
+            let mut body = Vec::new();
+            if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+                return respond_too_large(request, limit);
+            }
+            match serde_json::from_slice::<OpenUrlRequest>(&body) {
+                Ok(open) => open.url,
+                Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+            }
+        }
+    };
+    if let Err(e) = parse_url(&url) {
+        return respond_error(request, 400, e);
+    }
+    let path = match fetch(&url) {
+        Ok(path) => path,
+        Err(e) => return respond_error(request, 502, e),
+    };
+    match landing::register(state, viewer, &path) {
+        Ok(trace) => landing::respond_opened(request, state, &trace),
+        Err(e) => respond_error(request, 500, t!("catalog-add-failed", path = path.display(), error = e)),
+    }
+}
+
+fn load_config() -> Result<FetchFile, String> {
+    if let Some((path, config)) = config_file::section("fetch") {
+        return config.map_err(|e| t!("fetch-config-invalid", path = path.display(), error = e));
+    }
+    let path = config_path();
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| t!("fetch-config-invalid", path = path.display(), error = e))
+        }
+        Err(_) => Ok(FetchFile::default()),
+    }
+}
+
+/// Replace `${VAR}` with the value of environment variable `VAR`
+fn expand_env(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else { break };
+        out.push_str(&rest[..start]);
+        out.push_str(&env::var(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Name from `Content-Disposition`, else the last path segment of the URL
+fn file_name(response: &ureq::Response, url: &Url) -> String {
+    let disposition = response.header("Content-Disposition").and_then(|value| {
+        let name = value.split(';').find_map(|part| part.trim().strip_prefix("filename="))?;
+        Some(name.trim_matches('"').to_string())
+    });
+    let name = disposition
+        .or_else(|| url.path_segments()?.next_back().map(str::to_string))
+        .unwrap_or_default();
+    let name = sanitize(&name);
+    if name.is_empty() {
+        "download".to_string()
+    } else {
+        name
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or("");
+    base.chars()
+        .filter(|c| !c.is_control() && !"<>:\"|?*".contains(*c))
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Extract the largest trace-looking file from a zip next to where the
+/// download would have gone, `download`
+fn extract_trace(archive_path: &Path, download: &Path) -> Result<PathBuf, String> {
+    let read = |e: zip::result::ZipError| t!("fetch-zip-failed", path = archive_path.display(), error = e);
+    let mut archive = zip::ZipArchive::new(File::open(archive_path).map_err(|e| e.to_string())?).map_err(read)?;
+    let mut best: Option<(bool, u64, usize)> = None;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(read)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else { continue };
+        let candidate = (import::has_trace_extension(&name), entry.size(), index);
+        if best.is_none_or(|b| (candidate.0, candidate.1) > (b.0, b.1)) {
+            best = Some(candidate);
+        }
+    }
+    let Some((_, _, index)) = best else {
+        return Err(t!("fetch-zip-empty", path = archive_path.display()));
+    };
+
+    let mut entry = archive.by_index(index).map_err(read)?;
+    let name = entry.enclosed_name().and_then(|n| Some(sanitize(&n.file_name()?.to_string_lossy())));
+    let stem = download.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let dir = download.with_file_name(format!("{}-extracted", stem));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(name.filter(|n| !n.is_empty()).unwrap_or_else(|| "trace".to_string()));
+    let mut file = File::create(&path).map_err(|e| e.to_string())?;
+    io::copy(&mut entry, &mut file).map_err(|e| t!("fetch-zip-failed", path = archive_path.display(), error = e))?;
+    say!("{}", t!("fetch-extracted", path = path.display()));
+    Ok(path)
+}
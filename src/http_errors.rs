@@ -0,0 +1,103 @@
+//! Error responses of the UI server.
+//!
+//! Browsers get a short page saying what went wrong with a few things to
+//! check; API clients, which ask for `application/json` or call `/api/`, get
+//! `{"error": "..."}` like the rest of the API. HEAD requests get the same
+//! headers without a body, which tiny_http takes care of.
+
+use crate::landing::escape;
+use crate::security_headers;
+use crate::stats;
+use serde_json::json;
+use tiny_http::{Header, Request, Response, StatusCode};
+
+const TEMPLATE: &str = include_str!("../templates/error.html");
+
+/// Answer with `status`; `detail` says what exactly was not found or failed
+pub fn respond(request: Request, status: u16, detail: &str) {
+    respond_with(request, status, detail, Vec::new());
+}
+
+fn respond_with(request: Request, status: u16, detail: &str, headers: Vec<Header>) {
+    let reason = StatusCode(status).default_reason_phrase();
+    if wants_json(&request) {
+        let message = if detail.is_empty() { reason } else { detail };
+        let mut response = Response::from_string(json!({ "error": message }).to_string()).with_status_code(status);
+        response.add_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        for header in headers {
+            response.add_header(header);
+        }
+        stats::respond(request, response);
+        return;
+    }
+    let (summary, hint) = match status {
+        403 => (t!("error-forbidden"), t!("error-forbidden-hint")),
+        404 => (t!("error-not-found"), t!("error-not-found-hint")),
+        405 => (t!("error-method"), t!("error-method-hint")),
+        500.. => (t!("error-server"), t!("error-server-hint")),
+        _ => (String::new(), String::new()),
+    };
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&format!("{} {}", status, reason))),
+        ("summary", escape(&summary)),
+        ("detail", escape(detail)),
+        ("hint", escape(&hint)),
+        ("back", escape(&t!("recent-back"))),
+        ("status", escape(&t!("landing-link-status"))),
+    ];
+    let mut page = TEMPLATE.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    let mut response = Response::from_string(page).with_status_code(status);
+    response.add_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap());
+    for header in headers {
+        response.add_header(header);
+    }
+    stats::respond(request, response);
+}
+
+pub fn not_found(request: Request) {
+    respond(request, 404, "");
+}
+
+pub fn forbidden(request: Request) {
+    respond(request, 403, "");
+}
+
+/// 405 with the methods the path does answer, e.g. `"GET, HEAD"`
+pub fn method_not_allowed(request: Request, allow: &str) {
+    let allow = format!("{}, OPTIONS", allow);
+    let detail = t!("error-method-detail", method = request.method().as_str(), allow = allow.as_str());
+    respond_with(request, 405, &detail, vec![Header::from_bytes("Allow", allow).unwrap()]);
+}
+
+/// Answer `OPTIONS`, including CORS preflights
+///
+/// Origins allowed with `--cors-origin` may only read; requests that change
+/// anything must come from the launcher's own pages. The allowed origin
+/// itself is added by `security_headers` as the response is sent.
+pub fn respond_options(request: Request, allow: &str) {
+    let allow = format!("{}, OPTIONS", allow);
+    let mut response = Response::empty(204).with_header(Header::from_bytes("Allow", allow.as_str()).unwrap());
+    let header = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.to_string())
+    };
+    if security_headers::allowed_origin(&request).is_some() && header("Access-Control-Request-Method").is_some() {
+        response.add_header(Header::from_bytes("Access-Control-Allow-Methods", "GET, HEAD").unwrap());
+        response.add_header(Header::from_bytes("Access-Control-Max-Age", "86400").unwrap());
+        if let Some(requested) = header("Access-Control-Request-Headers") {
+            response.add_header(Header::from_bytes("Access-Control-Allow-Headers", requested).unwrap());
+        }
+    }
+    stats::respond(request, response);
+}
+
+fn wants_json(request: &Request) -> bool {
+    request.url().starts_with("/api/")
+        || request
+            .headers()
+            .iter()
+            .any(|h| h.field.equiv("Accept") && h.value.as_str().trim_start().starts_with("application/json"))
+}
@@ -0,0 +1,128 @@
+//! Translations of user-facing messages.
+//!
+//! Messages live in Fluent-style `locales/<lang>.ftl` files embedded in the
+//! binary: one `key = value` per line, with `{ $name }` placeholders. Keys
+//! missing from a locale fall back to English.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Lang {
+    #[value(name = "en")]
+    En,
+    #[value(name = "zh-TW")]
+    ZhTw,
+    #[value(name = "zh-CN")]
+    ZhCn,
+}
+
+impl Lang {
+    /// Map a locale tag such as "zh_TW.UTF-8", "zh-Hant-HK" or "en-US"
+    pub fn from_locale(tag: &str) -> Lang {
+        let tag = tag.split('.').next().unwrap_or(tag).replace('_', "-").to_ascii_lowercase();
+        if !tag.starts_with("zh") {
+            return Lang::En;
+        }
+        if tag.contains("hant") || tag.ends_with("-tw") || tag.ends_with("-hk") || tag.ends_with("-mo") {
+            Lang::ZhTw
+        } else {
+            Lang::ZhCn
+        }
+    }
+
+    /// Detect the language from the environment or the OS user locale
+    pub fn detect() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+            .or_else(sys_locale::get_locale)
+            .map(|tag| Lang::from_locale(&tag))
+            .unwrap_or(Lang::En)
+    }
+
+    /// BCP 47 tag, e.g. for an HTML `lang` attribute
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::ZhTw => "zh-TW",
+            Lang::ZhCn => "zh-CN",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.ftl"),
+            Lang::ZhTw => include_str!("../locales/zh-TW.ftl"),
+            Lang::ZhCn => include_str!("../locales/zh-CN.ftl"),
+        }
+    }
+}
+
+struct Catalog {
+    lang: Lang,
+    messages: HashMap<&'static str, &'static str>,
+    fallback: HashMap<&'static str, &'static str>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Select the language for the rest of the process; only the first call has an effect
+pub fn init(lang: Option<Lang>) {
+    let lang = lang.unwrap_or_else(Lang::detect);
+    let _ = CATALOG.set(Catalog {
+        lang,
+        messages: parse(lang.source()),
+        fallback: parse(Lang::En.source()),
+    });
+}
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog {
+        lang: Lang::En,
+        messages: parse(Lang::En.source()),
+        fallback: parse(Lang::En.source()),
+    })
+}
+
+/// The language selected by `init`
+pub fn current() -> Lang {
+    catalog().lang
+}
+
+fn parse(source: &'static str) -> HashMap<&'static str, &'static str> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect()
+}
+
+/// Look up `key` and substitute `{ $name }` placeholders
+pub fn message(key: &str, args: &[(&str, String)]) -> String {
+    let catalog = catalog();
+    let template = catalog
+        .messages
+        .get(key)
+        .or_else(|| catalog.fallback.get(key))
+        .copied()
+        .unwrap_or(key);
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{ ${} }}", name), value);
+    }
+    text
+}
+
+/// Translate a message: `t!("key")` or `t!("key", path = p.display(), port = 80)`
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
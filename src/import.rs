@@ -0,0 +1,144 @@
+//! `catalog import`: register every trace under a directory and ingest them
+//! with a bounded pool of trace_processor instances.
+
+use crate::catalog::{Catalog, Trace};
+use crate::ingest::{self, Ingest};
+use crate::reports;
+use log::error;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// File extensions of trace formats trace_processor understands
+const TRACE_EXTENSIONS: &[&str] = &[
+    "pftrace",
+    "perfetto-trace",
+    "perfetto",
+    "pb",
+    "trace",
+    "ctrace",
+    "systrace",
+    "atrace",
+    "json",
+    "gz",
+    "zip",
+];
+
+#[derive(Default)]
+struct Summary {
+    found: usize,
+    added: usize,
+    already_known: usize,
+    ingested: usize,
+    failed: Vec<(String, String)>,
+}
+
+pub fn run(dir: &Path, recursive: bool, jobs: Option<usize>, ingest: Ingest) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Err(t!("import-not-dir", path = dir.display()));
+    }
+    let mut paths = Vec::new();
+    collect(dir, recursive, &mut paths);
+    paths.sort();
+
+    let mut summary = Summary { found: paths.len(), ..Summary::default() };
+    let mut catalog = Catalog::open();
+    let mut pending = VecDeque::new();
+    for path in &paths {
+        let known = catalog.contains_path(path);
+        match catalog.add(path) {
+            Ok(trace) => {
+                if known {
+                    summary.already_known += 1;
+                } else {
+                    summary.added += 1;
+                }
+                if !ingest::is_done(&trace) {
+                    pending.push_back(trace);
+                }
+            }
+            Err(e) => summary.failed.push((path.display().to_string(), e.to_string())),
+        }
+    }
+
+    let jobs = jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get() / 2))
+        .max(1);
+    say!("{}", t!("import-ingesting", count = pending.len(), jobs = jobs));
+    let total = pending.len();
+    let queue = Mutex::new(pending);
+    let done = AtomicUsize::new(0);
+    let results: Mutex<Vec<(Trace, Result<usize, String>)>> = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let Some(trace) = queue.lock().unwrap().pop_front() else { return };
+                let result = ingest.run(&trace);
+                let n = done.fetch_add(1, Ordering::SeqCst) + 1;
+                match &result {
+                    Ok(_) => {
+                        say!("[{}/{}] {}", n, total, trace.path.display());
+                        reports::trace_ingested(&trace);
+                    }
+                    Err(e) => error!("[{}/{}] {}: {}", n, total, trace.path.display(), e),
+                }
+                results.lock().unwrap().push((trace, result));
+            });
+        }
+    });
+    for (trace, result) in results.into_inner().unwrap() {
+        match result {
+            Ok(_) => summary.ingested += 1,
+            Err(e) => summary.failed.push((trace.path.display().to_string(), e)),
+        }
+    }
+
+    say!("\n{}", t!("import-summary-title"));
+    say!("  {}", t!("import-summary-found", count = summary.found));
+    say!("  {}", t!("import-summary-added", count = summary.added));
+    say!("  {}", t!("import-summary-known", count = summary.already_known));
+    say!("  {}", t!("import-summary-ingested", count = summary.ingested));
+    say!("  {}", t!("import-summary-failed", count = summary.failed.len()));
+    for (path, error) in &summary.failed {
+        say!("    {}: {}", path, error);
+    }
+    Ok(())
+}
+
+/// Add the traces in `dir`, and in its subdirectories if `recursive`, to `out`
+pub fn collect(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if recursive {
+                collect(&path, recursive, out);
+            }
+        } else if file_type.is_file() && is_trace(&path) {
+            out.push(path);
+        }
+    }
+}
+
+pub fn has_trace_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| TRACE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Known trace extension, or an extensionless file that starts like a
+/// protobuf trace (a length-delimited `Trace.packet` field)
+fn is_trace(path: &Path) -> bool {
+    match path.extension() {
+        Some(_) => has_trace_extension(path),
+        None => {
+            let mut first = [0u8; 1];
+            File::open(path).and_then(|mut f| f.read_exact(&mut first)).is_ok() && first[0] == 0x0a
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Work done when a trace is added to the catalog: loading it into a private
+//! trace_processor and extracting what later lookups need.
+
+use crate::catalog::Trace;
+use crate::metadata;
+use crate::reports;
+use crate::rpc::RpcClient;
+use crate::search;
+use crate::trace_processor::Instance;
+use log::warn;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// How long a trace may take to load for ingest
+const LOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Clone)]
+pub struct Ingest {
+    pub trace_processor: PathBuf,
+    /// Index and extract metadata; off unless `--index-traces` is given
+    pub enabled: bool,
+}
+
+impl Ingest {
+    /// Process `trace` in the background with its own trace_processor
+    pub fn submit(&self, trace: Trace) {
+        if !self.enabled || is_done(&trace) {
+            return;
+        }
+        let ingest = self.clone();
+        thread::spawn(move || report(&trace, ingest.run(&trace)));
+    }
+
+    /// Process `trace` with its own trace_processor, blocking until done;
+    /// returns the number of slice names indexed
+    pub fn run(&self, trace: &Trace) -> Result<usize, String> {
+        let instance = Instance::start(&self.trace_processor, &trace.path, LOAD_TIMEOUT)?;
+        process(&instance.client, trace)
+    }
+
+    /// Process `trace` using a trace_processor that already has it loaded
+    pub fn run_with(&self, client: &RpcClient, trace: &Trace) {
+        if self.enabled && !is_done(trace) {
+            report(trace, process(client, trace));
+        }
+    }
+}
+
+/// Print the outcome and run the reports due on ingest
+fn report(trace: &Trace, result: Result<usize, String>) {
+    match result {
+        Ok(count) => {
+            say!("{}", t!("ingest-indexed", name = trace.name, count = count));
+            reports::trace_ingested(trace);
+        }
+        Err(e) => warn!("{}", t!("ingest-failed", name = trace.name, error = e)),
+    }
+}
+
+/// Delete everything extracted for trace `id`
+pub fn forget(id: &str) {
+    search::remove(id);
+    metadata::remove(id);
+}
+
+pub fn is_done(trace: &Trace) -> bool {
+    search::is_indexed(&trace.id) && metadata::is_extracted(&trace.id)
+}
+
+fn process(client: &RpcClient, trace: &Trace) -> Result<usize, String> {
+    let count = search::build(client, &trace.id)?;
+    metadata::extract(client, &trace.id)?;
+    Ok(count)
+}
@@ -0,0 +1,74 @@
+//! `instance.json` in the data directory: where the running launcher serves,
+//! so other invocations (such as `perfetto://` links) can reach it, and the
+//! token `POST /launcher/shutdown` wants.
+
+use crate::paths::data_dir;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+/// How long a liveness probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize)]
+pub struct Instance {
+    pub pid: u32,
+    /// `localhost`, or the address the UI server only listens on
+    #[serde(default = "localhost")]
+    pub host: String,
+    pub http_port: u16,
+    pub rpc_port: u16,
+    #[serde(default)]
+    pub shutdown_token: String,
+}
+
+impl Instance {
+    pub fn base_url(&self) -> String {
+        format!("http://{}:{}", self.host, self.http_port)
+    }
+
+    /// `base_url` for requests from this process rather than a browser,
+    /// with `localhost` pinned to IPv4 where the launcher listens
+    pub fn request_url(&self) -> String {
+        let host = if self.host == "localhost" { "127.0.0.1" } else { &self.host };
+        format!("http://{}:{}", host, self.http_port)
+    }
+}
+
+fn localhost() -> String {
+    "localhost".to_string()
+}
+
+fn path() -> PathBuf {
+    data_dir().join("instance.json")
+}
+
+/// Record this process as the running instance
+pub fn register(host: &str, http_port: u16, rpc_port: u16, shutdown_token: &str) {
+    let shutdown_token = shutdown_token.to_string();
+    let instance = Instance { pid: process::id(), host: host.to_string(), http_port, rpc_port, shutdown_token };
+    let _ = fs::create_dir_all(data_dir());
+    let _ = fs::write(path(), serde_json::to_string_pretty(&instance).unwrap_or_default());
+}
+
+/// Remove the record if it is still ours
+pub fn unregister() {
+    let ours = read().is_some_and(|instance| instance.pid == process::id());
+    if ours {
+        let _ = fs::remove_file(path());
+    }
+}
+
+fn read() -> Option<Instance> {
+    serde_json::from_str(&fs::read_to_string(path()).ok()?).ok()
+}
+
+/// The recorded instance, if it answers on its status endpoint
+pub fn running() -> Option<Instance> {
+    let instance = read()?;
+    let url = format!("{}/launcher/status", instance.request_url());
+    ureq::get(&url).timeout(PROBE_TIMEOUT).call().ok()?;
+    Some(instance)
+}
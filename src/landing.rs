@@ -0,0 +1,317 @@
+//! Launcher landing page at `/launcher/` with trace upload.
+//!
+//! Uploaded traces are stored in the data directory, registered in the
+//! catalog and opened through their permalink, which loads them in the UI via
+//! its `?url=` parameter pointing back at `/launcher/traces/<id>`.
+//!
+//! `POST /launcher/open-trace` takes the same uploads from scripts, for
+//! example on another machine, and opens the trace in a browser on the
+//! launcher's machine right away.
+
+use crate::browser;
+use crate::catalog::Trace;
+use crate::http_errors;
+use crate::multipart::{self, FilePart};
+use crate::server::{origin, query_param, respond_too_large, serve_file, AppState, LimitedReader};
+use crate::stats;
+use crate::users::Viewer;
+use crate::workspace::{self, Area};
+use log::warn;
+use serde_json::json;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Request, Response};
+
+const TEMPLATE: &str = include_str!("../templates/landing.html");
+
+/// Maximum number of uploaded traces listed on the page
+const RECENT_LIMIT: usize = 20;
+
+pub fn respond_page(request: Request, state: &AppState, viewer: &Viewer) {
+    let ui_url = format!("/?rpc_port={}", state.ui_rpc_port);
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&t!("landing-title"))),
+        ("drop_hint", escape(&t!("landing-drop-hint"))),
+        ("upload_failed", escape(&t!("landing-upload-failed"))),
+        ("sessions_heading", escape(&t!("landing-sessions"))),
+        ("sessions", sessions_html(state, viewer, &ui_url)),
+        ("recent_heading", escape(&t!("landing-recent"))),
+        ("recent", recent_html(state, viewer)),
+        ("links_heading", escape(&t!("landing-links"))),
+        ("ui_url", escape(&ui_url)),
+        ("link_ui", escape(&t!("landing-link-ui"))),
+        ("link_status", escape(&t!("landing-link-status"))),
+        ("link_tp_logs", escape(&t!("landing-link-tp-logs"))),
+        ("link_recent", escape(&t!("landing-link-recent"))),
+        ("link_android", escape(&t!("android-title"))),
+        ("link_dashboards", escape(&t!("dashboards-title"))),
+        ("link_reports", escape(&t!("reports-title"))),
+        ("link_docs", escape(&t!("landing-link-docs"))),
+    ];
+    let mut page = TEMPLATE.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
+
+fn sessions_html(state: &AppState, viewer: &Viewer, ui_url: &str) -> String {
+    let trace = match &state.trace {
+        Some(trace) => trace.display().to_string(),
+        None => t!("landing-no-trace"),
+    };
+    let status = if state.backend.try_wait().is_none() {
+        t!("landing-session-running", pid = state.backend.pid())
+    } else {
+        t!("landing-session-stopped")
+    };
+    let mut items = format!(
+        "<li><a href=\"{}\">{}</a> <span class=\"meta\">{}</span></li>",
+        escape(ui_url),
+        escape(&trace),
+        escape(&status)
+    );
+    for session in state.sessions.list().iter().filter(|s| viewer.can_see(&s.trace)) {
+        let status = if session.alive() {
+            t!("landing-session-running", pid = session.pid())
+        } else {
+            t!("landing-session-stopped")
+        };
+        items.push_str(&format!(
+            "<li><a href=\"/?rpc_port={}\">{}</a> <span class=\"meta\">{}</span></li>",
+            session.port,
+            escape(&session.trace.name),
+            escape(&status)
+        ));
+    }
+    // In multi-user mode, also the traces this user opened
+    let opened = state.users.as_ref().map(|users| users.sessions(viewer)).unwrap_or_default();
+    let catalog = state.catalog.lock().unwrap();
+    for opened in opened {
+        let Some(trace) = catalog.get(&opened.id).filter(|trace| viewer.can_see(trace)) else { continue };
+        let time = format!("{:02}:{:02} UTC", opened.at % 86400 / 3600, opened.at % 3600 / 60);
+        items.push_str(&format!(
+            "<li><a href=\"/t/{}\">{}</a> <span class=\"meta\">{}</span></li>",
+            trace.id,
+            escape(&trace.name),
+            escape(&t!("landing-session-opened", time = time))
+        ));
+    }
+    format!("<ul>{}</ul>", items)
+}
+
+fn recent_html(state: &AppState, viewer: &Viewer) -> String {
+    let mut traces: Vec<Trace> =
+        state.catalog.lock().unwrap().traces().iter().filter(|trace| viewer.can_see(trace)).cloned().collect();
+    if traces.is_empty() {
+        return format!("<p class=\"empty\">{}</p>", escape(&t!("landing-no-recent")));
+    }
+    traces.sort_by_key(|trace| (Reverse(trace.pinned), Reverse(trace.added)));
+    let items: String = traces
+        .iter()
+        .take(RECENT_LIMIT)
+        .map(|trace| {
+            format!(
+                "<li>{}<a href=\"/t/{}\">{}</a> <span class=\"meta\">{:.1} MB</span></li>",
+                if trace.pinned { "&#9733; " } else { "" },
+                trace.id,
+                escape(&trace.name),
+                trace.size as f64 / (1024.0 * 1024.0)
+            )
+        })
+        .collect();
+    format!("<ul>{}</ul>", items)
+}
+
+/// `POST /launcher/upload?name=<file name>` with the trace as the body
+pub fn handle_upload(mut request: Request, state: &AppState, viewer: &Viewer) {
+    let response = match store_upload(&mut request, state, viewer) {
+        Err(e) if e.kind() == io::ErrorKind::FileTooLarge => {
+            return respond_too_large(request, state.body_limits.upload)
+        }
+        Err(e) if e.kind() == io::ErrorKind::QuotaExceeded => {
+            Response::from_string(e.to_string()).with_status_code(413)
+        }
+        Ok(trace) => {
+            let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+            let body = json!({ "id": trace.id, "name": trace.name }).to_string();
+            Response::from_string(body).with_header(content_type)
+        }
+        Err(e) => Response::from_string(t!("landing-upload-error", error = e)).with_status_code(500),
+    };
+    stats::respond(request, response);
+}
+
+/// `POST /launcher/open-trace?name=<file name>` with the trace as the body:
+/// store it like an upload and open it in the launcher's browser, unless
+/// `open=false` is given or the launcher runs with `--no-browser`
+pub fn handle_open_trace(mut request: Request, state: &AppState, viewer: &Viewer) {
+    let trace = match store_upload(&mut request, state, viewer) {
+        Ok(trace) => trace,
+        Err(e) if e.kind() == io::ErrorKind::FileTooLarge => {
+            return respond_too_large(request, state.body_limits.upload)
+        }
+        Err(e) => {
+            let status = match e.kind() {
+                io::ErrorKind::QuotaExceeded => 413,
+                io::ErrorKind::InvalidData => 400,
+                _ => 500,
+            };
+            let message = if status == 413 { e.to_string() } else { t!("landing-upload-error", error = e) };
+            stats::respond(request, Response::from_string(message).with_status_code(status));
+            return;
+        }
+    };
+    respond_opened(request, state, &trace);
+}
+
+/// Open a newly stored trace in the launcher's browser, unless `open=false`
+/// is given or the launcher runs with `--no-browser`, and answer with its link
+pub fn respond_opened(request: Request, state: &AppState, trace: &Trace) {
+    let link = format!("/t/{}", trace.id);
+    let wanted = query_param(request.url(), "open").is_none_or(|open| open != "false" && open != "0");
+    let url = format!("{}://{}:{}{}", state.scheme, state.url_host, state.http_port, link);
+    let opened = wanted
+        && state.open_browser
+        && match browser::open(&url, state.browser.as_deref()) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}", t!("browser-failed", error = e));
+                false
+            }
+        };
+    if opened {
+        say!("{}", t!("landing-trace-opened", name = trace.name.as_str()));
+    }
+    let body = json!({
+        "id": trace.id,
+        "name": trace.name,
+        "link": format!("{}{}", origin(&request), link),
+        "opened": opened,
+    });
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    stats::respond(request, Response::from_string(body.to_string()).with_header(content_type));
+}
+
+/// Save the trace of an upload request, register it in the catalog as owned
+/// by `viewer` and queue it for ingest
+///
+/// The body is either the raw trace or a `multipart/form-data` form, of which
+/// the first file is taken. The file is named by the `?name=` parameter,
+/// else by the form's file name. Fails with `QuotaExceeded` if the upload
+/// would take the viewer past their upload quota.
+pub fn store_upload(request: &mut Request, state: &AppState, viewer: &Viewer) -> io::Result<Trace> {
+    let boundary = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Content-Type"))
+        .and_then(|h| multipart::boundary(h.value.as_str()));
+    let name = query_param(request.url(), "name");
+    let quota = viewer.remaining_quota(state.catalog.lock().unwrap().traces());
+    let quota_exceeded = || {
+        let remaining = format!("{:.1}", quota.unwrap_or(0) as f64 / (1024.0 * 1024.0));
+        io::Error::new(io::ErrorKind::QuotaExceeded, t!("users-quota-exceeded", remaining = remaining))
+    };
+    if quota.is_some_and(|quota| request.body_length().is_some_and(|length| length as u64 > quota)) {
+        return Err(quota_exceeded());
+    }
+
+    let limit = quota.map_or(state.body_limits.upload, |quota| quota.min(state.body_limits.upload));
+    let body = LimitedReader::new(request.as_reader(), limit);
+    let (mut body, name): (Box<dyn Read>, _) = match boundary {
+        Some(boundary) => {
+            let (part, file_name) = FilePart::open(body, &boundary)?;
+            (Box::new(part), name.unwrap_or(file_name))
+        }
+        None => (Box::new(body), name.unwrap_or_default()),
+    };
+
+    fs::create_dir_all(workspace::dir(Area::Uploads))?;
+    let (path, mut file) = create_unique(&sanitize_name(&name))?;
+    if let Err(e) = io::copy(&mut body, &mut file) {
+        drop(file);
+        let _ = fs::remove_file(&path);
+        let over_quota = e.kind() == io::ErrorKind::FileTooLarge && limit < state.body_limits.upload;
+        return Err(if over_quota { quota_exceeded() } else { e });
+    }
+    drop(file);
+    register(state, viewer, &path)
+}
+
+/// Register a trace stored in the workspace as owned by `viewer`, converted
+/// by a plugin first if one handles it, and queue it for ingest; older
+/// traces are deleted if the workspace is now over its quota
+pub fn register(state: &AppState, viewer: &Viewer, path: &Path) -> io::Result<Trace> {
+    let path = state.plugins.convert(path).map_err(io::Error::other)?;
+    let trace = {
+        let mut catalog = state.catalog.lock().unwrap();
+        let trace = catalog.add(&path)?;
+        let trace = match viewer.name() {
+            Some(owner) => catalog.update(&trace.id, |t| t.owner = Some(owner.to_string()))?.unwrap_or(trace),
+            None => trace,
+        };
+        if let Some(quota) = state.workspace_quota {
+            let serving = state.trace.as_deref().into_iter();
+            workspace::enforce_quota(&mut catalog, quota, &serving.chain([path.as_path()]).collect::<Vec<_>>());
+        }
+        trace
+    };
+    state.ingest.submit(trace.clone());
+    Ok(trace)
+}
+
+/// `GET /launcher/traces/<id>` for a catalog trace
+pub fn serve_trace(request: Request, state: &AppState, id: &str) {
+    let path = state.catalog.lock().unwrap().get(id).map(|t| t.path.clone());
+    match path {
+        Some(path) => serve_file(request, &path),
+        None => http_errors::respond(request, 404, &t!("catalog-unknown-id", id = id)),
+    }
+}
+
+/// Create `name`, or the first free `<stem>-N.<ext>`, in the uploads
+/// directory; creating the file is what claims the name, so two uploads of
+/// the same name at once never share a file
+fn create_unique(name: &str) -> io::Result<(PathBuf, File)> {
+    let dir = workspace::dir(Area::Uploads);
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    let mut path = dir.join(name);
+    for n in 1.. {
+        match File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => path = dir.join(format!("{}-{}{}", stem, n, ext)),
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("every file name is taken")
+}
+
+/// Keep only characters that are safe in a file name and a URL path segment
+fn sanitize_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        "trace".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
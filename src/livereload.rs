@@ -0,0 +1,115 @@
+//! Live reload for UI development, enabled with `--watch`.
+//!
+//! The dist directory is watched for changes. `/launcher/livereload` is a
+//! Server-Sent Events stream with a `reload` event, whose data is the JSON
+//! list of changed paths, after each burst of changes; a build writing many
+//! files gives one event. `index.html` is served with a snippet that reloads
+//! the page on that event, so open UI tabs pick up a rebuilt UI by
+//! themselves.
+
+use crate::shutdown;
+use crate::sse::{self, KEEP_ALIVE};
+use log::{debug, warn};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde_json::json;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::Request;
+
+/// Quiet time that ends a burst of changes
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Added to `index.html` before `</body>`
+pub const SNIPPET: &str = "<script>new EventSource('/launcher/livereload')\
+.addEventListener('reload', function () { location.reload(); });</script>";
+
+/// The latest burst of changes, numbered so streams notice each one once
+#[derive(Default)]
+struct Changes {
+    generation: u64,
+    paths: Vec<String>,
+}
+
+pub struct LiveReload {
+    changes: Mutex<Changes>,
+    changed: Condvar,
+    /// Kept for as long as the launcher runs; dropping it stops watching
+    _watcher: Mutex<notify::RecommendedWatcher>,
+}
+
+impl LiveReload {
+    /// Watch `dir` and everything below it
+    pub fn start(dir: &Path) -> notify::Result<Arc<LiveReload>> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        let live_reload = Arc::new(LiveReload {
+            changes: Mutex::new(Changes::default()),
+            changed: Condvar::new(),
+            _watcher: Mutex::new(watcher),
+        });
+
+        let (reloader, dir) = (live_reload.clone(), dir.to_path_buf());
+        thread::spawn(move || {
+            let changed_paths = |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => event.paths,
+                Ok(_) => Vec::new(),
+                Err(e) => {
+                    warn!("{}", t!("livereload-watch-error", error = e));
+                    Vec::new()
+                }
+            };
+            while let Ok(event) = events.recv() {
+                let mut paths: Vec<PathBuf> = changed_paths(event);
+                while let Ok(event) = events.recv_timeout(SETTLE) {
+                    paths.extend(changed_paths(event));
+                }
+                if paths.is_empty() {
+                    continue;
+                }
+                let mut paths: Vec<String> = paths
+                    .iter()
+                    .map(|p| p.strip_prefix(&dir).unwrap_or(p).to_string_lossy().replace('\\', "/"))
+                    .collect();
+                paths.sort();
+                paths.dedup();
+                debug!("{}", t!("livereload-changed", count = paths.len()));
+                let mut changes = reloader.changes.lock().unwrap();
+                changes.generation += 1;
+                changes.paths = paths;
+                reloader.changed.notify_all();
+            }
+        });
+        Ok(live_reload)
+    }
+
+    /// Stream `reload` events to the client until it goes away or the
+    /// launcher shuts down
+    pub fn respond(self: &Arc<Self>, request: Request) {
+        let live_reload = self.clone();
+        sse::stream(request, move |writer| live_reload.stream(writer));
+    }
+
+    fn stream(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let mut seen = self.changes.lock().unwrap().generation;
+        while !shutdown::requested() {
+            let changes = self.changes.lock().unwrap();
+            let (changes, _) =
+                self.changed.wait_timeout_while(changes, KEEP_ALIVE, |changes| changes.generation == seen).unwrap();
+            let message = if changes.generation == seen {
+                ": keep-alive\n\n".to_string()
+            } else {
+                seen = changes.generation;
+                format!("event: reload\ndata: {}\n\n", json!(changes.paths))
+            };
+            drop(changes);
+            writer.write_all(message.as_bytes())?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
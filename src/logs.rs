@@ -0,0 +1,438 @@
+//! Access and trace_processor logs on disk, rotated by size and age.
+//!
+//! Enabled by a `[logging]` table in the config file (see `config_file`);
+//! every key is optional, and a relative `dir` is taken from the file's
+//! directory:
+//!
+//! ```toml
+//! [logging]
+//! dir = "/var/log/perfetto_launcher"
+//! access_log = true
+//! child_log = true
+//! rotate_size_mb = 10
+//! rotate_age_hours = 24
+//! compress = true
+//! keep_files = 10
+//! keep_days = 30
+//! ```
+//!
+//! Without the table, the same settings are read from `logging.json` in the
+//! data directory, where earlier versions kept them.
+//!
+//! `access.log` gets a line per request and `trace_processor.log` the
+//! child's stdout and stderr. A log past its size or age is renamed to
+//! `<log>.<timestamp>`, compressed to `.zst` in the background, and the
+//! oldest rotated files beyond `keep_files` or `keep_days` are deleted.
+//!
+//! Independently of that, messages go through the `log` facade. Errors and
+//! warnings are always printed; `-v` adds the launcher's info messages,
+//! `-vv` debug ones such as a line per request and `-vvv` everything. Without
+//! `-v`, `RUST_LOG` is read as `level` or `target=level` pairs separated by
+//! commas, e.g. `warn,perfetto_launcher::access=debug`. `--log-file` writes
+//! every message at info level or above, the console output and
+//! trace_processor's output to a file rotated like the logs above.
+
+use crate::config_file;
+use crate::dates::civil_from_days;
+use crate::paths::data_dir;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tiny_http::Request;
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+struct LoggingFile {
+    /// Defaults to `logs` in the data directory
+    dir: Option<PathBuf>,
+    access_log: bool,
+    child_log: bool,
+    rotate_size_mb: u64,
+    /// None rotates by size only
+    rotate_age_hours: Option<u64>,
+    compress: bool,
+    /// Rotated files kept per log
+    keep_files: usize,
+    /// Rotated files older than this are deleted regardless of count
+    keep_days: Option<u64>,
+}
+
+impl Default for LoggingFile {
+    fn default() -> Self {
+        LoggingFile {
+            dir: None,
+            access_log: true,
+            child_log: true,
+            rotate_size_mb: 10,
+            rotate_age_hours: Some(24),
+            compress: true,
+            keep_files: 10,
+            keep_days: None,
+        }
+    }
+}
+
+/// One log file and when it was started
+struct RotatingLog {
+    path: PathBuf,
+    config: LoggingFile,
+    file: File,
+    size: u64,
+    started: SystemTime,
+    /// Timestamp of the last rotation and how many shared it
+    last_rotation: (String, u32),
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, config: LoggingFile) -> io::Result<RotatingLog> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let started = metadata.created().or_else(|_| metadata.modified()).unwrap_or_else(|_| SystemTime::now());
+        Ok(RotatingLog { path, config, file, size: metadata.len(), started, last_rotation: (String::new(), 0) })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.is_due(line.len() as u64 + 1) {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn is_due(&self, incoming: u64) -> bool {
+        let max_size = self.config.rotate_size_mb.saturating_mul(1024 * 1024);
+        let too_big = self.size > 0 && self.size + incoming > max_size;
+        let too_old = self.config.rotate_age_hours.is_some_and(|hours| {
+            self.started.elapsed().unwrap_or_default() > Duration::from_secs(hours * 3600)
+        });
+        too_big || (too_old && self.size > 0)
+    }
+
+    /// Move the current file aside and start a new one
+    fn rotate(&mut self) -> io::Result<()> {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let stamp = file_timestamp();
+        // Later rotations within the same second get a counter: `<stamp>.1`, ...
+        let n = if self.last_rotation.0 == stamp { self.last_rotation.1 + 1 } else { 0 };
+        let rotated = match n {
+            0 => self.path.with_file_name(format!("{}.{}", name, stamp)),
+            n => self.path.with_file_name(format!("{}.{}.{}", name, stamp, n)),
+        };
+        self.last_rotation = (stamp, n);
+        fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        self.started = SystemTime::now();
+
+        let (path, config) = (self.path.clone(), self.config.clone());
+        thread::spawn(move || {
+            if config.compress {
+                if let Err(e) = compress(&rotated) {
+                    eprintln!("{}", t!("logs-compress-failed", path = rotated.display(), error = e));
+                }
+            }
+            prune(&path, &config);
+        });
+        Ok(())
+    }
+}
+
+/// Replace `path` with `path.zst`
+fn compress(path: &Path) -> io::Result<()> {
+    let mut target = path.as_os_str().to_owned();
+    target.push(".zst");
+    let mut input = File::open(path)?;
+    let mut encoder = zstd::Encoder::new(File::create(&target)?, 0)?;
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+/// Delete rotated copies of `log` beyond the retention limits, oldest first
+fn prune(log: &Path, config: &LoggingFile) {
+    let (Some(dir), Some(name)) = (log.parent(), log.file_name().and_then(|n| n.to_str())) else { return };
+    let prefix = format!("{}.", name);
+    let mut rotated: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_str().is_some_and(|n| n.starts_with(&prefix)))
+        .map(|entry| entry.path())
+        .collect();
+    // Newest first, by the timestamp and counter in the name
+    rotated.sort_by_key(|path| {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let suffix = name[prefix.len()..].trim_end_matches(".zst");
+        let (stamp, n) = suffix.split_once('.').unwrap_or((suffix, "0"));
+        Reverse((stamp.to_string(), n.parse::<u32>().unwrap_or(0)))
+    });
+    let max_age = config.keep_days.map(|days| Duration::from_secs(days * 86400));
+    for (index, path) in rotated.iter().enumerate() {
+        let age = fs::metadata(path).and_then(|m| m.modified()).ok().and_then(|m| m.elapsed().ok());
+        let expired = max_age.is_some_and(|max| age.is_some_and(|age| age > max));
+        if index >= config.keep_files || expired {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+struct Logs {
+    access: Option<Mutex<RotatingLog>>,
+    child: Option<Mutex<RotatingLog>>,
+}
+
+static LOGS: OnceLock<Logs> = OnceLock::new();
+
+pub fn config_path() -> PathBuf {
+    data_dir().join("logging.json")
+}
+
+/// Open the logs configured in `[logging]` or `logging.json`; without
+/// either nothing is logged to disk
+pub fn init() {
+    let (path, config) = match config_file::section::<LoggingFile>("logging") {
+        Some((path, config)) => {
+            let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+            let config = config.map(|config| LoggingFile { dir: config.dir.map(|d| dir.join(d)), ..config });
+            (path, config)
+        }
+        None => {
+            let path = config_path();
+            let Ok(text) = fs::read_to_string(&path) else { return };
+            let config = serde_json::from_str::<LoggingFile>(&text).map_err(|e| e.to_string());
+            (path, config)
+        }
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", t!("logs-config-invalid", path = path.display(), error = e));
+            return;
+        }
+    };
+    let dir = config.dir.clone().unwrap_or_else(|| data_dir().join("logs"));
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("{}", t!("logs-open-failed", path = dir.display(), error = e));
+        return;
+    }
+    let open = |enabled: bool, name: &str| {
+        if !enabled {
+            return None;
+        }
+        let path = dir.join(name);
+        prune(&path, &config);
+        match RotatingLog::open(path.clone(), config.clone()) {
+            Ok(log) => Some(Mutex::new(log)),
+            Err(e) => {
+                eprintln!("{}", t!("logs-open-failed", path = path.display(), error = e));
+                None
+            }
+        }
+    };
+    let logs = Logs {
+        access: open(config.access_log, "access.log"),
+        child: open(config.child_log, "trace_processor.log"),
+    };
+    let _ = LOGS.set(logs);
+}
+
+/// Level for each target, from `-v` or `RUST_LOG`
+struct Filter {
+    default: LevelFilter,
+    /// Target prefixes and their levels
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn from_verbosity(verbose: u8) -> Filter {
+        let level = match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+        Filter { default: LevelFilter::Warn, directives: vec![(env!("CARGO_CRATE_NAME").to_string(), level)] }
+    }
+
+    /// `RUST_LOG` syntax; unknown levels are ignored
+    fn parse(spec: &str) -> Filter {
+        let mut filter = Filter { default: LevelFilter::Warn, directives: Vec::new() };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        filter.directives.push((target.trim().to_string(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = directive.parse() {
+                        filter.default = level;
+                    } else {
+                        filter.directives.push((directive.to_string(), LevelFilter::Trace));
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    /// Level of the most specific directive matching `target`
+    fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| {
+                target.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max(&self) -> LevelFilter {
+        self.directives.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    /// `--log-file`
+    file: Option<Mutex<RotatingLog>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let level = self.filter.level(metadata.target()).max(LevelFilter::Warn);
+        let level = if self.file.is_some() { level.max(LevelFilter::Info) } else { level };
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        if record.level() <= self.filter.level(record.target()).max(LevelFilter::Warn) {
+            // Errors and warnings read as before; the rest shows its origin
+            let line = match record.level() {
+                Level::Error | Level::Warn => message.clone(),
+                level => format!("[{} {}] {}", level, record.target(), message),
+            };
+            eprintln!("{}", line);
+            crate::console::record(line);
+        }
+        self.write_file(record.level(), record.target(), &message);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().file.flush();
+        }
+    }
+}
+
+impl Logger {
+    fn write_file(&self, level: Level, target: &str, message: &str) {
+        let Some(file) = &self.file else { return };
+        let mut file = file.lock().unwrap();
+        for line in message.lines().filter(|line| !line.trim().is_empty()) {
+            let _ = file.write_line(&format!("{} {:<5} {}: {}", line_timestamp(), level, target, line));
+        }
+    }
+}
+
+/// Route `log` messages to the console and `log_file`, at the verbosity of
+/// `-v` repeated `verbose` times, or of `RUST_LOG` if that is 0
+pub fn init_logger(verbose: u8, log_file: Option<&Path>) {
+    let filter = match env::var("RUST_LOG") {
+        Ok(spec) if verbose == 0 => Filter::parse(&spec),
+        _ => Filter::from_verbosity(verbose),
+    };
+    let file = log_file.and_then(|path| {
+        let config = LoggingFile::default();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            let _ = fs::create_dir_all(dir);
+        }
+        prune(path, &config);
+        match RotatingLog::open(path.to_path_buf(), config) {
+            Ok(log) => Some(Mutex::new(log)),
+            Err(e) => {
+                eprintln!("{}", t!("logs-open-failed", path = path.display(), error = e));
+                None
+            }
+        }
+    });
+    let max = if file.is_some() { filter.max().max(LevelFilter::Info) } else { filter.max().max(LevelFilter::Warn) };
+    if let Ok(()) = log::set_logger(LOGGER.get_or_init(|| Logger { filter, file })) {
+        log::set_max_level(max);
+    }
+}
+
+/// Copy a line of console output to `--log-file`
+pub fn said(line: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger.write_file(Level::Info, "console", line);
+    }
+}
+
+fn write(log: Option<&Mutex<RotatingLog>>, line: &str) {
+    if let Some(log) = log {
+        let _ = log.lock().unwrap().write_line(&format!("{} {}", line_timestamp(), line));
+    }
+}
+
+/// Log a request; `user` is the signed-in user in multi-user mode
+pub fn access(request: &Request, user: Option<&str>) {
+    let remote = request.remote_addr().map(|a| a.ip().to_string()).unwrap_or_else(|| "-".to_string());
+    let line = format!("{} {} {} {}", remote, user.unwrap_or("-"), request.method(), redact(request.url()));
+    log::debug!(target: concat!(env!("CARGO_CRATE_NAME"), "::access"), "{}", line);
+    if let Some(logs) = LOGS.get() {
+        write(logs.access.as_ref(), &line);
+    }
+}
+
+/// Log a line of trace_processor output
+pub fn child(line: &str) {
+    if let Some(logs) = LOGS.get() {
+        write(logs.child.as_ref(), line);
+    }
+    if let Some(logger) = LOGGER.get() {
+        logger.write_file(Level::Info, "trace_processor", line);
+    }
+}
+
+/// Hide sign-in tokens passed in the query string
+fn redact(url: &str) -> String {
+    let Some((path, query)) = url.split_once('?') else { return url.to_string() };
+    let pairs: Vec<&str> =
+        query.split('&').map(|pair| if pair.starts_with("token=") { "token=*" } else { pair }).collect();
+    format!("{}?{}", path, pairs.join("&"))
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`
+pub fn line_timestamp() -> String {
+    let secs = now_secs();
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// `YYYYMMDD-HHMMSSZ`, which sorts chronologically
+fn file_timestamp() -> String {
+    let secs = now_secs();
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
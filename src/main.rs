@@ -1,216 +1,1084 @@
-use std::env;
-use std::fs;
-use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+#[macro_use]
+mod console;
+#[macro_use]
+mod i18n;
+mod access;
+mod android;
+mod archive;
+mod assets;
+mod backend;
+mod batch;
+mod bench;
+mod browser;
+mod bundle;
+mod capture;
+mod catalog;
+mod cli;
+mod compat;
+mod compression;
+mod config;
+mod config_file;
+mod convert;
+mod crash_report;
+mod dashboards;
+mod dates;
+mod deobfuscate;
+mod dist;
+mod doctor;
+mod dry_run;
+mod enrich;
+mod fetch;
+mod http_errors;
+mod import;
+mod instance;
+mod ingest;
+mod landing;
+mod livereload;
+mod logs;
+mod metadata;
+mod metrics;
+mod monitor;
+mod multipart;
+mod path_headers;
+mod paths;
+mod permalink;
+mod plugins;
+mod ports;
+mod presets;
+mod prometheus;
+mod query;
+mod readiness;
+mod recent;
+mod record;
+mod reports;
+mod restart;
+mod roots;
+mod rpc;
+mod rpc_proxy;
+mod search;
+mod security_headers;
+mod server;
+mod service;
+mod sessions;
+mod shutdown;
+mod sql_modules;
+mod sse;
+mod stats;
+mod symbol_server;
+mod symbols;
+mod timing;
+mod tls;
+mod trace_dirs;
+mod trace_processor;
+mod tp_download;
+mod tp_logs;
+mod traces_api;
+#[cfg(feature = "tray")]
+mod tray;
+mod ui_update;
+mod url_handler;
+mod version;
+mod wallclock;
+mod users;
+mod webhooks;
+mod workspace;
+
+use access::{Access, AuthMode};
+use backend::{Backend, Startup};
+use clap::{CommandFactory, FromArgMatches};
+use catalog::Catalog;
+use compression::Compression;
+use cli::{
+    AndroidCommand, BundleCommand, CaptureCommand, CatalogCommand, Cli, Commands, ReportsCommand, UrlHandlerCommand,
+};
+#[cfg(windows)]
+use cli::ServiceCommand;
+use config::Config;
+use ingest::Ingest;
+use log::{error, warn};
+use monitor::Limits;
+use plugins::Plugins;
+use ports::allocate_ports;
+use roots::ServePolicy;
+use rpc::RpcClient;
+use server::{AppState, BodyLimits};
+use sessions::Sessions;
+use timing::Timings;
+use sysinfo::System;
+use tls::Tls;
+use users::Users;
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use tiny_http::{Header, Response, Server};
-
-fn get_available_port() -> u16 {
-    std::net::TcpListener::bind("127.0.0.1:0")
-        .expect("Failed to bind to any port")
-        .local_addr()
-        .expect("Failed to get local address")
-        .port()
-}
+use std::time::{Duration, Instant};
+use tiny_http::Server;
+use trace_processor::{shared_args, trace_processor_args, trace_processor_env};
+
+/// How long to wait at exit for webhook notifications still being sent
+const WEBHOOK_FLUSH: Duration = Duration::from_secs(5);
 
-fn get_available_port_with_offset(offset: u16) -> u16 {
-    for _ in 0..20 {
-        let base = get_available_port();
-        let candidate = base as u32 + offset as u32;
-        if candidate > u16::MAX as u32 {
-            continue;
+/// Threads answering the plain loopback port that HTTPS adds
+const LOCAL_WORKERS: usize = 8;
+
+fn main() {
+    if let Some(code) = run_plugin_subcommand() {
+        std::process::exit(code);
+    }
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let file_errors = config_file::apply(&mut cli, &matches);
+    i18n::init(cli.lang);
+    logs::init_logger(cli.verbose, cli.log_file.as_deref());
+    if !file_errors.is_empty() {
+        let messages: Vec<String> = file_errors.iter().map(|e| e.message()).collect();
+        for message in &messages {
+            error!("{}", message);
         }
-        let port = candidate as u16;
-        if std::net::TcpListener::bind(("127.0.0.1", port)).is_ok() {
-            return port;
+        if cli.machine_readable {
+            readiness::enable();
         }
+        readiness::error("config_invalid", &messages.join("; "));
+        std::process::exit(2);
+    }
+    webhooks::init(cli.webhooks.as_deref());
+    crash_report::install();
+    if cli.version {
+        let config = Config::from_cli(&cli);
+        version::print(&config.dist_dir, &config.trace_processor);
+        return;
     }
-    get_available_port()
+    // `open --url` is `--fetch` as a subcommand
+    if let Some(Commands::Open { url }) = &cli.command {
+        cli.fetch = Some(url.clone());
+    }
+    match &cli.command {
+        Some(Commands::Doctor) => {
+            if !doctor::run(&Config::from_cli(&cli)) {
+                webhooks::flush(WEBHOOK_FLUSH);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Completions { shell }) => {
+            let mut cmd = Cli::command();
+            clap_complete::generate(*shell, &mut cmd, "perfetto_launcher", &mut std::io::stdout());
+        }
+        Some(Commands::Bench { trace, queries, iterations, format, output }) => {
+            let config = Config::from_cli(&cli);
+            let args = bench::BenchArgs {
+                trace_processor: &config.trace_processor,
+                trace,
+                queries,
+                iterations: *iterations,
+                format: *format,
+                output: output.as_ref(),
+            };
+            if let Err(e) = bench::run(args) {
+                error!("{}", t!("bench-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Capture {
+            command: CaptureCommand::Schedule { every, duration, config, device, keep, max_age, index },
+        }) => {
+            let launcher_config = Config::from_cli(&cli);
+            reports::init(report_runner(&launcher_config));
+            let schedule = capture::Schedule {
+                every: *every,
+                duration: *duration,
+                config: config.clone(),
+                device: device.clone(),
+                keep: *keep,
+                max_age: *max_age,
+                tools_dir: launcher_config.dist_dir,
+                ingest: Ingest { trace_processor: launcher_config.trace_processor, enabled: *index },
+            };
+            if let Err(e) = capture::run_schedule(schedule) {
+                error!("{}", t!("capture-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Record { list_templates: true, .. }) => {
+            for name in record::template_names() {
+                println!("{}", name);
+            }
+        }
+        Some(Commands::Record { template, config, duration, device, output, no_open, .. }) => {
+            let launcher_config = Config::from_cli(&cli);
+            let result = record::resolve_config(template.as_deref(), config.as_deref(), *duration).and_then(|text| {
+                let options = record::Options {
+                    config: text,
+                    device: device.clone(),
+                    output: output.clone(),
+                    tools_dir: launcher_config.dist_dir,
+                };
+                record::run(options, !*no_open, cli.browser.as_deref())
+            });
+            if let Err(e) = result {
+                error!("{}", t!("record-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Android { command }) => {
+            let result = match command {
+                AndroidCommand::Devices => android::print_devices(),
+                AndroidCommand::Traces { device } => android::print_traces(device.as_deref()),
+                AndroidCommand::Pull { names, device, all, no_open } => {
+                    android::run_pull(device.as_deref(), names, *all, !*no_open, cli.browser.as_deref())
+                }
+            };
+            if let Err(e) = result {
+                error!("{}", t!("android-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Catalog { command: CatalogCommand::Import { path, recursive, jobs } }) => {
+            let config = Config::from_cli(&cli);
+            reports::init(report_runner(&config));
+            let ingest = Ingest { trace_processor: config.trace_processor, enabled: true };
+            let result = if path.is_file() {
+                archive::import(path)
+            } else {
+                import::run(path, *recursive, *jobs, ingest)
+            };
+            if let Err(e) = result {
+                error!("{}", t!("import-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Catalog { command: CatalogCommand::Export { output, include_traces } }) => {
+            if let Err(e) = archive::export(output, *include_traces) {
+                error!("{}", t!("archive-export-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Symbolize { input, output }) => {
+            let mut config = Config::from_cli(&cli);
+            let server_dirs = symbol_server::prepare(&config.trace_processor, Some(input), &config.symbol_servers);
+            config.symbol_dirs.extend(server_dirs);
+            if let Err(e) = symbols::symbolize(&config.dist_dir, input, output, &config.symbol_dirs) {
+                error!("{}", t!("symbolize-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Clean { all, older_than, quota_mb, dry_run }) => {
+            let options = workspace::CleanOptions {
+                all: *all,
+                older_than: *older_than,
+                quota: quota_mb.or(cli.workspace_quota_mb).map(|mb| mb.saturating_mul(1024 * 1024)),
+                dry_run: *dry_run,
+            };
+            if let Err(e) = workspace::clean(&options) {
+                error!("{}", t!("clean-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Convert { input, output, to }) => {
+            let config = Config::from_cli(&cli);
+            let tools = convert::Tools {
+                tools_dir: config.dist_dir.clone(),
+                trace_processor: config.trace_processor.clone(),
+                auto_download: cli.auto_download,
+            };
+            if let Err(e) = convert::run(&tools, input, output, *to) {
+                error!("{}", t!("convert-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Metrics { run, list, format, output, trace }) => {
+            let config = Config::from_cli(&cli);
+            let result = match trace {
+                Some(trace) if !*list => metrics::run(
+                    &config.trace_processor,
+                    trace,
+                    run,
+                    &config.metric_extension_dirs,
+                    *format,
+                    output.as_deref(),
+                ),
+                _ => metrics::list(&config.trace_processor, &config.metric_extension_dirs),
+            };
+            if let Err(e) = result {
+                error!("{}", t!("metrics-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Query { trace, sql, file, format }) => {
+            let config = Config::from_cli(&cli);
+            let args = query::QueryArgs {
+                trace_processor: &config.trace_processor,
+                tp_args: trace_processor::shared_args(&config),
+                trace,
+                sql: sql.as_deref(),
+                file: file.as_ref(),
+                format: *format,
+                timeout: Duration::from_secs(cli.startup_timeout),
+            };
+            if let Err(e) = query::run(args) {
+                error!("{}", t!("query-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Batch { traces, sql, metrics, out, format, jobs }) => {
+            let config = Config::from_cli(&cli);
+            let job = match sql {
+                Some(path) => batch::Job::Sql(path.clone()),
+                None => batch::Job::Metrics(metrics.clone()),
+            };
+            let args = batch::BatchArgs {
+                trace_processor: &config.trace_processor,
+                tp_args: trace_processor::shared_args(&config),
+                metric_extension_dirs: &config.metric_extension_dirs,
+                traces,
+                job,
+                out,
+                format: *format,
+                jobs: *jobs,
+                timeout: Duration::from_secs(cli.startup_timeout),
+            };
+            if let Err(e) = batch::run(args) {
+                error!("{}", t!("batch-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Plugins) => list_plugins(),
+        Some(Commands::Reports { command: ReportsCommand::Timeline { trace, at, ts, window, base_url } }) => {
+            let config = Config::from_cli(&cli);
+            let args = wallclock::TimelineArgs {
+                trace_processor: &config.trace_processor,
+                trace,
+                at,
+                ts,
+                window: *window,
+                base_url: base_url.as_deref(),
+            };
+            if let Err(e) = wallclock::run(args) {
+                error!("{}", t!("timeline-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Reports { command: ReportsCommand::Run { name, trace } }) => {
+            let config = Config::from_cli(&cli);
+            let runner = report_runner(&config);
+            if let Err(e) = reports::run_now(&runner, name.as_deref(), trace.as_deref()) {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::UpdateUi { channel, source, version, check, rollback }) => {
+            let config = Config::from_cli(&cli);
+            let result = if *rollback {
+                ui_update::rollback(&config.dist_dir)
+            } else {
+                ui_update::run(&config.dist_dir, source, *channel, version.as_deref(), *check)
+            };
+            if let Err(e) = result {
+                error!("{}", t!("ui-update-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Bundle {
+            command: BundleCommand::Create { platform, output, launcher, trace_processor, ui_version, ui_source, args },
+        }) => {
+            let config = Config::from_cli(&cli);
+            let Some(platform) = platform.or_else(bundle::Platform::current) else {
+                error!("{}", t!("bundle-unknown-platform"));
+                std::process::exit(2);
+            };
+            let args = bundle::BundleArgs {
+                platform,
+                output,
+                launcher: launcher.as_deref(),
+                trace_processor: trace_processor.as_deref().or(cli.trace_processor_bin.as_deref()),
+                ui_version: ui_version.as_deref(),
+                ui_source,
+                dist_dir: &config.dist_dir,
+                launcher_args: args,
+            };
+            if let Err(e) = bundle::create(args) {
+                error!("{}", t!("bundle-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::OpenUrl { url }) => {
+            if let Err(e) = url_handler::open(url, cli.browser.as_deref()) {
+                error!("{}", t!("url-handler-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::RestartBackend { no_wait }) => {
+            if let Err(e) = restart::run(!no_wait) {
+                error!("{}", t!("restart-failed", error = e));
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::UrlHandler { command }) => {
+            let result = match command {
+                UrlHandlerCommand::Install => url_handler::install(),
+                UrlHandlerCommand::Uninstall => url_handler::uninstall(),
+            };
+            if let Err(e) = result {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Catalog { command: CatalogCommand::Pin { trace } }) => set_pinned(trace, true),
+        Some(Commands::Catalog { command: CatalogCommand::Unpin { trace } }) => set_pinned(trace, false),
+        #[cfg(windows)]
+        Some(Commands::Service { command }) => {
+            let result = match command {
+                ServiceCommand::Install { args } => service::install(args),
+                ServiceCommand::Uninstall => service::uninstall(),
+                ServiceCommand::Run { args } => service::run(args.clone(), run_service),
+            };
+            if let Err(e) = result {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        #[cfg(unix)]
+        None if cli.daemon => match service::daemonize(cli.log_file.as_deref()) {
+            Ok(pid) => {
+                let log_file = cli.log_file.clone().unwrap_or_else(service::default_log_file);
+                say!("{}", t!("daemon-started", pid = pid, log = log_file.display()));
+            }
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None | Some(Commands::Open { .. }) => run_launcher(cli),
+    }
+    webhooks::flush(WEBHOOK_FLUSH);
 }
 
-/// Get the dist directory path (parent of the executable's directory)
-fn get_dist_dir() -> PathBuf {
-    let exe_path = env::current_exe().expect("Failed to get executable path");
-    let exe_dir = exe_path.parent().expect("Failed to get executable directory");
-
-    // If running from target/release or target/debug, go up to dist
-    // Otherwise assume exe is directly in dist
-    if exe_dir.ends_with("release") || exe_dir.ends_with("debug") {
-        exe_dir
-            .parent() // target
-            .and_then(|p| p.parent()) // perfetto_launcher
-            .and_then(|p| p.parent()) // dist
-            .map(|p| p.to_path_buf())
-            .unwrap_or_else(|| exe_dir.to_path_buf())
-    } else {
-        exe_dir.to_path_buf()
+/// Plugin subcommands are dispatched before clap sees the arguments, since a
+/// word clap does not know would be taken as the trace file
+fn run_plugin_subcommand() -> Option<i32> {
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let first = args.first()?.to_str()?;
+    if first.starts_with('-') || Path::new(first).exists() || Cli::command().find_subcommand(first).is_some() {
+        return None;
     }
+    i18n::init(None);
+    match Plugins::discover().run_subcommand(&args)? {
+        Ok(code) => Some(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(2)
+        }
+    }
+}
+
+/// Open the trace, or just the UI, in the launcher that is already running
+/// rather than starting a second one; false if this one should start
+fn forward_to_running(cli: &Cli, config: &Config) -> bool {
+    let Some(running) = running_instance(cli, config) else {
+        return false;
+    };
+    let path = match (&config.trace, &cli.open_path) {
+        (Some(trace), _) => match Catalog::open().add(trace) {
+            Ok(entry) => format!("/t/{}", entry.id),
+            Err(e) => {
+                warn!("{}", t!("catalog-add-failed", path = trace.display(), error = e));
+                return false;
+            }
+        },
+        (None, Some(path)) => path.clone(),
+        (None, None) => format!("/?rpc_port={}", running.http_port),
+    };
+    open_in_running(cli, &running, &path);
+    true
 }
 
-/// Get MIME type based on file extension
-fn get_mime_type(path: &Path) -> &'static str {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some("html") => "text/html; charset=utf-8",
-        Some("js") => "application/javascript; charset=utf-8",
-        Some("css") => "text/css; charset=utf-8",
-        Some("json") => "application/json; charset=utf-8",
-        Some("wasm") => "application/wasm",
-        Some("png") => "image/png",
-        Some("jpg") | Some("jpeg") => "image/jpeg",
-        Some("gif") => "image/gif",
-        Some("svg") => "image/svg+xml",
-        Some("ico") => "image/x-icon",
-        Some("woff") => "font/woff",
-        Some("woff2") => "font/woff2",
-        Some("ttf") => "font/ttf",
-        Some("map") => "application/json",
-        _ => "application/octet-stream",
+/// Have the running launcher download `--fetch`'s trace into its own
+/// workspace and catalog, then open it like `forward_to_running`; false if
+/// this one should download it
+fn forward_fetch(cli: &Cli, config: &Config, url: &str) -> bool {
+    let Some(running) = running_instance(cli, config) else {
+        return false;
+    };
+    let api = format!("{}/launcher/open-url?open=false", running.request_url());
+    say!("{}", t!("fetch-forwarded", pid = running.pid, url = url));
+    let agent = ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(5)).build();
+    let body = serde_json::json!({ "url": url }).to_string();
+    let response = agent.post(&api).set("Content-Type", "application/json").send_string(&body);
+    let parse = |response: ureq::Response| -> serde_json::Value {
+        response.into_string().ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default()
+    };
+    let trace = match response.map(parse) {
+        Ok(trace) if trace["id"].is_string() => trace,
+        // Launchers from before `/launcher/open-url`, and multi-user ones,
+        // which want a token; the download then happens here
+        Ok(_) | Err(ureq::Error::Status(401 | 403 | 404 | 405, _)) | Err(ureq::Error::Transport(_)) => return false,
+        Err(ureq::Error::Status(_, response)) => {
+            error!("{}", parse(response)["error"].as_str().unwrap_or_default());
+            std::process::exit(2);
+        }
+    };
+    open_in_running(cli, &running, &format!("/t/{}", trace["id"].as_str().unwrap_or_default()));
+    true
+}
+
+/// The launcher already running, if this invocation should hand over to it
+///
+/// Callers waiting for `--machine-readable` output, and an explicit
+/// `--ui-port` other than the running launcher's, always get a launcher of
+/// their own.
+fn running_instance(cli: &Cli, config: &Config) -> Option<instance::Instance> {
+    if cli.new_instance || cli.machine_readable {
+        return None;
     }
+    let running = instance::running()?;
+    if config.ui_port.is_some_and(|port| port != running.http_port) {
+        return None;
+    }
+    Some(running)
 }
 
-fn main() {
-    println!("=== Perfetto Launcher ===\n");
+/// Open `path` of the running launcher in the browser
+fn open_in_running(cli: &Cli, running: &instance::Instance, path: &str) {
+    let url = format!("{}{}", running.base_url(), path);
+    say!("{}", t!("instance-forwarded", pid = running.pid, url = url.as_str()));
+    if !cli.open_browser {
+        say!("{}", t!("browser-not-opened", url = url.as_str()));
+    } else if let Err(e) = browser::open(&url, cli.browser.as_deref()) {
+        warn!("{}", t!("browser-failed", error = e));
+        say!("{}", t!("browser-manual", url = url.as_str()));
+    }
+}
 
-    // Get the dist directory
-    let dist_dir = get_dist_dir();
-    println!("Dist directory: {}\n", dist_dir.display());
+fn report_runner(config: &Config) -> reports::Runner {
+    reports::Runner {
+        trace_processor: config.trace_processor.clone(),
+        metric_extension_dirs: config.metric_extension_dirs.clone(),
+    }
+}
 
-    // Verify trace_processor_shell.exe exists
-    let trace_processor_path = dist_dir.join("trace_processor_shell.exe");
-    if !trace_processor_path.exists() {
-        eprintln!("Error: trace_processor_shell.exe not found at {}", trace_processor_path.display());
-        eprintln!("Make sure to place the launcher in the correct location.");
+fn list_plugins() {
+    let plugins = Plugins::discover();
+    if plugins.all().is_empty() {
+        say!("{}", t!("plugins-none", path = plugins::plugins_dir().display()));
         return;
     }
+    for plugin in plugins.all() {
+        say!("{}", t!("plugins-entry", name = plugin.name(), path = plugin.path.display()));
+        for subcommand in plugin.subcommands() {
+            say!("  {:<20} {}", subcommand.name, subcommand.about);
+        }
+    }
+}
+
+fn set_pinned(id_or_path: &str, pinned: bool) {
+    let mut catalog = Catalog::open();
+    let Some(id) = catalog.find(id_or_path).map(|t| t.id.clone()) else {
+        error!("{}", t!("catalog-unknown-id", id = id_or_path));
+        std::process::exit(1);
+    };
+    match catalog.set_pinned(&id, pinned) {
+        Ok(Some(trace)) if pinned => say!("{}", t!("catalog-pinned", name = trace.name, id = trace.id)),
+        Ok(Some(trace)) => say!("{}", t!("catalog-unpinned", name = trace.name, id = trace.id)),
+        Ok(None) => unreachable!(),
+        Err(e) => {
+            warn!("{}", t!("catalog-save-failed", path = catalog.path().display(), error = e));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The launcher as the Windows service runs it, with the arguments given to
+/// `service install`
+#[cfg(windows)]
+fn run_service(args: Vec<String>) {
+    let exe = std::env::args().next().unwrap_or_default();
+    let matches = match Cli::command().try_get_matches_from(std::iter::once(exe).chain(args)) {
+        Ok(matches) => matches,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let mut cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let file_errors = config_file::apply(&mut cli, &matches);
+    for e in &file_errors {
+        error!("{}", e.message());
+    }
+    if file_errors.is_empty() {
+        // Nobody is at the service's desktop
+        cli.open_browser = false;
+        cli.new_instance = true;
+        #[cfg(feature = "tray")]
+        {
+            cli.tray = false;
+        }
+        run_launcher(cli);
+    }
+}
+
+fn run_launcher(cli: Cli) {
+    let mut timings = Timings::start();
+    stats::start();
+    console::set_quiet(cli.quiet);
+    if cli.machine_readable {
+        readiness::enable();
+    }
+    let mut config = Config::from_cli(&cli);
+    if cli.dry_run {
+        let ok = dry_run::print_plan(&config);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    if let Err(e) = config.download_trace_processor() {
+        error!("{}", e);
+        readiness::error("tp_download_failed", &e);
+        std::process::exit(2);
+    }
+    if let Some(url) = &cli.fetch {
+        if forward_fetch(&cli, &config, url) {
+            return;
+        }
+        match fetch::fetch(url) {
+            Ok(path) => config.set_fetched_trace(path),
+            Err(e) => {
+                error!("{}", e);
+                readiness::error("fetch_failed", &e);
+                std::process::exit(2);
+            }
+        }
+    }
 
-    // Verify index.html exists
-    let index_path = dist_dir.join("index.html");
-    if !index_path.exists() {
-        eprintln!("Error: index.html not found at {}", index_path.display());
+    // Validate everything before starting anything
+    let problems = config.validate();
+    if !problems.is_empty() {
+        error!("{}", t!("config-invalid"));
+        for problem in &problems {
+            error!("  - {}", problem);
+        }
+        let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        readiness::error("invalid_config", &messages.join("; "));
+        std::process::exit(2);
+    }
+    if let Err(e) = trace_processor::ensure_executable(&config.trace_processor) {
+        warn!("{}", t!("tp-chmod-failed", path = config.trace_processor.display(), error = e));
+    }
+    timings.mark("Dist resolution");
+    if forward_to_running(&cli, &config) {
         return;
     }
+    if !cli.skip_preflight && !doctor::preflight(&config) {
+        error!("{}", t!("preflight-hint"));
+        readiness::error("preflight_failed", &t!("preflight-hint"));
+        std::process::exit(2);
+    }
+    timings.mark("Preflight");
 
-    // Pre-allocate ports so we can configure CORS on trace_processor_shell
-    // before the UI HTTP server starts.
-    let rpc_port = get_available_port_with_offset(10000);
-    let mut http_port = get_available_port_with_offset(10000);
-    while http_port == rpc_port {
-        http_port = get_available_port_with_offset(10000);
+    logs::init();
+    let users = match Users::load(cli.users.as_deref()) {
+        Ok(users) => users,
+        Err(e) => {
+            error!("{}", e);
+            readiness::error("invalid_users", &e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(users) = &users {
+        say!("{}", t!("users-enabled", count = users.count()));
+    }
+    // Users sign in with tokens of their own
+    let access = match Access::new(if users.is_some() { AuthMode::None } else { cli.auth }) {
+        Ok(access) => access,
+        Err(e) => {
+            error!("{}", e);
+            readiness::error("auth_failed", &e);
+            std::process::exit(2);
+        }
+    };
+    if users.is_some() && cli.auth != AuthMode::None {
+        warn!("{}", t!("auth-users-instead"));
+    }
+    if config.exposed() && users.is_none() && access.is_none() {
+        warn!("{}", t!("lan-unprotected"));
     }
-    println!("Starting trace_processor_shell...");
-    println!("  Path: {}", trace_processor_path.display());
-    println!("  HTTP port: {}", rpc_port);
+    let shutdown_token = match cli.shutdown_token.clone().map_or_else(access::random_token, Ok) {
+        Ok(token) => token,
+        Err(e) => {
+            error!("{}", e);
+            readiness::error("auth_failed", &e);
+            std::process::exit(2);
+        }
+    };
 
-    let cors_origins = format!(
-        "http://localhost:{},http://127.0.0.1:{}",
-        http_port, http_port
-    );
-    let mut args = vec![
-        "-D".to_string(),
-        "--http-ip-address".to_string(),
-        "127.0.0.1".to_string(),
-        "--http-port".to_string(),
-        rpc_port.to_string(),
-        "--http-additional-cors-origins".to_string(),
-        cors_origins,
-    ];
-    
-    // Check if a trace file was provided as a command line argument
-    if let Some(arg) = env::args().nth(1) {
-        let path = Path::new(&arg);
-        if path.exists() {
-            println!("  Loading trace file: {}", arg);
-            args.push(arg);
-        } else {
-            eprintln!("Warning: Provided trace file does not exist: {}", arg);
-        }
-    }
-
-    let mut trace_processor = Command::new(&trace_processor_path)
-        .args(&args)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()
-        .expect("Failed to start trace_processor_shell");
-
-    // Wait for trace_processor to start
-    println!("\nWaiting for trace_processor to start...");
-    thread::sleep(std::time::Duration::from_millis(500));
+    let plugins = Plugins::discover();
+    if let Some(trace) = &config.trace {
+        match plugins.convert(trace) {
+            Ok(path) => config.trace = Some(path),
+            Err(e) => {
+                error!("{}", e);
+                readiness::error("convert_failed", &e);
+                std::process::exit(2);
+            }
+        }
+    }
 
-    // Start HTTP server
-    println!("\nStarting HTTP server on port {}...", http_port);
-    let server = Server::http(format!("0.0.0.0:{}", http_port)).expect("Failed to start HTTP server");
+    let server_dirs = symbol_server::prepare(&config.trace_processor, config.trace.as_deref(), &config.symbol_servers);
+    config.symbol_dirs.extend(server_dirs);
+    if !config.symbol_dirs.is_empty() {
+        symbols::check_llvm_symbolizer(&config.dist_dir);
+    }
+    let sources = enrich::Sources { symbol_dirs: &config.symbol_dirs, proguard_maps: &config.proguard_maps };
+    match &config.trace {
+        Some(trace) if !sources.is_empty() => match enrich::enrich(&config.dist_dir, trace, &sources) {
+            Ok(path) => config.trace = Some(path),
+            Err(e) => warn!("{}", t!("enrich-failed", error = e)),
+        },
+        None if !config.proguard_maps.is_empty() => warn!("{}", t!("deobfuscate-no-trace")),
+        _ => {}
+    }
 
-    let ui_url = format!("http://localhost:{}/?rpc_port={}", http_port, rpc_port);
-    println!("\n=== Perfetto is ready! ===");
-    println!("  UI Server:            http://localhost:{}/", http_port);
-    println!("  Trace Processor RPC:  http://localhost:{}/", rpc_port);
-    println!("\nPress Ctrl+C to stop.\n");
+    let dist_dir = config.dist_dir.clone();
+    let trace_processor_path = config.trace_processor.clone();
+    crash_report::set_context("Dist directory", dist_dir.display().to_string());
+    crash_report::set_context("trace_processor", trace_processor_path.display().to_string());
+    if let Some(trace) = &config.trace {
+        crash_report::set_context("Trace", trace.display().to_string());
+    }
+    if let Some(ui) = dist::served_ui_version(&dist_dir) {
+        crash_report::set_context("UI version", ui);
+    }
+    if let Ok(tp) = trace_processor::query_version(&trace_processor_path) {
+        crash_report::set_context("trace_processor version", tp.version.clone());
+        stats::set_tp_version(tp);
+    }
+    say!("{}\n", t!("launcher-banner"));
+    say!("{}\n", t!("dist-directory", path = dist_dir.display()));
 
-    // Open browser
-    if let Err(e) = open::that(&ui_url) {
-        eprintln!("Warning: Failed to open browser: {}", e);
-        println!("Please open {} manually.", ui_url);
+    match compat::check(&dist_dir, &trace_processor_path) {
+        compat::Compat::Ok => {}
+        compat::Compat::Warning(msg) => warn!("{}", t!("compat-warning", message = msg)),
+        compat::Compat::Mismatch(msg) if cli.force => {
+            warn!("{}", t!("compat-forced", message = msg))
+        }
+        compat::Compat::Mismatch(msg) => {
+            error!("{}", t!("compat-error", message = msg));
+            error!("{}", t!("compat-hint"));
+            readiness::error("version_mismatch", &msg);
+            std::process::exit(2);
+        }
     }
 
-    // Handle requests
-    let dist_dir_clone = dist_dir.clone();
-    for request in server.incoming_requests() {
-        let url_path = request.url().trim_start_matches('/');
-        let url_path = url_path.split('?').next().unwrap_or(url_path); // Remove query string
+    timings.mark("Version checks");
 
-        let file_path = if url_path.is_empty() {
-            dist_dir_clone.join("index.html")
-        } else {
-            dist_dir_clone.join(url_path)
-        };
+    let (http_port, rpc_port) = allocate_ports(config.ui_port, config.rpc_port);
+    say!("{}", t!("tp-starting"));
+    say!("  {}", t!("tp-path", path = trace_processor_path.display()));
+    if let Some(preset) = &config.tp_preset {
+        say!("  {}", t!("tp-preset", name = preset, flags = config.tp_preset_args.join(" ")));
+    }
+    say!("  {}", t!("tp-port", port = rpc_port));
 
-        // Security: ensure path is within dist_dir
-        let canonical = match file_path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => {
-                let response = Response::from_string("Not Found").with_status_code(404);
-                let _ = request.respond(response);
-                continue;
-            }
-        };
+    if let Some(trace) = &config.trace {
+        say!("  {}", t!("trace-loading", path = trace.display()));
+    }
+    let args = trace_processor_args(rpc_port, &config);
+    let ssl = match config.tls.as_ref().map(Tls::ssl_config).transpose() {
+        Ok(ssl) => ssl,
+        Err(e) => {
+            error!("{}", e);
+            readiness::error("tls_failed", &e);
+            std::process::exit(2);
+        }
+    };
 
-        let dist_canonical = match dist_dir_clone.canonicalize() {
-            Ok(p) => p,
-            Err(_) => {
-                let response = Response::from_string("Internal Error").with_status_code(500);
-                let _ = request.respond(response);
-                continue;
-            }
-        };
+    shutdown::install(Duration::from_secs(cli.drain_timeout));
+    let startup_timeout = Duration::from_secs(cli.startup_timeout);
+    let env = trace_processor_env(&config);
+    let backend = match Backend::spawn(trace_processor_path, args, env, cli.quiet, rpc_port, startup_timeout) {
+        Ok(backend) => Arc::new(backend),
+        Err(e) => {
+            error!("{}", t!("tp-spawn-failed", error = e));
+            readiness::error("tp_spawn_failed", &e.to_string());
+            std::process::exit(2);
+        }
+    };
 
-        if !canonical.starts_with(&dist_canonical) {
-            let response = Response::from_string("Forbidden").with_status_code(403);
-            let _ = request.respond(response);
-            continue;
+    let sessions = Arc::new(Sessions::new(
+        config.trace_processor.clone(),
+        shared_args(&config),
+        trace_processor_env(&config),
+        http_port,
+        cli.max_upload_mb.saturating_mul(1024 * 1024),
+        cli.max_sessions,
+        (cli.session_idle_timeout > 0).then(|| Duration::from_secs(cli.session_idle_timeout)),
+    ));
+    shutdown::on_exit({
+        let (backend, sessions) = (backend.clone(), sessions.clone());
+        move || {
+            backend.stop();
+            sessions.stop_all();
         }
+    });
+    timings.mark("Child spawn");
 
-        // Read and serve file
-        match fs::read(&canonical) {
-            Ok(content) => {
-                let mime_type = get_mime_type(&canonical);
-                let content_type = Header::from_bytes("Content-Type", mime_type).unwrap();
-                let cors_origin = Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap();
+    // Wait for trace_processor in the background while the UI server comes up
+    say!("\n{}", t!("tp-waiting"));
+    let readiness_wait = {
+        let backend = backend.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let startup = backend.wait_until_ready();
+            (startup, start, Instant::now())
+        })
+    };
 
-                let response = Response::from_data(content)
-                    .with_header(content_type)
-                    .with_header(cors_origin);
-                let _ = request.respond(response);
+    // Start HTTP server
+    say!("\n{}", t!("server-starting", port = http_port));
+    let server = match ports::bind_with_retry((config.host, http_port), http_port)
+        .map_err(|e| e.into())
+        .and_then(|listener| Server::from_listener(listener, ssl.clone()))
+    {
+        Ok(server) => server,
+        Err(e) => {
+            error!("{}", t!("server-failed", error = e));
+            ports::report_port_conflict(http_port, "The UI server");
+            readiness::error("bind_failed", &e.to_string());
+            backend.stop();
+            std::process::exit(2);
+        }
+    };
+    let lan_addresses = if config.host.is_unspecified() { browser::lan_addresses() } else { Vec::new() };
+    rpc_proxy::allow_lan_origins(&lan_addresses);
+    let mut host_names = vec![config.url_host(), config.host.to_string()];
+    host_names.extend(System::host_name().filter(|_| config.exposed()));
+    rpc_proxy::allow_host_names(&host_names);
+    dist::set_mime_types(&cli.mime_type);
+    path_headers::configure(&cli.header);
+    security_headers::configure(http_port, &cli.cors_origin);
+    let resources = monitor::start(
+        backend.clone(),
+        Limits {
+            memory_mb: cli.tp_memory_limit,
+            cpu_percent: cli.tp_cpu_limit,
+            action: cli.tp_limit_action,
+        },
+    );
+    // With HTTPS the UI reaches its RPC, and other invocations the launcher,
+    // over plain HTTP on a loopback port
+    let local_server = match ssl.is_some().then(|| Server::http("127.0.0.1:0")).transpose() {
+        Ok(server) => server,
+        Err(e) => {
+            error!("{}", t!("server-failed", error = e));
+            readiness::error("bind_failed", &e.to_string());
+            backend.stop();
+            std::process::exit(2);
+        }
+    };
+    let local_port = local_server.as_ref().and_then(|server| server.server_addr().to_ip()).map(|addr| addr.port());
+    let ui_rpc_port = local_port.unwrap_or(http_port);
+    let scheme = config.scheme();
+    let url_host = config.url_host();
+    let session_url = format!("{}://{}:{}", scheme, url_host, http_port);
+    webhooks::set_session_url(&session_url);
+    // Register the trace so it can be linked to from the landing page and is
+    // listed among the recently opened ones
+    let mut catalog = Catalog::open();
+    let catalog_trace = config.trace.as_ref().and_then(|trace| match catalog.add(trace) {
+        Ok(entry) => catalog.mark_opened(&entry.id).ok().flatten().or(Some(entry)),
+        Err(e) => {
+            warn!("{}", t!("catalog-add-failed", path = trace.display(), error = e));
+            None
+        }
+    });
+    workspace::remove_stale_scratch();
+    let workspace_quota = cli.workspace_quota_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    if let Some(quota) = workspace_quota {
+        let keep: Vec<&Path> = config.trace.as_deref().into_iter().collect();
+        workspace::enforce_quota(&mut catalog, quota, &keep);
+    }
+    let ingest = Ingest { trace_processor: config.trace_processor.clone(), enabled: cli.index_traces };
+    reports::init(report_runner(&config));
+    reports::start_scheduler();
+    // Uploads and captures are only served as `/launcher/traces/<id>`, where
+    // the catalog decides who may see them
+    let mut roots = ServePolicy::new().with_ui_root("", &dist_dir).with_embedded_ui("");
+    for (prefix, dir) in &config.mounts {
+        roots = roots.with_root(prefix, dir);
+    }
+    for (name, dir) in &config.trace_dirs {
+        roots = roots.with_root(&format!("trace/{}", name), dir);
+    }
+    let live_reload = if !cli.watch {
+        None
+    } else {
+        match livereload::LiveReload::start(&dist_dir) {
+            Ok(live_reload) => {
+                say!("{}", t!("livereload-watching", path = dist_dir.display()));
+                Some(live_reload)
+            }
+            Err(e) => {
+                warn!("{}", t!("livereload-failed", path = dist_dir.display(), error = e));
+                None
             }
-            Err(_) => {
-                let response = Response::from_string("Not Found").with_status_code(404);
-                let _ = request.respond(response);
+        }
+    };
+    let state = Arc::new(AppState {
+        roots,
+        backend: backend.clone(),
+        resources,
+        http_port,
+        rpc_port,
+        trace: config.trace.clone(),
+        catalog: Mutex::new(catalog),
+        ingest: ingest.clone(),
+        sql_modules: Mutex::new(Vec::new()),
+        body_limits: BodyLimits {
+            upload: cli.max_upload_mb.saturating_mul(1024 * 1024),
+            api: cli.max_api_body_kb.saturating_mul(1024),
+        },
+        workspace_quota,
+        plugins,
+        users,
+        access,
+        compression: Compression::new(cli.compress),
+        open_browser: cli.open_browser,
+        browser: cli.browser.clone(),
+        sessions: sessions.clone(),
+        trace_dirs: config.trace_dirs.iter().map(|(name, _)| name.clone()).collect(),
+        recorder: record::Recorder::new(dist_dir.clone()),
+        shutdown_token,
+        dist_dir: dist_dir.clone(),
+        spa_fallback: cli.spa_fallback,
+        ui_version_dir: dist::versioned_layout(&dist_dir),
+        live_reload,
+        scheme,
+        url_host: url_host.clone(),
+        ui_rpc_port,
+    });
+    let max_connections = cli.max_connections;
+    let workers = cli.workers.map_or_else(server::default_workers, |n| n as usize);
+    let serving = {
+        let state = state.clone();
+        thread::spawn(move || server::serve(server, state, workers, max_connections))
+    };
+    if let Some(local_server) = local_server {
+        let state = state.clone();
+        thread::spawn(move || server::serve(local_server, state, LOCAL_WORKERS, max_connections));
+    }
+
+    timings.mark("Server bind");
+
+    let (startup, wait_start, wait_end) = readiness_wait.join().unwrap();
+    timings.span("Readiness wait", wait_start, wait_end);
+    // The part of the wait the server bind did not cover, which holds up the browser
+    timings.mark("Backend wait");
+    match startup {
+        Startup::Ready => {}
+        Startup::Exited(status) => {
+            backend::print_crash(&backend.crash_report(status));
+            readiness::error("tp_exited", &status.to_string());
+            if ports::find_port_owner(rpc_port).is_some() {
+                ports::report_port_conflict(rpc_port, "trace_processor_shell");
             }
+            std::process::exit(2);
         }
+        Startup::Cancelled => {
+            backend.stop();
+            std::process::exit(2);
+        }
+        Startup::TimedOut => {
+            error!("{}", t!("tp-start-timeout", seconds = cli.startup_timeout, port = rpc_port));
+            readiness::error("tp_timeout", &format!("no answer on port {} after {}s", rpc_port, cli.startup_timeout));
+            backend.stop();
+            std::process::exit(2);
+        }
+    }
+
+    // Open the browser only now that the UI will find its RPC backend. The UI
+    // reaches trace_processor through this server's RPC relay.
+    let link = |url: String| state.access.as_ref().map_or(url.clone(), |access| access.link(&url));
+    let ui_url = link(format!("{}://{}:{}/?rpc_port={}", scheme, url_host, http_port, ui_rpc_port));
+    let browser_url = match &cli.open_path {
+        Some(path) => link(format!("{}://{}:{}{}", scheme, url_host, http_port, path)),
+        None => ui_url.clone(),
+    };
+    if !cli.open_browser {
+        say!("{}", t!("browser-not-opened", url = browser_url));
+    } else if let Err(e) = browser::open(&browser_url, cli.browser.as_deref()) {
+        warn!("{}", t!("browser-failed", error = e));
+        say!("{}", t!("browser-manual", url = browser_url));
+    }
+    timings.mark("Browser open");
+
+    if !config.sql_module_dirs.is_empty() {
+        let packages: Vec<_> = config.sql_module_dirs.iter().filter_map(|dir| sql_modules::scan(dir).ok()).collect();
+        let statuses = sql_modules::validate(&RpcClient::new(rpc_port), &packages);
+        let loaded = statuses.iter().filter(|s| s.loaded).count();
+        say!("{}", t!("sql-modules-loaded", loaded = loaded, total = statuses.len()));
+        *state.sql_modules.lock().unwrap() = statuses;
+    }
+
+    say!("\n{}", t!("ready-banner"));
+    say!("  {}", t!("ready-ui", url = format!("{}://{}:{}/", scheme, url_host, http_port)));
+    // Listening on all interfaces, e.g. for a browser on another machine
+    for ip in lan_addresses.iter() {
+        let host = if ip.is_ipv6() { format!("[{}]", ip) } else { ip.to_string() };
+        let url = format!("{}://{}:{}/?rpc_port={}", scheme, host, http_port, ui_rpc_port);
+        say!("  {}", t!("ready-network", url = link(url)));
+    }
+    if !config.exposed() {
+        say!("  {}", t!("ready-local-only"));
+    }
+    if let Some(access) = state.access.as_ref().filter(|access| access.mode == AuthMode::Basic) {
+        say!("  {}", t!("ready-basic-auth", user = access::BASIC_USER, password = access.token.as_str()));
+    }
+    say!("  {}", t!("ready-rpc", url = format!("http://localhost:{}/", rpc_port)));
+    say!("\n{}\n", t!("ready-stop"));
+    readiness::ready(&ui_url, &format!("http://127.0.0.1:{}/", rpc_port), backend.pid());
+    // With HTTPS other invocations use the loopback port
+    let instance_host = if local_port.is_some() { "localhost" } else { url_host.as_str() };
+    instance::register(instance_host, local_port.unwrap_or(http_port), rpc_port, &state.shutdown_token);
+    webhooks::session_opened(&session_url);
+    if cli.quiet && !cli.machine_readable {
+        println!("{}", ui_url);
+    }
+    if cli.timings {
+        timings.print();
+    }
+    if let Some(path) = &cli.timings_trace {
+        if let Err(e) = timings.write_trace(path) {
+            warn!("{}", t!("timings-trace-failed", path = path.display(), error = e));
+        }
+    }
+
+    // The startup trace is already loaded, so ingest it with the session's own
+    // trace_processor instead of loading it a second time
+    if let Some(trace) = catalog_trace {
+        thread::spawn(move || ingest.run_with(&RpcClient::new(rpc_port), &trace));
+    }
+
+    // Handle requests until shutdown
+    if let Some(timeout) = cli.idle_timeout {
+        shutdown::exit_when_idle(timeout);
+    }
+    backend.watch(cli.max_restarts);
+    sessions.watch();
+    #[cfg(feature = "tray")]
+    if cli.tray {
+        tray::run(&ui_url, cli.browser.as_deref(), &backend);
     }
+    let _ = serving.join();
 
-    // Cleanup (this won't be reached normally, but just in case)
-    let _ = trace_processor.kill();
-    let _ = trace_processor.wait();
-    println!("Goodbye!");
+    // Cleanup
+    instance::unregister();
+    webhooks::session_closed(&session_url);
+    backend.stop();
+    sessions.stop_all();
+    // The recorder writes out what it has before it exits
+    state.recorder.stop();
+    workspace::remove_scratch();
+    say!("{}", t!("goodbye"));
 }
@@ -0,0 +1,140 @@
+//! Trace metadata extracted at ingest: capture time, device and OS build,
+//! enabled data sources, duration and data-loss statistics.
+//!
+//! Stored as `metadata/<id>.json` in the data directory and served at
+//! `/api/traces/<id>/metadata`.
+
+use crate::http_errors;
+use crate::paths::data_dir;
+use crate::rpc::RpcClient;
+use crate::server::AppState;
+use crate::stats;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use tiny_http::{Header, Request, Response};
+
+const BOUNDS_QUERY: &str = "select trace_start() as start_ns, trace_end() as end_ns";
+
+/// Wall-clock time at trace start, from the first REALTIME clock snapshot
+const CAPTURE_TIME_QUERY: &str = "select clock_value - (ts - trace_start()) as capture_time_ns \
+    from clock_snapshot where clock_name = 'REALTIME' order by ts limit 1";
+
+const METADATA_QUERY: &str = "select name, coalesce(str_value, cast(int_value as text)) as value from metadata";
+
+/// Non-zero statistics that mean events were lost or misparsed
+const DATA_LOSS_QUERY: &str = "select name, idx, severity, value from stats \
+    where value > 0 and severity in ('data_loss', 'error') order by value desc";
+
+/// Metadata keys describing the recording device
+const DEVICE_KEYS: &[&str] = &[
+    "android_build_fingerprint",
+    "android_sdk_version",
+    "system_name",
+    "system_release",
+    "system_version",
+    "system_machine",
+];
+
+pub fn metadata_path(id: &str) -> PathBuf {
+    data_dir().join("metadata").join(format!("{}.json", id))
+}
+
+pub fn is_extracted(id: &str) -> bool {
+    metadata_path(id).is_file()
+}
+
+pub fn remove(id: &str) {
+    let _ = fs::remove_file(metadata_path(id));
+}
+
+/// Extract metadata from the trace loaded in `client` and store it for `id`
+///
+/// Each part is queried separately so that a table missing from an older
+/// trace_processor only leaves that part empty.
+pub fn extract(client: &RpcClient, id: &str) -> Result<(), String> {
+    let first_row = |sql: &str| -> Map<String, Value> {
+        client
+            .query_rows(sql)
+            .ok()
+            .and_then(|rows| {
+                let row = rows.rows.into_iter().next()?;
+                Some(rows.columns.into_iter().zip(row).collect())
+            })
+            .unwrap_or_default()
+    };
+
+    let bounds = first_row(BOUNDS_QUERY);
+    let (start, end) = (bounds.get("start_ns").and_then(Value::as_i64), bounds.get("end_ns").and_then(Value::as_i64));
+    let capture_time = first_row(CAPTURE_TIME_QUERY).remove("capture_time_ns").unwrap_or(Value::Null);
+
+    let raw: Map<String, Value> = client
+        .query_rows(METADATA_QUERY)?
+        .rows
+        .into_iter()
+        .filter_map(|row| Some((row.first()?.as_str()?.to_string(), row.get(1)?.clone())))
+        .collect();
+    let device: Map<String, Value> = DEVICE_KEYS
+        .iter()
+        .filter_map(|key| Some((key.to_string(), raw.get(*key)?.clone())))
+        .collect();
+    let data_sources = raw
+        .get("trace_config_pbtxt")
+        .and_then(Value::as_str)
+        .map(data_sources)
+        .unwrap_or_default();
+
+    let data_loss: Vec<Value> = client
+        .query_rows(DATA_LOSS_QUERY)
+        .map(|rows| {
+            rows.rows
+                .into_iter()
+                .map(|row| Value::Object(rows.columns.iter().cloned().zip(row).collect()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let metadata = json!({
+        "trace_id": id,
+        "capture_time_ns": capture_time,
+        "start_ns": start,
+        "end_ns": end,
+        "duration_ns": start.zip(end).map(|(s, e)| e - s),
+        "device": device,
+        "data_sources": data_sources,
+        "data_loss": data_loss,
+        "metadata": raw,
+    });
+    let path = metadata_path(id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&metadata).unwrap()).map_err(|e| e.to_string())
+}
+
+/// Data source names from a text-format trace config:
+/// `data_sources { config { name: "linux.ftrace" ... } }`
+fn data_sources(config: &str) -> Vec<String> {
+    let mut names: Vec<String> = config
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("name:"))
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// `GET /api/traces/<id>/metadata`
+pub fn respond(request: Request, state: &AppState, id: &str) {
+    if state.catalog.lock().unwrap().get(id).is_none() {
+        return http_errors::respond(request, 404, &t!("catalog-unknown-id", id = id));
+    }
+    match fs::read_to_string(metadata_path(id)) {
+        Ok(body) => {
+            let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+            stats::respond(request, Response::from_string(body).with_header(content_type));
+        }
+        Err(_) => http_errors::respond(request, 404, &t!("metadata-not-extracted", id = id)),
+    }
+}
@@ -0,0 +1,149 @@
+//! `metrics` subcommand: run built-in and custom trace-based metrics.
+//!
+//! A metric extension directory holds `protos/` with the metric's proto
+//! definitions and `sql/` with one `<metric name>.sql` per metric, the layout
+//! trace_processor's `--metric-extension` expects. Extensions are mounted at
+//! the metrics root, so custom metrics are run by name like built-in ones.
+
+use crate::trace_processor::Instance;
+use crate::workspace;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum MetricsFormat {
+    Text,
+    Json,
+}
+
+impl MetricsFormat {
+    fn as_arg(self) -> &'static str {
+        match self {
+            MetricsFormat::Text => "text",
+            MetricsFormat::Json => "json",
+        }
+    }
+}
+
+const LIST_QUERY: &str = "select name from trace_metrics order by name";
+
+/// `--metric-extension` arguments for trace_processor
+pub fn args(extension_dirs: &[PathBuf]) -> Vec<String> {
+    extension_dirs
+        .iter()
+        .flat_map(|dir| ["--metric-extension".to_string(), format!("{}@/", dir.display())])
+        .collect()
+}
+
+/// Check that `dir` looks like a metric extension
+pub fn check_extension_dir(dir: &Path) -> Result<(), String> {
+    if !dir.join("sql").is_dir() {
+        return Err(t!("metrics-extension-invalid", path = dir.display()));
+    }
+    Ok(())
+}
+
+/// Metric names defined by an extension: the `.sql` files directly in `sql/`
+fn custom_metrics(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir.join("sql"))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "sql"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Print every metric that can be run, and where it comes from
+pub fn list(trace_processor: &Path, extension_dirs: &[PathBuf]) -> Result<(), String> {
+    let mut metrics: BTreeMap<String, String> = BTreeMap::new();
+    for name in builtin_metrics(trace_processor)? {
+        metrics.insert(name, t!("metrics-builtin"));
+    }
+    for dir in extension_dirs {
+        check_extension_dir(dir)?;
+        for name in custom_metrics(dir) {
+            metrics.insert(name, dir.display().to_string());
+        }
+    }
+    let width = metrics.keys().map(|name| name.len()).max().unwrap_or(0);
+    for (name, source) in &metrics {
+        println!("{:width$}  {}", name, source, width = width);
+    }
+    Ok(())
+}
+
+/// Built-in metrics, read from trace_processor's `trace_metrics` table with
+/// an empty trace loaded
+fn builtin_metrics(trace_processor: &Path) -> Result<Vec<String>, String> {
+    let empty = workspace::temp_path("empty.pftrace").map_err(|e| e.to_string())?;
+    fs::write(&empty, b"").map_err(|e| e.to_string())?;
+    let rows = Instance::start(trace_processor, &empty, Duration::from_secs(60))
+        .and_then(|instance| instance.client.query_rows(LIST_QUERY));
+    let _ = fs::remove_file(&empty);
+    Ok(rows?.rows.into_iter().filter_map(|row| Some(row.first()?.as_str()?.to_string())).collect())
+}
+
+/// Compute `names` on `trace` and write the result to `output`, or to stdout
+///
+/// Nothing is written unless every metric is computed, so a failed run never
+/// leaves a partial file behind.
+pub fn run(
+    trace_processor: &Path,
+    trace: &Path,
+    names: &[String],
+    extension_dirs: &[PathBuf],
+    format: MetricsFormat,
+    output: Option<&Path>,
+) -> Result<(), String> {
+    let result = compute(trace_processor, trace, names, extension_dirs, format)?;
+    match output {
+        Some(path) => fs::write(path, result).map_err(|e| t!("metrics-write-failed", path = path.display(), error = e)),
+        None => {
+            print!("{}", result);
+            Ok(())
+        }
+    }
+}
+
+/// Compute `names` on `trace` and return the output
+pub fn compute(
+    trace_processor: &Path,
+    trace: &Path,
+    names: &[String],
+    extension_dirs: &[PathBuf],
+    format: MetricsFormat,
+) -> Result<String, String> {
+    let output = command(trace_processor, trace, names, extension_dirs, format)?
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| t!("instance-spawn-failed", error = e))?;
+    if !output.status.success() {
+        return Err(t!("metrics-tp-failed", status = output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn command(
+    trace_processor: &Path,
+    trace: &Path,
+    names: &[String],
+    extension_dirs: &[PathBuf],
+    format: MetricsFormat,
+) -> Result<Command, String> {
+    for dir in extension_dirs {
+        check_extension_dir(dir)?;
+    }
+    let mut command = Command::new(trace_processor);
+    command
+        .args(args(extension_dirs))
+        .args(["--run-metrics", &names.join(","), "--metrics-output", format.as_arg()])
+        .arg(trace);
+    Ok(command)
+}
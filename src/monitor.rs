@@ -0,0 +1,100 @@
+//! Periodic sampling of trace_processor's memory and CPU usage.
+//!
+//! Loading a corrupt or huge trace can make trace_processor grow without
+//! bound. Soft limits either warn or restart the backend before it takes the
+//! whole workstation down.
+
+use crate::backend::Backend;
+use log::{error, warn};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// Consecutive samples above the CPU limit before acting on it
+const CPU_SUSTAINED_SAMPLES: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum LimitAction {
+    /// Print a warning when a limit is exceeded
+    Warn,
+    /// Restart trace_processor when a limit is exceeded
+    Restart,
+}
+
+pub struct Limits {
+    pub memory_mb: Option<u64>,
+    pub cpu_percent: Option<f32>,
+    pub action: LimitAction,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ResourceSample {
+    pub memory_bytes: u64,
+    pub cpu_percent: f32,
+}
+
+/// Latest sample, shared with the status endpoint
+pub type Resources = Arc<Mutex<Option<ResourceSample>>>;
+
+pub fn start(backend: Arc<Backend>, limits: Limits) -> Resources {
+    let resources: Resources = Arc::new(Mutex::new(None));
+    let latest = resources.clone();
+    thread::spawn(move || {
+        let mut system = System::new();
+        let mut over_memory = false;
+        let mut cpu_over_count = 0;
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            let pid = Pid::from_u32(backend.pid());
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[pid]),
+                true,
+                ProcessRefreshKind::nothing().with_memory().with_cpu(),
+            );
+            let sample = system.process(pid).map(|p| ResourceSample {
+                memory_bytes: p.memory(),
+                cpu_percent: p.cpu_usage(),
+            });
+            *latest.lock().unwrap() = sample;
+            let Some(sample) = sample else { continue };
+
+            let memory_mb = sample.memory_bytes / (1024 * 1024);
+            let memory_exceeded = limits.memory_mb.is_some_and(|limit| memory_mb > limit);
+            cpu_over_count = match limits.cpu_percent {
+                Some(limit) if sample.cpu_percent > limit => cpu_over_count + 1,
+                _ => 0,
+            };
+            let cpu_exceeded = cpu_over_count >= CPU_SUSTAINED_SAMPLES;
+
+            if memory_exceeded && !over_memory {
+                warn!(
+                    "{}",
+                    t!("monitor-memory-exceeded", used = memory_mb, limit = limits.memory_mb.unwrap_or(0))
+                );
+            }
+            if cpu_exceeded && cpu_over_count == CPU_SUSTAINED_SAMPLES {
+                warn!(
+                    "{}",
+                    t!(
+                        "monitor-cpu-exceeded",
+                        used = format!("{:.0}", sample.cpu_percent),
+                        limit = format!("{:.0}", limits.cpu_percent.unwrap_or(0.0))
+                    )
+                );
+            }
+            over_memory = memory_exceeded;
+
+            if (memory_exceeded || cpu_exceeded) && limits.action == LimitAction::Restart {
+                warn!("{}", t!("monitor-restarting"));
+                if let Err(e) = backend.restart() {
+                    error!("{}", t!("tp-spawn-failed", error = e));
+                }
+                over_memory = false;
+                cpu_over_count = 0;
+            }
+        }
+    });
+    resources
+}
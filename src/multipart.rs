@@ -0,0 +1,338 @@
+//! The file in a `multipart/form-data` upload, read as it arrives so a trace
+//! of any size goes straight to disk.
+
+use std::io::{self, Read};
+
+/// Bytes read from the body at a time
+const CHUNK: usize = 64 * 1024;
+
+/// Boundary of a `multipart/form-data` Content-Type, if it is one
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    if !params.next()?.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Contents of the first part that carries a file name
+pub struct FilePart<R> {
+    reader: R,
+    /// `\r\n--<boundary>`, which ends the part
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    /// Start of the unread bytes in `buffer`
+    start: usize,
+    finished: bool,
+}
+
+impl<R: Read> FilePart<R> {
+    /// Skip to the first file in `reader` and return it with its file name
+    pub fn open(reader: R, boundary: &str) -> io::Result<(FilePart<R>, String)> {
+        let mut part = FilePart {
+            reader,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            buffer: Vec::new(),
+            start: 0,
+            finished: false,
+        };
+        // The first boundary need not follow a line break
+        let first = part.delimiter[2..].to_vec();
+        part.skip_past(&first)?;
+        loop {
+            if part.read_line()?.starts_with("--") {
+                return Err(invalid("no file in the form"));
+            }
+            let mut file_name = None;
+            loop {
+                let line = part.read_line()?;
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("Content-Disposition") {
+                        file_name = disposition_file_name(value);
+                    }
+                }
+            }
+            if let Some(file_name) = file_name {
+                return Ok((part, file_name));
+            }
+            let delimiter = part.delimiter.clone();
+            part.skip_past(&delimiter)?;
+        }
+    }
+
+    fn unread(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    /// Read more of the body into the buffer; false at its end
+    fn fill(&mut self) -> io::Result<bool> {
+        self.buffer.drain(..self.start);
+        self.start = 0;
+        let len = self.buffer.len();
+        self.buffer.resize(len + CHUNK, 0);
+        let n = self.reader.read(&mut self.buffer[len..])?;
+        self.buffer.truncate(len + n);
+        Ok(n > 0)
+    }
+
+    /// Consume everything up to and including `pattern`
+    fn skip_past(&mut self, pattern: &[u8]) -> io::Result<()> {
+        loop {
+            if let Some(at) = find(self.unread(), pattern) {
+                self.start += at + pattern.len();
+                return Ok(());
+            }
+            // Keep a tail that may hold the start of the pattern
+            self.start = self.buffer.len().saturating_sub(pattern.len() - 1).max(self.start);
+            if !self.fill()? {
+                return Err(invalid("the form ends early"));
+            }
+        }
+    }
+
+    /// The next line, without its `\r\n`
+    fn read_line(&mut self) -> io::Result<String> {
+        loop {
+            if let Some(at) = find(self.unread(), b"\r\n") {
+                let line = String::from_utf8_lossy(&self.unread()[..at]).into_owned();
+                self.start += at + 2;
+                return Ok(line);
+            }
+            if self.unread().len() > CHUNK || !self.fill()? {
+                return Err(invalid("malformed part headers"));
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for FilePart<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while !self.finished {
+            // Bytes that cannot be the start of the delimiter are safe to hand out
+            let available = match find(self.unread(), &self.delimiter) {
+                Some(0) => {
+                    self.finished = true;
+                    return Ok(0);
+                }
+                Some(at) => at,
+                None => self.unread().len().saturating_sub(self.delimiter.len() - 1),
+            };
+            if available > 0 {
+                let n = available.min(out.len());
+                out[..n].copy_from_slice(&self.unread()[..n]);
+                self.start += n;
+                return Ok(n);
+            }
+            if !self.fill()? {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the form ends inside the file"));
+            }
+        }
+        Ok(0)
+    }
+}
+
+/// `filename` of a Content-Disposition value such as
+/// `form-data; name="trace"; filename="a.pftrace"`
+fn disposition_file_name(value: &str) -> Option<String> {
+    let mut rest = value;
+    loop {
+        rest = &rest[rest.find(';')? + 1..];
+        let (name, value) = rest.split_once('=')?;
+        if name.contains(';') {
+            // A parameter without a value
+            continue;
+        }
+        let value = value.trim_start();
+        let (value, after) = match value.strip_prefix('"') {
+            Some(quoted) => unquote(quoted)?,
+            None => {
+                let end = value.find(';').unwrap_or(value.len());
+                (value[..end].trim().to_string(), &value[end..])
+            }
+        };
+        if name.trim().eq_ignore_ascii_case("filename") {
+            return Some(value);
+        }
+        rest = after;
+    }
+}
+
+/// The contents of a quoted string up to its closing quote, and what follows
+/// it; `\"` and `\\` stand for `"` and `\`, other backslashes for themselves,
+/// as browsers leave those in file names
+fn unquote(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &text[i + 1..])),
+            '\\' => match chars.next_if(|(_, next)| matches!(next, '"' | '\\')) {
+                Some((_, escaped)) => value.push(escaped),
+                None => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Position of `needle` in `haystack`, checking only where its first byte occurs
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let mut from = 0;
+    while let Some(offset) = haystack[from..].iter().position(|&b| b == needle[0]) {
+        let at = from + offset;
+        if haystack[at..].starts_with(needle) {
+            return Some(at);
+        }
+        if haystack.len() - at < needle.len() {
+            return None;
+        }
+        from = at + 1;
+    }
+    None
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `step` bytes per read, to split the body anywhere
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(self.data.len()).min(out.len());
+            out[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    const STEPS: [usize; 6] = [1, 2, 3, 7, 64, usize::MAX];
+
+    fn read_file(body: &[u8], step: usize) -> io::Result<(String, Vec<u8>)> {
+        let (mut part, file_name) = FilePart::open(Trickle { data: body, step }, "XyZ")?;
+        let mut contents = Vec::new();
+        part.read_to_end(&mut contents)?;
+        Ok((file_name, contents))
+    }
+
+    fn form(disposition: &str, contents: &[u8]) -> Vec<u8> {
+        let mut body = format!("--XyZ\r\nContent-Disposition: {}\r\n\r\n", disposition).into_bytes();
+        body.extend_from_slice(contents);
+        body.extend_from_slice(b"\r\n--XyZ--\r\n");
+        body
+    }
+
+    #[test]
+    fn reads_boundaries() {
+        assert_eq!(boundary("multipart/form-data; boundary=XyZ").as_deref(), Some("XyZ"));
+        assert_eq!(boundary("Multipart/Form-Data;BOUNDARY=\"a b\"").as_deref(), Some("a b"));
+        assert_eq!(boundary("multipart/form-data; charset=utf-8; boundary=XyZ").as_deref(), Some("XyZ"));
+        assert_eq!(boundary("multipart/form-data"), None);
+        assert_eq!(boundary("multipart/form-data; boundary=\"\""), None);
+        assert_eq!(boundary("multipart/mixed; boundary=XyZ"), None);
+    }
+
+    #[test]
+    fn reads_the_file_however_the_body_is_split() {
+        let body = form("form-data; name=\"trace\"; filename=\"a.pftrace\"", b"trace bytes");
+        for step in STEPS {
+            let (file_name, contents) = read_file(&body, step).unwrap();
+            assert_eq!(file_name, "a.pftrace");
+            assert_eq!(contents, b"trace bytes", "step {}", step);
+        }
+    }
+
+    #[test]
+    fn keeps_line_breaks_and_partial_delimiters_in_the_file() {
+        // The boundary only ends the file at the start of a line
+        let contents = b"\r\nline\r\n-\r\n--Xy\r\n\r\n --XyZ\r\n\r\n";
+        let body = form("form-data; name=\"trace\"; filename=\"a\"", contents);
+        for step in STEPS {
+            let (_, read) = read_file(&body, step).unwrap();
+            assert_eq!(read, contents, "step {}", step);
+        }
+    }
+
+    #[test]
+    fn reads_an_empty_file() {
+        let body = form("form-data; name=\"trace\"; filename=\"empty\"", b"");
+        for step in STEPS {
+            assert_eq!(read_file(&body, step).unwrap(), ("empty".to_string(), Vec::new()));
+        }
+    }
+
+    #[test]
+    fn skips_the_preamble_and_fields_before_the_file() {
+        let mut body = b"This is the preamble.\r\n--XyZ\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\n\
+            not the file\r\n"
+            .to_vec();
+        body.extend(form("form-data; name=\"trace\"; filename=\"b.pftrace\"", b"file"));
+        for step in STEPS {
+            assert_eq!(read_file(&body, step).unwrap(), ("b.pftrace".to_string(), b"file".to_vec()));
+        }
+    }
+
+    #[test]
+    fn fails_without_a_closing_boundary() {
+        let mut body = form("form-data; name=\"trace\"; filename=\"a\"", b"truncated");
+        body.truncate(body.len() - "\r\n--XyZ--\r\n".len());
+        for step in STEPS {
+            let error = read_file(&body, step).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "step {}", step);
+        }
+        // A body cut off inside the delimiter leaves its start unread as well
+        body.extend_from_slice(b"\r\n--Xy");
+        assert_eq!(read_file(&body, 1).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn fails_without_a_file() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\ntext\r\n--XyZ--\r\n";
+        for step in STEPS {
+            assert_eq!(read_file(body, step).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+        assert_eq!(read_file(b"no boundary here", 3).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_file(b"", 3).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reads_quoted_and_escaped_file_names() {
+        let cases = [
+            ("form-data; name=\"trace\"; filename=\"a.pftrace\"", "a.pftrace"),
+            ("form-data; name=\"trace\"; filename=plain.pftrace", "plain.pftrace"),
+            ("form-data; filename=plain.pftrace; name=trace", "plain.pftrace"),
+            ("form-data; name=\"trace\"; FILENAME = \"upper.pftrace\"", "upper.pftrace"),
+            ("form-data; name=\"a;filename=x\"; filename=\"real\"", "real"),
+            ("form-data; name=\"trace\"; filename=\"semi;colon=.pftrace\"", "semi;colon=.pftrace"),
+            ("form-data; name=\"trace\"; filename=\"say \\\"hi\\\".pftrace\"", "say \"hi\".pftrace"),
+            ("form-data; name=\"trace\"; filename=\"back\\\\slash\"", "back\\slash"),
+            ("form-data; name=\"trace\"; filename=\"C:\\traces\\a.pftrace\"", "C:\\traces\\a.pftrace"),
+            ("form-data; flag; filename=\"after-flag\"", "after-flag"),
+            ("form-data; name=\"trace\"; filename=\"\"", ""),
+        ];
+        for (disposition, expected) in cases {
+            let (file_name, _) = read_file(&form(disposition, b"x"), usize::MAX).unwrap();
+            assert_eq!(file_name, expected, "{}", disposition);
+        }
+        // `filename*` is a different parameter, and an unclosed quote no name at all
+        for disposition in ["form-data; name=\"t\"; filename*=UTF-8''a.pftrace", "form-data; filename=\"open"] {
+            assert!(read_file(&form(disposition, b"x"), usize::MAX).is_err(), "{}", disposition);
+        }
+    }
+}
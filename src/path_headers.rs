@@ -0,0 +1,99 @@
+//! Extra response headers by URL path, from `--header` and the config file's
+//! `[headers]` section.
+//!
+//! They are applied after the ones `security_headers` adds to every response,
+//! so they can change those as well. A `*` in a pattern matches any run of
+//! characters, where patterns overlap the longer one's value wins, and an
+//! empty value leaves a header out:
+//!
+//! ```toml
+//! [headers."/*"]
+//! Cross-Origin-Embedder-Policy = ""
+//!
+//! [headers."/v*/*.wasm"]
+//! Cache-Control = "public, max-age=600"
+//! ```
+
+use std::sync::OnceLock;
+use tiny_http::Header;
+
+/// Rules from the command line or the config file
+static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+
+/// Header `name: value` for URL paths matching `pattern`
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub pattern: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl Rule {
+    /// Parse `PATTERN=NAME:VALUE`, as `--header` takes it
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let expected = || format!("expected PATTERN=NAME:VALUE, got '{}'", text);
+        let (pattern, header) = text.split_once('=').ok_or_else(expected)?;
+        let (name, value) = header.split_once(':').ok_or_else(expected)?;
+        Rule::new(pattern, name.trim(), value.trim())
+    }
+
+    /// A rule whose pattern is a path and whose header tiny_http can send
+    pub fn new(pattern: &str, name: &str, value: &str) -> Result<Rule, String> {
+        if !pattern.starts_with('/') {
+            return Err(format!("path pattern '{}' does not start with /", pattern));
+        }
+        Header::from_bytes(name, value).map_err(|_| format!("'{}: {}' is not a valid header", name, value))?;
+        Ok(Rule { pattern: pattern.to_string(), name: name.to_string(), value: value.to_string() })
+    }
+}
+
+/// Use `rules`; called once at startup
+pub fn configure(rules: &[Rule]) {
+    let _ = RULES.set(rules.to_vec());
+}
+
+/// Add the headers for a response to `url` to `headers`, replacing or, for
+/// an empty value, removing the ones of the same name
+pub fn apply(url: &str, headers: &mut Vec<Header>) {
+    for (name, value) in for_url(url) {
+        headers.retain(|h| !h.field.equiv(name));
+        if let Some(header) = Header::from_bytes(name, value).ok().filter(|_| !value.is_empty()) {
+            headers.push(header);
+        }
+    }
+}
+
+/// Names and values of the headers for a response to `url`, whose query is
+/// ignored
+fn for_url(url: &str) -> Vec<(&'static str, &'static str)> {
+    let path = url.split('?').next().unwrap_or(url);
+    let mut rules: Vec<&Rule> = RULES.get().into_iter().flatten().filter(|rule| matches(&rule.pattern, path)).collect();
+    rules.sort_by_key(|rule| rule.pattern.len());
+    let mut headers: Vec<(&str, &str)> = Vec::new();
+    for rule in rules {
+        headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&rule.name));
+        headers.push((&rule.name, &rule.value));
+    }
+    headers
+}
+
+/// Whether `path` matches `pattern`, where `*` stands for any run of characters
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
@@ -0,0 +1,60 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Per-user data directory for caches, logs and managed traces
+///
+/// - Windows: `%LOCALAPPDATA%\perfetto_launcher`
+/// - macOS:   `~/Library/Application Support/perfetto_launcher`
+/// - Linux:   `$XDG_DATA_HOME/perfetto_launcher` or `~/.local/share/perfetto_launcher`
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|h| h.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|h| h.join(".local").join("share")))
+    };
+    base.unwrap_or_else(env::temp_dir).join("perfetto_launcher")
+}
+
+/// Per-user directory for settings
+///
+/// - Windows: `%APPDATA%\perfetto_launcher`
+/// - macOS:   `~/Library/Application Support/perfetto_launcher`
+/// - Linux:   `$XDG_CONFIG_HOME/perfetto_launcher` or `~/.config/perfetto_launcher`
+pub fn config_dir() -> PathBuf {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|h| h.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|h| h.join(".config")))
+    };
+    base.unwrap_or_else(env::temp_dir).join("perfetto_launcher")
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from)
+}
+
+/// Look up an executable by name in `PATH`
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            let exe = dir.join(format!("{}.exe", name));
+            if exe.is_file() {
+                return Some(exe);
+            }
+        }
+        None
+    })
+}
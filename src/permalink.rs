@@ -0,0 +1,44 @@
+//! Shareable links to a catalog trace plus UI state: `/t/<id>#<ui-state>`.
+//!
+//! The UI state after `#` never reaches the server; a small page reads it in
+//! the browser and forwards it to the Perfetto UI as route arguments, e.g.
+//! `/t/k3x9a0qz#visStart=1200000000&visEnd=1450000000`.
+
+use crate::http_errors;
+use crate::server::{origin, AppState};
+use crate::stats;
+use serde_json::json;
+use tiny_http::{Header, Request, Response};
+
+const TEMPLATE: &str = include_str!("../templates/permalink.html");
+
+/// `GET /t/<id>`: open the trace in the UI with the state from the fragment
+pub fn respond_open(request: Request, state: &AppState, id: &str) {
+    let trace = state.catalog.lock().unwrap().get(id).cloned();
+    let Some(trace) = trace else {
+        return http_errors::respond(request, 404, &t!("catalog-unknown-id", id = id));
+    };
+    let page = TEMPLATE
+        .replace("{{name}}", &crate::landing::escape(&trace.name))
+        .replace("{{id}}", &trace.id);
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
+
+/// `GET /api/traces/<id>/permalink?<ui-state>`: the absolute link for `id`,
+/// with the query string moved into the fragment
+pub fn respond_api(request: Request, state: &AppState, id: &str) {
+    if state.catalog.lock().unwrap().get(id).is_none() {
+        return http_errors::respond(request, 404, &t!("catalog-unknown-id", id = id));
+    }
+    let mut url = format!("{}/t/{}", origin(&request), id);
+    if let Some((_, ui_state)) = request.url().split_once('?') {
+        if !ui_state.is_empty() {
+            url.push('#');
+            url.push_str(ui_state);
+        }
+    }
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let body = json!({ "id": id, "url": url }).to_string();
+    stats::respond(request, Response::from_string(body).with_header(content_type));
+}
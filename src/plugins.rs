@@ -0,0 +1,247 @@
+//! Subprocess plugins discovered in the `plugins` directory of the data
+//! directory.
+//!
+//! Every executable there is a plugin. `<plugin> describe` prints what it
+//! provides as JSON:
+//!
+//! ```json
+//! {
+//!   "name": "acme",
+//!   "subcommands": [{ "name": "acme-upload", "about": "Upload to the ACME store" }],
+//!   "converters": [{ "extensions": ["acmetrace"] }],
+//!   "routes": true
+//! }
+//! ```
+//!
+//! - Subcommands run as `<plugin> run <subcommand> [args...]` with the
+//!   launcher's stdio, and the plugin's exit code becomes the launcher's.
+//! - Converters and routes run as `<plugin> handle` with one JSON request on
+//!   stdin and one JSON response on stdout:
+//!   - `{"type": "convert", "input": "...", "output": "..."}` writes a trace
+//!     trace_processor can read to `output`; answered with `{}` or
+//!     `{"error": "..."}`.
+//!   - `{"type": "http", "method", "path", "query", "headers", "body"}` for
+//!     requests to `/plugins/<name>/...`; answered with
+//!     `{"status", "headers", "body"}`.
+
+use crate::http_errors;
+use crate::paths::data_dir;
+use crate::server::{AppState, LimitedReader};
+use crate::stats;
+use crate::workspace::{self, Area};
+use log::warn;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tiny_http::{Header, Request, Response};
+
+#[derive(Clone, Deserialize)]
+pub struct Subcommand {
+    pub name: String,
+    #[serde(default)]
+    pub about: String,
+}
+
+#[derive(Clone, Deserialize)]
+struct Converter {
+    extensions: Vec<String>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Description {
+    name: String,
+    #[serde(default)]
+    subcommands: Vec<Subcommand>,
+    #[serde(default)]
+    converters: Vec<Converter>,
+    #[serde(default)]
+    routes: bool,
+}
+
+#[derive(Clone)]
+pub struct Plugin {
+    pub path: PathBuf,
+    description: Description,
+}
+
+impl Plugin {
+    pub fn name(&self) -> &str {
+        &self.description.name
+    }
+
+    pub fn subcommands(&self) -> &[Subcommand] {
+        &self.description.subcommands
+    }
+
+    fn converts(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else { return false };
+        let ext = ext.to_ascii_lowercase();
+        self.description.converters.iter().any(|c| c.extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)))
+    }
+
+    /// Send one request to `<plugin> handle` and read its response
+    fn handle(&self, request: &Value) -> Result<Value, String> {
+        let mut child = Command::new(&self.path)
+            .arg("handle")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| t!("plugin-failed", name = self.name(), error = e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(request.to_string().as_bytes());
+        }
+        let output = child.wait_with_output().map_err(|e| t!("plugin-failed", name = self.name(), error = e))?;
+        serde_json::from_slice(&output.stdout).map_err(|e| t!("plugin-bad-response", name = self.name(), error = e))
+    }
+}
+
+pub fn plugins_dir() -> PathBuf {
+    data_dir().join("plugins")
+}
+
+#[derive(Clone, Default)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+impl Plugins {
+    /// Ask every executable in the plugins directory to describe itself;
+    /// ones that fail are reported and skipped
+    pub fn discover() -> Plugins {
+        let mut paths: Vec<PathBuf> = fs::read_dir(plugins_dir())
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| is_executable(path))
+            .collect();
+        paths.sort();
+        let plugins = paths
+            .into_iter()
+            .filter_map(|path| match describe(&path) {
+                Ok(description) => Some(Plugin { path, description }),
+                Err(e) => {
+                    warn!("{}", t!("plugin-describe-failed", path = path.display(), error = e));
+                    None
+                }
+            })
+            .collect();
+        Plugins { plugins }
+    }
+
+    pub fn all(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    fn get(&self, name: &str) -> Option<&Plugin> {
+        self.plugins.iter().find(|p| p.name() == name)
+    }
+
+    /// Run the plugin subcommand named by `args[0]` and return its exit code;
+    /// None if no plugin provides it
+    pub fn run_subcommand(&self, args: &[OsString]) -> Option<Result<i32, String>> {
+        let name = args.first()?;
+        let plugin = self.plugins.iter().find(|p| p.subcommands().iter().any(|s| *name == *s.name))?;
+        let status = Command::new(&plugin.path)
+            .arg("run")
+            .args(args)
+            .status()
+            .map_err(|e| t!("plugin-failed", name = plugin.name(), error = e));
+        Some(status.map(|s| s.code().unwrap_or(1)))
+    }
+
+    /// Convert `path` with the plugin registered for its extension, if any;
+    /// returns the path to load
+    pub fn convert(&self, path: &Path) -> Result<PathBuf, String> {
+        let Some(plugin) = self.plugins.iter().find(|p| p.converts(path)) else {
+            return Ok(path.to_path_buf());
+        };
+        let dir = workspace::dir(Area::Converted);
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let output = dir.join(format!("{}.pftrace", stem));
+        say!("{}", t!("plugin-converting", path = path.display(), name = plugin.name()));
+        let response = plugin.handle(&json!({
+            "type": "convert",
+            "input": path,
+            "output": output,
+        }))?;
+        if let Some(error) = response.get("error").and_then(Value::as_str) {
+            return Err(t!("plugin-failed", name = plugin.name(), error = error));
+        }
+        if !output.is_file() {
+            return Err(t!("plugin-no-output", name = plugin.name(), path = output.display()));
+        }
+        Ok(output)
+    }
+}
+
+fn describe(path: &Path) -> Result<Description, String> {
+    let output = Command::new(path).arg("describe").stderr(Stdio::inherit()).output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("exited with {}", output.status));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e.eq_ignore_ascii_case("exe") || e.eq_ignore_ascii_case("bat"))
+}
+
+/// `/plugins/<name>/...`, forwarded to the plugin
+pub fn respond(mut request: Request, state: &AppState, rest: &str) {
+    let (name, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let Some(plugin) = state.plugins.get(name).filter(|p| p.description.routes) else {
+        return http_errors::not_found(request);
+    };
+
+    let mut body = Vec::new();
+    let limit = state.body_limits.api;
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        crate::server::respond_too_large(request, limit);
+        return;
+    }
+    let headers: HashMap<String, String> = request
+        .headers()
+        .iter()
+        .map(|h| (h.field.as_str().to_string(), h.value.as_str().to_string()))
+        .collect();
+    let query = request.url().split_once('?').map(|(_, q)| q.to_string()).unwrap_or_default();
+    let result = plugin.handle(&json!({
+        "type": "http",
+        "method": request.method().as_str(),
+        "path": format!("/{}", path),
+        "query": query,
+        "headers": headers,
+        "body": String::from_utf8_lossy(&body),
+    }));
+
+    let response = match result {
+        Ok(reply) => {
+            let status = reply.get("status").and_then(Value::as_u64).unwrap_or(200) as u16;
+            let body = reply.get("body").and_then(Value::as_str).unwrap_or_default().to_string();
+            let mut response = Response::from_string(body).with_status_code(status);
+            for (field, value) in reply.get("headers").and_then(Value::as_object).into_iter().flatten() {
+                if let Some(Ok(header)) = value.as_str().map(|v| Header::from_bytes(field.as_bytes(), v.as_bytes())) {
+                    response.add_header(header);
+                }
+            }
+            response
+        }
+        Err(e) => Response::from_string(e).with_status_code(502),
+    };
+    stats::respond(request, response);
+}
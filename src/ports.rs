@@ -0,0 +1,212 @@
+use log::{error, warn};
+use std::fs;
+use std::io;
+use std::net::{TcpListener, ToSocketAddrs};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub fn get_available_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .expect("Failed to bind to any port")
+        .local_addr()
+        .expect("Failed to get local address")
+        .port()
+}
+
+pub fn get_available_port_with_offset(offset: u16) -> u16 {
+    for _ in 0..20 {
+        let base = get_available_port();
+        let candidate = base as u32 + offset as u32;
+        if candidate > u16::MAX as u32 {
+            continue;
+        }
+        let port = candidate as u16;
+        if TcpListener::bind(("127.0.0.1", port)).is_ok() {
+            return port;
+        }
+    }
+    get_available_port()
+}
+
+/// Pre-allocate the (UI, RPC) port pair so CORS on trace_processor_shell can
+/// be configured before the UI HTTP server starts; ports given by the user
+/// are kept if free and moved to the next free port otherwise, and the rest
+/// are picked at random
+pub fn allocate_ports(ui_port: Option<u16>, rpc_port: Option<u16>) -> (u16, u16) {
+    let rpc_port = rpc_port.map(|port| free_port_from(port, ui_port, "trace_processor"));
+    let ui_port = ui_port.map(|port| free_port_from(port, rpc_port, "The UI server"));
+    let rpc_port = rpc_port.unwrap_or_else(|| {
+        let mut port = get_available_port_with_offset(10000);
+        while Some(port) == ui_port {
+            port = get_available_port_with_offset(10000);
+        }
+        port
+    });
+    let http_port = ui_port.unwrap_or_else(|| {
+        let mut port = get_available_port_with_offset(10000);
+        while port == rpc_port {
+            port = get_available_port_with_offset(10000);
+        }
+        port
+    });
+    (http_port, rpc_port)
+}
+
+fn is_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok() && TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// `port` if it can be bound, else the next port above it that can, skipping
+/// `taken`; falls back to a random port when none is left
+fn free_port_from(port: u16, taken: Option<u16>, what: &str) -> u16 {
+    if Some(port) != taken && is_free(port) {
+        return port;
+    }
+    let chosen = (port.saturating_add(1)..=u16::MAX)
+        .take(100)
+        .find(|&p| Some(p) != taken && is_free(p))
+        .unwrap_or_else(get_available_port);
+    let holder = find_port_owner(port)
+        .map(|o| format!("{} (PID {})", o.name, o.pid))
+        .unwrap_or_else(|| t!("port-holder-unknown"));
+    warn!("{}", t!("port-moved", what = what, port = port, holder = holder, chosen = chosen));
+    chosen
+}
+
+/// Bind `addr`, retrying with exponential backoff while the port is in use
+///
+/// A quick restart can race with the previous instance's socket (still open
+/// or in TIME_WAIT). On Unix the standard library already sets SO_REUSEADDR,
+/// which covers TIME_WAIT; we do not set it on Windows, where it would allow
+/// stealing a port from a live process. Retrying covers the rest.
+pub fn bind_with_retry<A: ToSocketAddrs + Copy>(addr: A, port: u16) -> io::Result<TcpListener> {
+    const ATTEMPTS: u32 = 6;
+    let mut delay = Duration::from_millis(100);
+    for attempt in 1.. {
+        match TcpListener::bind(addr) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && attempt < ATTEMPTS => {
+                let holder = find_port_owner(port)
+                    .map(|o| format!("{} (PID {})", o.name, o.pid))
+                    .unwrap_or_else(|| t!("port-holder-unknown"));
+                warn!(
+                    "{}",
+                    t!("port-busy-retry", port = port, holder = holder, delay = delay.as_millis())
+                );
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    unreachable!()
+}
+
+/// A process that holds a listening TCP port
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Find the process listening on `port`, if the platform lets us see it
+pub fn find_port_owner(port: u16) -> Option<PortOwner> {
+    if cfg!(windows) {
+        find_port_owner_windows(port)
+    } else if cfg!(target_os = "linux") {
+        find_port_owner_procfs(port)
+    } else {
+        find_port_owner_lsof(port)
+    }
+}
+
+/// Print what is holding `port` and how to get around it
+pub fn report_port_conflict(port: u16, what: &str) {
+    error!("{}", t!("port-conflict", what = what, port = port));
+    match find_port_owner(port) {
+        Some(owner) => error!(
+            "  {}",
+            t!("port-owner", port = port, name = owner.name, pid = owner.pid)
+        ),
+        None => error!("  {}", t!("port-owner-unknown", port = port)),
+    }
+    error!("  {}", t!("port-conflict-hint"));
+}
+
+fn find_port_owner_procfs(port: u16) -> Option<PortOwner> {
+    let inode = ["/proc/net/tcp", "/proc/net/tcp6"].iter().find_map(|table| {
+        let content = fs::read_to_string(table).ok()?;
+        content.lines().skip(1).find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit(':').next()?;
+            // State 0A is TCP_LISTEN
+            if fields.get(3) != Some(&"0A") || u16::from_str_radix(local_port, 16).ok()? != port {
+                return None;
+            }
+            fields.get(9).map(|s| s.to_string())
+        })
+    })?;
+    let needle = format!("socket:[{}]", inode);
+
+    for proc_entry in fs::read_dir("/proc").ok()?.flatten() {
+        let pid: u32 = match proc_entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        let fds = match fs::read_dir(proc_entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        let owns_socket = fds
+            .flatten()
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target.as_os_str() == needle.as_str()));
+        if owns_socket {
+            let name = fs::read_to_string(proc_entry.path().join("comm"))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            return Some(PortOwner { pid, name });
+        }
+    }
+    None
+}
+
+fn find_port_owner_lsof(port: u16) -> Option<PortOwner> {
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let pid = text.lines().find_map(|l| l.strip_prefix('p')?.parse().ok())?;
+    let name = text
+        .lines()
+        .find_map(|l| l.strip_prefix('c'))
+        .unwrap_or("unknown")
+        .to_string();
+    Some(PortOwner { pid, name })
+}
+
+fn find_port_owner_windows(port: u16) -> Option<PortOwner> {
+    let output = Command::new("netstat").args(["-ano", "-p", "TCP"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let suffix = format!(":{}", port);
+    let pid: u32 = text.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 5 && fields[1].ends_with(&suffix) && fields[3] == "LISTENING" {
+            fields[4].parse().ok()
+        } else {
+            None
+        }
+    })?;
+
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .and_then(|l| l.split(',').next())
+        .map(|s| s.trim_matches('"').to_string())
+        .filter(|s| !s.is_empty() && !s.starts_with("INFO:"))
+        .unwrap_or_else(|| "unknown".to_string());
+    Some(PortOwner { pid, name })
+}
@@ -0,0 +1,56 @@
+//! Named sets of extra trace_processor flags, selected with `--tp-preset`.
+//!
+//! Defined in `[presets.<name>]` tables in the config file (see
+//! `config_file`):
+//!
+//! ```toml
+//! [presets.heavy]
+//! args = ["--full-sort", "--extra-checks"]
+//!
+//! [presets.fast]
+//! args = ["--no-ftrace-raw"]
+//! ```
+//!
+//! Without them, presets are read from `tp_presets.json` in the data
+//! directory, where earlier versions kept them as a map from name to flags.
+
+use crate::config_file;
+use crate::paths::data_dir;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Preset {
+    args: Vec<String>,
+}
+
+pub fn presets_path() -> PathBuf {
+    data_dir().join("tp_presets.json")
+}
+
+/// All defined presets and the file defining them; a missing file defines none
+fn load() -> Result<(PathBuf, BTreeMap<String, Vec<String>>), String> {
+    if let Some((path, presets)) = config_file::section::<BTreeMap<String, Preset>>("presets") {
+        let presets = presets.map_err(|e| t!("presets-invalid", path = path.display(), error = e))?;
+        return Ok((path, presets.into_iter().map(|(name, preset)| (name, preset.args)).collect()));
+    }
+    let path = presets_path();
+    let presets = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| t!("presets-invalid", path = path.display(), error = e))?,
+        Err(_) => BTreeMap::new(),
+    };
+    Ok((path, presets))
+}
+
+/// Flags of preset `name`
+pub fn resolve(name: &str) -> Result<Vec<String>, String> {
+    let (path, mut presets) = load()?;
+    presets.remove(name).ok_or_else(|| {
+        let known: Vec<&str> = presets.keys().map(String::as_str).collect();
+        let known = if known.is_empty() { "-".to_string() } else { known.join(", ") };
+        t!("presets-unknown", name = name, path = path.display(), known = known)
+    })
+}
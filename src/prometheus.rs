@@ -0,0 +1,79 @@
+//! `GET /metrics` in the Prometheus text format, for launchers running on a
+//! shared analysis server.
+//!
+//! Requests are counted by route and status, with a latency histogram and
+//! the body bytes sent; see `stats` for how they are collected. The gauges
+//! for trace_processor and the sessions are read when scraped. The endpoint
+//! sits behind `--auth` like every other one; Prometheus can send the token
+//! as `Authorization: Bearer`.
+
+use crate::server::AppState;
+use crate::shutdown;
+use crate::stats::{self, LATENCY_BUCKETS};
+use std::fmt::{Display, Write};
+use tiny_http::{Header, Request, Response};
+
+pub fn respond(request: Request, state: &AppState) {
+    let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(render(state)).with_header(content_type));
+}
+
+fn render(state: &AppState) -> String {
+    let mut out = String::new();
+    let version = format!("{{version=\"{}\"}} 1", env!("CARGO_PKG_VERSION"));
+    metric(&mut out, "build_info", "gauge", "Launcher version", &[version]);
+    value(&mut out, "uptime_seconds", "gauge", "Seconds since the launcher started", stats::uptime().as_secs());
+
+    let routes = stats::routes();
+    let labels = |route: &str, status: u16| format!("route=\"{}\",status=\"{}\"", route, status);
+    let mut requests = Vec::new();
+    let mut durations = Vec::new();
+    let mut bytes = Vec::new();
+    for ((route, status), series) in &routes {
+        let labels = labels(route, *status);
+        requests.push(format!("{{{}}} {}", labels, series.count));
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(series.buckets) {
+            durations.push(format!("_bucket{{{},le=\"{}\"}} {}", labels, bound, count));
+        }
+        durations.push(format!("_bucket{{{},le=\"+Inf\"}} {}", labels, series.count));
+        durations.push(format!("_sum{{{}}} {}", labels, series.seconds));
+        durations.push(format!("_count{{{}}} {}", labels, series.count));
+        bytes.push(format!("{{{}}} {}", labels, series.bytes));
+    }
+    metric(&mut out, "http_requests_total", "counter", "HTTP requests answered, by route and status", &requests);
+    metric(&mut out, "http_request_duration_seconds", "histogram", "Time taken to answer HTTP requests", &durations);
+    metric(&mut out, "http_response_bytes_total", "counter", "Response body bytes sent", &bytes);
+    let rejected = "HTTP requests turned away with 503 because too many were in flight";
+    value(&mut out, "http_requests_rejected_total", "counter", rejected, stats::requests().rejected);
+    value(&mut out, "http_requests_in_flight", "gauge", "HTTP responses being sent", shutdown::in_flight());
+
+    let backend = &state.backend;
+    let up = u8::from(backend.try_wait().is_none());
+    value(&mut out, "trace_processor_up", "gauge", "Whether trace_processor is running", up);
+    let restarts = "Restarts of trace_processor after crashes";
+    value(&mut out, "trace_processor_restarts_total", "counter", restarts, backend.restarts());
+    if let Some(resources) = *state.resources.lock().unwrap() {
+        let memory = resources.memory_bytes;
+        value(&mut out, "trace_processor_memory_bytes", "gauge", "Memory used by trace_processor", memory);
+        let cpu = resources.cpu_percent;
+        value(&mut out, "trace_processor_cpu_percent", "gauge", "CPU used by trace_processor", cpu);
+    }
+    let sessions = "trace_processors open for extra traces under /api/sessions";
+    value(&mut out, "sessions", "gauge", sessions, state.sessions.list().len());
+    out
+}
+
+fn value(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    metric(out, name, kind, help, &[format!(" {}", value)]);
+}
+
+/// Write one metric family; each sample is what follows the metric name,
+/// i.e. an optional suffix and labels, then the value
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[String]) {
+    let name = format!("perfetto_launcher_{}", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for sample in samples {
+        let _ = writeln!(out, "{}{}", name, sample);
+    }
+}
@@ -0,0 +1,196 @@
+//! `query` subcommand: run SQL on a trace and print the result, for scripts.
+//!
+//! trace_processor is started on a free port without the UI, with the same
+//! SQL modules, presets and flags as the UI's session, and stopped again once
+//! the result is printed. With several statements, trace_processor answers
+//! with the result of the last one.
+//!
+//! `POST /launcher/query` does the same on the trace the UI has open, or on
+//! an extra session's with `?session=<id>`, for scripts, editors and
+//! dashboards that would rather not speak trace_processor's protobuf RPC.
+//! The SQL is the request body, or the `sql` field of a JSON body, and the
+//! answer is `{"columns": [...], "rows": [[...], ...]}`. In multi-user mode
+//! `?session=` is required: the UI's trace_processor holds whatever trace
+//! the last tab loaded into it, which may be another user's.
+
+use crate::rpc::{self, RpcClient, Rows};
+use crate::server::{query_param, respond_too_large, AppState, LimitedReader};
+use crate::trace_processor::Instance;
+use crate::traces_api::{respond_error, respond_json};
+use crate::users::Viewer;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tiny_http::Request;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum QueryFormat {
+    /// Aligned columns for reading
+    Table,
+    Csv,
+    /// An array with an object per row
+    Json,
+}
+
+pub struct QueryArgs<'a> {
+    pub trace_processor: &'a Path,
+    /// Flags for trace_processor besides the RPC port and the trace
+    pub tp_args: Vec<String>,
+    pub trace: &'a Path,
+    /// SQL given on the command line
+    pub sql: Option<&'a str>,
+    /// File to read the SQL from, `-` for stdin, when `sql` is None
+    pub file: Option<&'a PathBuf>,
+    pub format: QueryFormat,
+    pub timeout: Duration,
+}
+
+pub fn run(args: QueryArgs) -> Result<(), String> {
+    let sql = match (args.sql, args.file) {
+        (Some(sql), _) => sql.to_string(),
+        (None, Some(path)) if path.as_os_str() != "-" => {
+            fs::read_to_string(path).map_err(|e| t!("query-read-failed", path = path.display(), error = e))?
+        }
+        _ => {
+            let mut sql = String::new();
+            io::stdin().read_to_string(&mut sql).map_err(|e| t!("query-read-failed", path = "-", error = e))?;
+            sql
+        }
+    };
+    if sql.trim().is_empty() {
+        return Err(t!("query-empty"));
+    }
+    let instance = Instance::start_with(args.trace_processor, &args.tp_args, args.trace, args.timeout)?;
+    let rows = instance.client.query_rows(&sql)?;
+    let output = match args.format {
+        QueryFormat::Table => format_table(&rows),
+        QueryFormat::Csv => format_csv(&rows),
+        QueryFormat::Json => format_json(&rows),
+    };
+    print!("{}", output);
+    Ok(())
+}
+
+/// JSON body of `POST /launcher/query`
+#[derive(Deserialize)]
+struct QueryRequest {
+    sql: String,
+}
+
+/// `POST /launcher/query[?session=<id>]`
+pub fn respond(mut request: Request, state: &AppState, viewer: &Viewer) {
+    let limit = state.body_limits.api;
+    let mut body = Vec::new();
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        return respond_too_large(request, limit);
+    }
+    let json = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Content-Type") && h.value.as_str().trim_start().starts_with("application/json"));
+    let sql = if json {
+        match serde_json::from_slice::<QueryRequest>(&body) {
+            Ok(query) => query.sql,
+            Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+        }
+    } else {
+        String::from_utf8_lossy(&body).into_owned()
+    };
+    if sql.trim().is_empty() {
+        return respond_error(request, 400, t!("query-api-empty"));
+    }
+
+    let (port, _in_use) = match query_param(request.url(), "session") {
+        Some(id) => match state.sessions.get(&id).filter(|s| viewer.can_see(&s.trace)) {
+            Some(session) => (session.tp_port, Some(session.use_guard())),
+            None => return respond_error(request, 404, t!("session-unknown", id = id)),
+        },
+        None if state.users.is_some() => return respond_error(request, 400, t!("query-api-session-required")),
+        None => (state.rpc_port, None),
+    };
+    let started = Instant::now();
+    let result = match RpcClient::new(port).query_raw(&sql) {
+        Ok(result) => result,
+        Err(e) => return respond_error(request, 502, t!("query-api-unreachable", error = e)),
+    };
+    if let Some(error) = rpc::query_error(&result) {
+        return respond_error(request, 400, error);
+    }
+    match rpc::decode_rows(&result) {
+        Ok(rows) => {
+            let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+            respond_json(request, 200, json!({ "columns": rows.columns, "rows": rows.rows, "elapsed_ms": elapsed_ms }))
+        }
+        Err(e) => respond_error(request, 502, e),
+    }
+}
+
+/// A cell as text: strings as they are, nulls empty
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn format_table(rows: &Rows) -> String {
+    // Statements such as CREATE answer with no columns at all
+    if rows.columns.is_empty() {
+        return format!("{}\n", t!("query-row-count", count = 0));
+    }
+    let cells: Vec<Vec<String>> = rows.rows.iter().map(|row| row.iter().map(cell_text).collect()).collect();
+    let widths: Vec<usize> = (0..rows.columns.len())
+        .map(|i| {
+            let widest = cells.iter().map(|row| row.get(i).map_or(0, |cell| cell.chars().count())).max();
+            widest.unwrap_or(0).max(rows.columns[i].chars().count())
+        })
+        .collect();
+    let line = |values: &[String]| {
+        let padded: Vec<String> = values.iter().zip(&widths).map(|(v, w)| format!("{:w$}", v, w = w)).collect();
+        format!("{}\n", padded.join(" | ").trim_end())
+    };
+    let mut out = line(&rows.columns);
+    out.push_str(&format!("{}\n", widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-")));
+    for row in &cells {
+        out.push_str(&line(row));
+    }
+    out.push_str(&format!("{}\n", t!("query-row-count", count = rows.rows.len())));
+    out
+}
+
+pub fn format_csv(rows: &Rows) -> String {
+    let field = |text: String| {
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text
+        }
+    };
+    let line = |values: Vec<String>| format!("{}\n", values.into_iter().map(field).collect::<Vec<_>>().join(","));
+    let mut out = line(rows.columns.clone());
+    for row in &rows.rows {
+        out.push_str(&line(row.iter().map(cell_text).collect()));
+    }
+    out
+}
+
+/// An object per row, written out by hand to keep the columns in order
+pub fn format_json(rows: &Rows) -> String {
+    let objects: Vec<String> = rows
+        .rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> =
+                rows.columns.iter().zip(row).map(|(column, value)| format!("{}: {}", json!(column), value)).collect();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+    if objects.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
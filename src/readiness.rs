@@ -0,0 +1,47 @@
+//! Machine-readable startup protocol.
+//!
+//! With `--machine-readable`, the launcher prints exactly one line to stdout
+//! once startup completes:
+//!
+//!   READY {"ui_url":"http://localhost:43123/?rpc_port=43123","rpc_url":"http://127.0.0.1:43124/","pid":1234,"tp_pid":1235}
+//!
+//! or, if startup fails:
+//!
+//!   ERROR {"code":"tp_not_found","message":"..."}
+//!
+//! Parent processes can wait for either prefix instead of scraping human output.
+
+use serde_json::json;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn ready(ui_url: &str, rpc_url: &str, tp_pid: u32) {
+    emit(
+        "READY",
+        json!({
+            "ui_url": ui_url,
+            "rpc_url": rpc_url,
+            "pid": std::process::id(),
+            "tp_pid": tp_pid,
+        }),
+    );
+}
+
+pub fn error(code: &str, message: &str) {
+    emit("ERROR", json!({ "code": code, "message": message }));
+}
+
+fn emit(kind: &str, payload: serde_json::Value) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{} {}", kind, payload);
+    let _ = stdout.flush();
+}
@@ -0,0 +1,108 @@
+//! Recently opened traces at `/launcher/recent`.
+//!
+//! Every catalog trace remembers when it was last opened through the
+//! launcher, so the list survives restarts. The page links each trace to its
+//! permalink, which loads it back into the UI; the same list is served as
+//! JSON to clients that ask for `application/json` or pass `?format=json`.
+
+use crate::catalog::Trace;
+use crate::dates::civil_from_days;
+use crate::landing::escape;
+use crate::server::{origin, query_param, AppState};
+use crate::stats;
+use crate::users::Viewer;
+use serde_json::json;
+use std::cmp::Reverse;
+use tiny_http::{Header, Request, Response};
+
+const TEMPLATE: &str = include_str!("../templates/recent.html");
+
+/// Traces listed unless `?limit=` asks for another number
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+/// `GET /launcher/recent[?limit=N][&format=json]`
+pub fn respond(request: Request, state: &AppState, viewer: &Viewer) {
+    let url = request.url();
+    let limit = query_param(url, "limit").and_then(|l| l.parse().ok()).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let mut traces: Vec<Trace> = state
+        .catalog
+        .lock()
+        .unwrap()
+        .traces()
+        .iter()
+        .filter(|trace| trace.opened.is_some() && viewer.can_see(trace))
+        .cloned()
+        .collect();
+    traces.sort_by_key(|trace| (Reverse(trace.opened), Reverse(trace.added)));
+    traces.truncate(limit);
+
+    let wants_json = query_param(url, "format").is_some_and(|f| f == "json")
+        || request
+            .headers()
+            .iter()
+            .any(|h| h.field.equiv("Accept") && h.value.as_str().trim_start().starts_with("application/json"));
+    if wants_json {
+        let origin = origin(&request);
+        let traces: Vec<_> = traces
+            .iter()
+            .map(|trace| {
+                json!({
+                    "id": trace.id,
+                    "name": trace.name,
+                    "size": trace.size,
+                    "opened": trace.opened,
+                    "link": format!("{}/t/{}", origin, trace.id),
+                })
+            })
+            .collect();
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let body = json!({ "traces": traces }).to_string();
+        stats::respond(request, Response::from_string(body).with_header(content_type));
+        return;
+    }
+
+    let list = if traces.is_empty() {
+        format!("<p class=\"empty\">{}</p>", escape(&t!("recent-empty")))
+    } else {
+        let rows: String = traces
+            .iter()
+            .map(|trace| {
+                format!(
+                    "<tr><td><a href=\"/t/{}\">{}</a></td><td class=\"num\">{:.1} MB</td><td>{}</td></tr>",
+                    trace.id,
+                    escape(&trace.name),
+                    trace.size as f64 / (1024.0 * 1024.0),
+                    format_time(trace.opened.unwrap_or(0))
+                )
+            })
+            .collect();
+        format!(
+            "<table><tr><th>{}</th><th class=\"num\">{}</th><th>{}</th></tr>{}</table>",
+            escape(&t!("recent-name")),
+            escape(&t!("recent-size")),
+            escape(&t!("recent-opened")),
+            rows
+        )
+    };
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&t!("recent-title"))),
+        ("list", list),
+        ("back", escape(&t!("recent-back"))),
+    ];
+    let mut page = TEMPLATE.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
+
+/// `YYYY-MM-DD HH:MM UTC`
+pub fn format_time(secs: u64) -> String {
+    let secs = secs as i64;
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, rem / 3600, rem % 3600 / 60)
+}
@@ -0,0 +1,390 @@
+//! Recording a trace with `perfetto`/`tracebox` and opening it in the UI once
+//! done: the `record` subcommand and `/launcher/record`.
+//!
+//! The trace config is a text-format file or a template. Built-in templates
+//! are `cpu`, `memory` and `android`; `<name>.pbtxt` files in
+//! `record_templates` in the data directory add more or replace them.
+//! Recordings are saved in `recordings` in the data directory and added to
+//! the catalog.
+//!
+//! - `GET /launcher/record` lists the templates and shows the current or
+//!   last recording
+//! - `POST /launcher/record` with `{"template": "cpu", "duration": "30s"}`
+//!   or `{"config": "<text>"}` starts one; `"device"` records on an adb
+//!   device and `"open": false` leaves the browser alone
+//! - `DELETE /launcher/record` stops it early, keeping what was recorded
+//!
+//! One recording runs at a time. In multi-user mode only admins may record,
+//! as it runs commands on the launcher's machine.
+
+use crate::android;
+use crate::browser;
+use crate::capture::{self, config_duration, find_recorder, parse_duration, with_duration};
+use crate::catalog::{random_id, Catalog, Trace};
+use crate::http_errors;
+use crate::paths::data_dir;
+use crate::server::{origin, respond_too_large, AppState, LimitedReader};
+use crate::shutdown;
+use crate::traces_api::{respond_error, respond_json};
+use crate::url_handler;
+use crate::users::Viewer;
+use crate::workspace::{self, Area};
+use log::warn;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tiny_http::{Method, Request};
+
+const TEMPLATES: &[(&str, &str)] = &[
+    ("android", include_str!("../templates/record/android.pbtxt")),
+    ("cpu", include_str!("../templates/record/cpu.pbtxt")),
+    ("memory", include_str!("../templates/record/memory.pbtxt")),
+];
+
+/// Template used when neither a template nor a config is given
+pub const DEFAULT_TEMPLATE: &str = "cpu";
+
+/// Interval of the progress lines the subcommand prints
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn templates_dir() -> PathBuf {
+    data_dir().join("record_templates")
+}
+
+/// Names of the built-in and user templates, sorted
+pub fn template_names() -> Vec<String> {
+    let mut names: Vec<String> = TEMPLATES.iter().map(|(name, _)| name.to_string()).collect();
+    let user = fs::read_dir(templates_dir()).into_iter().flatten().filter_map(Result::ok).map(|e| e.path());
+    names.extend(
+        user.filter(|path| path.extension().is_some_and(|e| e == "pbtxt"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned())),
+    );
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Text of template `name`, the user's if there is one
+pub fn template(name: &str) -> Result<String, String> {
+    let user = templates_dir().join(format!("{}.pbtxt", name));
+    if !name.contains(['/', '\\']) && user.is_file() {
+        return fs::read_to_string(&user).map_err(|e| t!("capture-config-unreadable", path = user.display(), error = e));
+    }
+    match TEMPLATES.iter().find(|(builtin, _)| *builtin == name) {
+        Some((_, text)) => Ok(text.to_string()),
+        None => Err(t!("record-unknown-template", name = name, known = template_names().join(", "))),
+    }
+}
+
+/// What to record
+pub struct Options {
+    /// Text-format trace config, with any duration override applied
+    pub config: String,
+    /// adb serial of the device to record on; records locally if unset
+    pub device: Option<String>,
+    /// Trace file to write; a new one in the recordings area if unset
+    pub output: Option<PathBuf>,
+    /// Directory searched for tracebox before PATH
+    pub tools_dir: PathBuf,
+}
+
+enum State {
+    Recording,
+    Done(Trace),
+    Failed(String),
+}
+
+/// A recording, running or finished
+pub struct Recording {
+    pub id: String,
+    pub output: PathBuf,
+    pub device: Option<String>,
+    /// Length set by the config; None if it records until stopped
+    pub duration: Option<Duration>,
+    /// Unix time the recording was started
+    pub started: u64,
+    begun: Instant,
+    /// How long it took, once it is over
+    took: OnceLock<Duration>,
+    recorder: PathBuf,
+    pid: u32,
+    state: Mutex<State>,
+    finished: Condvar,
+}
+
+impl Recording {
+    /// Start the recorder; once it is done the trace is added to the catalog
+    /// and passed to `on_done`
+    pub fn start(options: Options, on_done: impl FnOnce(&Trace) + Send + 'static) -> Result<Arc<Recording>, String> {
+        let recorder = match &options.device {
+            Some(_) => android::adb()?,
+            None => find_recorder(&options.tools_dir).ok_or_else(|| t!("capture-no-recorder"))?,
+        };
+        let output = match options.output {
+            Some(path) => path,
+            None => {
+                let dir = workspace::dir(Area::Recordings);
+                fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                dir.join(format!("record-{}.pftrace", capture::timestamp()))
+            }
+        };
+        let child = capture::spawn(&recorder, options.device.as_deref(), &options.config, &output)?;
+        let recording = Arc::new(Recording {
+            id: random_id(),
+            output,
+            device: options.device,
+            duration: config_duration(&options.config),
+            started: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            begun: Instant::now(),
+            took: OnceLock::new(),
+            recorder,
+            pid: child.id(),
+            state: Mutex::new(State::Recording),
+            finished: Condvar::new(),
+        });
+
+        let finishing = recording.clone();
+        thread::spawn(move || {
+            let recording = finishing;
+            let result = capture::finish(child, &recording.recorder, recording.device.as_deref(), &recording.output)
+                .and_then(|_| {
+                    Catalog::open()
+                        .add(&recording.output)
+                        .map_err(|e| t!("catalog-add-failed", path = recording.output.display(), error = e))
+                });
+            let _ = recording.took.set(recording.begun.elapsed());
+            let state = match result {
+                Ok(trace) => {
+                    on_done(&trace);
+                    State::Done(trace)
+                }
+                Err(e) => State::Failed(e),
+            };
+            *recording.state.lock().unwrap() = state;
+            recording.finished.notify_all();
+        });
+        Ok(recording)
+    }
+
+    pub fn running(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), State::Recording)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.took.get().copied().unwrap_or_else(|| self.begun.elapsed())
+    }
+
+    /// Stop recording early; the recorder still writes out what it has
+    pub fn stop(&self) {
+        if !self.running() {
+            return;
+        }
+        let status = match &self.device {
+            // The recorder's command line names the file it writes
+            Some(serial) => {
+                let remote = capture::device_path(&self.output);
+                Command::new(&self.recorder).args(["-s", serial, "shell", "pkill", "-INT", "-f", &remote]).status()
+            }
+            None if cfg!(unix) => Command::new("kill").args(["-INT", &self.pid.to_string()]).status(),
+            None => Command::new("taskkill").args(["/PID", &self.pid.to_string(), "/F"]).status(),
+        };
+        if let Err(e) = status {
+            warn!("{}", t!("record-stop-failed", error = e));
+        }
+    }
+
+    /// Wait for the recording to end and return its trace
+    pub fn wait(&self) -> Result<Trace, String> {
+        let state = self.state.lock().unwrap();
+        let state = self.finished.wait_while(state, |state| matches!(state, State::Recording)).unwrap();
+        match &*state {
+            State::Done(trace) => Ok(trace.clone()),
+            State::Failed(e) => Err(e.clone()),
+            State::Recording => unreachable!(),
+        }
+    }
+
+    /// Description for API clients, with links from `origin`
+    fn json(&self, origin: &str) -> Value {
+        let (state, trace, error) = match &*self.state.lock().unwrap() {
+            State::Recording => ("recording", None, None),
+            State::Done(trace) => ("done", Some(trace.clone()), None),
+            State::Failed(e) => ("failed", None, Some(e.clone())),
+        };
+        json!({
+            "id": self.id,
+            "state": state,
+            "device": self.device,
+            "started": self.started,
+            "elapsed_ms": self.elapsed().as_millis() as u64,
+            "duration_ms": self.duration.map(|d| d.as_millis() as u64),
+            "output": self.output,
+            "trace": trace.map(|t| json!({ "id": t.id, "name": t.name, "link": format!("{}/t/{}", origin, t.id) })),
+            "error": error,
+        })
+    }
+}
+
+/// Record with `options` until done or Ctrl+C, printing progress, then
+/// open the trace in the launcher unless `open` is false
+pub fn run(options: Options, open: bool, browser: Option<&str>) -> Result<(), String> {
+    shutdown::install(Duration::from_secs(30));
+    let recording = Recording::start(options, |_| {})?;
+    say!("{}", t!("record-started", path = recording.output.display()));
+    let mut next_progress = PROGRESS_INTERVAL;
+    let mut stopping = false;
+    while recording.running() {
+        thread::sleep(Duration::from_millis(200));
+        if shutdown::requested() && !stopping {
+            stopping = true;
+            say!("{}", t!("record-stopping"));
+            recording.stop();
+        }
+        if !stopping && recording.elapsed() >= next_progress {
+            next_progress += PROGRESS_INTERVAL;
+            let elapsed = capture::format_duration(Duration::from_secs(recording.elapsed().as_secs()));
+            match recording.duration {
+                Some(duration) => {
+                    say!("{}", t!("record-progress", elapsed = elapsed, duration = capture::format_duration(duration)))
+                }
+                None => say!("{}", t!("record-progress-open", elapsed = elapsed)),
+            }
+        }
+    }
+    let trace = recording.wait()?;
+    say!("{}", t!("capture-saved", path = recording.output.display(), id = trace.id.as_str()));
+    if open {
+        url_handler::open_in_launcher(&format!("/t/{}", trace.id), browser)?;
+    }
+    Ok(())
+}
+
+/// Config for a template or a config file, with the duration override
+pub fn resolve_config(
+    template: Option<&str>,
+    config: Option<&Path>,
+    duration: Option<Duration>,
+) -> Result<String, String> {
+    let text = match config {
+        Some(path) => {
+            fs::read_to_string(path).map_err(|e| t!("capture-config-unreadable", path = path.display(), error = e))?
+        }
+        None => self::template(template.unwrap_or(DEFAULT_TEMPLATE))?,
+    };
+    Ok(match duration {
+        Some(duration) => with_duration(&text, duration),
+        None => text,
+    })
+}
+
+/// The server's recording, at most one at a time
+pub struct Recorder {
+    /// Directory searched for tracebox before PATH
+    tools_dir: PathBuf,
+    current: Mutex<Option<Arc<Recording>>>,
+}
+
+impl Recorder {
+    pub fn new(tools_dir: PathBuf) -> Recorder {
+        Recorder { tools_dir, current: Mutex::new(None) }
+    }
+
+    /// Stop a recording in progress, e.g. when the launcher exits
+    pub fn stop(&self) {
+        if let Some(recording) = self.current.lock().unwrap().as_ref() {
+            recording.stop();
+        }
+    }
+}
+
+/// Body of `POST /launcher/record`
+#[derive(Deserialize)]
+struct NewRecording {
+    template: Option<String>,
+    /// Text-format trace config, instead of a template
+    config: Option<String>,
+    /// e.g. `30s`, replacing the config's `duration_ms`
+    duration: Option<String>,
+    device: Option<String>,
+    open: Option<bool>,
+}
+
+/// `/launcher/record`
+pub fn respond(request: Request, state: &AppState, viewer: &Viewer) {
+    let method = request.method().clone();
+    if !matches!(method, Method::Get | Method::Head) && matches!(viewer, Viewer::User { admin: false, .. }) {
+        return respond_error(request, 403, t!("record-admins-only"));
+    }
+    let current = state.recorder.current.lock().unwrap().clone();
+    match method {
+        Method::Get | Method::Head => {
+            let recording = current.map(|r| r.json(&origin(&request)));
+            respond_json(request, 200, json!({ "templates": template_names(), "recording": recording }));
+        }
+        Method::Post => respond_start(request, state),
+        Method::Delete => match current.filter(|r| r.running()) {
+            Some(recording) => {
+                recording.stop();
+                let body = recording.json(&origin(&request));
+                respond_json(request, 202, body);
+            }
+            None => respond_error(request, 404, t!("record-none")),
+        },
+        _ => http_errors::method_not_allowed(request, "GET, HEAD, POST, DELETE"),
+    }
+}
+
+fn respond_start(mut request: Request, state: &AppState) {
+    let limit = state.body_limits.api;
+    let mut body = Vec::new();
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        return respond_too_large(request, limit);
+    }
+    let new: NewRecording = match serde_json::from_slice(&body) {
+        Ok(new) => new,
+        Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+    };
+    let duration = match new.duration.as_deref().map(parse_duration).transpose() {
+        Ok(duration) => duration,
+        Err(e) => return respond_error(request, 400, e),
+    };
+    let config = match new.config {
+        Some(config) => Ok(duration.map_or(config.clone(), |duration| with_duration(&config, duration))),
+        None => resolve_config(new.template.as_deref(), None, duration),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => return respond_error(request, 400, e),
+    };
+
+    let mut current = state.recorder.current.lock().unwrap();
+    if current.as_ref().is_some_and(|r| r.running()) {
+        return respond_error(request, 409, t!("record-busy"));
+    }
+    let options = Options { config, device: new.device, output: None, tools_dir: state.recorder.tools_dir.clone() };
+    let open = new.open.unwrap_or(true) && state.open_browser;
+    let (scheme, host, port, browser) = (state.scheme, state.url_host.clone(), state.http_port, state.browser.clone());
+    let started = Recording::start(options, move |trace| {
+        if open {
+            let url = format!("{}://{}:{}/t/{}", scheme, host, port, trace.id);
+            if let Err(e) = browser::open(&url, browser.as_deref()) {
+                warn!("{}", t!("browser-failed", error = e));
+            }
+        }
+    });
+    match started {
+        Ok(recording) => {
+            *current = Some(recording.clone());
+            drop(current);
+            let body = recording.json(&origin(&request));
+            respond_json(request, 202, body);
+        }
+        Err(e) => respond_error(request, 500, t!("record-failed", error = e)),
+    }
+}
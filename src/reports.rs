@@ -0,0 +1,584 @@
+//! Reports generated for catalog traces and served under `/reports/`.
+//!
+//! Configured in `[[reports]]` tables in the config file (see `config_file`):
+//!
+//! ```toml
+//! [[reports]]
+//! name = "summary"
+//! kind = "summary"
+//! on_ingest = true
+//!
+//! [[reports]]
+//! name = "startup"
+//! kind = "metrics"
+//! metrics = ["android_startup"]
+//! schedule = "daily 02:00"
+//!
+//! [[reports]]
+//! name = "custom"
+//! kind = "command"
+//! command = ["python3", "report.py", "{trace}", "{output}"]
+//! extension = "txt"
+//! schedule = "every 6h"
+//! ```
+//!
+//! Without them, reports are read from `reports.json` in the data directory,
+//! where earlier versions kept them as `{"reports": [...]}`.
+//!
+//! Each report writes one file per trace to `reports/<name>/<trace id>.<ext>`;
+//! a trace that already has its file is not reported again. `on_ingest`
+//! reports run as soon as a trace has been ingested (see `--index-traces`),
+//! scheduled ones (`every <duration>` or `daily HH:MM` in UTC) run over every
+//! catalog trace still missing a report.
+//!
+//! - `summary`: an HTML page from the trace's extracted metadata and its most
+//!   frequent slices
+//! - `metrics`: trace_processor metrics as JSON, with the numeric changes
+//!   against the previous report of the same name
+//! - `command`: any command, with `{trace}`, `{output}`, `{id}` and `{name}`
+//!   replaced in its arguments
+
+use crate::catalog::{Catalog, Trace};
+use crate::config_file;
+use crate::capture::parse_duration;
+use crate::http_errors;
+use crate::ingest::Ingest;
+use crate::landing::escape;
+use crate::metadata;
+use crate::metrics::{self, MetricsFormat};
+use crate::paths::data_dir;
+use crate::roots::{Resolved, ServePolicy};
+use crate::search;
+use crate::server::serve_file;
+use crate::shutdown;
+use crate::stats;
+use log::warn;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Request, Response};
+
+const SUMMARY_TEMPLATE: &str = include_str!("../templates/report_summary.html");
+const INDEX_TEMPLATE: &str = include_str!("../templates/reports.html");
+
+/// Slice names listed in a summary
+const SUMMARY_TOP_SLICES: usize = 20;
+
+#[derive(Deserialize)]
+struct ReportsFile {
+    reports: Vec<Report>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Report {
+    pub name: String,
+    #[serde(flatten)]
+    kind: Kind,
+    /// Run for each trace as soon as it has been ingested
+    #[serde(default)]
+    on_ingest: bool,
+    /// `every <duration>` or `daily HH:MM` (UTC)
+    schedule: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Kind {
+    Summary,
+    Metrics {
+        metrics: Vec<String>,
+    },
+    Command {
+        command: Vec<String>,
+        #[serde(default = "default_extension")]
+        extension: String,
+    },
+}
+
+fn default_extension() -> String {
+    "txt".to_string()
+}
+
+impl Report {
+    fn extension(&self) -> &str {
+        match &self.kind {
+            Kind::Summary => "html",
+            Kind::Metrics { .. } => "json",
+            Kind::Command { extension, .. } => extension,
+        }
+    }
+
+    fn output_path(&self, trace: &Trace) -> PathBuf {
+        reports_dir().join(&self.name).join(format!("{}.{}", trace.id, self.extension()))
+    }
+}
+
+enum Schedule {
+    Every(Duration),
+    /// Seconds after midnight UTC
+    Daily(u64),
+}
+
+impl Schedule {
+    fn parse(text: &str) -> Result<Schedule, String> {
+        let text = text.trim();
+        if let Some(every) = text.strip_prefix("every ") {
+            let every = parse_duration(every)?;
+            if every.is_zero() {
+                return Err(format!("'{}' must be longer than zero", text));
+            }
+            return Ok(Schedule::Every(every));
+        }
+        if let Some(time) = text.strip_prefix("daily ") {
+            let parsed = time
+                .trim()
+                .split_once(':')
+                .and_then(|(h, m)| Some((h.parse::<u64>().ok()?, m.parse::<u64>().ok()?)));
+            return match parsed {
+                Some((hour, minute)) if hour < 24 && minute < 60 => Ok(Schedule::Daily(hour * 3600 + minute * 60)),
+                _ => Err(format!("invalid time '{}' (use HH:MM)", time)),
+            };
+        }
+        Err(format!("invalid schedule '{}' (use 'every <duration>' or 'daily HH:MM')", text))
+    }
+
+    fn next_after(&self, now: SystemTime) -> SystemTime {
+        match self {
+            Schedule::Every(every) => now + *every,
+            Schedule::Daily(offset) => {
+                let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                let mut next = secs - secs % 86400 + offset;
+                if next <= secs {
+                    next += 86400;
+                }
+                UNIX_EPOCH + Duration::from_secs(next)
+            }
+        }
+    }
+}
+
+fn legacy_path() -> PathBuf {
+    data_dir().join("reports.json")
+}
+
+/// Where reports are configured: the config file with `[[reports]]`, or
+/// `reports.json` if that exists, or else the user's config file
+pub fn reports_path() -> PathBuf {
+    if let Some((path, _)) = config_file::section::<toml::Value>("reports") {
+        return path;
+    }
+    let legacy = legacy_path();
+    if legacy.is_file() {
+        return legacy;
+    }
+    config_file::paths().swap_remove(0)
+}
+
+pub fn reports_dir() -> PathBuf {
+    data_dir().join("reports")
+}
+
+/// All configured reports; without any configuration, none
+pub fn load() -> Result<Vec<Report>, String> {
+    let (path, reports) = match config_file::section::<Vec<Report>>("reports") {
+        Some(section) => section,
+        None => {
+            let path = legacy_path();
+            let Ok(text) = fs::read_to_string(&path) else { return Ok(Vec::new()) };
+            let file = serde_json::from_str::<ReportsFile>(&text).map_err(|e| e.to_string());
+            (path, file.map(|file| file.reports))
+        }
+    };
+    let invalid = |error: String| t!("reports-invalid", path = path.display(), error = error);
+    let reports = reports.map_err(invalid)?;
+    for report in &reports {
+        if report.name.is_empty() || report.name.contains(['/', '\\', '.']) {
+            return Err(invalid(format!("invalid report name '{}'", report.name)));
+        }
+        if let Some(schedule) = &report.schedule {
+            Schedule::parse(schedule).map_err(invalid)?;
+        }
+        if let Kind::Command { command, .. } = &report.kind {
+            if command.is_empty() {
+                return Err(invalid(format!("report '{}' has an empty command", report.name)));
+            }
+        }
+    }
+    Ok(reports)
+}
+
+/// What generating reports needs from the launcher configuration
+pub struct Runner {
+    pub trace_processor: PathBuf,
+    pub metric_extension_dirs: Vec<PathBuf>,
+}
+
+static RUNNER: OnceLock<Runner> = OnceLock::new();
+
+/// Enable `on_ingest` reports for this process
+pub fn init(runner: Runner) {
+    let _ = RUNNER.set(runner);
+}
+
+/// Run every `on_ingest` report that `trace` does not have yet
+pub fn trace_ingested(trace: &Trace) {
+    let Some(runner) = RUNNER.get() else { return };
+    let reports = match load() {
+        Ok(reports) => reports,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    for report in reports.iter().filter(|r| r.on_ingest) {
+        if !report.output_path(trace).is_file() {
+            write_report(runner, report, trace);
+        }
+    }
+}
+
+/// Generate `report` for `trace`, printing the outcome; true on success
+fn write_report(runner: &Runner, report: &Report, trace: &Trace) -> bool {
+    match generate(runner, report, trace) {
+        Ok(path) => {
+            say!("{}", t!("report-written", report = report.name, name = trace.name, path = path.display()));
+            true
+        }
+        Err(e) => {
+            warn!("{}", t!("report-failed", report = report.name, name = trace.name, error = e));
+            false
+        }
+    }
+}
+
+/// Generate `report` for every catalog trace that does not have it yet;
+/// returns how many were written
+fn run_pending(runner: &Runner, report: &Report) -> usize {
+    let traces = Catalog::open().traces().to_vec();
+    traces
+        .iter()
+        .filter(|trace| trace.path.is_file() && !report.output_path(trace).is_file())
+        .map(|trace| write_report(runner, report, trace))
+        .filter(|written| *written)
+        .count()
+}
+
+/// `reports run`: generate `name` (or every report) now, for one trace or
+/// every trace still missing it
+pub fn run_now(runner: &Runner, name: Option<&str>, trace: Option<&str>) -> Result<(), String> {
+    let reports: Vec<Report> = load()?.into_iter().filter(|r| name.is_none_or(|name| r.name == name)).collect();
+    if let Some(name) = name.filter(|_| reports.is_empty()) {
+        return Err(t!("reports-unknown", name = name, path = reports_path().display()));
+    }
+    let trace = match trace {
+        Some(id) => Some(Catalog::open().find(id).cloned().ok_or_else(|| t!("catalog-unknown-id", id = id))?),
+        None => None,
+    };
+    let mut failed = false;
+    for report in &reports {
+        match &trace {
+            Some(trace) => failed |= !write_report(runner, report, trace),
+            None => {
+                let written = run_pending(runner, report);
+                say!("{}", t!("reports-ran", report = report.name, count = written));
+            }
+        }
+    }
+    if failed {
+        return Err(t!("reports-some-failed"));
+    }
+    Ok(())
+}
+
+/// Run scheduled reports in the background until shutdown; needs `init`
+pub fn start_scheduler() {
+    let Some(runner) = RUNNER.get() else { return };
+    let reports = match load() {
+        Ok(reports) => reports,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    let mut scheduled: Vec<(Report, Schedule, SystemTime)> = reports
+        .into_iter()
+        .filter_map(|report| {
+            let schedule = Schedule::parse(report.schedule.as_deref()?).ok()?;
+            let next = schedule.next_after(SystemTime::now());
+            Some((report, schedule, next))
+        })
+        .collect();
+    if scheduled.is_empty() {
+        return;
+    }
+    say!("{}", t!("reports-scheduled", count = scheduled.len()));
+    thread::spawn(move || {
+        while !shutdown::requested() {
+            let now = SystemTime::now();
+            for (report, schedule, next) in scheduled.iter_mut().filter(|(_, _, next)| *next <= now) {
+                run_pending(runner, report);
+                *next = schedule.next_after(SystemTime::now());
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+/// Write the report through a `.partial` file so a failed run leaves nothing
+/// that would count as done
+fn generate(runner: &Runner, report: &Report, trace: &Trace) -> Result<PathBuf, String> {
+    let output = report.output_path(trace);
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let partial = output.with_extension(format!("{}.partial", report.extension()));
+    let result = match &report.kind {
+        Kind::Summary => summary(runner, trace).and_then(|html| fs::write(&partial, html).map_err(|e| e.to_string())),
+        Kind::Metrics { metrics } => metrics_report(runner, report, trace, metrics)
+            .and_then(|json| fs::write(&partial, json).map_err(|e| e.to_string())),
+        Kind::Command { command, .. } => run_command(command, trace, &partial),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &output).map_err(|e| e.to_string())?;
+    Ok(output)
+}
+
+fn run_command(command: &[String], trace: &Trace, output: &Path) -> Result<(), String> {
+    let expand = |arg: &String| {
+        arg.replace("{trace}", &trace.path.display().to_string())
+            .replace("{output}", &output.display().to_string())
+            .replace("{id}", &trace.id)
+            .replace("{name}", &trace.name)
+    };
+    let args: Vec<String> = command.iter().map(expand).collect();
+    let status = Command::new(&args[0]).args(&args[1..]).status().map_err(|e| format!("{}: {}", args[0], e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", args[0], status));
+    }
+    if !output.is_file() {
+        return Err(t!("report-no-output", path = output.display()));
+    }
+    Ok(())
+}
+
+/// HTML page from the trace's metadata and slice index, ingesting it first
+/// if needed
+fn summary(runner: &Runner, trace: &Trace) -> Result<String, String> {
+    let ingest = Ingest { trace_processor: runner.trace_processor.clone(), enabled: true };
+    if !crate::ingest::is_done(trace) {
+        ingest.run(trace)?;
+    }
+    let read = |path: PathBuf| -> Value {
+        fs::read_to_string(path).ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or(Value::Null)
+    };
+    let meta = read(metadata::metadata_path(&trace.id));
+    let index = read(search::index_path(&trace.id));
+
+    let row = |label: String, value: &Value| -> String {
+        let value = match value {
+            Value::Null => "-".to_string(),
+            Value::String(s) => s.clone(),
+            Value::Array(items) => {
+                let items: Vec<String> =
+                    items.iter().map(|v| v.as_str().map(String::from).unwrap_or(v.to_string())).collect();
+                items.join(", ")
+            }
+            other => other.to_string(),
+        };
+        format!("<tr><th>{}</th><td>{}</td></tr>", escape(&label), escape(&value))
+    };
+    let mut overview = String::new();
+    overview.push_str(&row(t!("report-summary-file"), &json!(trace.path)));
+    overview.push_str(&row(t!("report-summary-size"), &json!(trace.size)));
+    overview.push_str(&row(t!("report-summary-duration"), &meta["duration_ns"]));
+    overview.push_str(&row(t!("report-summary-capture-time"), &meta["capture_time_ns"]));
+    overview.push_str(&row(t!("report-summary-data-sources"), &meta["data_sources"]));
+    for (key, value) in meta["device"].as_object().into_iter().flatten() {
+        overview.push_str(&row(key.clone(), value));
+    }
+
+    let data_loss: String = meta["data_loss"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|stat| row(stat["name"].as_str().unwrap_or_default().to_string(), &stat["value"]))
+        .collect();
+    let data_loss = if data_loss.is_empty() {
+        format!("<p class=\"empty\">{}</p>", escape(&t!("report-summary-no-data-loss")))
+    } else {
+        format!("<table>{}</table>", data_loss)
+    };
+
+    let mut slices: Vec<&Value> = index.as_array().into_iter().flatten().collect();
+    slices.sort_by_key(|slice| Reverse(slice["count"].as_u64().unwrap_or(0)));
+    let slices: String = slices
+        .iter()
+        .take(SUMMARY_TOP_SLICES)
+        .map(|slice| row(slice["name"].as_str().unwrap_or_default().to_string(), &slice["count"]))
+        .collect();
+
+    let values = [
+        ("lang", crate::i18n::current().tag().to_string()),
+        ("title", escape(&trace.name)),
+        ("overview", overview),
+        ("data_loss_heading", escape(&t!("report-summary-data-loss"))),
+        ("data_loss", data_loss),
+        ("slices_heading", escape(&t!("report-summary-top-slices"))),
+        ("slices", format!("<table>{}</table>", slices)),
+        ("open", format!("/t/{}", trace.id)),
+        ("open_label", escape(&t!("report-summary-open"))),
+    ];
+    let mut page = SUMMARY_TEMPLATE.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    Ok(page)
+}
+
+/// Metrics as JSON plus the numeric changes against the most recent earlier
+/// report of the same name
+fn metrics_report(runner: &Runner, report: &Report, trace: &Trace, names: &[String]) -> Result<String, String> {
+    let (tp, extensions) = (&runner.trace_processor, &runner.metric_extension_dirs);
+    let output = metrics::compute(tp, &trace.path, names, extensions, MetricsFormat::Json)?;
+    let values: Value = serde_json::from_str(&output).map_err(|e| e.to_string())?;
+
+    let baseline = latest_report(report, trace);
+    let changes: Vec<Value> = baseline
+        .as_ref()
+        .map(|(_, previous)| {
+            let (mut before, mut after) = (Map::new(), Map::new());
+            flatten("", &previous["metrics"], &mut before);
+            flatten("", &values, &mut after);
+            after
+                .iter()
+                .filter_map(|(path, value)| {
+                    let (old, new) = (before.get(path)?.as_f64()?, value.as_f64()?);
+                    let percent = if old == 0.0 { Value::Null } else { json!((new - old) / old.abs() * 100.0) };
+                    let change = json!({ "metric": path, "baseline": old, "value": new, "change_percent": percent });
+                    (old != new).then_some(change)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let document = json!({
+        "trace": { "id": trace.id, "name": trace.name },
+        "generated": SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        "metrics": values,
+        "baseline": baseline.map(|(id, _)| id),
+        "changes": changes,
+    });
+    Ok(serde_json::to_string_pretty(&document).unwrap())
+}
+
+/// The newest other report output of `report`, as (trace id, contents)
+fn latest_report(report: &Report, trace: &Trace) -> Option<(String, Value)> {
+    let dir = reports_dir().join(&report.name);
+    let mut outputs: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .filter(|path| path.file_stem().is_some_and(|stem| *stem != *trace.id))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    outputs.sort_by_key(|(modified, _)| Reverse(*modified));
+    let (_, path) = outputs.into_iter().next()?;
+    let value: Value = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+    Some((path.file_stem()?.to_string_lossy().into_owned(), value))
+}
+
+/// Numeric leaves of `value` keyed by their dotted path
+fn flatten(prefix: &str, value: &Value, out: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, child, out);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                flatten(&format!("{}[{}]", prefix, index), child, out);
+            }
+        }
+        Value::Number(_) => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+        _ => {}
+    }
+}
+
+/// `/reports/`, `/reports/<name>/` and the report files below them
+pub fn respond(request: Request, rest: &str) {
+    let rest = rest.trim_matches('/');
+    if rest.is_empty() {
+        let mut names: Vec<String> = load().unwrap_or_default().into_iter().map(|r| r.name).collect();
+        names.sort();
+        let items = names.iter().map(|name| (format!("/reports/{}/", name), name.clone(), String::new())).collect();
+        return respond_index(request, t!("reports-title"), items);
+    }
+    if !rest.contains('/') {
+        let mut files: Vec<(SystemTime, String)> = fs::read_dir(reports_dir().join(rest))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.to_string();
+                (!name.ends_with(".partial")).then_some(())?;
+                Some((entry.metadata().ok()?.modified().ok()?, name))
+            })
+            .collect();
+        files.sort_by_key(|(modified, _)| Reverse(*modified));
+        let catalog = Catalog::open();
+        let items = files
+            .into_iter()
+            .map(|(_, file)| {
+                let id = file.split('.').next().unwrap_or_default();
+                let trace = catalog.get(id).map(|t| t.name.clone()).unwrap_or_default();
+                (format!("/reports/{}/{}", rest, file), file, trace)
+            })
+            .collect();
+        return respond_index(request, rest.to_string(), items);
+    }
+    match ServePolicy::new().with_root("", &reports_dir()).resolve(rest) {
+        Resolved::File(path) => serve_file(request, &path),
+        Resolved::Embedded(..) | Resolved::NotFound => http_errors::not_found(request),
+        Resolved::Forbidden => http_errors::forbidden(request),
+    }
+}
+
+/// A list of (link, label, note) entries
+fn respond_index(request: Request, title: String, items: Vec<(String, String, String)>) {
+    let list = if items.is_empty() {
+        format!("<p class=\"empty\">{}</p>", escape(&t!("reports-none", path = reports_path().display())))
+    } else {
+        let items: String = items
+            .iter()
+            .map(|(link, label, note)| {
+                format!(
+                    "<li><a href=\"{}\">{}</a> <span class=\"meta\">{}</span></li>",
+                    escape(link),
+                    escape(label),
+                    escape(note)
+                )
+            })
+            .collect();
+        format!("<ul>{}</ul>", items)
+    };
+    let page = INDEX_TEMPLATE
+        .replace("{{lang}}", crate::i18n::current().tag())
+        .replace("{{title}}", &escape(&title))
+        .replace("{{list}}", &list);
+    let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(page).with_header(content_type));
+}
@@ -0,0 +1,128 @@
+//! Restarting trace_processor on request, e.g. after a trace got it stuck,
+//! while the UI server stays up.
+//!
+//! `POST /launcher/restart-backend`, the `restart-backend` subcommand and the
+//! tray menu restart it in place: the new trace_processor listens on the same
+//! port, so the RPC relay and the extra sessions carry on as they are.
+//! Requests that arrive together restart it once. The endpoint answers when
+//! the new trace_processor does, or at once with `?wait=false`.
+//!
+//! `/launcher/events` is a Server-Sent Events stream with `backend-restarting`
+//! when trace_processor is started again, for whatever reason, and
+//! `backend-restarted` once it answers. `index.html` is served with a snippet
+//! that reloads the tab on the latter, so open tabs reconnect by themselves;
+//! tabs pointed at a session's `rpc_port` are left alone.
+
+use crate::backend::Backend;
+use crate::instance;
+use crate::server::{query_param, AppState};
+use crate::shutdown;
+use crate::sse;
+use crate::traces_api::{respond_error, respond_json};
+use crate::users::Viewer;
+use log::info;
+use serde_json::json;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tiny_http::Request;
+
+/// Longest wait for a restarted trace_processor; the backend gives up after
+/// the startup timeout, normally well before this
+const MAX_WAIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `POST /launcher/restart-backend[?wait=false]`
+pub fn respond(request: Request, state: &AppState, viewer: &Viewer) {
+    if matches!(viewer, Viewer::User { admin: false, .. }) {
+        return respond_error(request, 403, t!("restart-admins-only"));
+    }
+    let backend = &state.backend;
+    info!("{}", t!("restart-requested"));
+    let start = match backend.restart_after(backend.starts().started) {
+        Ok(start) => start,
+        Err(e) => return respond_error(request, 500, t!("tp-spawn-failed", error = e)),
+    };
+    say!("{}", t!("tp-restarted", pid = backend.pid()));
+    if query_param(request.url(), "wait").is_some_and(|wait| wait == "false" || wait == "0") {
+        return respond_json(request, 202, json!({ "start": start, "pid": backend.pid(), "ready": false }));
+    }
+    // The latest start may be a later request's; it is the one that counts
+    let starts = backend.wait_starts(MAX_WAIT, |starts| starts.settled < starts.started);
+    if starts.ready == starts.started {
+        respond_json(request, 200, json!({ "start": starts.started, "pid": backend.pid(), "ready": true }));
+    } else if let Some(status) = backend.try_wait() {
+        respond_error(request, 502, t!("restart-exited", status = status));
+    } else {
+        respond_error(request, 504, t!("restart-not-answering"));
+    }
+}
+
+/// `restart-backend` subcommand: have the running launcher restart its
+/// trace_processor, waiting for it to answer unless `wait` is false
+pub fn run(wait: bool) -> Result<(), String> {
+    let running = instance::running().ok_or_else(|| t!("restart-no-launcher"))?;
+    say!("{}", t!("restart-asking", pid = running.pid));
+    let url = format!("{}/launcher/restart-backend?wait={}", running.request_url(), wait);
+    let agent = ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(5)).build();
+    let parse = |response: ureq::Response| -> serde_json::Value {
+        response.into_string().ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default()
+    };
+    let restarted = match agent.post(&url).call() {
+        Ok(response) => parse(response),
+        Err(ureq::Error::Status(status, response)) => {
+            let error = parse(response)["error"].as_str().map(String::from);
+            return Err(error.unwrap_or_else(|| format!("HTTP {}", status)));
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    let pid = restarted["pid"].as_u64().unwrap_or_default();
+    let key = if restarted["ready"].as_bool() == Some(true) { "tp-restarted" } else { "restart-started" };
+    say!("{}", t!(key, pid = pid));
+    Ok(())
+}
+
+/// `GET /launcher/events`: stream events until the client goes away or the
+/// launcher shuts down
+pub fn respond_events(request: Request, state: &AppState) {
+    let backend = state.backend.clone();
+    sse::stream(request, move |writer| stream(&backend, writer));
+}
+
+fn stream(backend: &Arc<Backend>, writer: &mut dyn Write) -> io::Result<()> {
+    let mut seen = backend.starts();
+    while !shutdown::requested() {
+        let starts = backend.wait_starts(sse::KEEP_ALIVE, |starts| starts == &seen);
+        let mut message = String::new();
+        if starts.started != seen.started {
+            let data = json!({ "start": starts.started });
+            message.push_str(&format!("event: backend-restarting\ndata: {}\n\n", data));
+        }
+        if starts.ready != seen.ready {
+            let data = json!({ "start": starts.ready, "pid": backend.pid() });
+            message.push_str(&format!("event: backend-restarted\ndata: {}\n\n", data));
+        }
+        if message.is_empty() {
+            message.push_str(": keep-alive\n\n");
+        }
+        seen = starts;
+        writer.write_all(message.as_bytes())?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Added to `index.html` before `</body>`: reloads the tab once a restarted
+/// trace_processor answers, if the tab uses the one on `ui_rpc_port`
+///
+/// The query is taken before the UI may drop it from the address, so the
+/// reload still finds the launcher's RPC.
+pub fn snippet(ui_rpc_port: u16) -> String {
+    format!(
+        "<script>(function () {{ var search = location.search; \
+if (new URLSearchParams(search).get('rpc_port') !== '{}') return; \
+new EventSource('/launcher/events').addEventListener('backend-restarted', function () {{ \
+if (location.search === search) location.reload(); \
+else location.replace(location.pathname + search + location.hash); }}); }})();</script>",
+        ui_rpc_port
+    )
+}
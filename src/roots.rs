@@ -0,0 +1,362 @@
+//! Which files the UI server may serve: a set of directories, each mounted
+//! at a URL prefix.
+//!
+//! A request path is percent-decoded, rejected outright if any segment is
+//! `..` or otherwise unsafe, and only then joined to its root; the result must
+//! still lie inside that root once symlinks are resolved. Roots are resolved
+//! per request, so directories created after startup work; a root that cannot
+//! be canonicalized at all is compared lexically instead of being treated as a
+//! mismatch for every file.
+//!
+//! The UI embedded in the binary, if any, can be mounted at a prefix too; its
+//! files take precedence over a directory at the same prefix.
+//!
+//! The UI directory is often the executable's own, with trace_processor, the
+//! config file and maybe logs next to the UI, so a root can be limited to the
+//! file types the UI is made of.
+
+use crate::assets;
+use std::path::{Component, Path, PathBuf};
+
+struct Root {
+    /// URL prefix without leading or trailing slashes; empty for the top level
+    prefix: String,
+    /// Absolute, lexically normalized directory
+    dir: PathBuf,
+    /// Only files with one of `UI_EXTENSIONS` are served
+    ui_only: bool,
+}
+
+/// File types of a Perfetto UI build
+const UI_EXTENSIONS: &[&str] = &[
+    "html", "htm", "js", "mjs", "cjs", "map", "css", "json", "webmanifest", "wasm", "png", "jpg", "jpeg", "gif", "webp",
+    "svg", "ico", "woff", "woff2", "ttf", "otf", "pb", "data",
+];
+
+pub enum Resolved {
+    File(PathBuf),
+    /// A file of the embedded UI, by its path within the UI
+    Embedded(String, &'static [u8]),
+    NotFound,
+    Forbidden,
+}
+
+#[derive(Default)]
+pub struct ServePolicy {
+    roots: Vec<Root>,
+    /// URL prefix of the embedded UI
+    embedded: Option<String>,
+}
+
+impl ServePolicy {
+    pub fn new() -> ServePolicy {
+        ServePolicy::default()
+    }
+
+    /// Allow files under `dir` at `/<prefix>/...`; longer prefixes win
+    pub fn with_root(self, prefix: &str, dir: &Path) -> ServePolicy {
+        self.add_root(prefix, dir, false)
+    }
+
+    /// Like `with_root`, but only for the files of a UI build
+    pub fn with_ui_root(self, prefix: &str, dir: &Path) -> ServePolicy {
+        self.add_root(prefix, dir, true)
+    }
+
+    fn add_root(mut self, prefix: &str, dir: &Path, ui_only: bool) -> ServePolicy {
+        let dir = if dir.is_absolute() {
+            dir.to_path_buf()
+        } else {
+            std::env::current_dir().map(|cwd| cwd.join(dir)).unwrap_or_else(|_| dir.to_path_buf())
+        };
+        self.roots.push(Root { prefix: prefix.trim_matches('/').to_string(), dir: normalize(&dir), ui_only });
+        self.roots.sort_by_key(|root| std::cmp::Reverse(root.prefix.len()));
+        self
+    }
+
+    /// Serve the embedded UI at `/<prefix>/...`, ahead of directory roots
+    pub fn with_embedded_ui(mut self, prefix: &str) -> ServePolicy {
+        if assets::has_ui() {
+            self.embedded = Some(prefix.trim_matches('/').to_string());
+        }
+        self
+    }
+
+    /// Map a request path (without query string) to a file to serve
+    pub fn resolve(&self, url_path: &str) -> Resolved {
+        let Some(decoded) = percent_decode(url_path.trim_start_matches('/')) else {
+            return Resolved::Forbidden;
+        };
+        if let Some(rest) = self.embedded.as_deref().and_then(|prefix| strip_prefix(&decoded, prefix)) {
+            let rest = rest.trim_start_matches('/');
+            if let Some(contents) = rest.split('/').all(is_safe_segment).then(|| assets::get(rest)).flatten() {
+                return Resolved::Embedded(rest.to_string(), contents);
+            }
+        }
+        let Some(root) = self.roots.iter().find(|root| strip_prefix(&decoded, &root.prefix).is_some()) else {
+            return Resolved::NotFound;
+        };
+        let rest = strip_prefix(&decoded, &root.prefix).unwrap_or_default();
+        let mut path = root.dir.clone();
+        for segment in rest.split('/').filter(|s| !s.is_empty()) {
+            if !is_safe_segment(segment) {
+                return Resolved::Forbidden;
+            }
+            path.push(segment);
+        }
+
+        let resolved = match (path.canonicalize(), root.dir.canonicalize()) {
+            (Ok(file), Ok(root_dir)) if file.starts_with(&root_dir) => Resolved::File(file),
+            (Ok(file), Err(_)) if file.starts_with(&root.dir) => Resolved::File(file),
+            (Ok(_), _) => Resolved::Forbidden,
+            // Every segment was checked above, so the joined path cannot escape
+            (Err(_), Err(_)) if path.is_file() => Resolved::File(path),
+            (Err(_), _) => Resolved::NotFound,
+        };
+        match resolved {
+            Resolved::File(file) if root.ui_only && file.is_file() && !is_ui_file(&file) => Resolved::Forbidden,
+            resolved => resolved,
+        }
+    }
+}
+
+/// Whether `path` is one of the file types of a UI build, or a
+/// pre-compressed copy of one
+fn is_ui_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let name = name.strip_suffix(".br").or_else(|| name.strip_suffix(".gz")).unwrap_or(&name);
+    let extension = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    UI_EXTENSIONS.contains(&extension)
+}
+
+/// The part of `path` below `prefix`, if `path` is `prefix` or under it
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// A single path segment that cannot climb out of or jump away from its parent
+fn is_safe_segment(segment: &str) -> bool {
+    if segment == "." || segment == ".." || segment.contains(['\\', '\0', ':']) {
+        return false;
+    }
+    // Anything the platform would parse as more than one normal component
+    let mut components = Path::new(segment).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+/// Remove `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes (`+` is literal in paths); None if the result is
+/// not valid UTF-8
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = text.get(i + 1..i + 3).and_then(hex_byte) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(out).ok()
+}
+
+/// The byte two hex digits stand for; `from_str_radix` alone would take a sign
+pub fn hex_byte(hex: &str) -> Option<u8> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u8::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh directory tree:
+    /// `dist/index.html`, `dist/assets/app.js`, `dist/a b+c.txt`,
+    /// `uploads/trace.pftrace` and `secret.txt` next to them
+    fn fixture(name: &str) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("perfetto_launcher-roots-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("dist/assets")).unwrap();
+        fs::create_dir_all(base.join("uploads")).unwrap();
+        fs::write(base.join("dist/index.html"), "index").unwrap();
+        fs::write(base.join("dist/assets/app.js"), "js").unwrap();
+        fs::write(base.join("dist/a b+c.txt"), "spaces").unwrap();
+        fs::write(base.join("uploads/trace.pftrace"), "trace").unwrap();
+        fs::write(base.join("secret.txt"), "secret").unwrap();
+        base
+    }
+
+    fn policy(base: &Path) -> ServePolicy {
+        ServePolicy::new().with_root("", &base.join("dist")).with_root("launcher/uploads", &base.join("uploads"))
+    }
+
+    fn served(resolved: Resolved) -> Option<String> {
+        match resolved {
+            Resolved::File(path) => Some(fs::read_to_string(path).unwrap()),
+            _ => None,
+        }
+    }
+
+    fn forbidden(resolved: Resolved) -> bool {
+        matches!(resolved, Resolved::Forbidden)
+    }
+
+    #[test]
+    fn serves_files_inside_roots() {
+        let base = fixture("inside");
+        let policy = policy(&base);
+        assert_eq!(served(policy.resolve("index.html")).as_deref(), Some("index"));
+        assert_eq!(served(policy.resolve("/assets/app.js")).as_deref(), Some("js"));
+        assert_eq!(served(policy.resolve("assets//app.js")).as_deref(), Some("js"));
+        assert_eq!(served(policy.resolve("a%20b+c.txt")).as_deref(), Some("spaces"));
+        assert_eq!(served(policy.resolve("launcher/uploads/trace.pftrace")).as_deref(), Some("trace"));
+        assert!(matches!(policy.resolve("missing.js"), Resolved::NotFound));
+    }
+
+    #[test]
+    fn rejects_parent_segments() {
+        let base = fixture("parent");
+        let policy = policy(&base);
+        for path in [
+            "../secret.txt",
+            "assets/../../secret.txt",
+            "%2e%2e/secret.txt",
+            "%2E%2E%2fsecret.txt",
+            "assets/%2e%2e/%2e%2e/secret.txt",
+            "launcher/uploads/../../secret.txt",
+            "launcher/uploads/%2e%2e/dist/index.html",
+            "./index.html",
+        ] {
+            assert!(forbidden(policy.resolve(path)), "{} was not forbidden", path);
+        }
+    }
+
+    #[test]
+    fn percent_decodes_paths() {
+        assert_eq!(percent_decode("a%2Fb%2fc").as_deref(), Some("a/b/c"));
+        // `+` only means a space in queries
+        assert_eq!(percent_decode("a+b%20c").as_deref(), Some("a+b c"));
+        assert_eq!(percent_decode("%e6%97%a5.pftrace").as_deref(), Some("日.pftrace"));
+        // Escapes cut short or not hex are kept as they are
+        assert_eq!(percent_decode("%4").as_deref(), Some("%4"));
+        assert_eq!(percent_decode("50%").as_deref(), Some("50%"));
+        assert_eq!(percent_decode("%zz").as_deref(), Some("%zz"));
+        assert_eq!(percent_decode("%+f").as_deref(), Some("%+f"));
+        assert_eq!(percent_decode("%%41").as_deref(), Some("%A"));
+        assert_eq!(percent_decode("%日").as_deref(), Some("%日"));
+        // Bytes that are not UTF-8 make the whole path invalid
+        assert_eq!(percent_decode("%FF"), None);
+        assert_eq!(percent_decode("ok%E6%97"), None);
+    }
+
+    #[test]
+    fn rejects_separators_and_absolute_paths() {
+        let base = fixture("separators");
+        let policy = policy(&base);
+        let absolute = base.join("secret.txt").display().to_string();
+        for path in [
+            "..%5csecret.txt".to_string(),
+            "assets\\..\\..\\secret.txt".to_string(),
+            "index.html%00.js".to_string(),
+            "C:%5cWindows%5cwin.ini".to_string(),
+            format!("%2f{}", absolute.trim_start_matches('/')),
+        ] {
+            assert!(!matches!(policy.resolve(&path), Resolved::File(_)), "{} was served", path);
+        }
+        assert!(forbidden(policy.resolve("%ff%fe")));
+    }
+
+    #[test]
+    fn prefixes_match_whole_segments() {
+        let base = fixture("prefix");
+        fs::create_dir_all(base.join("dist/launcher/uploadsx")).unwrap();
+        fs::write(base.join("dist/launcher/uploadsx/f.txt"), "dist").unwrap();
+        let policy = policy(&base);
+        assert_eq!(served(policy.resolve("launcher/uploadsx/f.txt")).as_deref(), Some("dist"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_out_of_the_root() {
+        let base = fixture("symlink");
+        std::os::unix::fs::symlink(base.join("secret.txt"), base.join("dist/link.txt")).unwrap();
+        let policy = policy(&base);
+        assert!(forbidden(policy.resolve("link.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn accepts_a_root_reached_through_a_symlink() {
+        let base = fixture("symlinked-root");
+        std::os::unix::fs::symlink(base.join("dist"), base.join("dist-link")).unwrap();
+        let policy = ServePolicy::new().with_root("", &base.join("dist-link"));
+        assert_eq!(served(policy.resolve("assets/app.js")).as_deref(), Some("js"));
+    }
+
+    #[test]
+    fn accepts_relative_and_unnormalized_roots() {
+        let base = fixture("unnormalized");
+        let policy = ServePolicy::new().with_root("", &base.join("uploads/../dist/./"));
+        assert_eq!(served(policy.resolve("index.html")).as_deref(), Some("index"));
+    }
+
+    #[test]
+    fn ui_roots_serve_only_ui_files() {
+        let base = fixture("ui-only");
+        for name in ["perfetto_launcher.toml", "trace_processor_shell", "trace_processor_shell.exe", "access.log"] {
+            fs::write(base.join("dist").join(name), "secret").unwrap();
+        }
+        fs::write(base.join("dist/assets/app.js.gz"), "gz").unwrap();
+        let policy = ServePolicy::new().with_ui_root("", &base.join("dist"));
+        assert_eq!(served(policy.resolve("index.html")).as_deref(), Some("index"));
+        assert_eq!(served(policy.resolve("assets/app.js.gz")).as_deref(), Some("gz"));
+        assert!(matches!(policy.resolve("assets"), Resolved::File(_)));
+        for path in ["perfetto_launcher.toml", "trace_processor_shell", "trace_processor_shell.exe", "access.log"] {
+            assert!(forbidden(policy.resolve(path)), "{} was not forbidden", path);
+        }
+        // The same files under a plain root are served
+        let plain = ServePolicy::new().with_root("", &base.join("dist"));
+        assert_eq!(served(plain.resolve("perfetto_launcher.toml")).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn roots_created_after_startup_are_served() {
+        let base = fixture("late");
+        let late = base.join("late");
+        let policy = ServePolicy::new().with_root("late", &late);
+        assert!(matches!(policy.resolve("late/f.txt"), Resolved::NotFound));
+        fs::create_dir_all(&late).unwrap();
+        fs::write(late.join("f.txt"), "late").unwrap();
+        assert_eq!(served(policy.resolve("late/f.txt")).as_deref(), Some("late"));
+    }
+}
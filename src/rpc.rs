@@ -0,0 +1,238 @@
+//! Minimal client for trace_processor_shell's HTTP RPC interface.
+//!
+//! Queries are sent to `/query` as a protobuf `QueryArgs` message and answered
+//! with a protobuf `QueryResult`. Only the handful of fields the launcher needs
+//! are encoded/decoded here, so no protobuf dependency is required.
+
+use serde_json::Value;
+use std::io::Read;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct RpcClient {
+    base_url: String,
+}
+
+impl RpcClient {
+    pub fn new(port: u16) -> RpcClient {
+        RpcClient { base_url: format!("http://127.0.0.1:{}", port) }
+    }
+
+    /// GET /status; succeeds once trace_processor accepts RPC requests
+    pub fn status(&self) -> Result<Vec<u8>, String> {
+        let response = ureq::get(&format!("{}/status", self.base_url))
+            .timeout(Duration::from_secs(2))
+            .call()
+            .map_err(|e| e.to_string())?;
+        read_body(response)
+    }
+
+    /// Poll /status until it answers or `timeout` elapses; returns the time waited
+    pub fn wait_ready(&self, timeout: Duration) -> Result<Duration, String> {
+        let start = Instant::now();
+        let mut delay = Duration::from_millis(20);
+        loop {
+            match self.status() {
+                Ok(_) => return Ok(start.elapsed()),
+                Err(e) if start.elapsed() >= timeout => return Err(e),
+                Err(_) => {
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(Duration::from_millis(500));
+                }
+            }
+        }
+    }
+
+    /// POST /query and return the raw `QueryResult` bytes
+    pub fn query_raw(&self, sql: &str) -> Result<Vec<u8>, String> {
+        let response = ureq::post(&format!("{}/query", self.base_url))
+            .send_bytes(&encode_query_args(sql))
+            .map_err(|e| e.to_string())?;
+        read_body(response)
+    }
+
+    /// Run `sql` and fail with trace_processor's error message if it reports one
+    pub fn query(&self, sql: &str) -> Result<Vec<u8>, String> {
+        let result = self.query_raw(sql)?;
+        match query_error(&result) {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    /// Run `sql` and decode the result into rows
+    pub fn query_rows(&self, sql: &str) -> Result<Rows, String> {
+        decode_rows(&self.query(sql)?)
+    }
+}
+
+/// A decoded query result; blobs are reported as their length
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+// QueryResult.CellsBatch.CellType
+const CELL_NULL: u64 = 1;
+const CELL_VARINT: u64 = 2;
+const CELL_FLOAT64: u64 = 3;
+const CELL_STRING: u64 = 4;
+const CELL_BLOB: u64 = 5;
+
+/// Decode `QueryResult { column_names = 1; batch = 3 }`, where each
+/// `CellsBatch` holds the cell types (1) and per-type packed values: varints
+/// (2), doubles (3), blobs (4) and NUL-separated strings (5)
+pub fn decode_rows(result: &[u8]) -> Result<Rows, String> {
+    let malformed = || "malformed query result".to_string();
+    let mut columns = Vec::new();
+    let mut cells = Vec::new();
+    for (field, bytes) in ProtoFields::new(result) {
+        match field {
+            1 => columns.push(String::from_utf8_lossy(bytes).into_owned()),
+            3 => decode_batch(bytes, &mut cells).ok_or_else(malformed)?,
+            _ => {}
+        }
+    }
+    if columns.is_empty() {
+        return Ok(Rows { columns, rows: Vec::new() });
+    }
+    let rows = cells.chunks(columns.len()).map(|row| row.to_vec()).collect();
+    Ok(Rows { columns, rows })
+}
+
+fn decode_batch(batch: &[u8], cells: &mut Vec<Value>) -> Option<()> {
+    let mut types = Vec::new();
+    let mut varints = Vec::new();
+    let mut doubles = Vec::new();
+    let mut blobs = Vec::new();
+    let mut strings = Vec::new();
+    for (field, bytes) in ProtoFields::new(batch) {
+        match field {
+            1 => decode_packed_varints(bytes, &mut types)?,
+            2 => decode_packed_varints(bytes, &mut varints)?,
+            3 => doubles.extend(bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()))),
+            4 => blobs.push(bytes.len()),
+            5 => {
+                let text = String::from_utf8_lossy(bytes);
+                let text = text.strip_suffix('\0').unwrap_or(&text);
+                strings.extend(text.split('\0').map(str::to_string));
+            }
+            _ => {}
+        }
+    }
+    let (mut varints, mut doubles) = (varints.into_iter(), doubles.into_iter());
+    let (mut blobs, mut strings) = (blobs.into_iter(), strings.into_iter());
+    for cell_type in types {
+        cells.push(match cell_type {
+            CELL_NULL => Value::Null,
+            CELL_VARINT => Value::from(varints.next()? as i64),
+            CELL_FLOAT64 => Value::from(doubles.next()?),
+            CELL_STRING => Value::from(strings.next()?),
+            CELL_BLOB => Value::from(format!("<blob {} bytes>", blobs.next()?)),
+            _ => return None,
+        });
+    }
+    Some(())
+}
+
+fn decode_packed_varints(bytes: &[u8], out: &mut Vec<u64>) -> Option<()> {
+    let mut pos = 0;
+    while pos < bytes.len() {
+        out.push(decode_varint(bytes, &mut pos)?);
+    }
+    Some(())
+}
+
+fn read_body(response: ureq::Response) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+/// `QueryArgs { sql_query = 1 }`
+fn encode_query_args(sql: &str) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(sql.len() + 8);
+    encode_key(&mut buf, 1, WIRE_LEN);
+    encode_varint(&mut buf, sql.len() as u64);
+    buf.extend_from_slice(sql.as_bytes());
+    buf
+}
+
+/// `QueryResult.error` (field 2), if set
+pub fn query_error(result: &[u8]) -> Option<String> {
+    ProtoFields::new(result)
+        .find(|(field, _)| *field == 2)
+        .map(|(_, bytes)| String::from_utf8_lossy(bytes).into_owned())
+        .filter(|e| !e.is_empty())
+}
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_I64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_I32: u8 = 5;
+
+fn encode_key(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    encode_varint(buf, ((field as u64) << 3) | wire_type as u64);
+}
+
+fn encode_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn decode_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Iterator over the top-level length-delimited `(field number, bytes)` pairs
+/// of a message; scalar fields are skipped
+struct ProtoFields<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoFields<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        ProtoFields { buf, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for ProtoFields<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.buf.len() {
+            let key = decode_varint(self.buf, &mut self.pos)?;
+            match (key & 7) as u8 {
+                WIRE_VARINT => {
+                    decode_varint(self.buf, &mut self.pos)?;
+                }
+                WIRE_I64 => self.pos += 8,
+                WIRE_I32 => self.pos += 4,
+                WIRE_LEN => {
+                    let len = decode_varint(self.buf, &mut self.pos)? as usize;
+                    let bytes = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+                    self.pos += len;
+                    return Some(((key >> 3) as u32, bytes));
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+}
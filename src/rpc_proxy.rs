@@ -0,0 +1,622 @@
+//! trace_processor's RPC served on the UI port, so the browser only ever
+//! talks to one origin.
+//!
+//! `/rpc/<path>` is relayed to `<path>` on trace_processor's own port. The UI
+//! is opened with `rpc_port` set to the UI port, where it finds the two
+//! endpoints it uses, `/status` and `/websocket`, relayed the same way. Only
+//! this server's own origins may use them, which replaces trace_processor's
+//! `--http-additional-cors-origins`, and a single tunnel to the UI port is
+//! enough for remote access.
+//!
+//! `/rpc/<session id>/<path>` goes to the trace_processor of an extra
+//! session instead; see `sessions`.
+//!
+//! In multi-user mode a session is only relayed for users who can see its
+//! trace, and the shared trace_processor only for admins and the owner of
+//! the trace it was started with, as it may have any user's trace loaded.
+//!
+//! With `--expose-lan` pages loaded from the machine's network addresses are
+//! allowed as well, whatever `Host` a proxy in between sends.
+//!
+//! Requests must also be addressed to this machine: a `Host` other than a
+//! loopback name, `--host`, the machine's name or, with `--expose-lan`, its
+//! network addresses is refused, here and on the UI port. Otherwise a site
+//! whose DNS name was re-pointed at 127.0.0.1 would pass as same-origin.
+
+use crate::catalog::Trace;
+use crate::server::{AppState, LimitedReader};
+use crate::sessions::{InUse, Session};
+use crate::shutdown;
+use crate::stats;
+use crate::users::Viewer;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, ReadWrite, Request, Response, StatusCode};
+
+/// Largest response head trace_processor may send for a WebSocket handshake
+const HEAD_LIMIT: usize = 16 * 1024;
+
+/// How long trace_processor may be quiet before the browser is pinged: right
+/// after traffic, and at most when both sides are idle
+const POLL_MIN: Duration = Duration::from_millis(1);
+const POLL_MAX: Duration = Duration::from_millis(100);
+
+/// A WebSocket ping without payload, as a server sends it
+const PING: [u8; 2] = [0x89, 0x00];
+const OPCODE_PONG: u8 = 0xa;
+
+/// Network addresses of this machine pages may be loaded from, with `--expose-lan`
+static LAN_HOSTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Names besides the loopback ones and `LAN_HOSTS` requests may be addressed to
+static HOST_NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Host names that always mean this machine
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Request headers passed on to trace_processor
+const FORWARDED: &[&str] = &["Accept", "Content-Type"];
+
+/// Handshake headers passed on for a WebSocket upgrade
+const WEBSOCKET_FORWARDED: &[&str] =
+    &["Sec-WebSocket-Key", "Sec-WebSocket-Version", "Sec-WebSocket-Protocol", "Sec-WebSocket-Extensions"];
+
+/// Path and query on trace_processor for a request to the UI port, if it is
+/// one to relay
+pub fn route(url: &str) -> Option<String> {
+    let path = url.split('?').next().unwrap_or(url);
+    let target = match path.strip_prefix("/rpc/") {
+        Some(_) => &url["/rpc".len()..],
+        None if path == "/status" || path == "/websocket" => url,
+        None => return None,
+    };
+    Some(target.to_string())
+}
+
+/// Allow pages loaded from `addresses` on the UI port
+pub fn allow_lan_origins(addresses: &[IpAddr]) {
+    let hosts = addresses.iter().map(|ip| if ip.is_ipv6() { format!("[{}]", ip) } else { ip.to_string() });
+    *LAN_HOSTS.lock().unwrap() = hosts.collect();
+}
+
+/// Accept requests addressed to `names` as well, such as `--host`
+pub fn allow_host_names(names: &[String]) {
+    *HOST_NAMES.lock().unwrap() = names.to_vec();
+}
+
+/// The request's `Host`, without the port, if it is not one of this
+/// machine's; requests without one, which browsers never send, are allowed
+pub fn foreign_host(request: &Request) -> Option<String> {
+    let host = header(request, "Host")?;
+    let name = match host.strip_prefix('[') {
+        Some(rest) => format!("[{}]", rest.split(']').next().unwrap_or_default()),
+        None => host.rsplit_once(':').map_or(host.as_str(), |(name, _)| name).to_string(),
+    };
+    let known = |known: &String| known.eq_ignore_ascii_case(&name);
+    let allowed = LOOPBACK_HOSTS.iter().any(|loopback| loopback.eq_ignore_ascii_case(&name))
+        || HOST_NAMES.lock().unwrap().iter().any(known)
+        || LAN_HOSTS.lock().unwrap().iter().any(known);
+    (!allowed).then_some(name)
+}
+
+/// Refuse a request `foreign_host` found addressed to `host`
+pub fn respond_foreign_host(request: Request, host: &str) {
+    let response = Response::from_string(t!("host-rejected", host = host)).with_status_code(403);
+    stats::respond(request, response);
+}
+
+/// Relay `request` to `target` on trace_processor, or on a session's if
+/// `target` starts with its id
+pub fn respond(request: Request, state: &AppState, viewer: &Viewer, target: &str, limit: u64) {
+    match state.sessions.route(target) {
+        Some((session, _)) if !viewer.can_see(&session.trace) => {
+            let response = Response::from_string(t!("session-unknown", id = session.id.as_str()));
+            stats::respond(request, response.with_status_code(404));
+        }
+        Some((session, target)) => relay_session(request, &session, &target, state.http_port, limit),
+        None => {
+            let shared_trace = {
+                let catalog = state.catalog.lock().unwrap();
+                state.trace.as_deref().and_then(|path| catalog.find(&path.to_string_lossy()).cloned())
+            };
+            if !may_use_shared(viewer, state.users.is_some(), shared_trace.as_ref()) {
+                let response = Response::from_string(t!("rpc-proxy-shared-forbidden"));
+                stats::respond(request, response.with_status_code(403));
+                return;
+            }
+            relay(request, state.rpc_port, target, state.http_port, limit, None)
+        }
+    }
+}
+
+/// Whether `viewer` may use the shared trace_processor, started with `trace`
+fn may_use_shared(viewer: &Viewer, multi_user: bool, trace: Option<&Trace>) -> bool {
+    !multi_user || matches!(viewer, Viewer::User { admin: true, .. }) || trace.is_some_and(|t| viewer.can_modify(t))
+}
+
+/// Relay `request` to `target` on a session's trace_processor, marking the
+/// session as used meanwhile
+pub fn relay_session(request: Request, session: &Arc<Session>, target: &str, ui_port: u16, limit: u64) {
+    relay(request, session.tp_port, target, ui_port, limit, Some(session.use_guard()));
+}
+
+fn relay(request: Request, port: u16, target: &str, ui_port: u16, limit: u64, in_use: Option<InUse>) {
+    if let Some(host) = foreign_host(&request) {
+        return respond_foreign_host(request, &host);
+    }
+    let origin = match allowed_origin(&request, ui_port) {
+        Ok(origin) => origin,
+        Err(origin) => {
+            let response = Response::from_string(t!("rpc-proxy-origin-rejected", origin = origin));
+            stats::respond(request, response.with_status_code(403));
+            return;
+        }
+    };
+    if *request.method() == Method::Options {
+        respond_preflight(request, origin);
+    } else if header(&request, "Upgrade").is_some_and(|u| u.eq_ignore_ascii_case("websocket")) {
+        relay_websocket(request, port, target, in_use);
+    } else {
+        relay_http(request, port, target, origin, limit);
+    }
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str().to_string())
+}
+
+/// The request's `Origin` if this server's pages may use the RPC, whichever
+/// of `localhost`, `127.0.0.1` and the LAN addresses they were loaded from;
+/// Err with the origin otherwise. Clients such as curl send none and are
+/// allowed.
+///
+/// Relay ports, such as a session's, are used by pages from the UI port,
+/// `ui_port`.
+pub fn allowed_origin(request: &Request, ui_port: u16) -> Result<Option<String>, String> {
+    let Some(origin) = header(request, "Origin") else {
+        return Ok(None);
+    };
+    let host = header(request, "Host").unwrap_or_default();
+    let scheme = if request.secure() { "https" } else { "http" };
+    let mut allowed = match host.rsplit_once(':') {
+        Some((_, port)) => vec![
+            format!("{}://{}", scheme, host),
+            format!("{}://localhost:{}", scheme, port),
+            format!("{}://127.0.0.1:{}", scheme, port),
+        ],
+        None => vec![format!("{}://{}", scheme, host)],
+    };
+    // Pages from the UI port, which is served over HTTPS with TLS
+    for scheme in ["http", "https"] {
+        allowed.push(format!("{}://localhost:{}", scheme, ui_port));
+        allowed.push(format!("{}://127.0.0.1:{}", scheme, ui_port));
+        for host in LAN_HOSTS.lock().unwrap().iter() {
+            allowed.push(format!("{}://{}:{}", scheme, host, ui_port));
+        }
+    }
+    if allowed.iter().any(|a| a.eq_ignore_ascii_case(&origin)) {
+        Ok(Some(origin))
+    } else {
+        Err(origin)
+    }
+}
+
+fn cors_headers(origin: Option<String>) -> Vec<Header> {
+    let Some(origin) = origin else {
+        return Vec::new();
+    };
+    vec![
+        Header::from_bytes("Access-Control-Allow-Origin", origin).unwrap(),
+        Header::from_bytes("Vary", "Origin").unwrap(),
+    ]
+}
+
+fn respond_preflight(request: Request, origin: Option<String>) {
+    let requested = header(&request, "Access-Control-Request-Headers").unwrap_or_default();
+    let mut response = Response::empty(204)
+        .with_header(Header::from_bytes("Access-Control-Allow-Methods", "GET, POST, OPTIONS").unwrap())
+        .with_header(Header::from_bytes("Access-Control-Max-Age", "86400").unwrap());
+    if !requested.is_empty() {
+        response.add_header(Header::from_bytes("Access-Control-Allow-Headers", requested).unwrap());
+    }
+    for header in cors_headers(origin) {
+        response.add_header(header);
+    }
+    stats::respond(request, response);
+}
+
+fn relay_http(mut request: Request, port: u16, target: &str, origin: Option<String>, limit: u64) {
+    let url = format!("http://127.0.0.1:{}{}", port, target);
+    let mut upstream = ureq::request(request.method().as_str(), &url);
+    for name in FORWARDED {
+        if let Some(value) = header(&request, name) {
+            upstream = upstream.set(name, &value);
+        }
+    }
+    // trace_processor expects a Content-Length, so the body is read up front
+    let mut body = Vec::new();
+    if let Err(e) = LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body) {
+        if e.kind() == io::ErrorKind::FileTooLarge {
+            crate::server::respond_too_large(request, limit);
+        }
+        return;
+    }
+    let result = match request.method() {
+        Method::Get | Method::Head => upstream.call(),
+        _ => upstream.send_bytes(&body),
+    };
+    let response = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => {
+            let message = t!("rpc-proxy-unreachable", port = port, error = e);
+            stats::respond(request, Response::from_string(message).with_status_code(502));
+            return;
+        }
+    };
+
+    let mut headers = cors_headers(origin);
+    if let Some(content_type) = response.header("Content-Type") {
+        headers.push(Header::from_bytes("Content-Type", content_type).unwrap());
+    }
+    let length = response.header("Content-Length").and_then(|l| l.parse().ok());
+    let status = StatusCode(response.status());
+    stats::respond(request, Response::new(status, headers, response.into_reader(), length, None));
+}
+
+/// Status code and headers of an HTTP response
+type ResponseHead = (u16, Vec<(String, String)>);
+
+/// Replay the handshake to trace_processor, hand its answer to the browser
+/// and then relay frames both ways until either side closes; see `pump`
+fn relay_websocket(request: Request, port: u16, target: &str, in_use: Option<InUse>) {
+    let (upstream, (status, headers)) = match handshake(&request, port, target) {
+        Ok(handshake) => handshake,
+        Err(e) => {
+            let message = t!("rpc-proxy-unreachable", port = port, error = e);
+            stats::respond(request, Response::from_string(message).with_status_code(502));
+            return;
+        }
+    };
+    if status != 101 {
+        let message = t!("rpc-proxy-upgrade-refused", status = status);
+        stats::respond(request, Response::from_string(message).with_status_code(502));
+        return;
+    }
+
+    let mut response = Response::empty(101);
+    for (name, value) in headers {
+        if !name.eq_ignore_ascii_case("Connection") && !name.eq_ignore_ascii_case("Upgrade") {
+            if let Ok(header) = Header::from_bytes(name, value) {
+                response.add_header(header);
+            }
+        }
+    }
+    stats::responded(101);
+    let client = request.upgrade("websocket", response);
+    let connected = shutdown::Connected::begin();
+    // The connection lives on its own thread rather than holding a worker
+    thread::spawn(move || {
+        pump(client, upstream);
+        drop(in_use);
+        drop(connected);
+    });
+}
+
+fn handshake(request: &Request, port: u16, target: &str) -> io::Result<(TcpStream, ResponseHead)> {
+    let mut upstream = TcpStream::connect(("127.0.0.1", port))?;
+    // No Origin: the browser's origin was checked here already
+    let mut head = format!(
+        "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n",
+        target, port
+    );
+    for name in WEBSOCKET_FORWARDED {
+        if let Some(value) = header(request, name) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str("\r\n");
+    upstream.write_all(head.as_bytes())?;
+    let head = read_response_head(&mut upstream)?;
+    Ok((upstream, head))
+}
+
+/// Response head read byte by byte, so none of the WebSocket frames after it
+/// are consumed
+fn read_response_head(stream: &mut TcpStream) -> io::Result<ResponseHead> {
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= HEAD_LIMIT || stream.read(&mut byte)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "incomplete response head"));
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status line"))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok((status, headers))
+}
+
+/// Copy frames both ways until either side closes
+///
+/// tiny_http's upgraded connection is a single stream that cannot be split,
+/// so one thread does both directions: it reads from trace_processor with a
+/// timeout, and once trace_processor has been quiet for a while between
+/// frames it pings the browser, so that reading from the browser returns as
+/// soon as the pong arrives. The pongs are not passed on.
+fn pump(mut client: Box<dyn ReadWrite + Send>, mut upstream: TcpStream) {
+    let _ = relay_frames(&mut *client, &mut upstream);
+    let _ = upstream.shutdown(Shutdown::Both);
+}
+
+fn relay_frames(client: &mut dyn ReadWrite, upstream: &mut TcpStream) -> io::Result<()> {
+    let mut buffer = vec![0; 64 * 1024];
+    let (mut to_browser, mut from_browser) = (Frames::new(false), Frames::new(true));
+    let mut out = Vec::new();
+    let mut poll = POLL_MIN;
+    loop {
+        upstream.set_read_timeout(Some(poll))?;
+        match upstream.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                to_browser.feed(&buffer[..n], &mut out);
+                client.write_all(&out)?;
+                client.flush()?;
+                out.clear();
+                poll = POLL_MIN;
+                continue;
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+        // A ping in the middle of a frame would corrupt it
+        if !to_browser.between_frames() {
+            continue;
+        }
+        client.write_all(&PING)?;
+        client.flush()?;
+        let n = client.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        from_browser.feed(&buffer[..n], &mut out);
+        if out.is_empty() {
+            poll = (poll * 2).min(POLL_MAX);
+        } else {
+            upstream.write_all(&out)?;
+            out.clear();
+            poll = POLL_MIN;
+        }
+    }
+}
+
+/// Where the frames of a WebSocket byte stream start and end as it passes
+/// through
+struct Frames {
+    /// Leave out pong frames
+    drop_pongs: bool,
+    /// The head of the current frame while it is incomplete
+    head: Vec<u8>,
+    /// Payload bytes of the current frame still to come
+    left: u64,
+    /// Whether the current frame is passed on
+    keep: bool,
+}
+
+impl Frames {
+    fn new(drop_pongs: bool) -> Frames {
+        Frames { drop_pongs, head: Vec::new(), left: 0, keep: true }
+    }
+
+    fn between_frames(&self) -> bool {
+        self.head.is_empty() && self.left == 0
+    }
+
+    /// Append the bytes of `data` that are passed on to `out`
+    fn feed(&mut self, mut data: &[u8], out: &mut Vec<u8>) {
+        while !data.is_empty() {
+            if self.left > 0 {
+                let n = usize::try_from(self.left).map_or(data.len(), |left| left.min(data.len()));
+                if self.keep {
+                    out.extend_from_slice(&data[..n]);
+                }
+                self.left -= n as u64;
+                data = &data[n..];
+                continue;
+            }
+            self.head.push(data[0]);
+            data = &data[1..];
+            if let Some(length) = payload_length(&self.head) {
+                self.keep = !(self.drop_pongs && self.head[0] & 0x0f == OPCODE_PONG);
+                if self.keep {
+                    out.extend_from_slice(&self.head);
+                }
+                self.head.clear();
+                self.left = length;
+            }
+        }
+    }
+}
+
+/// Payload length of the frame starting with `head`, once all of its head is there
+fn payload_length(head: &[u8]) -> Option<u64> {
+    let second = *head.get(1)?;
+    let extended = match second & 0x7f {
+        126 => 2,
+        127 => 8,
+        _ => 0,
+    };
+    let mask = if second & 0x80 != 0 { 4 } else { 0 };
+    if head.len() < 2 + extended + mask {
+        return None;
+    }
+    match extended {
+        0 => Some(u64::from(second & 0x7f)),
+        _ => Some(head[2..2 + extended].iter().fold(0, |n, &byte| n << 8 | u64::from(byte))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+
+    /// A frame with `payload`, masked as a browser sends it or not as a server does
+    fn frame(opcode: u8, payload: &[u8], masked: bool) -> Vec<u8> {
+        let mask_bit = if masked { 0x80 } else { 0 };
+        let mut out = vec![0x80 | opcode];
+        match payload.len() {
+            n if n < 126 => out.push(mask_bit | n as u8),
+            n if n <= 0xffff => {
+                out.push(mask_bit | 126);
+                out.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            n => {
+                out.push(mask_bit | 127);
+                out.extend_from_slice(&(n as u64).to_be_bytes());
+            }
+        }
+        let key = [0x12, 0x34, 0x56, 0x78];
+        if masked {
+            out.extend_from_slice(&key);
+            out.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ key[i % 4]));
+        } else {
+            out.extend_from_slice(payload);
+        }
+        out
+    }
+
+    fn trace(owner: Option<&str>) -> Trace {
+        Trace {
+            id: "shared00".to_string(),
+            name: "shared.pftrace".to_string(),
+            path: PathBuf::from("/traces/shared.pftrace"),
+            size: 1,
+            added: 0,
+            pinned: false,
+            tags: Vec::new(),
+            owner: owner.map(String::from),
+            opened: None,
+        }
+    }
+
+    fn user(name: &str, admin: bool) -> Viewer {
+        Viewer::User { name: name.to_string(), admin, upload_quota: None }
+    }
+
+    #[test]
+    fn shared_trace_processor_is_for_admins_and_the_trace_owner() {
+        let (alice, bob, admin) = (user("alice", false), user("bob", false), user("root", true));
+        let alices = trace(Some("alice"));
+        let unowned = trace(None);
+        // Single-user mode
+        assert!(may_use_shared(&Viewer::Local, false, None));
+        assert!(may_use_shared(&bob, false, Some(&alices)));
+        // Multi-user mode
+        assert!(may_use_shared(&alice, true, Some(&alices)));
+        assert!(!may_use_shared(&bob, true, Some(&alices)));
+        assert!(!may_use_shared(&bob, true, Some(&unowned)));
+        assert!(!may_use_shared(&bob, true, None));
+        assert!(may_use_shared(&admin, true, Some(&alices)));
+        assert!(may_use_shared(&admin, true, None));
+    }
+
+    #[test]
+    fn frames_pass_through_however_they_are_split() {
+        let long = vec![7; 70_000];
+        let kept = [
+            frame(0x1, b"text", true),
+            frame(0x2, &[1; 200], true),
+            frame(0x2, &long, false),
+            frame(0x8, b"", true),
+        ];
+        let mut stream = Vec::new();
+        for (i, kept) in kept.iter().enumerate() {
+            stream.extend_from_slice(kept);
+            stream.extend(frame(0xa, &[i as u8; 4], true));
+        }
+        for step in [1, 2, 3, 7, 64, 1024, stream.len()] {
+            let (mut all, mut no_pongs) = (Frames::new(false), Frames::new(true));
+            let (mut all_out, mut no_pongs_out) = (Vec::new(), Vec::new());
+            for chunk in stream.chunks(step) {
+                all.feed(chunk, &mut all_out);
+                no_pongs.feed(chunk, &mut no_pongs_out);
+            }
+            assert_eq!(all_out, stream, "step {}", step);
+            assert_eq!(no_pongs_out, kept.concat(), "step {}", step);
+            assert!(all.between_frames() && no_pongs.between_frames());
+        }
+    }
+
+    #[test]
+    fn tracks_frame_boundaries() {
+        let text = frame(0x1, b"text", false);
+        let mut frames = Frames::new(false);
+        let mut out = Vec::new();
+        assert!(frames.between_frames());
+        frames.feed(&text[..1], &mut out);
+        assert!(!frames.between_frames());
+        frames.feed(&text[1..4], &mut out);
+        assert!(!frames.between_frames());
+        frames.feed(&text[4..], &mut out);
+        assert!(frames.between_frames());
+    }
+
+    #[test]
+    fn pump_relays_frames_while_trace_processor_is_quiet() {
+        let request = frame(0x2, b"query", true);
+        let tp_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tp_port = tp_listener.local_addr().unwrap().port();
+        let expected = request.clone();
+        // Answers slower than the longest poll, so the browser is pinged meanwhile
+        let trace_processor = thread::spawn(move || {
+            let (mut stream, _) = tp_listener.accept().unwrap();
+            let mut received = vec![0; expected.len()];
+            stream.read_exact(&mut received).unwrap();
+            assert_eq!(received, expected);
+            thread::sleep(POLL_MAX * 3);
+            stream.write_all(&frame(0x2, b"result", false)).unwrap();
+            // The pongs are not passed on
+            stream.set_read_timeout(Some(POLL_MAX * 3)).unwrap();
+            assert!(stream.read(&mut [0; 1]).is_err());
+        });
+        let upstream = TcpStream::connect(("127.0.0.1", tp_port)).unwrap();
+        let browser_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut browser = TcpStream::connect(browser_listener.local_addr().unwrap()).unwrap();
+        let (client, _) = browser_listener.accept().unwrap();
+        let relay = thread::spawn(move || pump(Box::new(client), upstream));
+
+        browser.write_all(&request).unwrap();
+        let mut pings = 0;
+        loop {
+            let mut head = [0; 2];
+            browser.read_exact(&mut head).unwrap();
+            let mut payload = vec![0; usize::from(head[1] & 0x7f)];
+            browser.read_exact(&mut payload).unwrap();
+            if head[0] & 0x0f == 0x9 {
+                pings += 1;
+                browser.write_all(&frame(0xa, &payload, true)).unwrap();
+                continue;
+            }
+            assert_eq!(head[0] & 0x0f, 0x2);
+            assert_eq!(payload, b"result");
+            break;
+        }
+        assert!(pings > 0);
+        trace_processor.join().unwrap();
+        drop(browser);
+        relay.join().unwrap();
+    }
+}
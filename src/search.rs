@@ -0,0 +1,130 @@
+//! Full-text search over slice names across catalog traces.
+//!
+//! At ingest each trace's distinct slice names are written to
+//! `index/<id>.json` in the data directory with their count and first
+//! occurrence, so searching never needs a trace_processor.
+
+use crate::catalog::Trace;
+use crate::paths::data_dir;
+use crate::rpc::RpcClient;
+use crate::server::{query_param, AppState};
+use crate::stats;
+use crate::users::Viewer;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::PathBuf;
+use tiny_http::{Header, Request, Response};
+
+const INDEX_QUERY: &str = "select name, count(*) as count, min(ts) as ts, dur \
+    from slice where name is not null group by name";
+
+/// Default and maximum number of results per search
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Serialize, Deserialize)]
+struct SliceName {
+    name: String,
+    count: u64,
+    /// First occurrence
+    ts: i64,
+    dur: i64,
+}
+
+pub fn index_path(id: &str) -> PathBuf {
+    data_dir().join("index").join(format!("{}.json", id))
+}
+
+pub fn is_indexed(id: &str) -> bool {
+    index_path(id).is_file()
+}
+
+pub fn remove(id: &str) {
+    let _ = fs::remove_file(index_path(id));
+}
+
+/// Query the slice names of the trace loaded in `client` and store them for `id`
+pub fn build(client: &RpcClient, id: &str) -> Result<usize, String> {
+    let rows = client.query_rows(INDEX_QUERY)?;
+    let column = |name: &str| rows.columns.iter().position(|c| c == name);
+    let (Some(name), Some(count), Some(ts), Some(dur)) = (column("name"), column("count"), column("ts"), column("dur"))
+    else {
+        return Err("unexpected columns in slice query".to_string());
+    };
+    let names: Vec<SliceName> = rows
+        .rows
+        .iter()
+        .filter_map(|row| {
+            Some(SliceName {
+                name: row.get(name)?.as_str()?.to_string(),
+                count: row.get(count)?.as_u64()?,
+                ts: row.get(ts)?.as_i64()?,
+                dur: row.get(dur)?.as_i64().unwrap_or(0),
+            })
+        })
+        .collect();
+    let path = index_path(id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, serde_json::to_string(&names).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    Ok(names.len())
+}
+
+/// Slice names in `traces` containing every word of `query`, case-insensitively;
+/// exact matches first, then the most frequent
+pub fn search(traces: &[Trace], query: &str, limit: usize) -> Vec<Value> {
+    let query = query.trim().to_lowercase();
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    for trace in traces {
+        let Ok(text) = fs::read_to_string(index_path(&trace.id)) else { continue };
+        let Ok(names) = serde_json::from_str::<Vec<SliceName>>(&text) else { continue };
+        for slice in names {
+            let name = slice.name.to_lowercase();
+            if terms.iter().all(|term| name.contains(term)) {
+                matches.push((name == query, trace, slice));
+            }
+        }
+    }
+    matches.sort_by_key(|(exact, _, slice)| (Reverse(*exact), Reverse(slice.count)));
+    matches
+        .into_iter()
+        .take(limit.min(MAX_LIMIT))
+        .map(|(_, trace, slice)| {
+            // Incomplete slices have dur -1; show at least 1ns
+            let end = slice.ts + slice.dur.max(1);
+            json!({
+                "trace": trace.id,
+                "trace_name": trace.name,
+                "name": slice.name,
+                "count": slice.count,
+                "ts": slice.ts,
+                "dur": slice.dur,
+                "link": format!("/t/{}#visStart={}&visEnd={}", trace.id, slice.ts, end),
+            })
+        })
+        .collect()
+}
+
+/// `GET /api/search?q=<words>[&trace=<id>][&limit=<n>]`
+pub fn respond(request: Request, state: &AppState, viewer: &Viewer) {
+    let url = request.url();
+    let query = query_param(url, "q").unwrap_or_default();
+    let limit = query_param(url, "limit").and_then(|l| l.parse().ok()).unwrap_or(DEFAULT_LIMIT);
+    let traces: Vec<Trace> = {
+        let catalog = state.catalog.lock().unwrap();
+        match query_param(url, "trace") {
+            Some(id) => catalog.get(&id).filter(|t| viewer.can_see(t)).cloned().into_iter().collect(),
+            None => catalog.traces().iter().filter(|t| viewer.can_see(t)).cloned().collect(),
+        }
+    };
+    let body = json!({ "query": query, "results": search(&traces, &query, limit) }).to_string();
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(body).with_header(content_type));
+}
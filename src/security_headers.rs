@@ -0,0 +1,70 @@
+//! Headers every response gets, added by `stats::respond` as it is sent.
+//!
+//! The UI needs cross-origin isolation for SharedArrayBuffer and WebAssembly
+//! threads, so responses carry `Cross-Origin-Opener-Policy: same-origin` and
+//! `Cross-Origin-Embedder-Policy: require-corp`, along with
+//! `X-Content-Type-Options: nosniff` and a Content Security Policy that lets
+//! the UI reach trace_processor and traces on loopback and over HTTPS but
+//! keeps other pages from framing it. Rules from `--header` and the config
+//! file's `[headers]` come last, so they can replace any of these or, with
+//! an empty value, leave them out; see `path_headers`.
+//!
+//! Other origins may read a response only if they are this server's own, as
+//! for the RPC relay, or were allowed with `--cors-origin`. They get their
+//! origin back in `Access-Control-Allow-Origin` rather than `*`.
+
+use crate::path_headers;
+use crate::rpc_proxy;
+use std::sync::OnceLock;
+use tiny_http::{Header, Request};
+
+/// The UI port, and the origins from `--cors-origin`
+static CONFIG: OnceLock<(u16, Vec<String>)> = OnceLock::new();
+
+/// Everything the UI loads comes from this server, except traces opened by
+/// URL and the RPC of trace_processors started by hand
+const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline' 'wasm-unsafe-eval' blob:; style-src 'self' 'unsafe-inline'; \
+    img-src 'self' data: blob:; font-src 'self' data:; worker-src 'self' blob:; \
+    connect-src 'self' https: http://127.0.0.1:* http://localhost:* ws://127.0.0.1:* ws://localhost:*; \
+    object-src 'none'; base-uri 'self'; frame-ancestors 'self'";
+
+const DEFAULTS: [(&str, &str); 4] = [
+    ("Cross-Origin-Opener-Policy", "same-origin"),
+    ("Cross-Origin-Embedder-Policy", "require-corp"),
+    ("X-Content-Type-Options", "nosniff"),
+    ("Content-Security-Policy", CONTENT_SECURITY_POLICY),
+];
+
+/// Allow `origins` besides the server's own on `ui_port`; called once at startup
+pub fn configure(ui_port: u16, origins: &[String]) {
+    let _ = CONFIG.set((ui_port, origins.to_vec()));
+}
+
+/// Add the headers for the response to `request` to `headers`; ones the
+/// handler set itself, such as the RPC relay's CORS headers, are kept unless
+/// a `--header` rule replaces them
+pub fn apply(request: &Request, headers: &mut Vec<Header>) {
+    for (name, value) in DEFAULTS {
+        if !headers.iter().any(|h| h.field.equiv(name)) {
+            headers.push(Header::from_bytes(name, value).unwrap());
+        }
+    }
+    if !headers.iter().any(|h| h.field.equiv("Access-Control-Allow-Origin")) {
+        if let Some(origin) = allowed_origin(request) {
+            headers.push(Header::from_bytes("Access-Control-Allow-Origin", origin).unwrap());
+            headers.push(Header::from_bytes("Vary", "Origin").unwrap());
+        }
+    }
+    path_headers::apply(request.url(), headers);
+}
+
+/// The request's `Origin`, if it is one that may read responses from another
+/// origin: the server's own pages or one from `--cors-origin`
+pub fn allowed_origin(request: &Request) -> Option<String> {
+    let (ui_port, origins) = CONFIG.get()?;
+    match rpc_proxy::allowed_origin(request, *ui_port) {
+        Ok(origin) => origin,
+        Err(origin) => origins.iter().any(|o| o.eq_ignore_ascii_case(&origin)).then_some(origin),
+    }
+}
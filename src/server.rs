@@ -0,0 +1,1104 @@
+use crate::access::{Access, Check};
+use crate::android;
+use crate::backend::Backend;
+use crate::assets;
+use crate::dist::{get_mime_type, is_version_string};
+use crate::http_errors;
+use crate::catalog::Catalog;
+use crate::compression::{self, Compression, Encoding};
+use crate::dashboards;
+use crate::dates::{format_http_date, parse_http_date};
+use crate::fetch;
+use crate::ingest::Ingest;
+use crate::landing;
+use crate::livereload::{self, LiveReload};
+use crate::logs;
+use crate::metadata;
+use crate::permalink;
+use crate::plugins::{self, Plugins};
+use crate::prometheus;
+use crate::query;
+use crate::roots::{hex_byte, Resolved, ServePolicy};
+use crate::reports;
+use crate::recent;
+use crate::record::{self, Recorder};
+use crate::rpc_proxy;
+use crate::search;
+use crate::sessions::{self, Sessions};
+use crate::monitor::Resources;
+use crate::restart;
+use crate::shutdown::{self, InFlight};
+use crate::sql_modules::ModuleStatus;
+use crate::stats;
+use crate::tp_logs;
+use crate::trace_dirs;
+use crate::traces_api;
+use crate::users::{self, Auth, Users, Viewer};
+use log::warn;
+use serde_json::json;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How long a request for the UI page waits for trace_processor to come up,
+/// so the UI finds its RPC backend on first load
+const INDEX_WAIT: Duration = Duration::from_secs(60);
+
+/// State shared by all request handlers
+pub struct AppState {
+    /// Directories static files may be served from
+    pub roots: ServePolicy,
+    pub backend: Arc<Backend>,
+    pub resources: Resources,
+    pub http_port: u16,
+    /// trace_processor's own port, which `rpc_proxy` relays to
+    pub rpc_port: u16,
+    /// Trace loaded at startup, if any
+    pub trace: Option<PathBuf>,
+    pub catalog: Mutex<Catalog>,
+    pub ingest: Ingest,
+    /// Load results of `--sql-module-dir` modules, filled in once ready
+    pub sql_modules: Mutex<Vec<ModuleStatus>>,
+    pub body_limits: BodyLimits,
+    /// `--workspace-quota-mb` in bytes, enforced after each upload
+    pub workspace_quota: Option<u64>,
+    pub plugins: Plugins,
+    /// Multi-user mode; None when the launcher serves a single user
+    pub users: Option<Users>,
+    /// Sign-in for other machines with `--auth`; None when anyone may connect
+    pub access: Option<Access>,
+    pub compression: Compression,
+    /// Whether traces pushed to `/launcher/open-trace` are opened in a
+    /// browser, and which one; `--no-browser` and `--browser`
+    pub open_browser: bool,
+    pub browser: Option<String>,
+    /// trace_processors started for extra traces, under `/api/sessions`
+    pub sessions: Arc<Sessions>,
+    /// Names of the `--trace-dir` directories, served under `/trace/`
+    pub trace_dirs: Vec<String>,
+    /// Recording started from `/launcher/record`
+    pub recorder: Recorder,
+    /// Token `POST /launcher/shutdown` wants
+    pub shutdown_token: String,
+    /// Directory the UI is served from, for the status endpoint
+    pub dist_dir: PathBuf,
+    /// Set with `--watch`
+    pub live_reload: Option<Arc<LiveReload>>,
+    /// Whether unknown page paths get the UI's index.html; `--no-spa-fallback`
+    pub spa_fallback: bool,
+    /// Version directory of an official UI build, if the UI is laid out so
+    pub ui_version_dir: Option<String>,
+    /// "https" with `--tls-cert` or `--self-signed`, else "http"
+    pub scheme: &'static str,
+    /// Host of the URLs the launcher opens: `localhost` unless the UI server
+    /// only listens on another address
+    pub url_host: String,
+    /// `rpc_port` the UI is opened with: the UI port, or with HTTPS the plain
+    /// loopback port, since the UI only speaks plain HTTP to its RPC
+    pub ui_rpc_port: u16,
+}
+
+/// Largest request bodies accepted, in bytes
+#[derive(Clone, Copy)]
+pub struct BodyLimits {
+    /// Trace uploads
+    pub upload: u64,
+    /// Every other endpoint; these take small JSON bodies or none at all
+    pub api: u64,
+}
+
+impl BodyLimits {
+    fn for_path(&self, url_path: &str) -> u64 {
+        let uploads = ["launcher/upload", "launcher/open-trace", "api/traces"];
+        if uploads.contains(&url_path) || url_path.starts_with("rpc/") {
+            self.upload
+        } else {
+            self.api
+        }
+    }
+}
+
+/// Reader that fails once more than `limit` bytes have been read, so a body
+/// without a truthful Content-Length is still cut off while streaming
+pub struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub fn new(inner: R, limit: u64) -> Self {
+        LimitedReader { inner, remaining: limit }
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Read one byte past the limit to tell "exactly at" from "over"
+        let max = buf.len().min(usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        if n as u64 > self.remaining {
+            return Err(io::Error::new(io::ErrorKind::FileTooLarge, "request body too large"));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+pub fn respond_too_large(request: Request, limit: u64) {
+    let response = Response::from_string(t!("body-too-large", limit = limit)).with_status_code(413);
+    stats::respond(request, response);
+}
+
+/// Worker count when `--workers` is not given: enough that a few slow
+/// downloads do not hold up the UI's assets
+pub fn default_workers() -> usize {
+    let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    (cores * 2).clamp(8, 64)
+}
+
+/// Serve requests until shutdown is requested
+///
+/// Requests are answered by a pool of `workers` threads, so a slow response
+/// such as a large .wasm file does not hold up the others. Requests beyond
+/// the pool wait in a queue; beyond `max_connections` queued and in-flight
+/// requests, they get 503 with a Retry-After hint instead. A handler that
+/// panics costs only its own request, which tiny_http answers with 500 as it
+/// is dropped.
+pub fn serve(server: Server, state: Arc<AppState>, workers: usize, max_connections: usize) {
+    let (sender, receiver) = mpsc::channel::<(Request, InFlight)>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers.max(1) {
+        let receiver = receiver.clone();
+        let state = state.clone();
+        thread::spawn(move || loop {
+            // Hold the lock only while waiting, not while responding
+            let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+            let Ok((request, _in_flight)) = job else { break };
+            let route = route_label(request.url());
+            let started = Instant::now();
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| handle_request(request, &state)));
+            stats::finish(route, started.elapsed());
+        });
+    }
+
+    while !shutdown::requested() {
+        let request = match server.recv_timeout(Duration::from_millis(200)) {
+            Ok(Some(request)) => request,
+            Ok(None) => continue,
+            Err(_) => break,
+        };
+        let in_flight = match InFlight::try_begin(max_connections) {
+            Some(guard) => guard,
+            None => {
+                respond_busy(request);
+                continue;
+            }
+        };
+        let _ = sender.send((request, in_flight));
+    }
+    // Stop accepting, then let queued and in-flight responses finish. Dropping
+    // the server closes the listening socket; the workers exit once the queue
+    // is empty.
+    drop(server);
+    drop(sender);
+    shutdown::wait_for_in_flight();
+}
+
+fn respond_busy(request: Request) {
+    stats::rejected();
+    let retry_after = Header::from_bytes("Retry-After", "2").unwrap();
+    let response = Response::from_string("Service Unavailable: too many connections")
+        .with_status_code(503)
+        .with_header(retry_after);
+    stats::respond(request, response);
+}
+
+/// `POST /launcher/shutdown` with the shutdown token as `?token=` or
+/// `Authorization: Bearer`
+fn respond_shutdown(request: Request, state: &AppState) {
+    let bearer = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+    let token = query_param(request.url(), "token").or(bearer);
+    if !token.is_some_and(|token| users::constant_time_eq(&token, &state.shutdown_token)) {
+        return traces_api::respond_error(request, 403, t!("shutdown-bad-token"));
+    }
+    say!("{}", t!("shutdown-requested"));
+    traces_api::respond_json(request, 202, json!({ "stopping": true }));
+    shutdown::request();
+}
+
+/// `GET /launcher/status`: the launcher, its trace_processor and the trace
+/// loaded at startup, for dashboards and scripts
+fn respond_status(request: Request, state: &AppState) {
+    let resources = *state.resources.lock().unwrap();
+    let tp_version = stats::tp_version();
+    let requests = stats::requests();
+    let trace = state.trace.as_ref().map(|path| {
+        json!({
+            "path": path,
+            "name": path.file_name().map(|name| name.to_string_lossy()),
+            "size": fs::metadata(path).ok().map(|m| m.len()),
+        })
+    });
+    let body = json!({
+        "launcher": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "pid": std::process::id(),
+            "uptime_seconds": stats::uptime().as_secs(),
+            "http_port": state.http_port,
+            "dist_dir": state.dist_dir,
+            "ui_version": crate::dist::served_ui_version(&state.dist_dir),
+        },
+        "requests": {
+            "total": requests.total,
+            "rpc": requests.rpc,
+            "rejected": requests.rejected,
+            "in_flight": shutdown::in_flight(),
+        },
+        "trace": trace,
+        "trace_processor": {
+            "pid": state.backend.pid(),
+            "version": tp_version.map(|v| &v.version),
+            "api_version": tp_version.and_then(|v| v.api_version),
+            "port": state.rpc_port,
+            "alive": state.backend.try_wait().is_none(),
+            "uptime_seconds": state.backend.uptime().as_secs(),
+            "state": state.backend.health(),
+            "restarts": state.backend.restarts(),
+            "memory_bytes": resources.map(|r| r.memory_bytes),
+            "cpu_percent": resources.map(|r| r.cpu_percent),
+            "last_crash": state.backend.last_crash().map(|c| json!({
+                "status": c.status,
+                "exit_code": c.exit_code,
+                "explanation": c.explanation,
+                "stderr_tail": c.stderr_tail,
+            })),
+        },
+        "sql_modules": *state.sql_modules.lock().unwrap(),
+    });
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(body.to_string()).with_header(content_type));
+}
+
+fn handle_request(request: Request, state: &AppState) {
+    let url = request.url().to_string();
+    let url_path = url.trim_start_matches('/');
+    let url_path = url_path.split('?').next().unwrap_or(url_path); // Remove query string
+
+    // RPC requests come from the UI on the other loopback name, so they get
+    // their own origin check
+    let rpc_target = rpc_proxy::route(&url);
+    stats::request(rpc_target.is_some());
+    if let Some(host) = rpc_proxy::foreign_host(&request) {
+        return rpc_proxy::respond_foreign_host(request, &host);
+    }
+    let mutating = !matches!(request.method(), Method::Get | Method::Head | Method::Options);
+    if mutating && rpc_target.is_none() && is_cross_site(&request) {
+        let response = Response::from_string(t!("csrf-rejected")).with_status_code(403);
+        stats::respond(request, response);
+        return;
+    }
+
+    let limit = state.body_limits.for_path(url_path);
+    if request.body_length().is_some_and(|length| length as u64 > limit) {
+        respond_too_large(request, limit);
+        return;
+    }
+    if let Some(allow) = allowed_methods(url_path).filter(|_| rpc_target.is_none()) {
+        let method = request.method().as_str();
+        if method == "OPTIONS" {
+            return http_errors::respond_options(request, allow);
+        }
+        if !allow.split(", ").any(|allowed| allowed == method) {
+            return http_errors::method_not_allowed(request, allow);
+        }
+    }
+
+    let auth = state.users.as_ref().map(|users| users.authenticate(&request));
+    let user = match &auth {
+        Some(Auth::Viewer(viewer)) => viewer.name(),
+        _ => None,
+    };
+    logs::access(&request, user);
+    match state.access.as_ref().map(|access| (access, access.check(&request))) {
+        None | Some((_, Check::Allowed)) => {}
+        Some((access, Check::Login)) => return access.respond_login(request),
+        Some((access, Check::Denied)) => return access.respond_denied(request),
+    }
+    let viewer = match auth {
+        None => Viewer::Local,
+        Some(Auth::Viewer(viewer)) => viewer,
+        Some(Auth::Login(token)) => return users::respond_login(request, &token),
+        Some(Auth::Denied) => return users::respond_unauthorized(request),
+    };
+    if let Some((id, action)) = trace_route(url_path) {
+        let trace = {
+            let mut catalog = state.catalog.lock().unwrap();
+            // Traces registered by another process, such as `open-url`, are only on disk so far
+            if catalog.get(id).is_none() {
+                *catalog = Catalog::open();
+            }
+            catalog.get(id).cloned()
+        };
+        if trace.as_ref().is_some_and(|trace| !viewer.can_see(trace)) {
+            return http_errors::respond(request, 404, &t!("catalog-unknown-id", id = id));
+        }
+        let modifies = mutating && action != Some("open");
+        if modifies && trace.as_ref().is_some_and(|trace| !viewer.can_modify(trace)) {
+            return http_errors::respond(request, 403, &t!("users-forbidden", id = id));
+        }
+        let opens = url_path.starts_with("t/") || action == Some("open");
+        if let (true, Some(trace)) = (opens, &trace) {
+            let mut catalog = state.catalog.lock().unwrap();
+            if let Err(e) = catalog.mark_opened(&trace.id) {
+                warn!("{}", t!("catalog-save-failed", path = catalog.path().display(), error = e));
+            }
+            drop(catalog);
+            if let Some(users) = &state.users {
+                users.record_open(&viewer, &trace.id);
+            }
+        }
+    }
+
+    if let Some(target) = rpc_target {
+        rpc_proxy::respond(request, state, &viewer, &target, limit);
+        return;
+    }
+    if url_path == "launcher/status" {
+        respond_status(request, state);
+        return;
+    }
+    if url_path == "launcher/tp-logs" {
+        tp_logs::respond(request, state);
+        return;
+    }
+    if url_path == "metrics" {
+        prometheus::respond(request, state);
+        return;
+    }
+    if let (Some(live_reload), "launcher/livereload") = (&state.live_reload, url_path) {
+        live_reload.respond(request);
+        return;
+    }
+    if url_path == "launcher/shutdown" {
+        respond_shutdown(request, state);
+        return;
+    }
+    if url_path == "launcher/restart-backend" {
+        restart::respond(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/events" {
+        restart::respond_events(request, state);
+        return;
+    }
+    if url_path == "launcher/record" {
+        record::respond(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/android" {
+        android::respond_page(request);
+        return;
+    }
+    if url_path == "launcher/android/pull" {
+        android::respond_pull(request, state, &viewer);
+        return;
+    }
+    if url_path == "trace" || url_path == "trace/" {
+        trace_dirs::respond_index(request, state);
+        return;
+    }
+    if url_path.starts_with("trace/") {
+        if let Resolved::File(dir) = state.roots.resolve(url_path) {
+            if dir.is_dir() {
+                return trace_dirs::respond_listing(request, url_path, &dir);
+            }
+        }
+    }
+    if url_path == "launcher/recent" {
+        recent::respond(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher" || url_path == "launcher/" {
+        landing::respond_page(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/upload" {
+        landing::handle_upload(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/open-trace" {
+        landing::handle_open_trace(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/open-url" {
+        fetch::respond_open_url(request, state, &viewer);
+        return;
+    }
+    if url_path == "launcher/query" {
+        query::respond(request, state, &viewer);
+        return;
+    }
+    if let Some(id) = url_path.strip_prefix("launcher/traces/") {
+        landing::serve_trace(request, state, id);
+        return;
+    }
+    if let Some(id) = url_path.strip_prefix("t/") {
+        permalink::respond_open(request, state, id);
+        return;
+    }
+    if let Some(rest) = url_path.strip_prefix("plugins/") {
+        plugins::respond(request, state, rest);
+        return;
+    }
+    if url_path == "dashboards" || url_path.starts_with("dashboards/") {
+        dashboards::respond(request, state, url_path.trim_start_matches("dashboards").trim_start_matches('/'));
+        return;
+    }
+    if url_path == "reports" || url_path.starts_with("reports/") {
+        reports::respond(request, url_path.trim_start_matches("reports"));
+        return;
+    }
+    if url_path == "api/search" {
+        search::respond(request, state, &viewer);
+        return;
+    }
+    if url_path == "api/traces" {
+        traces_api::respond_collection(request, state, &viewer);
+        return;
+    }
+    if url_path == "api/sessions" {
+        sessions::respond_collection(request, state, &viewer);
+        return;
+    }
+    if let Some(id) = url_path.strip_prefix("api/sessions/") {
+        sessions::respond_session(request, state, &viewer, id);
+        return;
+    }
+    if let Some(rest) = url_path.strip_prefix("api/traces/") {
+        match rest.split_once('/') {
+            None => traces_api::respond_trace(request, state, rest),
+            Some((id, "download")) => traces_api::respond_download(request, state, id),
+            Some((id, "open")) => traces_api::respond_open(request, state, id),
+            Some((id, "permalink")) => permalink::respond_api(request, state, id),
+            Some((id, "metadata")) => metadata::respond(request, state, id),
+            Some((id, "pin")) => respond_pin(request, state, id),
+            _ => http_errors::not_found(request),
+        }
+        return;
+    }
+
+    let url_path = if url_path.is_empty() { "index.html" } else { url_path };
+    // Directories have no index page of their own
+    let fallback = match state.roots.resolve(url_path) {
+        Resolved::NotFound => ui_fallback(&request, state, url_path),
+        Resolved::File(path) if path.is_dir() => ui_fallback(&request, state, url_path),
+        _ => None,
+    };
+    let url_path = fallback.as_deref().unwrap_or(url_path);
+    if url_path == "index.html" {
+        state.backend.wait_settled(INDEX_WAIT);
+        return serve_index(request, state);
+    }
+    serve_static(request, state, url_path);
+}
+
+/// `index.html` with the snippets that reload open tabs after a backend
+/// restart and, with `--watch`, a rebuild; never cached, so a reload gets
+/// the current page
+fn serve_index(request: Request, state: &AppState) {
+    let page = match state.roots.resolve("index.html") {
+        Resolved::File(path) => fs::read(path).ok(),
+        Resolved::Embedded(_, contents) => Some(contents.to_vec()),
+        _ => None,
+    };
+    let Some(page) = page else {
+        return http_errors::not_found(request);
+    };
+    let mut page = String::from_utf8_lossy(&page).into_owned();
+    let mut snippets = restart::snippet(state.ui_rpc_port);
+    if state.live_reload.is_some() {
+        snippets.push_str(livereload::SNIPPET);
+    }
+    match page.rfind("</body>") {
+        Some(at) => page.insert_str(at, &snippets),
+        None => page.push_str(&snippets),
+    }
+    let response = Response::from_string(page)
+        .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap())
+        .with_header(Header::from_bytes("Cache-Control", "no-store").unwrap());
+    stats::respond(request, response);
+}
+
+/// What to serve for a path the static roots do not have
+///
+/// An asset of another UI version, as asked for by a page or service worker
+/// cached before the UI was updated, comes from the current version's
+/// directory. A page path such as `/viewer` or a bare `/v46.0-abc/` gets the
+/// UI's index.html, whose router takes it from there, when a browser asks for
+/// HTML; anything with a file extension stays a 404.
+fn ui_fallback(request: &Request, state: &AppState, url_path: &str) -> Option<String> {
+    let (first, rest) = url_path.split_once('/').unwrap_or((url_path, ""));
+    if let Some(current) = state.ui_version_dir.as_deref().filter(|v| is_version_string(first) && *v != first) {
+        let moved = format!("{}/{}", current, rest);
+        if !rest.is_empty() && !matches!(state.roots.resolve(&moved), Resolved::NotFound) {
+            return Some(moved);
+        }
+    }
+    let last = url_path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let page = !last.contains('.') || is_version_string(last);
+    let wants_html = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Accept") && h.value.as_str().contains("text/html"));
+    let reserved = ["launcher", "api", "trace", "t", "plugins", "reports", "dashboards"].contains(&first);
+    let readable = matches!(request.method(), Method::Get | Method::Head);
+    (state.spa_fallback && page && wants_html && readable && !reserved).then(|| "index.html".to_string())
+}
+
+/// Serve a file of the static roots, compressed if the client accepts it
+///
+/// Range requests always get the file as is, so their offsets keep referring
+/// to the uncompressed bytes. Files under a UI version directory never change
+/// and may be cached for good; everything else is revalidated on each use.
+fn serve_static(request: Request, state: &AppState, url_path: &str) {
+    let resolved = state.roots.resolve(url_path);
+    let ranged = request.headers().iter().any(|h| h.field.equiv("Range"));
+    let encodings = if ranged { Vec::new() } else { compression::accepted(&request) };
+    let fingerprinted = url_path.split('/').next().is_some_and(is_version_string);
+    let cache_control = if fingerprinted { "public, max-age=31536000, immutable" } else { "no-cache" };
+    let common = vec![
+        Header::from_bytes("Vary", "Accept-Encoding").unwrap(),
+        Header::from_bytes("Cache-Control", cache_control).unwrap(),
+    ];
+    let encoded = |encoding: Encoding| {
+        let mut headers = common.clone();
+        headers.push(Header::from_bytes("Content-Encoding", encoding.token()).unwrap());
+        headers
+    };
+
+    // A pre-compressed sibling from the same place as the file itself
+    for &encoding in &encodings {
+        let sibling = state.roots.resolve(&format!("{}{}", url_path, encoding.suffix()));
+        match (&resolved, sibling) {
+            (Resolved::File(path), Resolved::File(sibling)) if path.is_file() && sibling.is_file() => {
+                return serve_file_typed(request, &sibling, path, encoded(encoding));
+            }
+            (Resolved::Embedded(path, _), Resolved::Embedded(_, contents)) => {
+                return serve_embedded(request, path, contents, encoded(encoding));
+            }
+            _ => {}
+        }
+    }
+
+    if state.compression.on_the_fly && encodings.contains(&Encoding::Gzip) {
+        let gzipped = match &resolved {
+            Resolved::File(path) => fs::metadata(path)
+                .ok()
+                .filter(|m| m.is_file() && compression::compressible(path, m.len()))
+                .and_then(|m| {
+                    let validators = Validators::of_file(&m);
+                    let version = validators.etag.clone()?;
+                    let data = state.compression.gzip(&path.to_string_lossy(), &version, || fs::read(path)).ok()?;
+                    Some((path.clone(), data, validators))
+                }),
+            Resolved::Embedded(path, contents) if compression::compressible(Path::new(path), contents.len() as u64) => {
+                let key = format!("embedded:{}", path);
+                let data = state.compression.gzip(&key, "", || Ok(contents.to_vec())).ok();
+                data.map(|data| (PathBuf::from(path), data, Validators { etag: assets::etag(path), modified: None }))
+            }
+            _ => None,
+        };
+        if let Some((path, data, validators)) = gzipped {
+            // The gzipped bytes are a representation of their own
+            let etag = validators.etag.map(|tag| format!("{}-gz\"", tag.trim_end_matches('"')));
+            let validators = Validators { etag, ..validators };
+            let len = data.len() as u64;
+            serve_body(request, &path, io::Cursor::new(data), len, validators, encoded(Encoding::Gzip));
+            return;
+        }
+    }
+
+    match resolved {
+        Resolved::File(path) => serve_file_with_headers(request, &path, common),
+        Resolved::Embedded(path, contents) => serve_embedded(request, &path, contents, common),
+        Resolved::NotFound => http_errors::not_found(request),
+        Resolved::Forbidden => http_errors::forbidden(request),
+    }
+}
+
+/// Methods a path answers besides OPTIONS; None for plugin routes, which
+/// answer whatever their plugin does
+fn allowed_methods(url_path: &str) -> Option<&'static str> {
+    Some(match url_path {
+        "launcher/shutdown"
+        | "launcher/restart-backend"
+        | "launcher/upload"
+        | "launcher/open-trace"
+        | "launcher/open-url"
+        | "launcher/query"
+        | "launcher/android/pull" => "POST",
+        "launcher/record" => "GET, HEAD, POST, DELETE",
+        "api/traces" | "api/sessions" => "GET, HEAD, POST",
+        _ if url_path.starts_with("plugins/") => return None,
+        _ if url_path.starts_with("api/sessions/") => "GET, HEAD, DELETE",
+        _ => match url_path.strip_prefix("api/traces/").map(|rest| rest.split_once('/')) {
+            Some(None) => "GET, HEAD, PATCH, DELETE",
+            Some(Some((_, "open"))) => "POST",
+            Some(Some((_, "pin"))) => "POST, DELETE",
+            _ => "GET, HEAD",
+        },
+    })
+}
+
+/// Route `url` is counted under in `/metrics`
+///
+/// Ids and file paths are left out, so the number of series stays bounded
+/// however many traces are served; unknown paths count as static files.
+fn route_label(url: &str) -> &'static str {
+    if rpc_proxy::route(url).is_some() {
+        return "/rpc";
+    }
+    let url_path = url.trim_start_matches('/').split('?').next().unwrap_or("");
+    let exact = [
+        "/metrics",
+        "/launcher/",
+        "/launcher/status",
+        "/launcher/tp-logs",
+        "/launcher/livereload",
+        "/launcher/shutdown",
+        "/launcher/restart-backend",
+        "/launcher/events",
+        "/launcher/record",
+        "/launcher/android",
+        "/launcher/android/pull",
+        "/launcher/recent",
+        "/launcher/upload",
+        "/launcher/open-trace",
+        "/launcher/open-url",
+        "/launcher/query",
+        "/api/search",
+        "/api/traces",
+        "/api/sessions",
+    ];
+    if let Some(route) = exact.iter().find(|route| route[1..].trim_end_matches('/') == url_path.trim_end_matches('/')) {
+        return route;
+    }
+    let prefixes = [
+        ("launcher/traces/", "/launcher/traces/{id}"),
+        ("t/", "/t/{id}"),
+        ("api/sessions/", "/api/sessions/{id}"),
+        ("trace/", "/trace/{path}"),
+        ("plugins/", "/plugins/{path}"),
+        ("dashboards", "/dashboards"),
+        ("reports", "/reports"),
+    ];
+    if let Some((_, route)) = prefixes.iter().find(|(prefix, _)| url_path.starts_with(prefix)) {
+        return route;
+    }
+    match url_path.strip_prefix("api/traces/").map(|rest| rest.split_once('/')) {
+        Some(None) => "/api/traces/{id}",
+        Some(Some((_, "download"))) => "/api/traces/{id}/download",
+        Some(Some((_, "open"))) => "/api/traces/{id}/open",
+        Some(Some((_, "permalink"))) => "/api/traces/{id}/permalink",
+        Some(Some((_, "metadata"))) => "/api/traces/{id}/metadata",
+        Some(Some((_, "pin"))) => "/api/traces/{id}/pin",
+        Some(Some(_)) => "/api/traces/{id}/{other}",
+        None if url_path == "trace" => "/trace/{path}",
+        None => "static",
+    }
+}
+
+/// Catalog id and sub-resource of a request for a single trace
+fn trace_route(url_path: &str) -> Option<(&str, Option<&str>)> {
+    if let Some(id) = url_path.strip_prefix("launcher/traces/").or_else(|| url_path.strip_prefix("t/")) {
+        return Some((id, None));
+    }
+    let rest = url_path.strip_prefix("api/traces/")?;
+    Some(match rest.split_once('/') {
+        Some((id, action)) => (id, Some(action)),
+        None => (rest, None),
+    })
+}
+
+/// Whether a request was sent by a page from another origin
+///
+/// Browsers attach `Origin` (or at least `Referer`) to cross-site POSTs, so a
+/// mismatch with `Host` means another site is driving the launcher through
+/// the user's browser. Clients such as curl send neither and are allowed.
+/// `Host` itself was checked against this machine's names by then, see
+/// `rpc_proxy::foreign_host`.
+fn is_cross_site(request: &Request) -> bool {
+    let header = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str().to_string())
+    };
+    if header("Sec-Fetch-Site").is_some_and(|site| site == "cross-site") {
+        return true;
+    }
+    let Some(source) = header("Origin").or_else(|| header("Referer")) else {
+        return false;
+    };
+    let Some(host) = header("Host") else {
+        return true;
+    };
+    // Origin is `scheme://host[:port]`; a Referer carries a path after it
+    let source = source.split('/').take(3).collect::<Vec<_>>().join("/");
+    let scheme = if request.secure() { "https" } else { "http" };
+    !source.eq_ignore_ascii_case(&format!("{}://{}", scheme, host))
+}
+
+/// Stream a file from disk, honouring a single `Range: bytes=...` request
+///
+/// The body is never buffered in memory and lengths are 64-bit throughout, so
+/// multi-gigabyte traces are served intact.
+pub fn serve_file(request: Request, path: &Path) {
+    serve_file_with_headers(request, path, Vec::new());
+}
+
+/// `serve_file` with `extra` headers added to successful responses
+pub fn serve_file_with_headers(request: Request, path: &Path, extra: Vec<Header>) {
+    serve_file_typed(request, path, path, extra);
+}
+
+/// `serve_file_with_headers` with the Content-Type of `type_path`, for a
+/// compressed sibling served in place of the file
+fn serve_file_typed(request: Request, path: &Path, type_path: &Path, extra: Vec<Header>) {
+    match File::open(path).and_then(|f| Ok((f.metadata()?, f))) {
+        Ok((metadata, file)) if path.is_file() => {
+            serve_body(request, type_path, file, metadata.len(), Validators::of_file(&metadata), extra);
+        }
+        _ => http_errors::not_found(request),
+    }
+}
+
+/// Serve a file of the embedded UI, with the same range handling as files on disk
+fn serve_embedded(request: Request, path: &str, contents: &'static [u8], extra: Vec<Header>) {
+    let validators = Validators { etag: assets::etag(path), modified: None };
+    serve_body(request, Path::new(path), io::Cursor::new(contents), contents.len() as u64, validators, extra);
+}
+
+/// What conditional requests for a file are checked against
+struct Validators {
+    etag: Option<String>,
+    /// Seconds since the Unix epoch
+    modified: Option<i64>,
+}
+
+impl Validators {
+    /// Size and modification time tell a rewritten file from the copy a
+    /// client holds
+    fn of_file(metadata: &Metadata) -> Validators {
+        let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+        Validators {
+            etag: modified.map(|m| format!("\"{:x}-{:x}\"", metadata.len(), m.as_nanos())),
+            modified: modified.and_then(|m| i64::try_from(m.as_secs()).ok()),
+        }
+    }
+
+    /// Whether `If-None-Match`, or failing that `If-Modified-Since`, says the
+    /// client's copy is current
+    fn not_modified(&self, if_none_match: Option<&str>, if_modified_since: Option<&str>) -> bool {
+        if let Some(tags) = if_none_match {
+            let Some(etag) = &self.etag else { return false };
+            let etag = etag.trim_start_matches("W/");
+            return tags.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+        }
+        match (self.modified, if_modified_since.and_then(parse_http_date)) {
+            (Some(modified), Some(since)) => modified <= since,
+            _ => false,
+        }
+    }
+
+    /// Whether an `If-Range` value names this version of the file
+    fn matches(&self, if_range: &str) -> bool {
+        let if_range = if_range.trim();
+        if if_range.starts_with('"') {
+            return self.etag.as_deref() == Some(if_range);
+        }
+        self.modified.is_some() && self.modified == parse_http_date(if_range)
+    }
+}
+
+/// Respond with `body`, `len` bytes long, typed by the extension of `path`
+///
+/// A client whose copy matches `validators` gets 304 Not Modified, and a
+/// range request carrying `If-Range` for another version of the file gets
+/// the whole current file instead of a mismatched part.
+fn serve_body<B>(request: Request, path: &Path, mut body: B, len: u64, validators: Validators, extra: Vec<Header>)
+where
+    B: Read + Seek + Send + 'static,
+{
+    let header = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str().to_string())
+    };
+    let current = header("If-Range").is_none_or(|if_range| validators.matches(&if_range));
+    let range_header = header("Range").filter(|_| current);
+    let mut headers = vec![
+        Header::from_bytes("Content-Type", get_mime_type(path)).unwrap(),
+        Header::from_bytes("Accept-Ranges", "bytes").unwrap(),
+    ];
+    if let Some(etag) = &validators.etag {
+        headers.push(Header::from_bytes("ETag", etag.as_str()).unwrap());
+    }
+    if let Some(modified) = validators.modified {
+        headers.push(Header::from_bytes("Last-Modified", format_http_date(modified)).unwrap());
+    }
+    headers.extend(extra);
+
+    let cacheable = matches!(request.method(), Method::Get | Method::Head);
+    let (if_none_match, if_modified_since) = (header("If-None-Match"), header("If-Modified-Since"));
+    if cacheable && validators.not_modified(if_none_match.as_deref(), if_modified_since.as_deref()) {
+        let mut response = Response::empty(304);
+        for header in headers.into_iter().filter(|h| !h.field.equiv("Content-Type")) {
+            response.add_header(header);
+        }
+        stats::respond(request, response);
+        return;
+    }
+    let (status, start, end) = match range_header.map(|r| parse_range(&r, len)) {
+        None | Some(ByteRange::Ignored) => (200, 0, len),
+        Some(ByteRange::Satisfiable(start, end)) => {
+            let content_range = format!("bytes {}-{}/{}", start, end - 1, len);
+            headers.push(Header::from_bytes("Content-Range", content_range).unwrap());
+            (206, start, end)
+        }
+        Some(ByteRange::Unsatisfiable) => {
+            let content_range = Header::from_bytes("Content-Range", format!("bytes */{}", len)).unwrap();
+            let response = Response::from_string("Range Not Satisfiable")
+                .with_status_code(416)
+                .with_header(content_range);
+            stats::respond(request, response);
+            return;
+        }
+    };
+
+    if start > 0 && body.seek(SeekFrom::Start(start)).is_err() {
+        return http_errors::respond(request, 500, "");
+    }
+    // A length that does not fit in usize (32-bit targets) falls back to a
+    // chunked body instead of a truncated Content-Length.
+    let length = end - start;
+    let response = Response::new(status.into(), headers, body.take(length), usize::try_from(length).ok(), None)
+        .with_chunked_threshold(usize::MAX);
+    stats::respond(request, response);
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// Half-open `[start, end)` byte range within the file
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+    /// Malformed or multi-range requests are answered with the whole file
+    Ignored,
+}
+
+/// Parse a `Range` header value such as `bytes=0-499`, `bytes=500-` or `bytes=-500`
+fn parse_range(value: &str, len: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Ignored;
+    };
+    if spec.contains(',') {
+        return ByteRange::Ignored;
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return ByteRange::Ignored;
+    };
+    let (first, last) = (first.trim(), last.trim());
+    let range = if first.is_empty() {
+        // Suffix range: the last N bytes
+        match last.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(n) => (len.saturating_sub(n), len),
+            Err(_) => return ByteRange::Ignored,
+        }
+    } else {
+        let Ok(start) = first.parse::<u64>() else {
+            return ByteRange::Ignored;
+        };
+        let end = match last {
+            "" => len,
+            last => match last.parse::<u64>() {
+                Ok(last) if last >= start => last.saturating_add(1).min(len),
+                _ => return ByteRange::Ignored,
+            },
+        };
+        (start, end)
+    };
+    if range.0 >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Satisfiable(range.0, range.1)
+}
+
+/// The `Host` the client addressed, for building absolute links
+pub fn request_host(request: &Request) -> String {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Host"))
+        .map(|h| h.value.as_str().to_string())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// `scheme://host[:port]` the client reached this server at, for absolute links
+pub fn origin(request: &Request) -> String {
+    let scheme = if request.secure() { "https" } else { "http" };
+    format!("{}://{}", scheme, request_host(request))
+}
+
+/// Value of query parameter `name` in `url`, percent-decoded
+pub fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match s.get(i + 1..i + 3).and_then(hex_byte) {
+                Some(b) => {
+                    out.push(b);
+                    i += 3;
+                    continue;
+                }
+                None => out.push(b'%'),
+            },
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `POST /api/traces/<id>/pin` pins a catalog trace, `DELETE` unpins it
+fn respond_pin(request: Request, state: &AppState, id: &str) {
+    let pinned = match request.method() {
+        Method::Post => true,
+        Method::Delete => false,
+        _ => return http_errors::method_not_allowed(request, "POST, DELETE"),
+    };
+    let result = state.catalog.lock().unwrap().set_pinned(id, pinned);
+    let response = match result {
+        Ok(Some(trace)) => {
+            let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+            let body = json!({ "id": trace.id, "pinned": trace.pinned }).to_string();
+            Response::from_string(body).with_header(content_type)
+        }
+        Ok(None) => Response::from_string(t!("catalog-unknown-id", id = id)).with_status_code(404),
+        Err(e) => Response::from_string(e.to_string()).with_status_code(500),
+    };
+    stats::respond(request, response);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-499", 1000), ByteRange::Satisfiable(0, 500));
+        assert_eq!(parse_range("bytes=500-999", 1000), ByteRange::Satisfiable(500, 1000));
+        assert_eq!(parse_range(" bytes=10-10 ", 1000), ByteRange::Satisfiable(10, 11));
+        assert_eq!(parse_range("bytes= 1 - 2", 1000), ByteRange::Satisfiable(1, 3));
+    }
+
+    #[test]
+    fn parses_open_ended_ranges() {
+        assert_eq!(parse_range("bytes=500-", 1000), ByteRange::Satisfiable(500, 1000));
+        assert_eq!(parse_range("bytes=0-", 1000), ByteRange::Satisfiable(0, 1000));
+        assert_eq!(parse_range("bytes=999-", 1000), ByteRange::Satisfiable(999, 1000));
+    }
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(parse_range("bytes=-500", 1000), ByteRange::Satisfiable(500, 1000));
+        assert_eq!(parse_range("bytes=-1", 1000), ByteRange::Satisfiable(999, 1000));
+        // A suffix longer than the file is the whole file
+        assert_eq!(parse_range("bytes=-5000", 1000), ByteRange::Satisfiable(0, 1000));
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn clamps_ranges_past_the_end() {
+        assert_eq!(parse_range("bytes=900-5000", 1000), ByteRange::Satisfiable(900, 1000));
+        assert_eq!(parse_range("bytes=0-18446744073709551615", 1000), ByteRange::Satisfiable(0, 1000));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=1000-1999", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=5000-6000", 1000), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn ignores_reversed_ranges() {
+        assert_eq!(parse_range("bytes=500-499", 1000), ByteRange::Ignored);
+        assert_eq!(parse_range("bytes=999-0", 1000), ByteRange::Ignored);
+    }
+
+    #[test]
+    fn ignores_multiple_ranges() {
+        assert_eq!(parse_range("bytes=0-0,-1", 1000), ByteRange::Ignored);
+        assert_eq!(parse_range("bytes=0-99, 200-299", 1000), ByteRange::Ignored);
+    }
+
+    #[test]
+    fn ignores_malformed_ranges() {
+        let values = ["", "bytes=", "bytes=-", "bytes=abc", "bytes=a-b", "bytes=1-b", "bytes=--1", "items=0-1", "0-9"];
+        for value in values {
+            assert_eq!(parse_range(value, 1000), ByteRange::Ignored, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn decodes_query_params() {
+        let url = "/launcher/open?path=%2Ftmp%2Fa+b.pftrace&q=50%25&name=%e6%97%a5&flag";
+        assert_eq!(query_param(url, "path").as_deref(), Some("/tmp/a b.pftrace"));
+        assert_eq!(query_param(url, "q").as_deref(), Some("50%"));
+        assert_eq!(query_param(url, "name").as_deref(), Some("日"));
+        assert_eq!(query_param(url, "flag"), None);
+        assert_eq!(query_param(url, "missing"), None);
+        assert_eq!(query_param("/launcher/open", "path"), None);
+        assert_eq!(query_param("/?a=1&a=2", "a").as_deref(), Some("1"));
+        assert_eq!(query_param("/?empty=", "empty").as_deref(), Some(""));
+        assert_eq!(query_param("/?a%3Db=1", "a=b"), None);
+        assert_eq!(query_param("/?x=a=b", "x").as_deref(), Some("a=b"));
+    }
+
+    #[test]
+    fn percent_decodes_leniently() {
+        assert_eq!(percent_decode("%2F%2f"), "//");
+        assert_eq!(percent_decode("a+b%2Bc"), "a b+c");
+        // Escapes cut short or not hex are kept as they are
+        assert_eq!(percent_decode("%4"), "%4");
+        assert_eq!(percent_decode("ab%"), "ab%");
+        assert_eq!(percent_decode("%zz%4g"), "%zz%4g");
+        assert_eq!(percent_decode("%+1%-1"), "% 1%-1");
+        assert_eq!(percent_decode("%%41"), "%A");
+        assert_eq!(percent_decode("%日本"), "%日本");
+        // Bytes that are not UTF-8 become replacement characters
+        assert_eq!(percent_decode("%FF%FEok"), "\u{FFFD}\u{FFFD}ok");
+        assert_eq!(percent_decode("%E6%97"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn no_range_of_an_empty_file_is_satisfiable() {
+        for value in ["bytes=0-", "bytes=0-0", "bytes=-1", "bytes=-0"] {
+            assert_eq!(parse_range(value, 0), ByteRange::Unsatisfiable, "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Running in the background: `--daemon` on Unix, and a Windows service
+//! started at boot (`service install|uninstall|run`).
+//!
+//! Both log to a file, since there is no console to print to, and stop
+//! through the normal shutdown path so trace_processor is stopped with the
+//! launcher.
+
+use crate::paths::data_dir;
+use std::path::PathBuf;
+
+/// Log file used in the background unless `--log-file` is given
+pub fn default_log_file() -> PathBuf {
+    data_dir().join("logs").join("launcher.log")
+}
+
+/// Start this launcher again detached from the terminal, with the same
+/// arguments minus `--daemon`, and return the new process's pid
+#[cfg(unix)]
+pub fn daemonize(log_file: Option<&std::path::Path>) -> Result<u32, String> {
+    use std::env;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    extern "C" {
+        fn setsid() -> i32;
+    }
+
+    let exe = env::current_exe().map_err(|e| t!("daemon-failed", error = e))?;
+    let mut command = Command::new(exe);
+    command.args(env::args_os().skip(1).filter(|arg| arg != "--daemon")).arg("--no-browser");
+    if log_file.is_none() {
+        command.arg("--log-file").arg(default_log_file());
+    }
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe and touches nothing of this process
+    unsafe {
+        command.pre_exec(|| {
+            setsid();
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().map_err(|e| t!("daemon-failed", error = e))?;
+    // Problems such as a missing trace_processor end the launcher at once
+    thread::sleep(Duration::from_secs(1));
+    match child.try_wait() {
+        Ok(Some(status)) => Err(t!("daemon-exited", status = status)),
+        _ => Ok(child.id()),
+    }
+}
+
+#[cfg(windows)]
+pub use windows::{install, run, uninstall};
+
+#[cfg(windows)]
+mod windows {
+    use super::default_log_file;
+    use crate::shutdown;
+    use std::env;
+    use std::ffi::OsString;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode, ServiceInfo,
+        ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    const NAME: &str = "perfetto_launcher";
+    const DISPLAY_NAME: &str = "Perfetto Launcher";
+
+    /// The launcher and the arguments to run it with
+    type Launch = (fn(Vec<String>), Vec<String>);
+
+    /// Handed over to the thread the service control manager starts
+    static LAUNCH: OnceLock<Launch> = OnceLock::new();
+
+    /// Register the service to start at boot as LocalSystem, running the
+    /// launcher with `args`
+    pub fn install(args: &[String]) -> Result<(), String> {
+        let exe = env::current_exe().map_err(|e| t!("service-failed", error = e))?;
+        let log_file = default_log_file();
+        let mut launch_arguments: Vec<OsString> =
+            vec!["service".into(), "run".into(), "--log-file".into(), log_file.clone().into(), "--".into()];
+        launch_arguments.extend(args.iter().map(OsString::from));
+        let info = ServiceInfo {
+            name: NAME.into(),
+            display_name: DISPLAY_NAME.into(),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe,
+            launch_arguments,
+            dependencies: Vec::new(),
+            account_name: None,
+            account_password: None,
+        };
+        let access = ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE;
+        let manager = ServiceManager::local_computer(None::<&str>, access).map_err(failed)?;
+        let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG).map_err(failed)?;
+        let _ = service.set_description("Serves the Perfetto UI with trace_processor_shell");
+        say!("{}", t!("service-installed", name = NAME, log = log_file.display()));
+        Ok(())
+    }
+
+    /// Stop the service if it is running and remove it
+    pub fn uninstall() -> Result<(), String> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT).map_err(failed)?;
+        let access = ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE;
+        let service = manager.open_service(NAME, access).map_err(failed)?;
+        if service.query_status().map_err(failed)?.current_state != ServiceState::Stopped {
+            service.stop().map_err(failed)?;
+        }
+        service.delete().map_err(failed)?;
+        say!("{}", t!("service-uninstalled", name = NAME));
+        Ok(())
+    }
+
+    /// Hand this process to the service control manager, which runs `launch`
+    /// with `args` on a thread of its own until the service is stopped
+    pub fn run(args: Vec<String>, launch: fn(Vec<String>)) -> Result<(), String> {
+        let _ = LAUNCH.set((launch, args));
+        service_dispatcher::start(NAME, ffi_service_main).map_err(failed)
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let handler = |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                shutdown::request();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let status = match service_control_handler::register(NAME, handler) {
+            Ok(status) => status,
+            Err(e) => {
+                log::error!("{}", failed(e));
+                return;
+            }
+        };
+        let report = |state, accepted| {
+            let _ = status.set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state: state,
+                controls_accepted: accepted,
+                exit_code: ServiceExitCode::Win32(0),
+                checkpoint: 0,
+                wait_hint: Duration::default(),
+                process_id: None,
+            });
+        };
+        report(ServiceState::Running, ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN);
+        if let Some((launch, args)) = LAUNCH.get() {
+            launch(args.clone());
+        }
+        report(ServiceState::Stopped, ServiceControlAccept::empty());
+    }
+
+    fn failed(e: windows_service::Error) -> String {
+        t!("service-failed", error = e)
+    }
+}
@@ -0,0 +1,353 @@
+//! Extra trace_processor sessions, so several traces can be open side by
+//! side.
+//!
+//! trace_processor loads one trace per process, so each session is a
+//! trace_processor of its own on a free port, started for a catalog trace:
+//!
+//! - `GET /api/sessions` lists the sessions
+//! - `POST /api/sessions` with `{"trace": "<catalog id>"}` starts one
+//! - `GET` and `DELETE /api/sessions/<id>` show or stop one; in multi-user
+//!   mode only the user who started it, the trace's owner or an admin may
+//!   stop it
+//!
+//! A session's RPC is relayed under `/rpc/<session id>/` on the UI port. The
+//! UI expects its RPC at the root of a port, so every session also relays on
+//! a loopback port of its own, which a UI tab is pointed at with
+//! `?rpc_port=`. Sessions are stopped once unused for
+//! `--session-idle-timeout`, and all of them with the launcher.
+//!
+//! Each session's trace_processor keeps its temporary files in a scratch
+//! directory of the session's own (see `workspace`), deleted when it stops.
+
+use crate::catalog::{random_id, Trace};
+use crate::http_errors;
+use crate::ports::get_available_port;
+use crate::rpc_proxy;
+use crate::server::{origin, respond_too_large, AppState, LimitedReader};
+use crate::traces_api::{respond_error, respond_json};
+use crate::users::Viewer;
+use crate::workspace;
+use crate::{shutdown, trace_processor};
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tiny_http::{Method, Request, Server};
+
+/// How often idle sessions are looked for
+const REAP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Body of `POST /api/sessions`
+#[derive(Deserialize)]
+struct NewSession {
+    /// Catalog id, or the path of a trace in the catalog
+    trace: String,
+}
+
+/// A trace_processor started for one trace
+pub struct Session {
+    pub id: String,
+    pub trace: Trace,
+    /// trace_processor's own port
+    pub tp_port: u16,
+    /// Loopback port relaying to trace_processor, for the UI's `rpc_port`
+    pub port: u16,
+    /// Unix time the session was started
+    pub started: u64,
+    /// User who started the session in multi-user mode
+    started_by: Option<String>,
+    /// Temporary files of this session's trace_processor
+    scratch: PathBuf,
+    child: Mutex<Child>,
+    relay: Arc<Server>,
+    last_used: Mutex<Instant>,
+    in_use: AtomicUsize,
+}
+
+impl Session {
+    /// Mark the session as in use for as long as the guard is alive
+    pub fn use_guard(self: &Arc<Self>) -> InUse {
+        self.in_use.fetch_add(1, Ordering::SeqCst);
+        InUse(self.clone())
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+
+    pub fn alive(&self) -> bool {
+        matches!(self.child.lock().unwrap().try_wait(), Ok(None))
+    }
+
+    /// Whether `viewer` may stop the session
+    fn can_stop(&self, viewer: &Viewer) -> bool {
+        viewer.can_modify(&self.trace) || viewer.name().is_some_and(|name| self.started_by.as_deref() == Some(name))
+    }
+
+    fn idle_for(&self, timeout: Duration) -> bool {
+        self.in_use.load(Ordering::SeqCst) == 0 && self.last_used.lock().unwrap().elapsed() >= timeout
+    }
+
+    fn stop(&self) {
+        self.relay.unblock();
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = fs::remove_dir_all(&self.scratch);
+        info!("{}", t!("session-stopped", id = self.id.as_str(), pid = child.id()));
+    }
+
+    /// Description for API clients, with links from `origin`
+    fn json(&self, origin: &str) -> Value {
+        json!({
+            "id": self.id,
+            "trace": { "id": self.trace.id, "name": self.trace.name },
+            "pid": self.pid(),
+            "alive": self.alive(),
+            "started": self.started,
+            "rpc": format!("/rpc/{}/", self.id),
+            "rpc_port": self.port,
+            "ui_url": format!("{}/?rpc_port={}", origin, self.port),
+        })
+    }
+}
+
+/// A request or WebSocket relayed to a session; the session counts as used
+/// until it is dropped
+pub struct InUse(Arc<Session>);
+
+impl Drop for InUse {
+    fn drop(&mut self) {
+        *self.0.last_used.lock().unwrap() = Instant::now();
+        self.0.in_use.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct Sessions {
+    trace_processor: PathBuf,
+    /// Arguments every session shares, such as SQL modules and presets
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    /// The UI's port, whose pages may use the sessions' relays
+    ui_port: u16,
+    /// Largest request body relayed
+    body_limit: u64,
+    max: usize,
+    /// None to keep sessions until they are stopped
+    idle_timeout: Option<Duration>,
+    sessions: Mutex<Vec<Arc<Session>>>,
+}
+
+impl Sessions {
+    pub fn new(
+        trace_processor: PathBuf,
+        args: Vec<String>,
+        env: Vec<(String, String)>,
+        ui_port: u16,
+        body_limit: u64,
+        max: usize,
+        idle_timeout: Option<Duration>,
+    ) -> Sessions {
+        Sessions {
+            trace_processor,
+            args,
+            env,
+            ui_port,
+            body_limit,
+            max,
+            idle_timeout,
+            sessions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Start trace_processor for `trace` with a relay port of its own
+    ///
+    /// Returns at once; like the UI session, it answers RPC only once the
+    /// trace is loaded.
+    ///
+    /// Fails with `QuotaExceeded` if `--max-sessions` are running already.
+    pub fn start(&self, trace: &Trace, viewer: &Viewer) -> io::Result<Arc<Session>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= self.max {
+            return Err(io::Error::new(io::ErrorKind::QuotaExceeded, t!("session-limit", max = self.max)));
+        }
+        let relay = Server::http("127.0.0.1:0").map_err(io::Error::other)?;
+        let port = relay.server_addr().to_ip().map(|addr| addr.port()).unwrap_or_default();
+        let tp_port = get_available_port();
+        let id = loop {
+            let id = random_id();
+            if sessions.iter().all(|s| s.id != id) {
+                break id;
+            }
+        };
+        let scratch = workspace::session_scratch_dir(&id);
+        fs::create_dir_all(&scratch)?;
+        let _ = trace_processor::ensure_executable(&self.trace_processor);
+        let child = Command::new(&self.trace_processor)
+            .args(["-D", "--http-ip-address", "127.0.0.1", "--http-port", &tp_port.to_string()])
+            .args(&self.args)
+            .arg(&trace.path)
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .envs(["TMPDIR", "TEMP", "TMP"].map(|name| (name, &scratch)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .inspect_err(|_| {
+                let _ = fs::remove_dir_all(&scratch);
+            })?;
+        info!("{}", t!("session-started", id = id.as_str(), pid = child.id(), trace = trace.name.as_str()));
+        let session = Arc::new(Session {
+            id,
+            trace: trace.clone(),
+            tp_port,
+            port,
+            started: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            started_by: viewer.name().map(String::from),
+            scratch,
+            child: Mutex::new(child),
+            relay: Arc::new(relay),
+            last_used: Mutex::new(Instant::now()),
+            in_use: AtomicUsize::new(0),
+        });
+        sessions.push(session.clone());
+
+        let (relay, target) = (session.relay.clone(), session.clone());
+        let (ui_port, body_limit) = (self.ui_port, self.body_limit);
+        thread::spawn(move || {
+            for request in relay.incoming_requests() {
+                let session = target.clone();
+                // WebSockets hold their thread for as long as the UI is open
+                thread::spawn(move || {
+                    let url = request.url().to_string();
+                    rpc_proxy::relay_session(request, &session, &url, ui_port, body_limit);
+                });
+            }
+        });
+        Ok(session)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.lock().unwrap().iter().find(|s| s.id == id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Arc<Session>> {
+        self.sessions.lock().unwrap().clone()
+    }
+
+    /// Stop session `id`; false if there is none
+    pub fn stop(&self, id: &str) -> bool {
+        let session = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(index) = sessions.iter().position(|s| s.id == id) else { return false };
+            sessions.remove(index)
+        };
+        session.stop();
+        true
+    }
+
+    pub fn stop_all(&self) {
+        for session in self.sessions.lock().unwrap().drain(..) {
+            session.stop();
+        }
+    }
+
+    /// Session and path on its trace_processor for a relayed `/rpc/` target
+    /// such as `/<session id>/websocket`
+    pub fn route(&self, target: &str) -> Option<(Arc<Session>, String)> {
+        let rest = target.strip_prefix('/')?;
+        let (id, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        let id = id.split('?').next().unwrap_or(id);
+        let session = self.get(id)?;
+        Some((session, format!("/{}", rest)))
+    }
+
+    /// Stop sessions unused for the idle timeout, and forget those whose
+    /// trace_processor exited, until shutdown
+    pub fn watch(self: &Arc<Self>) {
+        let sessions = self.clone();
+        thread::spawn(move || {
+            while !shutdown::requested() {
+                thread::sleep(REAP_INTERVAL);
+                for session in sessions.list() {
+                    if !session.alive() {
+                        warn!("{}", t!("session-exited", id = session.id.as_str()));
+                        sessions.stop(&session.id);
+                    } else if sessions.idle_timeout.is_some_and(|timeout| session.idle_for(timeout)) {
+                        info!("{}", t!("session-idle", id = session.id.as_str()));
+                        sessions.stop(&session.id);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// `/api/sessions`
+pub fn respond_collection(request: Request, state: &AppState, viewer: &Viewer) {
+    match request.method() {
+        Method::Get | Method::Head => {
+            let origin = origin(&request);
+            let sessions: Vec<Value> = state
+                .sessions
+                .list()
+                .iter()
+                .filter(|s| viewer.can_see(&s.trace))
+                .map(|s| s.json(&origin))
+                .collect();
+            respond_json(request, 200, json!({ "sessions": sessions }));
+        }
+        Method::Post => respond_start(request, state, viewer),
+        _ => http_errors::method_not_allowed(request, "GET, HEAD, POST"),
+    }
+}
+
+fn respond_start(mut request: Request, state: &AppState, viewer: &Viewer) {
+    let limit = state.body_limits.api;
+    let mut body = Vec::new();
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        return respond_too_large(request, limit);
+    }
+    let new: NewSession = match serde_json::from_slice(&body) {
+        Ok(new) => new,
+        Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+    };
+    let trace = state.catalog.lock().unwrap().find(&new.trace).filter(|t| viewer.can_see(t)).cloned();
+    let Some(trace) = trace else {
+        return respond_error(request, 404, t!("catalog-unknown-id", id = new.trace));
+    };
+    match state.sessions.start(&trace, viewer) {
+        Ok(session) => {
+            let body = session.json(&origin(&request));
+            respond_json(request, 201, body);
+        }
+        Err(e) if e.kind() == io::ErrorKind::QuotaExceeded => respond_error(request, 429, e.to_string()),
+        Err(e) => respond_error(request, 500, t!("session-start-failed", error = e)),
+    }
+}
+
+/// `/api/sessions/<id>`
+pub fn respond_session(request: Request, state: &AppState, viewer: &Viewer, id: &str) {
+    let Some(session) = state.sessions.get(id).filter(|s| viewer.can_see(&s.trace)) else {
+        return respond_error(request, 404, t!("session-unknown", id = id));
+    };
+    match request.method() {
+        Method::Get | Method::Head => {
+            let body = session.json(&origin(&request));
+            respond_json(request, 200, body);
+        }
+        Method::Delete if !session.can_stop(viewer) => respond_error(request, 403, t!("session-forbidden", id = id)),
+        Method::Delete => {
+            state.sessions.stop(id);
+            respond_json(request, 200, json!({ "id": id, "stopped": true }));
+        }
+        _ => http_errors::method_not_allowed(request, "GET, HEAD, DELETE"),
+    }
+}
@@ -0,0 +1,198 @@
+//! Coordinated shutdown with draining of in-flight responses.
+//!
+//! The first Ctrl+C stops the server from accepting new requests and gives
+//! in-flight responses (e.g. a large trace download) up to the drain timeout
+//! to finish. A second Ctrl+C, or the timeout expiring, exits immediately.
+//!
+//! SIGTERM and SIGHUP are treated like Ctrl+C. Closing the console window on
+//! Windows leaves only a few seconds before the process is ended, so it exits
+//! right away. Every way out stops trace_processor first, so it is never
+//! left running without the launcher.
+//!
+//! Scripts can ask for the same shutdown with `POST /launcher/shutdown`, and
+//! `--idle-timeout` asks for it once no request has been in flight or
+//! arrived for that long. The RPC websocket of an open UI tab is relayed on a
+//! thread of its own after the upgrade, so it holds a `Connected` guard there
+//! and the tab counts as activity for as long as it is connected.
+
+use crate::capture::format_duration;
+use log::warn;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Relayed connections that outlive their request, such as RPC websockets;
+/// unlike responses in flight, shutdown does not wait for them
+static CONNECTED: AtomicUsize = AtomicUsize::new(0);
+static DRAIN_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+/// When the last request arrived or finished
+static LAST_ACTIVITY: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Cleanup for exits that skip the normal shutdown path
+static ON_EXIT: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+pub fn install(drain_timeout: Duration) {
+    let _ = DRAIN_TIMEOUT.set(drain_timeout);
+    let result = ctrlc::set_handler(move || {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            warn!("{}", t!("shutdown-forced"));
+            exit_now(130);
+        }
+        drain(drain_timeout);
+    });
+    if let Err(e) = result {
+        warn!("{}", t!("shutdown-handler-failed", error = e));
+    }
+    // Console handlers run newest first, so this one sees close events before ctrlc does
+    install_close_handler();
+}
+
+/// Run `cleanup` if the process has to exit before the normal shutdown path
+pub fn on_exit(cleanup: impl Fn() + Send + Sync + 'static) {
+    let _ = ON_EXIT.set(Box::new(cleanup));
+}
+
+fn exit_now(code: i32) -> ! {
+    if let Some(cleanup) = ON_EXIT.get() {
+        cleanup();
+    }
+    std::process::exit(code)
+}
+
+#[cfg(windows)]
+fn install_close_handler() {
+    const CTRL_CLOSE_EVENT: u32 = 2;
+    const CTRL_LOGOFF_EVENT: u32 = 5;
+    const CTRL_SHUTDOWN_EVENT: u32 = 6;
+
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(u32) -> i32>, add: i32) -> i32;
+    }
+
+    // Passes Ctrl+C and Ctrl+Break on to the ctrlc handler
+    unsafe extern "system" fn handler(event: u32) -> i32 {
+        if matches!(event, CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT) {
+            exit_now(0);
+        }
+        0
+    }
+
+    unsafe {
+        SetConsoleCtrlHandler(Some(handler), 1);
+    }
+}
+
+#[cfg(not(windows))]
+fn install_close_handler() {}
+
+/// Give in-flight responses `drain_timeout` to finish before exiting
+fn drain(drain_timeout: Duration) {
+    let in_flight = IN_FLIGHT.load(Ordering::SeqCst);
+    if in_flight > 0 {
+        warn!(
+            "{}",
+            t!("shutdown-draining", count = in_flight, seconds = drain_timeout.as_secs())
+        );
+    }
+    thread::spawn(move || {
+        thread::sleep(drain_timeout);
+        warn!("{}", t!("shutdown-drain-expired"));
+        exit_now(130);
+    });
+}
+
+/// Shut down as if Ctrl+C was pressed once, e.g. from the tray menu, the
+/// Windows service manager or `POST /launcher/shutdown`
+pub fn request() {
+    if !REQUESTED.swap(true, Ordering::SeqCst) {
+        if let Some(drain_timeout) = DRAIN_TIMEOUT.get() {
+            drain(*drain_timeout);
+        }
+    }
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Responses being sent right now
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+/// Wait until every in-flight response has finished
+///
+/// The drain timeout armed by the Ctrl+C handler bounds how long this can take.
+pub fn wait_for_in_flight() {
+    while IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Shut down once nothing has happened for `timeout`
+pub fn exit_when_idle(timeout: Duration) {
+    touch();
+    let check_every = (timeout / 10).clamp(Duration::from_millis(100), Duration::from_secs(5));
+    thread::spawn(move || loop {
+        thread::sleep(check_every);
+        if requested() {
+            return;
+        }
+        let last = LAST_ACTIVITY.lock().unwrap().unwrap_or_else(Instant::now);
+        let busy = IN_FLIGHT.load(Ordering::SeqCst) > 0 || CONNECTED.load(Ordering::SeqCst) > 0;
+        if !busy && last.elapsed() >= timeout {
+            say!("{}", t!("shutdown-idle", duration = format_duration(timeout)));
+            request();
+            return;
+        }
+    });
+}
+
+fn touch() {
+    *LAST_ACTIVITY.lock().unwrap() = Some(Instant::now());
+}
+
+/// Marks a response as in flight for as long as the guard is alive
+pub struct InFlight;
+
+impl InFlight {
+    /// Register a new response unless `limit` responses are already in flight
+    pub fn try_begin(limit: usize) -> Option<InFlight> {
+        IN_FLIGHT
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < limit).then_some(n + 1))
+            .ok()
+            .map(|_| {
+                touch();
+                InFlight
+            })
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        touch();
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Marks a relayed connection as open for as long as the guard is alive,
+/// which keeps `--idle-timeout` from firing
+pub struct Connected;
+
+impl Connected {
+    pub fn begin() -> Connected {
+        CONNECTED.fetch_add(1, Ordering::SeqCst);
+        touch();
+        Connected
+    }
+}
+
+impl Drop for Connected {
+    fn drop(&mut self) {
+        touch();
+        CONNECTED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,81 @@
+//! PerfettoSQL module packages passed to trace_processor with
+//! `--add-sql-module`.
+//!
+//! A package is a directory; the package name is the directory name and each
+//! `.sql` file below it is a module named after its path, so
+//! `android_team/startup/cold.sql` becomes `android_team.startup.cold`.
+
+use crate::rpc::RpcClient;
+use log::warn;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct Package {
+    pub name: String,
+    pub modules: Vec<String>,
+}
+
+/// Load result of one module in the session's trace_processor
+#[derive(Clone, Serialize)]
+pub struct ModuleStatus {
+    pub package: String,
+    pub module: String,
+    pub loaded: bool,
+    pub error: Option<String>,
+}
+
+/// List the modules of the package at `dir`
+pub fn scan(dir: &Path) -> Result<Package, String> {
+    let name = dir
+        .canonicalize()
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .ok_or_else(|| t!("sql-module-dir-missing", path = dir.display()))?;
+    let mut modules = Vec::new();
+    collect(dir, &name, &mut modules);
+    if modules.is_empty() {
+        return Err(t!("sql-module-dir-empty", path = dir.display()));
+    }
+    modules.sort();
+    Ok(Package { name, modules })
+}
+
+fn collect(dir: &Path, prefix: &str, out: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else { continue };
+        if path.is_dir() {
+            collect(&path, &format!("{}.{}", prefix, stem), out);
+        } else if path.extension().is_some_and(|e| e == "sql") {
+            out.push(format!("{}.{}", prefix, stem));
+        }
+    }
+}
+
+/// `--add-sql-module` arguments for trace_processor
+pub fn args(dirs: &[PathBuf]) -> Vec<String> {
+    dirs.iter().flat_map(|dir| ["--add-sql-module".to_string(), dir.display().to_string()]).collect()
+}
+
+/// Include every module of `packages` so that broken ones are reported at
+/// startup instead of at first use
+pub fn validate(client: &RpcClient, packages: &[Package]) -> Vec<ModuleStatus> {
+    let mut statuses = Vec::new();
+    for package in packages {
+        for module in &package.modules {
+            let result = client.query(&format!("INCLUDE PERFETTO MODULE {};", module));
+            if let Err(e) = &result {
+                warn!("{}", t!("sql-module-failed", module = module, error = e));
+            }
+            statuses.push(ModuleStatus {
+                package: package.name.clone(),
+                module: module.clone(),
+                loaded: result.is_ok(),
+                error: result.err(),
+            });
+        }
+    }
+    statuses
+}
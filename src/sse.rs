@@ -0,0 +1,39 @@
+//! Server-Sent Events streams, such as `/launcher/events`.
+//!
+//! A stream holds its connection until the client goes away or the launcher
+//! shuts down, so it is written on a thread of its own rather than a worker.
+//! Its head carries the headers every response gets (see `security_headers`)
+//! and is counted in the request statistics like any other response.
+
+use crate::security_headers;
+use crate::stats;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Request};
+
+/// Interval of the comments that keep idle streams open through proxies
+pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Answer `request` with an event stream, then have `events` write the
+/// events on a thread of its own until it returns
+pub fn stream<F>(request: Request, events: F)
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+{
+    let mut headers = vec![
+        Header::from_bytes("Content-Type", "text/event-stream").unwrap(),
+        Header::from_bytes("Cache-Control", "no-cache").unwrap(),
+    ];
+    security_headers::apply(&request, &mut headers);
+    let mut head = String::from("HTTP/1.1 200 OK\r\n");
+    for header in &headers {
+        head.push_str(&format!("{}: {}\r\n", header.field, header.value));
+    }
+    head.push_str("Connection: close\r\n\r\nretry: 1000\n\n");
+    stats::responded(200);
+    let mut writer = request.into_writer();
+    thread::spawn(move || {
+        let _ = writer.write_all(head.as_bytes()).and_then(|_| writer.flush()).and_then(|_| events(&mut writer));
+    });
+}
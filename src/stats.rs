@@ -0,0 +1,143 @@
+//! Counters behind `GET /launcher/status` and `GET /metrics`.
+//!
+//! The request loop counts what it accepts and turns away, and startup notes
+//! when the launcher came up and which trace_processor it runs. Everything is
+//! process-wide, like the shutdown state, so any thread can update it without
+//! a handle being passed around.
+//!
+//! Responses are sent with `respond`, which notes their status and counts
+//! the body bytes as they are written; the worker then files both, with how
+//! long the request took, under the request's route. It also adds the headers
+//! every response gets; see `security_headers`.
+
+use crate::security_headers;
+use crate::trace_processor::TpVersion;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tiny_http::{Request, Response};
+
+/// Upper bounds of the latency histogram buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 12] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 60.0];
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static TP_VERSION: OnceLock<TpVersion> = OnceLock::new();
+static REQUESTS: AtomicU64 = AtomicU64::new(0);
+static RPC_REQUESTS: AtomicU64 = AtomicU64::new(0);
+/// Turned away with 503 because too many were in flight
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+static ROUTES: Mutex<BTreeMap<(&'static str, u16), Series>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    /// Status and body bytes of the response the current worker is sending
+    static RESPONSE: Cell<Option<(u16, u64)>> = const { Cell::new(None) };
+}
+
+/// Requests of one route that got one status
+#[derive(Clone, Default)]
+pub struct Series {
+    pub count: u64,
+    pub bytes: u64,
+    pub seconds: f64,
+    /// Requests that took at most each of `LATENCY_BUCKETS`, cumulative
+    pub buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+#[derive(Clone, Copy)]
+pub struct Requests {
+    pub total: u64,
+    pub rpc: u64,
+    pub rejected: u64,
+}
+
+/// Start counting uptime; called once at startup
+pub fn start() {
+    STARTED.get_or_init(Instant::now);
+}
+
+pub fn uptime() -> Duration {
+    STARTED.get().map(Instant::elapsed).unwrap_or_default()
+}
+
+pub fn set_tp_version(version: TpVersion) {
+    let _ = TP_VERSION.set(version);
+}
+
+pub fn tp_version() -> Option<&'static TpVersion> {
+    TP_VERSION.get()
+}
+
+/// Count a request the server accepted; `rpc` for ones relayed to trace_processor
+pub fn request(rpc: bool) {
+    REQUESTS.fetch_add(1, Ordering::Relaxed);
+    if rpc {
+        RPC_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn rejected() {
+    REJECTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Send `response` with the security headers, noting its status and counting
+/// the bytes of its body
+pub fn respond<R: Read>(request: Request, response: Response<R>) {
+    let status = response.status_code().0;
+    let (mut headers, length, threshold) =
+        (response.headers().to_vec(), response.data_length(), response.chunked_threshold());
+    security_headers::apply(&request, &mut headers);
+    RESPONSE.with(|current| current.set(Some((status, 0))));
+    let counted = Counted(response.into_reader());
+    let response = Response::new(status.into(), headers, counted, length, None).with_chunked_threshold(threshold);
+    let _ = request.respond(response);
+}
+
+/// Note the status of a response not sent with `respond`, such as a
+/// websocket upgrade or an event stream
+pub fn responded(status: u16) {
+    RESPONSE.with(|current| current.set(Some((status, 0))));
+}
+
+struct Counted<R>(R);
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        RESPONSE.with(|current| current.set(current.get().map(|(status, bytes)| (status, bytes + n as u64))));
+        Ok(n)
+    }
+}
+
+/// File the request the current worker just handled under `route`
+///
+/// A request dropped without a response is answered 500 by tiny_http.
+pub fn finish(route: &'static str, elapsed: Duration) {
+    let (status, bytes) = RESPONSE.with(Cell::take).unwrap_or((500, 0));
+    let seconds = elapsed.as_secs_f64();
+    let mut routes = ROUTES.lock().unwrap();
+    let series = routes.entry((route, status)).or_default();
+    series.count += 1;
+    series.bytes += bytes;
+    series.seconds += seconds;
+    for (bucket, bound) in series.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+        if seconds <= bound {
+            *bucket += 1;
+        }
+    }
+}
+
+/// Everything filed by `finish` so far, by route and status
+pub fn routes() -> BTreeMap<(&'static str, u16), Series> {
+    ROUTES.lock().unwrap().clone()
+}
+
+pub fn requests() -> Requests {
+    Requests {
+        total: REQUESTS.load(Ordering::Relaxed),
+        rpc: RPC_REQUESTS.load(Ordering::Relaxed),
+        rejected: REJECTED.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,173 @@
+//! Symbol servers: `_NT_SYMBOL_PATH` on Windows and `--symbol-server`.
+//!
+//! Before a trace is loaded, the PDBs of the modules its stacks reference are
+//! downloaded from the configured servers into a symbol-store layout cache
+//! (`<pdb>/<GUID+age>/<pdb>`), which then joins the symbol directories used
+//! for symbolization. Without a cache in the symbol path, the launcher's own
+//! cache in the data directory is used.
+
+use crate::paths::data_dir;
+use crate::trace_processor::Instance;
+use log::warn;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Modules with a build id that their stacks were sampled in
+const MAPPINGS_QUERY: &str = "select distinct name, build_id from stack_profile_mapping \
+    where build_id is not null and build_id != ''";
+
+/// A parsed symbol path
+#[derive(Default)]
+pub struct SymbolPath {
+    /// Plain directories
+    pub local_dirs: Vec<PathBuf>,
+    /// Servers and the cache each downloads into
+    pub servers: Vec<(PathBuf, String)>,
+}
+
+impl SymbolPath {
+    pub fn is_empty(&self) -> bool {
+        self.local_dirs.is_empty() && self.servers.is_empty()
+    }
+
+    /// Every directory symbols can be found in after downloading
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = self.local_dirs.clone();
+        for (cache, _) in &self.servers {
+            if !dirs.contains(cache) {
+                dirs.push(cache.clone());
+            }
+        }
+        dirs
+    }
+}
+
+pub fn cache_dir() -> PathBuf {
+    data_dir().join("symbols")
+}
+
+/// Symbol path from `_NT_SYMBOL_PATH` (Windows only) plus `servers`
+pub fn configured(servers: &[String]) -> SymbolPath {
+    let mut path = if cfg!(windows) {
+        env::var("_NT_SYMBOL_PATH").map(|text| parse(&text)).unwrap_or_default()
+    } else {
+        SymbolPath::default()
+    };
+    path.servers.extend(servers.iter().map(|url| (cache_dir(), url.clone())));
+    path
+}
+
+/// Parse `srv*C:\cache*https://server;C:\local;cache*C:\cache2`
+pub fn parse(text: &str) -> SymbolPath {
+    let mut path = SymbolPath::default();
+    for element in text.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let parts: Vec<&str> = element.split('*').collect();
+        match parts.as_slice() {
+            [kind, rest @ ..] if kind.eq_ignore_ascii_case("srv") || kind.eq_ignore_ascii_case("symsrv") => {
+                // symsrv*symsrv.dll*cache*server names the DLL first
+                let rest = if kind.eq_ignore_ascii_case("symsrv") { rest.get(1..).unwrap_or_default() } else { rest };
+                let (caches, urls): (Vec<&str>, Vec<&str>) = rest.iter().partition(|p| !p.contains("://"));
+                let cache = caches.first().filter(|c| !c.is_empty()).map(PathBuf::from).unwrap_or_else(cache_dir);
+                for url in urls {
+                    path.servers.push((cache.clone(), url.trim_end_matches('/').to_string()));
+                }
+                if !path.local_dirs.contains(&cache) {
+                    path.local_dirs.push(cache);
+                }
+            }
+            [kind, cache] if kind.eq_ignore_ascii_case("cache") => path.local_dirs.push(PathBuf::from(cache)),
+            [dir] => path.local_dirs.push(PathBuf::from(dir)),
+            _ => {}
+        }
+    }
+    path
+}
+
+/// Download what `trace` needs from the configured symbol servers and return
+/// the directories to symbolize with, in addition to `--symbol-dir`
+pub fn prepare(trace_processor: &Path, trace: Option<&Path>, servers: &[String]) -> Vec<PathBuf> {
+    let symbol_path = configured(servers);
+    if symbol_path.is_empty() {
+        return Vec::new();
+    }
+    if let Some(trace) = trace {
+        say!("{}", t!("symbol-server-checking", count = symbol_path.servers.len()));
+        match download_for_trace(trace_processor, trace, &symbol_path) {
+            Ok(0) => {}
+            Ok(count) => say!("{}", t!("symbol-server-summary", count = count)),
+            Err(e) => warn!("{}", t!("symbol-server-skipped", error = e)),
+        }
+    }
+    symbol_path.dirs()
+}
+
+/// Download the PDBs `trace` needs that are not cached yet; returns how many
+/// were downloaded
+pub fn download_for_trace(trace_processor: &Path, trace: &Path, symbol_path: &SymbolPath) -> Result<usize, String> {
+    if symbol_path.servers.is_empty() {
+        return Ok(0);
+    }
+    let instance = Instance::start(trace_processor, trace, Duration::from_secs(600))?;
+    let modules: Vec<(String, String)> = instance
+        .client
+        .query_rows(MAPPINGS_QUERY)?
+        .rows
+        .into_iter()
+        .filter_map(|row| {
+            let name = row.first()?.as_str()?;
+            let build_id = row.get(1)?.as_str()?;
+            Some((pdb_name(name)?, build_id.to_ascii_uppercase()))
+        })
+        .collect();
+    drop(instance);
+
+    let mut downloaded = 0;
+    for (pdb, key) in &modules {
+        let cached = symbol_path.servers.iter().any(|(cache, _)| cache.join(pdb).join(key).join(pdb).is_file());
+        if cached {
+            continue;
+        }
+        for (cache, server) in &symbol_path.servers {
+            match download(server, cache, pdb, key) {
+                Ok(true) => {
+                    downloaded += 1;
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("{}", t!("symbol-server-failed", server = server, pdb = pdb, error = e)),
+            }
+        }
+    }
+    Ok(downloaded)
+}
+
+/// `C:\Windows\System32\ntdll.dll` -> `ntdll.pdb`; only PE modules have PDBs
+fn pdb_name(module: &str) -> Option<String> {
+    let file = module.rsplit(['\\', '/']).next()?;
+    let (stem, ext) = file.rsplit_once('.')?;
+    let ext = ext.to_ascii_lowercase();
+    ["dll", "exe", "sys"].contains(&ext.as_str()).then(|| format!("{}.pdb", stem))
+}
+
+/// Fetch `<server>/<pdb>/<key>/<pdb>` into the cache; Ok(false) if the server
+/// does not have it
+fn download(server: &str, cache: &Path, pdb: &str, key: &str) -> Result<bool, String> {
+    let url = format!("{}/{}/{}/{}", server, pdb, key, pdb);
+    let response = match ureq::get(&url).timeout(Duration::from_secs(120)).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(false),
+        Err(e) => return Err(e.to_string()),
+    };
+    let dir = cache.join(pdb).join(key);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(pdb);
+    let partial = path.with_extension("pdb.partial");
+    let mut file = File::create(&partial).map_err(|e| e.to_string())?;
+    io::copy(&mut response.into_reader(), &mut file).map_err(|e| e.to_string())?;
+    fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+    say!("{}", t!("symbol-server-downloaded", pdb = pdb));
+    Ok(true)
+}
@@ -0,0 +1,104 @@
+//! Native symbolization from local symbol directories.
+//!
+//! trace_processor and traceconv symbolize callstacks themselves when
+//! `PERFETTO_BINARY_PATH` lists directories with unstripped binaries;
+//! `PERFETTO_SYMBOLIZER_MODE=index` makes them match binaries by build id
+//! instead of by path, which is what a directory of symbols usually needs.
+//! Both run `llvm-symbolizer` for the lookups; one next to trace_processor is
+//! put on their PATH, and without any the symbol packets come out empty.
+
+use crate::paths::find_in_path;
+use log::warn;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Environment for a trace_processor or traceconv from `tools_dir` that
+/// should symbolize with `symbol_dirs`; empty if there are none
+pub fn env(tools_dir: &Path, symbol_dirs: &[PathBuf]) -> Vec<(String, String)> {
+    if symbol_dirs.is_empty() {
+        return Vec::new();
+    }
+    let binary_path = env::join_paths(symbol_dirs).map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut vars = vec![
+        ("PERFETTO_BINARY_PATH".to_string(), binary_path),
+        ("PERFETTO_SYMBOLIZER_MODE".to_string(), "index".to_string()),
+    ];
+    if bundled(tools_dir, "llvm-symbolizer").is_some() {
+        let path = env::var_os("PATH").unwrap_or_default();
+        let dirs = std::iter::once(tools_dir.to_path_buf()).chain(env::split_paths(&path));
+        if let Ok(path) = env::join_paths(dirs) {
+            vars.push(("PATH".to_string(), path.to_string_lossy().into_owned()));
+        }
+    }
+    vars
+}
+
+/// `name` next to trace_processor, with `.exe` on Windows
+fn bundled(tools_dir: &Path, name: &str) -> Option<PathBuf> {
+    let path = tools_dir.join(if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() });
+    path.is_file().then_some(path)
+}
+
+/// traceconv next to trace_processor, or on PATH
+pub fn find_traceconv(tools_dir: &Path) -> Option<PathBuf> {
+    bundled(tools_dir, "traceconv").or_else(|| find_in_path("traceconv"))
+}
+
+/// Warn if symbolizing with `tools_dir`'s tools would find no llvm-symbolizer
+pub fn check_llvm_symbolizer(tools_dir: &Path) {
+    if bundled(tools_dir, "llvm-symbolizer").is_none() && find_in_path("llvm-symbolizer").is_none() {
+        warn!("{}", t!("symbolize-no-llvm-symbolizer"));
+    }
+}
+
+/// `symbolize` subcommand: write `input` followed by the symbol packets
+/// traceconv produces for it, so the output opens symbolized anywhere
+pub fn symbolize(tools_dir: &Path, input: &Path, output: &Path, symbol_dirs: &[PathBuf]) -> Result<(), String> {
+    if symbol_dirs.is_empty() {
+        return Err(t!("symbolize-no-dirs"));
+    }
+    for dir in symbol_dirs {
+        if !dir.is_dir() {
+            return Err(t!("symbol-dir-missing", path = dir.display()));
+        }
+    }
+    let traceconv = find_traceconv(tools_dir).ok_or_else(|| t!("symbolize-no-traceconv"))?;
+    check_llvm_symbolizer(tools_dir);
+    say!("{}", t!("symbolize-running", path = input.display()));
+    let added = augment(&traceconv, "symbolize", &env(tools_dir, symbol_dirs), input, output)?;
+    if added == 0 {
+        warn!("{}", t!("symbolize-nothing"));
+    }
+    say!("{}", t!("symbolize-written", path = output.display(), bytes = added));
+    Ok(())
+}
+
+/// Write `input` followed by the packets `traceconv <command> input` prints,
+/// returning the number of bytes added
+pub fn augment(
+    traceconv: &Path,
+    command: &str,
+    env: &[(String, String)],
+    input: &Path,
+    output: &Path,
+) -> Result<usize, String> {
+    let trace = fs::read(input).map_err(|e| t!("symbolize-read-failed", path = input.display(), error = e))?;
+    let result = Command::new(traceconv)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .arg(command)
+        .arg(input)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !result.status.success() {
+        return Err(t!("symbolize-traceconv-failed", status = result.status));
+    }
+
+    // A trace is a sequence of packets, so appending more packets is valid
+    let mut augmented = trace;
+    augmented.extend_from_slice(&result.stdout);
+    fs::write(output, augmented).map_err(|e| t!("symbolize-write-failed", path = output.display(), error = e))?;
+    Ok(result.stdout.len())
+}
@@ -0,0 +1,89 @@
+//! Startup phase timing, printable as a table or as a Chrome JSON trace that
+//! the Perfetto UI itself can open.
+
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct Timings {
+    start: Instant,
+    last_mark: Instant,
+    phases: Vec<Phase>,
+}
+
+struct Phase {
+    name: &'static str,
+    /// Offset of the phase start from launcher start
+    start: Duration,
+    duration: Duration,
+    /// Ran alongside the main sequence rather than as a step of it
+    concurrent: bool,
+}
+
+impl Timings {
+    pub fn start() -> Timings {
+        let now = Instant::now();
+        Timings { start: now, last_mark: now, phases: Vec::new() }
+    }
+
+    /// Close the phase that began at the previous mark
+    pub fn mark(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push(Phase {
+            name,
+            start: self.last_mark - self.start,
+            duration: now - self.last_mark,
+            concurrent: false,
+        });
+        self.last_mark = now;
+    }
+
+    /// Record a phase that ran on another thread from `start` to `end`
+    pub fn span(&mut self, name: &'static str, start: Instant, end: Instant) {
+        self.phases.push(Phase {
+            name,
+            start: start - self.start,
+            duration: end - start,
+            concurrent: true,
+        });
+    }
+
+    pub fn print(&self) {
+        println!("\n{}", t!("timings-title"));
+        for phase in &self.phases {
+            let marker = if phase.concurrent { " *" } else { "" };
+            println!("  {:<18} {:>7.1} ms{}", phase.name, phase.duration.as_secs_f64() * 1000.0, marker);
+        }
+        let total = self.phases.iter().map(|p| p.start + p.duration).max().unwrap_or_default();
+        println!("  {:<18} {:>7.1} ms", t!("timings-total"), total.as_secs_f64() * 1000.0);
+        if self.phases.iter().any(|p| p.concurrent) {
+            println!("  {}", t!("timings-concurrent"));
+        }
+    }
+
+    /// Write the phases as a Chrome JSON trace
+    pub fn write_trace(&self, path: &Path) -> io::Result<()> {
+        let pid = std::process::id();
+        let mut events = vec![json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": pid,
+            "args": { "name": "perfetto_launcher" },
+        })];
+        events.extend(self.phases.iter().map(|phase| {
+            json!({
+                "name": phase.name,
+                "cat": "startup",
+                "ph": "X",
+                "pid": pid,
+                // Concurrent phases get their own track
+                "tid": if phase.concurrent { pid + 1 } else { pid },
+                "ts": phase.start.as_micros() as u64,
+                "dur": phase.duration.as_micros() as u64,
+            })
+        }));
+        fs::write(path, json!({ "traceEvents": events }).to_string())
+    }
+}
@@ -0,0 +1,243 @@
+//! Prebuilt trace_processor_shell from the official Perfetto releases, for
+//! `--auto-download` and `--tp-version`, and traceconv for `convert`.
+//!
+//! Each release's `tools/trace_processor` script carries a manifest with the
+//! download URL and SHA-256 of the binary for every platform. The binary is
+//! fetched from there, checked against the manifest and kept under
+//! `trace_processor/<version>/` in the data directory, so each version is
+//! downloaded once. `tools/traceconv` works the same way, with its binaries
+//! kept under `traceconv/<version>/`.
+
+use crate::dist::{is_version_string, version_key};
+use crate::paths::data_dir;
+use crate::trace_processor::ensure_executable;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Launcher script of the latest release
+const LATEST_SCRIPT: &str = "https://get.perfetto.dev/{tool}";
+
+/// Launcher script of a given release, by git tag
+const TAGGED_SCRIPT: &str = "https://raw.githubusercontent.com/google/perfetto/{version}/tools/{tool}";
+
+const ARTIFACTS: &str = "https://commondatastorage.googleapis.com/perfetto-luci-artifacts";
+
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A binary published with a launcher script in the releases
+struct Tool {
+    /// Name of the script, which is also the cache directory
+    script: &'static str,
+    /// Variable of the script holding the manifest
+    manifest: &'static str,
+    binary: &'static str,
+}
+
+const TRACE_PROCESSOR: Tool =
+    Tool { script: "trace_processor", manifest: "TRACE_PROCESSOR_SHELL_MANIFEST", binary: "trace_processor_shell" };
+
+const TRACECONV: Tool = Tool { script: "traceconv", manifest: "TRACECONV_MANIFEST", binary: "traceconv" };
+
+/// One platform's entry of a manifest
+struct Prebuilt {
+    arch: String,
+    file_name: String,
+    url: Option<String>,
+    sha256: String,
+}
+
+/// Manifest name of this platform, as used in the release artifacts
+fn current_arch() -> Option<&'static str> {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("windows", "x86_64") => Some("windows-amd64"),
+        ("linux", "x86_64") => Some("linux-amd64"),
+        ("linux", "aarch64") => Some("linux-arm64"),
+        ("linux", "arm") => Some("linux-arm"),
+        ("macos", "x86_64") => Some("mac-amd64"),
+        ("macos", "aarch64") => Some("mac-arm64"),
+        _ => None,
+    }
+}
+
+/// Path of trace_processor_shell `version` (e.g. `v47.0`), or of the latest
+/// release if None, downloading it into the cache first if needed
+pub fn ensure(version: Option<&str>) -> Result<PathBuf, String> {
+    ensure_tool(&TRACE_PROCESSOR, version)
+}
+
+/// Path of traceconv `version`, or of the latest release, as `ensure`
+pub fn ensure_traceconv(version: Option<&str>) -> Result<PathBuf, String> {
+    ensure_tool(&TRACECONV, version)
+}
+
+fn ensure_tool(tool: &Tool, version: Option<&str>) -> Result<PathBuf, String> {
+    let platform = format!("{}-{}", env::consts::OS, env::consts::ARCH);
+    let arch = current_arch().ok_or_else(|| t!("tp-download-unsupported", tool = tool.binary, platform = platform))?;
+    if let Some(cached) = version.and_then(|v| cached(tool, v, arch)) {
+        return Ok(cached);
+    }
+
+    let script_url = match version {
+        Some(version) => TAGGED_SCRIPT.replace("{version}", version),
+        None => LATEST_SCRIPT.to_string(),
+    }
+    .replace("{tool}", tool.script);
+    let script = String::from_utf8_lossy(&get(&script_url)?).into_owned();
+    let release = version.map(String::from).or_else(|| revision(&script));
+    let no_prebuilt = || t!("tp-download-no-prebuilt", tool = tool.binary, url = script_url.as_str(), platform = arch);
+    let prebuilt = manifest(&script, tool.manifest).into_iter().find(|p| p.arch == arch).ok_or_else(no_prebuilt)?;
+    let release = release.ok_or_else(no_prebuilt)?;
+
+    let target = data_dir().join(tool.script).join(&release).join(&prebuilt.file_name);
+    if hash_file(&target).is_ok_and(|hash| hash.eq_ignore_ascii_case(&prebuilt.sha256)) {
+        return Ok(target);
+    }
+    let url = match &prebuilt.url {
+        Some(url) => url.clone(),
+        None => format!("{}/{}/{}/{}", ARTIFACTS, release, arch, prebuilt.file_name),
+    };
+    say!("{}", t!("tp-download-fetching", tool = tool.binary, version = release.as_str(), url = url.as_str()));
+    download(&url, &prebuilt.sha256, &target)?;
+    say!("{}", t!("tp-download-done", tool = tool.binary, path = target.display()));
+    Ok(target)
+}
+
+/// A cached binary of `version` whose hash was checked when it was stored
+fn cached(tool: &Tool, version: &str, arch: &str) -> Option<PathBuf> {
+    let name = if arch.starts_with("windows") { format!("{}.exe", tool.binary) } else { tool.binary.to_string() };
+    let path = data_dir().join(tool.script).join(version).join(name);
+    path.is_file().then_some(path)
+}
+
+/// The newest release in the cache, for when downloading is not possible
+pub fn newest_cached() -> Option<PathBuf> {
+    newest_cached_tool(&TRACE_PROCESSOR)
+}
+
+/// The newest traceconv in the cache
+pub fn newest_cached_traceconv() -> Option<PathBuf> {
+    newest_cached_tool(&TRACECONV)
+}
+
+fn newest_cached_tool(tool: &Tool) -> Option<PathBuf> {
+    let arch = current_arch()?;
+    let mut versions: Vec<String> = fs::read_dir(data_dir().join(tool.script))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_version_string(name))
+        .collect();
+    versions.sort_by_key(|v| version_key(v));
+    versions.iter().rev().find_map(|v| cached(tool, v, arch))
+}
+
+/// Download `url` to `target` via a `.partial` file, keeping it only if its
+/// SHA-256 is `expected`
+fn download(url: &str, expected: &str, target: &Path) -> Result<(), String> {
+    let response = match ureq::get(url).timeout(TIMEOUT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) => {
+            return Err(t!("fetch-http-error", url = url, status = status, reason = response.status_text()));
+        }
+        Err(e) => return Err(t!("fetch-failed", url = url, error = e)),
+    };
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = (|| -> io::Result<String> {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut reader = response.into_reader();
+        let mut file = File::create(&partial)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+            file.write_all(&buffer[..n])?;
+        }
+        Ok(hex(&hasher.finalize()))
+    })();
+    let actual = match result {
+        Ok(actual) => actual,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(t!("fetch-failed", url = url, error = e));
+        }
+    };
+    if !actual.eq_ignore_ascii_case(expected) {
+        let _ = fs::remove_file(&partial);
+        return Err(t!("tp-download-hash-mismatch", url = url, expected = expected, actual = actual));
+    }
+    let installed = fs::rename(&partial, target).and_then(|_| ensure_executable(target));
+    installed.map_err(|e| t!("tp-download-write-failed", path = target.display(), error = e))
+}
+
+/// Release named by the script, from its `# Revision: v47.0` line or its URLs
+fn revision(script: &str) -> Option<String> {
+    let tagged = script.lines().find_map(|line| line.trim().strip_prefix("# Revision:")).map(|r| r.trim().to_string());
+    tagged.filter(|r| !r.is_empty()).or_else(|| {
+        let start = script.find(ARTIFACTS)? + ARTIFACTS.len() + 1;
+        let end = start + script[start..].find('/')?;
+        Some(script[start..end].to_string())
+    })
+}
+
+/// Entries of e.g. `TRACE_PROCESSOR_SHELL_MANIFEST = [{...}, {...}]`
+fn manifest(script: &str, name: &str) -> Vec<Prebuilt> {
+    let Some(start) = script.find(name) else {
+        return Vec::new();
+    };
+    let body = &script[start..];
+    let body = &body[..body.find("}]").map_or(body.len(), |end| end + 1)];
+    body.split('}')
+        .filter_map(|entry| {
+            Some(Prebuilt {
+                arch: field(entry, "arch")?,
+                file_name: field(entry, "file_name")?,
+                url: field(entry, "url"),
+                sha256: field(entry, "sha256")?,
+            })
+        })
+        .collect()
+}
+
+/// String value of `'key': 'value'` in a Python dict literal
+fn field(entry: &str, key: &str) -> Option<String> {
+    let start = entry.find(&format!("'{}':", key))? + key.len() + 3;
+    let rest = entry[start..].trim_start().strip_prefix(['\'', '"'])?;
+    let end = rest.find(['\'', '"'])?;
+    Some(rest[..end].to_string())
+}
+
+fn get(url: &str) -> Result<Vec<u8>, String> {
+    let response = match ureq::get(url).timeout(TIMEOUT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) => {
+            return Err(t!("fetch-http-error", url = url, status = status, reason = response.status_text()));
+        }
+        Err(e) => return Err(t!("fetch-failed", url = url, error = e)),
+    };
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body).map_err(|e| t!("fetch-failed", url = url, error = e))?;
+    Ok(body)
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
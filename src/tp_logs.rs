@@ -0,0 +1,116 @@
+//! trace_processor's stdout and stderr, kept for `GET /launcher/tp-logs`.
+//!
+//! Both pipes are read by the backend, echoed to the console and the logs,
+//! and the latest lines kept here across restarts, so a launcher running
+//! from the tray or as a service can still show why a trace failed to load.
+//! The endpoint answers with plain text, or JSON with `?format=json` or
+//! `Accept: application/json`; `?follow=1` or `Accept: text/event-stream`
+//! streams new lines as Server-Sent Events after the kept ones.
+
+use crate::logs::line_timestamp;
+use crate::server::{query_param, AppState};
+use crate::shutdown;
+use crate::sse;
+use crate::stats;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Condvar, Mutex};
+use tiny_http::{Header, Request, Response};
+
+/// Lines kept for the endpoint
+const KEPT_LINES: usize = 1000;
+
+/// Lines returned unless `?lines=` asks for another number
+const DEFAULT_LINES: usize = 200;
+
+#[derive(Clone, Serialize)]
+pub struct Line {
+    /// Numbers lines across restarts, so followers see each one once
+    pub seq: u64,
+    pub time: String,
+    /// "stdout", "stderr", or "launcher" for the launcher's own notes such
+    /// as each start of trace_processor
+    pub stream: &'static str,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct Kept {
+    lines: VecDeque<Line>,
+    next_seq: u64,
+}
+
+#[derive(Default)]
+pub struct ChildOutput {
+    kept: Mutex<Kept>,
+    added: Condvar,
+}
+
+impl ChildOutput {
+    pub fn push(&self, stream: &'static str, text: String) {
+        let mut kept = self.kept.lock().unwrap();
+        if kept.lines.len() == KEPT_LINES {
+            kept.lines.pop_front();
+        }
+        let seq = kept.next_seq;
+        kept.next_seq += 1;
+        kept.lines.push_back(Line { seq, time: line_timestamp(), stream, text });
+        self.added.notify_all();
+    }
+
+    /// The last `count` lines
+    pub fn tail(&self, count: usize) -> Vec<Line> {
+        let kept = self.kept.lock().unwrap();
+        kept.lines.iter().skip(kept.lines.len().saturating_sub(count)).cloned().collect()
+    }
+
+    /// Send `lines` and then every new line as `line` events until the
+    /// client goes away or the launcher shuts down
+    fn stream(&self, writer: &mut dyn Write, lines: Vec<Line>) -> io::Result<()> {
+        let mut next = lines.last().map_or_else(|| self.kept.lock().unwrap().next_seq, |line| line.seq + 1);
+        let mut pending = lines;
+        while !shutdown::requested() {
+            let mut message = String::new();
+            for line in pending.drain(..) {
+                message.push_str(&format!("event: line\ndata: {}\n\n", json!(line)));
+            }
+            if message.is_empty() {
+                message.push_str(": keep-alive\n\n");
+            }
+            writer.write_all(message.as_bytes())?;
+            writer.flush()?;
+            let kept = self.kept.lock().unwrap();
+            let (kept, _) = self.added.wait_timeout_while(kept, sse::KEEP_ALIVE, |kept| kept.next_seq == next).unwrap();
+            pending = kept.lines.iter().filter(|line| line.seq >= next).cloned().collect();
+            next = kept.next_seq;
+        }
+        Ok(())
+    }
+}
+
+/// `GET /launcher/tp-logs[?lines=N][&format=json][&follow=1]`
+pub fn respond(request: Request, state: &AppState) {
+    let url = request.url();
+    let count = query_param(url, "lines").and_then(|n| n.parse().ok()).unwrap_or(DEFAULT_LINES).min(KEPT_LINES);
+    let accepts = |kind: &str| {
+        request.headers().iter().any(|h| h.field.equiv("Accept") && h.value.as_str().trim_start().starts_with(kind))
+    };
+    let follow = query_param(url, "follow").is_some() || accepts("text/event-stream");
+    let wants_json = query_param(url, "format").is_some_and(|f| f == "json") || accepts("application/json");
+    let output = state.backend.output().clone();
+    let lines = output.tail(count);
+    if follow {
+        return sse::stream(request, move |writer| output.stream(writer, lines));
+    }
+    if wants_json {
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let body = json!({ "lines": lines }).to_string();
+        stats::respond(request, Response::from_string(body).with_header(content_type));
+        return;
+    }
+    let text: String = lines.iter().map(|line| format!("{} [{}] {}\n", line.time, line.stream, line.text)).collect();
+    let content_type = Header::from_bytes("Content-Type", "text/plain; charset=utf-8").unwrap();
+    stats::respond(request, Response::from_string(text).with_header(content_type));
+}
@@ -0,0 +1,74 @@
+//! Local trace directories served read-only at `/trace/<name>/`, registered
+//! with `--trace-dir [NAME=]DIR`.
+//!
+//! The directories are static roots like `--mount` ones, so the same path
+//! checks, Range support and caching apply to the files in them; this module
+//! only answers for the directories themselves, with a JSON listing. Every
+//! file in a listing comes with a UI link that opens it by URL, so the UI
+//! loads it straight from its directory instead of a copy. `GET /trace/`
+//! lists the registered names.
+
+use crate::http_errors;
+use crate::roots::percent_decode;
+use crate::server::{origin, AppState};
+use crate::traces_api::{encode_component, respond_json};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tiny_http::Request;
+
+/// `GET /trace/`
+pub fn respond_index(request: Request, state: &AppState) {
+    let origin = origin(&request);
+    let dirs: Vec<Value> = state
+        .trace_dirs
+        .iter()
+        .map(|name| json!({ "name": name, "url": format!("{}/trace/{}/", origin, encode_component(name)) }))
+        .collect();
+    respond_json(request, 200, json!({ "dirs": dirs }));
+}
+
+/// `GET /trace/<name>/[<subdir>/]`, with `dir` the directory it resolved to
+///
+/// Subdirectories come first, then files, each by name; hidden entries are
+/// left out.
+pub fn respond_listing(request: Request, url_path: &str, dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return http_errors::forbidden(request),
+    };
+    let origin = origin(&request);
+    // The URL path as requested, already percent-encoded, with one trailing slash
+    let base = format!("{}/{}/", origin, url_path.trim_end_matches('/'));
+    let mut listed: Vec<(bool, String, Value)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if name.starts_with('.') {
+                return None;
+            }
+            let metadata = entry.path().metadata().ok()?;
+            let url = format!("{}{}", base, encode_component(&name));
+            let value = if metadata.is_dir() {
+                json!({ "name": name, "dir": true, "url": format!("{}/", url) })
+            } else {
+                let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+                json!({
+                    "name": name,
+                    "size": metadata.len(),
+                    "modified": modified.map(|d| d.as_secs()),
+                    "ui": format!("{}/#!/?url={}", origin, encode_component(&url)),
+                    "url": url,
+                })
+            };
+            Some((!metadata.is_dir(), name, value))
+        })
+        .collect();
+    listed.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    let decoded = percent_decode(url_path.trim_end_matches('/')).unwrap_or_default();
+    let mut parts = decoded.splitn(3, '/').skip(1);
+    let (name, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let entries: Vec<Value> = listed.into_iter().map(|(_, _, value)| value).collect();
+    respond_json(request, 200, json!({ "name": name, "path": path, "entries": entries }));
+}
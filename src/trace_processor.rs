@@ -0,0 +1,176 @@
+use crate::config::Config;
+use crate::metrics;
+use crate::paths::find_in_path;
+use crate::ports::get_available_port;
+use crate::rpc::RpcClient;
+use crate::sql_modules;
+use crate::symbols;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// File name of trace_processor_shell on Windows or elsewhere
+pub fn binary_name(windows: bool) -> &'static str {
+    if windows {
+        "trace_processor_shell.exe"
+    } else {
+        "trace_processor_shell"
+    }
+}
+
+/// Location of trace_processor_shell: in the dist directory, or on `PATH` if
+/// the dist directory has none
+///
+/// When neither has it, the dist directory location is returned so errors
+/// point at where it is expected.
+pub fn trace_processor_path(dist_dir: &Path) -> PathBuf {
+    let bundled = dist_dir.join(binary_name(cfg!(windows)));
+    if bundled.is_file() {
+        return bundled;
+    }
+    find_in_path("trace_processor_shell").unwrap_or(bundled)
+}
+
+/// Add the executable bits to a trace_processor_shell that lost them, as
+/// files extracted from zips or copied from Windows shares often do
+#[cfg(unix)]
+pub fn ensure_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    if mode & 0o111 == 0 {
+        // Executable for whoever may read it
+        permissions.set_mode(mode | (mode & 0o444) >> 2);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn ensure_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Command line for trace_processor_shell serving RPC on `rpc_port`
+///
+/// The UI reaches it through `rpc_proxy` on its own origin, so only the CORS
+/// origins asked for with `--tp-cors-origin` are added.
+pub fn trace_processor_args(rpc_port: u16, config: &Config) -> Vec<String> {
+    let mut args = vec![
+        "-D".to_string(),
+        "--http-ip-address".to_string(),
+        "127.0.0.1".to_string(),
+        "--http-port".to_string(),
+        rpc_port.to_string(),
+    ];
+    args.extend(shared_args(config));
+    if let Some(trace) = &config.trace {
+        args.push(trace.display().to_string());
+    }
+    args
+}
+
+/// Arguments the session's trace_processor and those of extra sessions
+/// share: SQL modules, metric extensions, the launcher's own flags, the
+/// preset and `--tp-arg`s
+pub fn shared_args(config: &Config) -> Vec<String> {
+    let mut args = sql_modules::args(&config.sql_module_dirs);
+    args.extend(metrics::args(&config.metric_extension_dirs));
+    if config.full_sort {
+        args.push("--full-sort".to_string());
+    }
+    if !config.cors_origins.is_empty() {
+        args.push("--http-additional-cors-origins".to_string());
+        args.push(config.cors_origins.join(","));
+    }
+    args.extend(config.tp_preset_args.iter().cloned());
+    args.extend(config.tp_extra_args.iter().cloned());
+    args
+}
+
+/// Environment variables for the session's trace_processor
+pub fn trace_processor_env(config: &Config) -> Vec<(String, String)> {
+    symbols::env(&config.dist_dir, &config.symbol_dirs)
+}
+
+/// A private trace_processor_shell with one trace loaded, for running queries
+/// outside the UI session; killed when dropped
+pub struct Instance {
+    child: Child,
+    pub client: RpcClient,
+}
+
+impl Instance {
+    /// Start trace_processor on a free port and wait until `trace` is loaded
+    pub fn start(path: &Path, trace: &Path, timeout: Duration) -> Result<Instance, String> {
+        Instance::start_with(path, &[], trace, timeout)
+    }
+
+    /// Like `start`, with extra flags such as `shared_args`
+    pub fn start_with(path: &Path, args: &[String], trace: &Path, timeout: Duration) -> Result<Instance, String> {
+        let port = get_available_port();
+        let _ = ensure_executable(path);
+        let child = Command::new(path)
+            .args(["-D", "--http-port", &port.to_string()])
+            .args(args)
+            .arg(trace)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| t!("instance-spawn-failed", error = e))?;
+        let mut instance = Instance { child, client: RpcClient::new(port) };
+
+        // trace_processor parses the whole trace before it starts serving RPC
+        let ready = instance.client.wait_ready(timeout);
+        if let Ok(Some(status)) = instance.child.try_wait() {
+            return Err(t!("instance-exited", status = status));
+        }
+        ready.map_err(|e| t!("instance-not-ready", error = e))?;
+        Ok(instance)
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Version information reported by `trace_processor_shell --version`
+pub struct TpVersion {
+    /// e.g. "v46.0-8a7e2f1c"
+    pub version: String,
+    /// "Trace Processor RPC API version", if the binary reports one
+    pub api_version: Option<u32>,
+}
+
+/// Run `trace_processor_shell --version` and parse its output
+pub fn query_version(path: &Path) -> Result<TpVersion, String> {
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", path.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("{} --version exited with {}", path.display(), output.status));
+    }
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "unrecognized --version output".to_string())
+}
+
+/// Parse output such as:
+///   Perfetto v46.0-8a7e2f1c (8a7e2f1c...)
+///   Trace Processor RPC API version: 11
+fn parse_version(text: &str) -> Option<TpVersion> {
+    let version = text
+        .split_whitespace()
+        .find(|w| w.starts_with('v') && w[1..].starts_with(|c: char| c.is_ascii_digit()))?
+        .to_string();
+    let api_version = text
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Trace Processor RPC API version:"))
+        .and_then(|v| v.trim().parse().ok());
+    Some(TpVersion { version, api_version })
+}
@@ -0,0 +1,251 @@
+//! Trace management REST API under `/api/traces`, so the catalog can be
+//! scripted from any HTTP client:
+//!
+//! - `GET /api/traces?q=&tag=&pinned=&offset=&limit=` lists traces
+//! - `POST /api/traces?name=<file name>` uploads a trace (raw body)
+//! - `GET`, `PATCH` (`{"name", "tags", "pinned"}`) and `DELETE /api/traces/<id>`
+//! - `GET /api/traces/<id>/download` returns the trace file
+//! - `POST /api/traces/<id>/open` returns the session and a deep link
+
+use crate::catalog::Trace;
+use crate::http_errors;
+use crate::ingest;
+use crate::landing;
+use crate::server::{
+    origin, query_param, respond_too_large, serve_file_with_headers, AppState, LimitedReader,
+};
+use crate::stats;
+use crate::users::Viewer;
+use crate::workspace;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{self, Read};
+use tiny_http::{Header, Method, Request, Response};
+
+/// Default and maximum number of traces per page
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 1000;
+
+/// Body of `PATCH /api/traces/<id>`; absent fields are left unchanged
+#[derive(Deserialize)]
+struct Changes {
+    name: Option<String>,
+    tags: Option<Vec<String>>,
+    pinned: Option<bool>,
+}
+
+fn trace_json(trace: &Trace) -> Value {
+    json!({
+        "id": trace.id,
+        "name": trace.name,
+        "path": trace.path,
+        "size": trace.size,
+        "added": trace.added,
+        "pinned": trace.pinned,
+        "tags": trace.tags,
+        "owner": trace.owner,
+        "ingested": ingest::is_done(trace),
+        "links": {
+            "self": format!("/api/traces/{}", trace.id),
+            "download": format!("/api/traces/{}/download", trace.id),
+            "metadata": format!("/api/traces/{}/metadata", trace.id),
+            "open": format!("/t/{}", trace.id),
+        },
+    })
+}
+
+pub fn respond_json(request: Request, status: u16, body: Value) {
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+    let response = Response::from_string(body.to_string()).with_status_code(status).with_header(content_type);
+    stats::respond(request, response);
+}
+
+pub fn respond_error(request: Request, status: u16, message: String) {
+    respond_json(request, status, json!({ "error": message }));
+}
+
+fn respond_unknown(request: Request, id: &str) {
+    respond_error(request, 404, t!("catalog-unknown-id", id = id));
+}
+
+fn respond_method_not_allowed(request: Request, allow: &str) {
+    http_errors::method_not_allowed(request, allow);
+}
+
+/// `/api/traces`
+pub fn respond_collection(mut request: Request, state: &AppState, viewer: &Viewer) {
+    match request.method() {
+        Method::Get | Method::Head => respond_list(request, state, viewer),
+        Method::Post => match landing::store_upload(&mut request, state, viewer) {
+            Ok(trace) => respond_json(request, 201, trace_json(&trace)),
+            Err(e) if e.kind() == io::ErrorKind::FileTooLarge => respond_too_large(request, state.body_limits.upload),
+            Err(e) if e.kind() == io::ErrorKind::QuotaExceeded => respond_error(request, 413, e.to_string()),
+            Err(e) => respond_error(request, 500, t!("landing-upload-error", error = e)),
+        },
+        _ => respond_method_not_allowed(request, "GET, HEAD, POST"),
+    }
+}
+
+/// Pinned traces first, then the newest; `tag` may list several
+/// comma-separated tags, all of which must be present. Only traces the
+/// viewer may see are listed.
+fn respond_list(request: Request, state: &AppState, viewer: &Viewer) {
+    let url = request.url();
+    let query = query_param(url, "q").map(|q| q.to_lowercase());
+    let tags: Vec<String> = query_param(url, "tag")
+        .map(|tags| tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect())
+        .unwrap_or_default();
+    let pinned = query_param(url, "pinned").and_then(|p| p.parse::<bool>().ok());
+    let offset = query_param(url, "offset").and_then(|o| o.parse().ok()).unwrap_or(0);
+    let limit = query_param(url, "limit").and_then(|l| l.parse().ok()).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let mut traces: Vec<Trace> = state
+        .catalog
+        .lock()
+        .unwrap()
+        .traces()
+        .iter()
+        .filter(|t| viewer.can_see(t))
+        .filter(|t| query.as_ref().is_none_or(|q| t.name.to_lowercase().contains(q)))
+        .filter(|t| tags.iter().all(|tag| t.tags.contains(tag)))
+        .filter(|t| pinned.is_none_or(|p| t.pinned == p))
+        .cloned()
+        .collect();
+    traces.sort_by_key(|trace| (Reverse(trace.pinned), Reverse(trace.added)));
+    let page: Vec<Value> = traces.iter().skip(offset).take(limit).map(trace_json).collect();
+    respond_json(
+        request,
+        200,
+        json!({ "total": traces.len(), "offset": offset, "limit": limit, "traces": page }),
+    );
+}
+
+/// `/api/traces/<id>`
+pub fn respond_trace(request: Request, state: &AppState, id: &str) {
+    match request.method() {
+        Method::Get | Method::Head => match state.catalog.lock().unwrap().get(id).cloned() {
+            Some(trace) => respond_json(request, 200, trace_json(&trace)),
+            None => respond_unknown(request, id),
+        },
+        Method::Patch => respond_update(request, state, id),
+        Method::Delete => respond_delete(request, state, id),
+        _ => respond_method_not_allowed(request, "GET, HEAD, PATCH, DELETE"),
+    }
+}
+
+fn respond_update(mut request: Request, state: &AppState, id: &str) {
+    let limit = state.body_limits.api;
+    let mut body = Vec::new();
+    if LimitedReader::new(request.as_reader(), limit).read_to_end(&mut body).is_err() {
+        return respond_too_large(request, limit);
+    }
+    let changes: Changes = match serde_json::from_slice(&body) {
+        Ok(changes) => changes,
+        Err(e) => return respond_error(request, 400, t!("api-bad-body", error = e)),
+    };
+    let name = changes.name.map(|name| name.trim().to_string());
+    if name.as_ref().is_some_and(|name| name.is_empty()) {
+        return respond_error(request, 400, t!("api-empty-name"));
+    }
+    let tags = changes.tags.map(|tags| {
+        let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    });
+
+    let result = state.catalog.lock().unwrap().update(id, |trace| {
+        if let Some(name) = name {
+            trace.name = name;
+        }
+        if let Some(tags) = tags {
+            trace.tags = tags;
+        }
+        if let Some(pinned) = changes.pinned {
+            trace.pinned = pinned;
+        }
+    });
+    match result {
+        Ok(Some(trace)) => respond_json(request, 200, trace_json(&trace)),
+        Ok(None) => respond_unknown(request, id),
+        Err(e) => respond_error(request, 500, e.to_string()),
+    }
+}
+
+/// Unregister the trace and drop its index and metadata; the file itself is
+/// deleted only if the launcher created it (uploads and captures) and
+/// `?keep_file=true` was not given
+fn respond_delete(request: Request, state: &AppState, id: &str) {
+    let result = state.catalog.lock().unwrap().remove(id);
+    let trace = match result {
+        Ok(Some(trace)) => trace,
+        Ok(None) => return respond_unknown(request, id),
+        Err(e) => return respond_error(request, 500, e.to_string()),
+    };
+    ingest::forget(&trace.id);
+    let keep_file = query_param(request.url(), "keep_file").is_some_and(|k| k == "true");
+    let file_deleted = workspace::owns(&trace.path) && !keep_file && fs::remove_file(&trace.path).is_ok();
+    respond_json(request, 200, json!({ "id": trace.id, "file_deleted": file_deleted }));
+}
+
+/// `GET /api/traces/<id>/download`
+pub fn respond_download(request: Request, state: &AppState, id: &str) {
+    let trace = state.catalog.lock().unwrap().get(id).cloned();
+    let Some(trace) = trace else {
+        return respond_unknown(request, id);
+    };
+    let file_name = trace.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or(trace.name);
+    let disposition = format!("attachment; filename=\"{}\"", file_name.replace(['"', '\\'], "_"));
+    let headers = Header::from_bytes("Content-Disposition", disposition).into_iter().collect();
+    serve_file_with_headers(request, &trace.path, headers);
+}
+
+/// `POST /api/traces/<id>/open[?<ui-state>]`: the session serving the trace
+/// and links that open it in the UI, with the query string passed on as UI
+/// state like permalinks
+pub fn respond_open(request: Request, state: &AppState, id: &str) {
+    if *request.method() != Method::Post {
+        return respond_method_not_allowed(request, "POST");
+    }
+    if state.catalog.lock().unwrap().get(id).is_none() {
+        return respond_unknown(request, id);
+    }
+    let origin = origin(&request);
+    let ui_state = request.url().split_once('?').map(|(_, q)| q.to_string()).filter(|q| !q.is_empty());
+    let trace_url = format!("{}/launcher/traces/{}", origin, id);
+    let mut link = format!("{}/t/{}", origin, id);
+    let mut ui_url = format!("{}/#!/?url={}", origin, encode_component(&trace_url));
+    if let Some(ui_state) = &ui_state {
+        link.push('#');
+        link.push_str(ui_state);
+        ui_url.push('&');
+        ui_url.push_str(ui_state);
+    }
+    let body = json!({
+        "id": id,
+        "session": {
+            "ui": format!("{}/", origin),
+            "rpc_port": state.rpc_port,
+            "pid": state.backend.pid(),
+            "alive": state.backend.try_wait().is_none(),
+        },
+        "link": link,
+        "ui_url": ui_url,
+    });
+    respond_json(request, 200, body);
+}
+
+/// Percent-encode everything but unreserved characters, like
+/// `encodeURIComponent`
+pub fn encode_component(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
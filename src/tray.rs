@@ -0,0 +1,139 @@
+//! System tray icon, in builds with `--features tray`.
+//!
+//! While the launcher runs, the icon's menu opens the UI, restarts
+//! trace_processor, copies the UI's address and quits through the normal
+//! shutdown path, so trace_processor is stopped too. `--no-tray` leaves the
+//! icon out, and where there is no tray the launcher carries on without one.
+
+use crate::backend::Backend;
+use crate::browser;
+use crate::shutdown;
+use log::warn;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIconBuilder};
+
+/// Width and height of the generated icon
+const ICON_SIZE: u32 = 32;
+
+/// How often menu clicks and shutdown are checked for
+const POLL: Duration = Duration::from_millis(50);
+
+/// Show the icon and act on its menu until shutdown; call on the main thread,
+/// which on Windows has to pump the icon's window messages
+pub fn run(url: &str, browser: Option<&str>, backend: &Arc<Backend>) {
+    if cfg!(target_os = "macos") {
+        // The status bar needs an AppKit event loop the launcher does not run
+        warn!("{}", t!("tray-unsupported"));
+        return;
+    }
+    let open = MenuItem::new(t!("tray-open-ui"), true, None);
+    let restart = MenuItem::new(t!("tray-restart-tp"), true, None);
+    let copy = MenuItem::new(t!("tray-copy-url"), true, None);
+    let quit = MenuItem::new(t!("tray-quit"), true, None);
+    let menu = Menu::new();
+    let tray = menu
+        .append_items(&[&open, &restart, &copy, &PredefinedMenuItem::separator(), &quit])
+        .map_err(|e| e.to_string())
+        .and_then(|_| icon())
+        .and_then(|icon| {
+            TrayIconBuilder::new()
+                .with_menu(Box::new(menu))
+                .with_tooltip(t!("tray-tooltip", url = url))
+                .with_icon(icon)
+                .build()
+                .map_err(|e| e.to_string())
+        });
+    let _tray = match tray {
+        Ok(tray) => tray,
+        Err(e) => {
+            warn!("{}", t!("tray-failed", error = e));
+            return;
+        }
+    };
+
+    // Kept for as long as the launcher runs: on X11 the copied text is only
+    // available while its owner is alive
+    let mut clipboard = None;
+    while !shutdown::requested() {
+        pump_messages();
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            if event.id == *open.id() {
+                if let Err(e) = browser::open(url, browser) {
+                    warn!("{}", t!("browser-failed", error = e));
+                }
+            } else if event.id == *restart.id() {
+                match backend.restart() {
+                    Ok(_) => say!("{}", t!("tp-restarted", pid = backend.pid())),
+                    Err(e) => warn!("{}", t!("tp-spawn-failed", error = e)),
+                }
+            } else if event.id == *copy.id() {
+                let copied = clipboard.take().map_or_else(arboard::Clipboard::new, Ok).and_then(|mut owner| {
+                    let copied = owner.set_text(url);
+                    clipboard = Some(owner);
+                    copied
+                });
+                if let Err(e) = copied {
+                    warn!("{}", t!("tray-copy-failed", error = e));
+                }
+            } else if event.id == *quit.id() {
+                shutdown::request();
+            }
+        }
+        thread::sleep(POLL);
+    }
+}
+
+/// A blue disc with a white ring, drawn rather than shipped as a file
+fn icon() -> Result<Icon, String> {
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = (x as f32 - center).hypot(y as f32 - center) / center;
+            let pixel = match distance {
+                d if d > 1.0 => [0, 0, 0, 0],
+                d if (0.45..0.65).contains(&d) => [255, 255, 255, 255],
+                _ => [0x3d, 0x5a, 0xfe, 255],
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).map_err(|e| e.to_string())
+}
+
+#[cfg(windows)]
+fn pump_messages() {
+    #[repr(C)]
+    struct Msg {
+        hwnd: isize,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+        time: u32,
+        pt: [i32; 2],
+        private: u32,
+    }
+
+    const PM_REMOVE: u32 = 1;
+
+    extern "system" {
+        fn PeekMessageW(msg: *mut Msg, hwnd: isize, min: u32, max: u32, remove: u32) -> i32;
+        fn TranslateMessage(msg: *const Msg) -> i32;
+        fn DispatchMessageW(msg: *const Msg) -> isize;
+    }
+
+    let mut msg = Msg { hwnd: 0, message: 0, wparam: 0, lparam: 0, time: 0, pt: [0; 2], private: 0 };
+    unsafe {
+        while PeekMessageW(&mut msg, 0, 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+/// Elsewhere the icon is served over D-Bus from a thread of its own
+#[cfg(not(windows))]
+fn pump_messages() {}
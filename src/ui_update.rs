@@ -0,0 +1,339 @@
+//! `update-ui`: bring the Perfetto UI in the dist directory up to date,
+//! downloading only the files that changed.
+//!
+//! Every official UI build publishes `<version>/manifest.json`, mapping each
+//! asset to its `sha256-<base64>` hash. Assets whose hash matches a file
+//! already in a local version directory are linked from there; only the rest
+//! is downloaded. The new version is assembled in a staging directory and
+//! renamed into place before `index.html` is switched over, so the dist
+//! directory serves a complete UI at every moment.
+//!
+//! The version that was replaced stays next to the new one, with its
+//! `index.html` and service worker kept as `*.previous`, until the update
+//! after next; `update-ui --rollback` switches back to it.
+
+use crate::dist::{channel_versions, is_version_string, ui_version, version_key};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const DEFAULT_SOURCE: &str = "https://ui.perfetto.dev";
+
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Attribute of the root index.html naming each channel's version
+const VERSION_ATTRIBUTE: &str = "data-perfetto_version='";
+
+/// Files at the top of the dist directory that belong to one UI version
+const ROOT_FILES: [&str; 2] = ["index.html", "service_worker.js"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Channel {
+    Stable,
+    Canary,
+    Autopush,
+}
+
+impl Channel {
+    fn key(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Canary => "canary",
+            Channel::Autopush => "autopush",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    /// Path within the version directory to `sha256-<base64>`
+    resources: BTreeMap<String, String>,
+}
+
+/// Update `dist_dir` to `version`, or else the latest `channel` release,
+/// from `source`; with `check`, only say whether that is a change
+pub fn run(dist_dir: &Path, source: &str, channel: Channel, version: Option<&str>, check: bool) -> Result<(), String> {
+    let source = source.trim_end_matches('/');
+    let index = fetch_index(source)?;
+    let version = match version {
+        Some(version) if is_version_string(version) => version.to_string(),
+        Some(version) => return Err(t!("ui-update-bad-version", version = version)),
+        None => channel_version(&index, channel).ok_or_else(|| t!("ui-update-no-version", channel = channel.key()))?,
+    };
+    let current = ui_version(dist_dir);
+    say!("{}", t!("ui-update-versions", current = current.as_deref().unwrap_or("-"), latest = version.as_str()));
+    if check {
+        match current.as_deref() == Some(version.as_str()) {
+            true => say!("{}", t!("ui-update-up-to-date", version = version.as_str())),
+            false => say!("{}", t!("ui-update-available", version = version.as_str())),
+        }
+        return Ok(());
+    }
+
+    let (manifest, manifest_bytes) = fetch_manifest(source, &version)?;
+    let installed = dist_dir.join(&version);
+    let up_to_date = current.as_deref() == Some(version.as_str())
+        && manifest.resources.iter().all(|(path, hash)| hash_file(&installed.join(path)).is_ok_and(|h| h == *hash));
+    if up_to_date {
+        say!("{}", t!("ui-update-up-to-date", version = version.as_str()));
+        return Ok(());
+    }
+
+    let staging = dist_dir.join(format!(".{}.partial", version));
+    stage(source, &version, &manifest, &manifest_bytes, dist_dir, &staging)?;
+    swap_in(&staging, &installed)?;
+    if current.as_deref().is_some_and(|current| current != version) {
+        keep_root_files(dist_dir);
+    }
+    install_root_files(dist_dir, source, &index, &version)?;
+    remove_old_versions(dist_dir, &version, current.as_deref());
+    say!("{}", t!("ui-update-done", version = version.as_str()));
+    Ok(())
+}
+
+/// Switch back to the version the last update replaced
+pub fn rollback(dist_dir: &Path) -> Result<(), String> {
+    let previous_index = dist_dir.join("index.html.previous");
+    let previous = fs::read_to_string(&previous_index)
+        .ok()
+        .and_then(|index| channel_versions(&index)?.remove("stable"))
+        .filter(|version| is_version_string(version) && dist_dir.join(version).is_dir())
+        .ok_or_else(|| t!("ui-update-no-previous"))?;
+    let current = ui_version(dist_dir);
+    for name in ROOT_FILES {
+        let kept = dist_dir.join(format!("{}.previous", name));
+        if kept.is_file() {
+            fs::rename(&kept, dist_dir.join(name)).map_err(|e| e.to_string())?;
+        }
+    }
+    if let Some(current) = current.as_deref().filter(|current| *current != previous) {
+        let _ = fs::remove_dir_all(dist_dir.join(current));
+    }
+    say!("{}", t!("ui-update-rolled-back", from = current.as_deref().unwrap_or("-"), to = previous.as_str()));
+    Ok(())
+}
+
+/// Keep the root files of the version being replaced for `rollback`
+fn keep_root_files(dist_dir: &Path) {
+    for name in ROOT_FILES {
+        let kept = dist_dir.join(format!("{}.previous", name));
+        let _ = fs::remove_file(&kept);
+        if dist_dir.join(name).is_file() {
+            let _ = fs::copy(dist_dir.join(name), kept);
+        }
+    }
+}
+
+/// Download UI `version` from `source` into the empty directory `target`,
+/// laid out like a dist directory; files matching ones in `local_dist` are
+/// copied from there
+pub fn download(source: &str, version: &str, local_dist: &Path, target: &Path) -> Result<(), String> {
+    let source = source.trim_end_matches('/');
+    let index = fetch_index(source)?;
+    let (manifest, manifest_bytes) = fetch_manifest(source, version)?;
+    stage(source, version, &manifest, &manifest_bytes, local_dist, &target.join(version))?;
+    install_root_files(target, source, &index, version)
+}
+
+fn fetch_index(source: &str) -> Result<String, String> {
+    String::from_utf8(get(&format!("{}/index.html", source))?).map_err(|e| t!("ui-update-bad-index", error = e))
+}
+
+fn fetch_manifest(source: &str, version: &str) -> Result<(Manifest, Vec<u8>), String> {
+    let bytes = get(&format!("{}/{}/manifest.json", source, version))?;
+    let manifest: Manifest = serde_json::from_slice(&bytes).map_err(|e| t!("ui-update-bad-manifest", error = e))?;
+    match manifest.resources.keys().find(|path| !is_safe_relative(path)) {
+        Some(path) => Err(t!("ui-update-bad-manifest", error = path)),
+        None => Ok((manifest, bytes)),
+    }
+}
+
+/// Fill `staging` with the version's assets, removing it again on failure
+fn stage(
+    source: &str,
+    version: &str,
+    manifest: &Manifest,
+    manifest_bytes: &[u8],
+    local_dist: &Path,
+    staging: &Path,
+) -> Result<(), String> {
+    // Local copies to reuse: the same version first, then the others newest first
+    let mut local_dirs: Vec<String> = version_dirs(local_dist);
+    local_dirs.sort_by_key(|v| Reverse((v == version, version_key(v))));
+    let reusable = |relative: &str, hash: &str| {
+        local_dirs.iter().map(|v| local_dist.join(v).join(relative)).find(|p| hash_file(p).is_ok_and(|h| h == hash))
+    };
+
+    let _ = fs::remove_dir_all(staging);
+    let (reused, downloaded, bytes) = match fill(source, version, manifest, manifest_bytes, staging, reusable) {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = fs::remove_dir_all(staging);
+            return Err(e);
+        }
+    };
+    let mb = format!("{:.1}", bytes as f64 / (1024.0 * 1024.0));
+    say!("{}", t!("ui-update-transferred", reused = reused, downloaded = downloaded, mb = mb));
+    Ok(())
+}
+
+/// Returns how many files were reused and downloaded, and the bytes downloaded
+fn fill(
+    source: &str,
+    version: &str,
+    manifest: &Manifest,
+    manifest_bytes: &[u8],
+    staging: &Path,
+    reusable: impl Fn(&str, &str) -> Option<PathBuf>,
+) -> Result<(usize, usize, u64), String> {
+    let (mut reused, mut downloaded, mut bytes) = (0, 0, 0);
+    for (relative, hash) in &manifest.resources {
+        let target = staging.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        if let Some(local) = reusable(relative, hash) {
+            fs::hard_link(&local, &target)
+                .or_else(|_| fs::copy(&local, &target).map(|_| ()))
+                .map_err(|e| e.to_string())?;
+            reused += 1;
+            continue;
+        }
+        let body = get(&format!("{}/{}/{}", source, version, relative))?;
+        let actual = hash_bytes(&body);
+        if actual != *hash {
+            return Err(t!("ui-update-hash-mismatch", path = relative, expected = hash, actual = actual));
+        }
+        fs::write(&target, &body).map_err(|e| e.to_string())?;
+        say!("  {}", t!("ui-update-downloaded", path = relative));
+        downloaded += 1;
+        bytes += body.len() as u64;
+    }
+    fs::write(staging.join("manifest.json"), manifest_bytes).map_err(|e| e.to_string())?;
+    Ok((reused, downloaded, bytes))
+}
+
+/// Replace `installed` with `staging` by renames only
+fn swap_in(staging: &Path, installed: &Path) -> Result<(), String> {
+    let name = installed.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let old = installed.with_file_name(format!(".{}.old", name));
+    let _ = fs::remove_dir_all(&old);
+    if installed.exists() {
+        fs::rename(installed, &old).map_err(|e| e.to_string())?;
+    }
+    if let Err(e) = fs::rename(staging, installed) {
+        let _ = fs::rename(&old, installed);
+        return Err(e.to_string());
+    }
+    let _ = fs::remove_dir_all(&old);
+    Ok(())
+}
+
+/// Write the new index.html, pinned to `version` whatever channel the UI
+/// would pick, and the service worker next to it
+fn install_root_files(dist_dir: &Path, source: &str, index: &str, version: &str) -> Result<(), String> {
+    let pinned = serde_json::json!({ "stable": version }).to_string();
+    let index = match index.find(VERSION_ATTRIBUTE) {
+        Some(start) => {
+            let value_start = start + VERSION_ATTRIBUTE.len();
+            let value_end = index[value_start..].find('\'').map_or(index.len(), |end| value_start + end);
+            format!("{}{}{}", &index[..value_start], pinned, &index[value_end..])
+        }
+        None => index.to_string(),
+    };
+    write_atomically(&dist_dir.join("index.html"), index.as_bytes())?;
+    match get(&format!("{}/service_worker.js", source)) {
+        Ok(body) => write_atomically(&dist_dir.join("service_worker.js"), &body),
+        // Older builds have no service worker
+        Err(_) => Ok(()),
+    }
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    fs::write(&partial, contents).map_err(|e| e.to_string())?;
+    fs::rename(&partial, path).map_err(|e| e.to_string())
+}
+
+/// Delete version directories other than the new one and the one it replaced
+fn remove_old_versions(dist_dir: &Path, version: &str, previous: Option<&str>) {
+    for old in version_dirs(dist_dir) {
+        if old != version && Some(old.as_str()) != previous {
+            let _ = fs::remove_dir_all(dist_dir.join(&old));
+        }
+    }
+}
+
+fn version_dirs(dist_dir: &Path) -> Vec<String> {
+    fs::read_dir(dist_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_version_string(name))
+        .collect()
+}
+
+/// Version published for `channel` in the root index.html
+fn channel_version(index: &str, channel: Channel) -> Option<String> {
+    channel_versions(index)?.remove(channel.key())
+}
+
+/// Files `manifest.json` lists that are missing from `version_dir`, or with
+/// `hashes` also those whose contents differ; None if the version has no
+/// manifest, as with a UI built locally
+pub fn damaged_files(version_dir: &Path, hashes: bool) -> Option<Vec<String>> {
+    let bytes = fs::read(version_dir.join("manifest.json")).ok()?;
+    let Ok(manifest) = serde_json::from_slice::<Manifest>(&bytes) else {
+        return Some(vec!["manifest.json".to_string()]);
+    };
+    let damaged = |(path, hash): &(&String, &String)| {
+        let file = version_dir.join(path);
+        if hashes { !hash_file(&file).is_ok_and(|actual| actual == **hash) } else { !file.is_file() }
+    };
+    Some(manifest.resources.iter().filter(damaged).map(|(path, _)| path.clone()).collect())
+}
+
+/// A manifest path that stays inside the version directory
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with(['/', '\\'])
+        && !path.contains(':')
+        && path.split(['/', '\\']).all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+fn get(url: &str) -> Result<Vec<u8>, String> {
+    let response = match ureq::get(url).timeout(TIMEOUT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) => {
+            return Err(t!("fetch-http-error", url = url, status = status, reason = response.status_text()));
+        }
+        Err(e) => return Err(t!("fetch-failed", url = url, error = e)),
+    };
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body).map_err(|e| t!("fetch-failed", url = url, error = e))?;
+    Ok(body)
+}
+
+/// `sha256-<base64>`, the form used in manifest.json
+fn hash_bytes(bytes: &[u8]) -> String {
+    encode_hash(&Sha256::digest(bytes))
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(encode_hash(&hasher.finalize()))
+}
+
+fn encode_hash(digest: &[u8]) -> String {
+    format!("sha256-{}", base64::engine::general_purpose::STANDARD.encode(digest))
+}
@@ -0,0 +1,246 @@
+//! `perfetto://` links, so dashboards and chat messages can open a trace in
+//! the local viewer directly.
+//!
+//! `url-handler install` registers the launcher for the scheme with the
+//! desktop: the registry on Windows, a `.desktop` file and xdg-mime on Linux,
+//! and a small applet on macOS. The desktop then runs `open-url <link>`:
+//!
+//! ```text
+//! perfetto://open?trace=k3x9a0qz&visStart=1200000000&visEnd=1450000000
+//! perfetto://open?trace=https://ci.example.com/artifacts/boot.pftrace
+//! perfetto://open?trace=/home/me/traces/jank.perfetto-trace
+//! ```
+//!
+//! `trace` is a catalog id, a URL to fetch (with the headers in `[fetch]`)
+//! or a local path. The other parameters are UI state, passed on like the
+//! fragment of a `/t/<id>` permalink. The trace opens in the running launcher
+//! if there is one, otherwise in a new one.
+
+use crate::browser;
+use crate::catalog::Catalog;
+use crate::fetch;
+use crate::instance;
+use crate::paths::home_dir;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use url::form_urlencoded;
+use url::Url;
+
+const SCHEME: &str = "perfetto";
+
+/// Name of the `.desktop` file on Linux
+const DESKTOP_FILE: &str = "perfetto-launcher-url.desktop";
+
+/// Name of the applet that receives the links on macOS
+const MAC_APP: &str = "Perfetto Launcher URL Handler.app";
+
+/// Open the trace a `perfetto://open?...` link points to
+pub fn open(link: &str, browser: Option<&str>) -> Result<(), String> {
+    let (trace, ui_state) = parse(link)?;
+    let id = resolve(&trace)?;
+    let mut path = format!("/t/{}", id);
+    if !ui_state.is_empty() {
+        path.push('#');
+        path.push_str(&ui_state);
+    }
+    open_in_launcher(&path, browser)
+}
+
+/// Open `path`, such as `/t/<id>`, in the running launcher, or start one
+/// that opens it
+pub fn open_in_launcher(path: &str, browser: Option<&str>) -> Result<(), String> {
+    if let Some(running) = instance::running() {
+        let url = format!("{}{}", running.base_url(), path);
+        say!("{}", t!("url-handler-forwarded", url = url.as_str()));
+        return browser::open(&url, browser).map_err(|e| t!("browser-failed", error = e));
+    }
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    say!("{}", t!("url-handler-starting", path = path));
+    let mut command = Command::new(exe);
+    if let Some(browser) = browser {
+        command.arg("--browser").arg(browser);
+    }
+    command
+        .arg("--open-path")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| t!("url-handler-spawn-failed", error = e))
+}
+
+/// The `trace` parameter and the rest of the query as UI state
+fn parse(link: &str) -> Result<(String, String), String> {
+    let url = Url::parse(link).map_err(|e| t!("url-handler-bad-link", link = link, error = e))?;
+    // `perfetto://open?...` has `open` as the host, `perfetto:open?...` as the path
+    let action = url.host_str().unwrap_or_else(|| url.path()).trim_matches('/');
+    if url.scheme() != SCHEME || action != "open" {
+        return Err(t!("url-handler-bad-link", link = link, error = t!("url-handler-expected")));
+    }
+    let mut trace = None;
+    let mut ui_state = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in url.query_pairs() {
+        match name.as_ref() {
+            "trace" => trace = Some(value.into_owned()),
+            _ => {
+                ui_state.append_pair(&name, &value);
+            }
+        }
+    }
+    match trace.filter(|t| !t.is_empty()) {
+        Some(trace) => Ok((trace, ui_state.finish())),
+        None => Err(t!("url-handler-bad-link", link = link, error = t!("url-handler-expected"))),
+    }
+}
+
+/// Catalog id for a trace given as id, URL or path, registering it if needed
+fn resolve(trace: &str) -> Result<String, String> {
+    let mut catalog = Catalog::open();
+    if let Some(known) = catalog.get(trace) {
+        return Ok(known.id.clone());
+    }
+    let path = if trace.starts_with("http://") || trace.starts_with("https://") {
+        fetch::fetch(trace)?
+    } else {
+        PathBuf::from(trace)
+    };
+    if !path.is_file() {
+        return Err(t!("catalog-unknown-id", id = trace));
+    }
+    catalog
+        .add(&path)
+        .map(|entry| entry.id)
+        .map_err(|e| t!("catalog-add-failed", path = path.display(), error = e))
+}
+
+/// Register this launcher as the handler for `perfetto://` links
+pub fn install() -> Result<(), String> {
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let result = match env::consts::OS {
+        "windows" => install_windows(&exe),
+        "macos" => install_mac(&exe),
+        _ => install_xdg(&exe),
+    };
+    result.map_err(|e| t!("url-handler-register-failed", error = e))?;
+    say!("{}", t!("url-handler-installed", exe = exe.display()));
+    Ok(())
+}
+
+pub fn uninstall() -> Result<(), String> {
+    let result = match env::consts::OS {
+        "windows" => uninstall_windows(),
+        "macos" => uninstall_mac(),
+        _ => uninstall_xdg(),
+    };
+    result.map_err(|e| t!("url-handler-register-failed", error = e))?;
+    say!("{}", t!("url-handler-uninstalled"));
+    Ok(())
+}
+
+fn install_windows(exe: &Path) -> io::Result<()> {
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = format!("\"{}\" open-url \"%1\"", exe.display());
+    run_tool("reg", &["add", &key, "/ve", "/d", "URL:Perfetto trace", "/f"])?;
+    run_tool("reg", &["add", &key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    run_tool("reg", &["add", &format!(r"{}\shell\open\command", key), "/ve", "/d", &command, "/f"])
+}
+
+fn uninstall_windows() -> io::Result<()> {
+    run_tool("reg", &["delete", &format!(r"HKCU\Software\Classes\{}", SCHEME), "/f"])
+}
+
+fn applications_dir() -> io::Result<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".local").join("share")))
+        .map(|base| base.join("applications"))
+        .ok_or_else(|| io::Error::other("no home directory"))
+}
+
+fn install_xdg(exe: &Path) -> io::Result<()> {
+    let dir = applications_dir()?;
+    fs::create_dir_all(&dir)?;
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Perfetto Launcher\nExec=\"{}\" open-url %u\n\
+         MimeType=x-scheme-handler/{};\nNoDisplay=true\nTerminal=false\n",
+        exe.display(),
+        SCHEME
+    );
+    fs::write(dir.join(DESKTOP_FILE), entry)?;
+    run_tool("xdg-mime", &["default", DESKTOP_FILE, &format!("x-scheme-handler/{}", SCHEME)])
+}
+
+fn uninstall_xdg() -> io::Result<()> {
+    match fs::remove_file(applications_dir()?.join(DESKTOP_FILE)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn mac_app() -> io::Result<PathBuf> {
+    home_dir().map(|h| h.join("Applications").join(MAC_APP)).ok_or_else(|| io::Error::other("no home directory"))
+}
+
+/// An AppleScript applet receives the link and passes it to `open-url`
+fn install_mac(exe: &Path) -> io::Result<()> {
+    let app = mac_app()?;
+    if let Some(parent) = app.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let exe = exe.display().to_string().replace('\\', "\\\\").replace('"', "\\\"");
+    let script = format!(
+        "on open location theURL\n  do shell script quoted form of \"{}\" & \" open-url \" & \
+         quoted form of theURL & \" > /dev/null 2>&1 &\"\nend open location\n",
+        exe
+    );
+    let source = env::temp_dir().join("perfetto_launcher_url_handler.applescript");
+    fs::write(&source, script)?;
+    let _ = fs::remove_dir_all(&app);
+    let compiled = run_tool("osacompile", &["-o", &app.to_string_lossy(), &source.to_string_lossy()]);
+    let _ = fs::remove_file(&source);
+    compiled?;
+
+    let plist = app.join("Contents").join("Info.plist");
+    let plist = plist.to_string_lossy();
+    for entry in [
+        "Add :CFBundleIdentifier string dev.perfetto.launcher.urlhandler".to_string(),
+        "Add :CFBundleURLTypes array".to_string(),
+        "Add :CFBundleURLTypes:0 dict".to_string(),
+        "Add :CFBundleURLTypes:0:CFBundleURLName string Perfetto trace".to_string(),
+        "Add :CFBundleURLTypes:0:CFBundleURLSchemes array".to_string(),
+        format!("Add :CFBundleURLTypes:0:CFBundleURLSchemes:0 string {}", SCHEME),
+    ] {
+        // The identifier may already be set by osacompile
+        let _ = run_tool("/usr/libexec/PlistBuddy", &["-c", &entry, &plist]);
+    }
+    run_tool(
+        "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister",
+        &["-f", &app.to_string_lossy()],
+    )
+}
+
+fn uninstall_mac() -> io::Result<()> {
+    let app = mac_app()?;
+    let _ = run_tool(
+        "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister",
+        &["-u", &app.to_string_lossy()],
+    );
+    match fs::remove_dir_all(&app) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn run_tool(program: &str, args: &[&str]) -> io::Result<()> {
+    let output = Command::new(program).args(args).stdin(Stdio::null()).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(io::Error::other(format!("{} {}: {}", program, output.status, stderr.trim())))
+}
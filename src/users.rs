@@ -0,0 +1,257 @@
+//! Multi-user mode, for one launcher shared by a team.
+//!
+//! Enabled by the config file's `[users]` table (see `config_file`):
+//!
+//! ```toml
+//! [users]
+//! trust_header = "X-Forwarded-User"
+//! default_upload_quota_mb = 4096
+//!
+//! [[users.accounts]]
+//! name = "alice"
+//! token = "…"
+//! admin = true
+//!
+//! [[users.accounts]]
+//! name = "bob"
+//! token = "…"
+//! upload_quota_mb = 1024
+//! ```
+//!
+//! `--users FILE` reads a JSON file instead, as does `users.json` in the
+//! data directory without the table, where earlier versions kept it; in JSON
+//! the list of accounts is named `users`.
+//!
+//! Users sign in with their token as `Authorization: Bearer <token>`, or by
+//! opening any page with `?token=<token>` once, which stores it in a cookie.
+//! With `trust_header` set, the user named by that header is accepted as
+//! well, for launchers behind an OIDC proxy such as oauth2-proxy; only set it
+//! when the proxy is the only way to reach the launcher. Users not listed get
+//! the default quota.
+//!
+//! Uploads belong to their uploader. Users see traces they own plus shared
+//! ones (those added from the command line), and can change only their own;
+//! admins see and change everything.
+
+use crate::catalog::Trace;
+use crate::config_file;
+use crate::paths::data_dir;
+use crate::server::query_param;
+use crate::stats;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Request, Response};
+
+const COOKIE: &str = "perfetto_launcher_token";
+
+/// Traces remembered per user for the landing page's session list
+const SESSION_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+struct UsersFile {
+    /// Header set by an authenticating proxy that names the user
+    trust_header: Option<String>,
+    default_upload_quota_mb: Option<u64>,
+    #[serde(default, alias = "accounts")]
+    users: Vec<User>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    token: Option<String>,
+    #[serde(default)]
+    admin: bool,
+    upload_quota_mb: Option<u64>,
+}
+
+/// Who a request is from
+#[derive(Clone)]
+pub enum Viewer {
+    /// Single-user mode: everything is visible
+    Local,
+    User {
+        name: String,
+        admin: bool,
+        /// Bytes of uploads the user may keep in the catalog
+        upload_quota: Option<u64>,
+    },
+}
+
+impl Viewer {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Viewer::Local => None,
+            Viewer::User { name, .. } => Some(name),
+        }
+    }
+
+    pub fn can_see(&self, trace: &Trace) -> bool {
+        match self {
+            Viewer::Local | Viewer::User { admin: true, .. } => true,
+            Viewer::User { name, .. } => trace.owner.as_ref().is_none_or(|owner| owner == name),
+        }
+    }
+
+    pub fn can_modify(&self, trace: &Trace) -> bool {
+        match self {
+            Viewer::Local | Viewer::User { admin: true, .. } => true,
+            Viewer::User { name, .. } => trace.owner.as_ref() == Some(name),
+        }
+    }
+
+    /// Bytes the user may still upload; None if unlimited
+    pub fn remaining_quota(&self, traces: &[Trace]) -> Option<u64> {
+        let Viewer::User { name, upload_quota: Some(quota), .. } = self else {
+            return None;
+        };
+        let used: u64 = traces.iter().filter(|t| t.owner.as_ref() == Some(name)).map(|t| t.size).sum();
+        Some(quota.saturating_sub(used))
+    }
+}
+
+/// A trace a user opened
+#[derive(Clone)]
+pub struct Opened {
+    pub id: String,
+    /// Unix time it was last opened
+    pub at: u64,
+}
+
+pub enum Auth {
+    Viewer(Viewer),
+    /// A valid `?token=`: store it in a cookie and reload without it
+    Login(String),
+    Denied,
+}
+
+pub struct Users {
+    file: UsersFile,
+    sessions: Mutex<HashMap<String, Vec<Opened>>>,
+}
+
+pub fn default_path() -> PathBuf {
+    data_dir().join("users.json")
+}
+
+impl Users {
+    /// Load users from `path`, the config file, or the data directory if the
+    /// file exists there; None means single-user mode
+    pub fn load(path: Option<&Path>) -> Result<Option<Users>, String> {
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<UsersFile>(&text).map_err(|e| e.to_string()))
+        };
+        let (file, parsed) = match path {
+            Some(path) => (path.to_path_buf(), read(path)),
+            None => match config_file::section::<UsersFile>("users") {
+                Some(section) => section,
+                None if default_path().is_file() => (default_path(), read(&default_path())),
+                None => return Ok(None),
+            },
+        };
+        let parsed = parsed.map_err(|e| t!("users-invalid", path = file.display(), error = e))?;
+        if parsed.users.is_empty() && parsed.trust_header.is_none() {
+            return Err(t!("users-none", path = file.display()));
+        }
+        Ok(Some(Users { file: parsed, sessions: Mutex::new(HashMap::new()) }))
+    }
+
+    pub fn count(&self) -> usize {
+        self.file.users.len()
+    }
+
+    fn viewer(&self, name: &str) -> Viewer {
+        let user = self.file.users.iter().find(|u| u.name == name);
+        let quota_mb = user.and_then(|u| u.upload_quota_mb).or(self.file.default_upload_quota_mb);
+        Viewer::User {
+            name: name.to_string(),
+            admin: user.is_some_and(|u| u.admin),
+            upload_quota: quota_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+        }
+    }
+
+    fn by_token(&self, token: &str) -> Option<Viewer> {
+        let user = self.file.users.iter().find(|u| u.token.as_deref().is_some_and(|t| constant_time_eq(t, token)))?;
+        Some(self.viewer(&user.name))
+    }
+
+    pub fn authenticate(&self, request: &Request) -> Auth {
+        let header = |name: &str| {
+            let header = request.headers().iter().find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name));
+            header.map(|h| h.value.as_str().to_string())
+        };
+        if let Some(token) = query_param(request.url(), "token") {
+            return match self.by_token(&token) {
+                Some(_) => Auth::Login(token),
+                None => Auth::Denied,
+            };
+        }
+        let bearer = header("Authorization").and_then(|h| h.strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+        let cookie = header("Cookie").and_then(|cookies| {
+            cookies.split(';').find_map(|c| c.trim().strip_prefix(&format!("{}=", COOKIE)).map(String::from))
+        });
+        if let Some(token) = bearer.or(cookie) {
+            return self.by_token(&token).map_or(Auth::Denied, Auth::Viewer);
+        }
+        let proxied = self.file.trust_header.as_deref().and_then(header).filter(|name| !name.trim().is_empty());
+        match proxied {
+            Some(name) => Auth::Viewer(self.viewer(name.trim())),
+            None => Auth::Denied,
+        }
+    }
+
+    /// Remember that `viewer` opened trace `id`
+    pub fn record_open(&self, viewer: &Viewer, id: &str) {
+        let Some(name) = viewer.name() else { return };
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut sessions = self.sessions.lock().unwrap();
+        let opened = sessions.entry(name.to_string()).or_default();
+        opened.retain(|o| o.id != id);
+        opened.insert(0, Opened { id: id.to_string(), at });
+        opened.truncate(SESSION_LIMIT);
+    }
+
+    /// Traces `viewer` opened, most recent first
+    pub fn sessions(&self, viewer: &Viewer) -> Vec<Opened> {
+        let Some(name) = viewer.name() else { return Vec::new() };
+        self.sessions.lock().unwrap().get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// Compare tokens without exiting early on the first differing byte
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Set the session cookie and redirect to the same URL without `token=`
+pub fn respond_login(request: Request, token: &str) {
+    respond_with_cookie(request, COOKIE, token)
+}
+
+/// Set cookie `name` to `token` and redirect to the same URL without
+/// `token=`, so it does not linger in the address bar or history
+pub fn respond_with_cookie(request: Request, name: &str, token: &str) {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let rest: Vec<&str> = query.split('&').filter(|pair| !pair.starts_with("token=") && !pair.is_empty()).collect();
+    let location = if rest.is_empty() { path.to_string() } else { format!("{}?{}", path, rest.join("&")) };
+    let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Lax", name, token);
+    let mut response = Response::empty(302);
+    let headers = [Header::from_bytes("Location", location), Header::from_bytes("Set-Cookie", cookie)];
+    for header in headers.into_iter().flatten() {
+        response.add_header(header);
+    }
+    stats::respond(request, response);
+}
+
+pub fn respond_unauthorized(request: Request) {
+    let response = Response::from_string(t!("users-unauthorized"))
+        .with_status_code(401)
+        .with_header(Header::from_bytes("WWW-Authenticate", "Bearer").unwrap());
+    stats::respond(request, response);
+}
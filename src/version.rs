@@ -0,0 +1,28 @@
+use crate::assets;
+use crate::dist::served_ui_version;
+use crate::trace_processor::query_version;
+use std::path::Path;
+
+/// Print the launcher, UI bundle and trace_processor versions
+pub fn print(dist_dir: &Path, tp_path: &Path) {
+    println!("{}", t!("version-launcher", version = env!("CARGO_PKG_VERSION")));
+
+    let location = if assets::has_ui() { t!("version-ui-embedded") } else { dist_dir.display().to_string() };
+    match served_ui_version(dist_dir) {
+        Some(v) => println!("{}", t!("version-ui", version = v, location = location)),
+        None => println!("{}", t!("version-ui-unknown", location = location)),
+    }
+
+    if !tp_path.is_file() {
+        println!("{}", t!("version-tp-missing", path = tp_path.display()));
+        return;
+    }
+    let path = tp_path.display();
+    match query_version(tp_path) {
+        Ok(v) => match v.api_version {
+            Some(api) => println!("{}", t!("version-tp-api", version = v.version, api = api, path = path)),
+            None => println!("{}", t!("version-tp", version = v.version, path = path)),
+        },
+        Err(e) => println!("{}", t!("version-tp-unknown", error = e)),
+    }
+}
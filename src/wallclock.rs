@@ -0,0 +1,281 @@
+//! `report timeline`: map wall-clock times to trace timestamps and back,
+//! using the trace's REALTIME clock snapshots.
+//!
+//! Each snapshot pairs a trace timestamp with the REALTIME clock at that
+//! moment. A conversion uses the snapshot nearest to the time converted, so
+//! clock adjustments during the trace (NTP steps) are followed.
+
+use crate::catalog::Catalog;
+use crate::dates::{civil_from_days, days_from_civil};
+use crate::trace_processor::Instance;
+use std::path::Path;
+use std::time::Duration;
+
+const SNAPSHOT_QUERY: &str = "select ts, clock_value from clock_snapshot \
+    where clock_name = 'REALTIME' order by ts";
+
+const BOUNDS_QUERY: &str = "select trace_start() as start_ns, trace_end() as end_ns";
+
+/// How long the trace may take to load
+const LOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+const NS_PER_SEC: i64 = 1_000_000_000;
+
+pub struct TimelineArgs<'a> {
+    pub trace_processor: &'a Path,
+    pub trace: &'a Path,
+    /// Wall-clock times to convert
+    pub at: &'a [String],
+    /// Trace timestamps to convert
+    pub ts: &'a [i64],
+    /// Width of the UI selection around each point
+    pub window: Duration,
+    /// Address of a running launcher to prefix links with
+    pub base_url: Option<&'a str>,
+}
+
+/// A trace timestamp and the REALTIME clock at that moment, in ns
+#[derive(Clone, Copy)]
+struct Snapshot {
+    ts: i64,
+    realtime: i64,
+}
+
+struct Mapping {
+    snapshots: Vec<Snapshot>,
+}
+
+impl Mapping {
+    fn to_trace(&self, realtime: i64) -> i64 {
+        let s = self.snapshots.iter().min_by_key(|s| (s.realtime - realtime).unsigned_abs()).unwrap();
+        realtime - (s.realtime - s.ts)
+    }
+
+    fn to_realtime(&self, ts: i64) -> i64 {
+        let s = self.snapshots.iter().min_by_key(|s| (s.ts - ts).unsigned_abs()).unwrap();
+        ts + (s.realtime - s.ts)
+    }
+
+    /// Largest difference between snapshot offsets: how far REALTIME was
+    /// adjusted during the trace
+    fn drift(&self) -> i64 {
+        let offsets = self.snapshots.iter().map(|s| s.realtime - s.ts);
+        offsets.clone().max().unwrap_or(0) - offsets.min().unwrap_or(0)
+    }
+}
+
+pub fn run(args: TimelineArgs) -> Result<(), String> {
+    let points: Vec<i64> = args.at.iter().map(|text| parse_wall_time(text)).collect::<Result<_, _>>()?;
+    let instance = Instance::start(args.trace_processor, args.trace, LOAD_TIMEOUT)?;
+
+    let rows = instance.client.query_rows(SNAPSHOT_QUERY)?;
+    let snapshots: Vec<Snapshot> = rows
+        .rows
+        .iter()
+        .filter_map(|row| Some(Snapshot { ts: row.first()?.as_i64()?, realtime: row.get(1)?.as_i64()? }))
+        .collect();
+    if snapshots.is_empty() {
+        return Err(t!("timeline-no-snapshots", path = args.trace.display()));
+    }
+    let mapping = Mapping { snapshots };
+    let bounds = instance.client.query_rows(BOUNDS_QUERY)?;
+    let bound = |i: usize| bounds.rows.first().and_then(|row| row.get(i)?.as_i64()).unwrap_or(0);
+    let (start, end) = (bound(0), bound(1));
+    drop(instance);
+
+    let id = Catalog::open()
+        .add(args.trace)
+        .map(|trace| trace.id)
+        .map_err(|e| t!("catalog-add-failed", path = args.trace.display(), error = e))?;
+    let link = |ts: i64| {
+        let half = (args.window.as_nanos() / 2) as i64;
+        format!(
+            "{}/t/{}#visStart={}&visEnd={}",
+            args.base_url.unwrap_or("").trim_end_matches('/'),
+            id,
+            ts - half,
+            ts + half.max(1)
+        )
+    };
+
+    println!("{}", t!("timeline-start", wall = format_wall_time(mapping.to_realtime(start)), ts = start));
+    println!("{}", t!("timeline-end", wall = format_wall_time(mapping.to_realtime(end)), ts = end));
+    let drift = mapping.drift();
+    if drift > 0 {
+        println!("{}", t!("timeline-drift", ms = format!("{:.3}", drift as f64 / 1e6)));
+    }
+
+    let position = |ts: i64| {
+        let offset = format!("{:+.6}", (ts - start) as f64 / NS_PER_SEC as f64);
+        if ts < start || ts > end {
+            t!("timeline-outside", offset = offset)
+        } else {
+            t!("timeline-offset", offset = offset)
+        }
+    };
+    for (text, realtime) in args.at.iter().zip(&points) {
+        let ts = mapping.to_trace(*realtime);
+        println!();
+        println!("{}", t!("timeline-wall-to-trace", wall = text, ts = ts, position = position(ts)));
+        println!("  {}", link(ts));
+    }
+    for ts in args.ts {
+        println!();
+        let wall = format_wall_time(mapping.to_realtime(*ts));
+        println!("{}", t!("timeline-trace-to-wall", ts = ts, wall = wall, position = position(*ts)));
+        println!("  {}", link(*ts));
+    }
+    Ok(())
+}
+
+/// Parse `YYYY-MM-DD[ T]HH:MM[:SS[.fraction]][Z|±HH:MM]` into ns since the
+/// Unix epoch; times without an offset are UTC
+pub fn parse_wall_time(text: &str) -> Result<i64, String> {
+    let invalid = || t!("timeline-invalid-time", time = text);
+    let text = text.trim();
+    let (date, time) = text.split_once([' ', 'T']).ok_or_else(invalid)?;
+
+    let mut date_parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (Some(Some(year)), Some(Some(month)), Some(Some(day)), None) =
+        (date_parts.next(), date_parts.next(), date_parts.next(), date_parts.next())
+    else {
+        return Err(invalid());
+    };
+
+    // Split off a trailing `Z` or `±HH:MM` offset
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(index) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(index);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        (time, sign * (hours * 3600 + minutes * 60))
+    } else {
+        (time, 0)
+    };
+
+    let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut clock_parts = clock.split(':').map(|p| p.parse::<i64>().ok());
+    let (Some(Some(hour)), Some(Some(minute))) = (clock_parts.next(), clock_parts.next()) else {
+        return Err(invalid());
+    };
+    let second = match clock_parts.next() {
+        Some(Some(second)) => second,
+        None => 0,
+        Some(None) => return Err(invalid()),
+    };
+    // Years are bounded so the date arithmetic cannot overflow; only 1677 to
+    // 2262 fit in ns anyway
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let nanos = format!("{:0<9}", fraction).parse::<i64>().map_err(|_| invalid())?;
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset_secs;
+    secs.checked_mul(NS_PER_SEC).and_then(|ns| ns.checked_add(nanos)).ok_or_else(invalid)
+}
+
+/// `YYYY-MM-DD HH:MM:SS.nnnnnnnnn UTC`
+pub fn format_wall_time(ns: i64) -> String {
+    let (secs, nanos) = (ns.div_euclid(NS_PER_SEC), ns.rem_euclid(NS_PER_SEC));
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        nanos
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wall_times() {
+        assert_eq!(parse_wall_time("1970-01-01 00:00"), Ok(0));
+        assert_eq!(parse_wall_time("1970-01-01T00:00:01Z"), Ok(NS_PER_SEC));
+        assert_eq!(parse_wall_time(" 2024-03-05 12:34:56.789 "), Ok(1_709_642_096_789_000_000));
+        assert_eq!(parse_wall_time("2024-03-05T12:34:56.000000001"), Ok(1_709_642_096_000_000_001));
+        assert_eq!(parse_wall_time("2024-03-05T14:34:56+02:00"), Ok(1_709_642_096 * NS_PER_SEC));
+        assert_eq!(parse_wall_time("2024-03-05T07:04:56-05:30"), Ok(1_709_642_096 * NS_PER_SEC));
+        assert_eq!(parse_wall_time("2024-03-05T14:34:56+02"), Ok(1_709_642_096 * NS_PER_SEC));
+        assert_eq!(parse_wall_time("1969-12-31 23:59:59.5"), Ok(-NS_PER_SEC / 2));
+        assert_eq!(parse_wall_time("2016-12-31 23:59:60Z"), parse_wall_time("2017-01-01 00:00:00Z"));
+    }
+
+    #[test]
+    fn rejects_invalid_wall_times() {
+        for text in [
+            "",
+            "2024-03-05",
+            "12:34:56",
+            "2024-03 12:34",
+            "2024-03-05-01 12:34",
+            "2024-13-05 12:34",
+            "2024-03-00 12:34",
+            "2024-03-05 24:00",
+            "2024-03-05 12:60",
+            "2024-03-05 12:34:61",
+            "2024-03-05 12",
+            "2024-03-05 12:34:xx",
+            "2024-03-05 12:34:56.1234567890",
+            "2024-03-05 12:34:56.1e3",
+            "2024-03-05 12:34+24:00",
+            "2024-03-05 12:34+02:60",
+            "2024-03-05 12:34+xx",
+        ] {
+            assert!(parse_wall_time(text).is_err(), "{:?} was accepted", text);
+        }
+    }
+
+    #[test]
+    fn rejects_wall_times_out_of_range() {
+        for text in [
+            "1677-09-21 00:12:43Z",
+            "2262-04-11 23:47:17Z",
+            "9999-12-31 23:59:59Z",
+            "10000-01-01 00:00",
+            "-1-01-01 00:00",
+            "99999999999999999-01-01 00:00",
+            "2024-03-05 12:34+99999999999999999:00",
+        ] {
+            assert!(parse_wall_time(text).is_err(), "{:?} was accepted", text);
+        }
+        assert_eq!(parse_wall_time("2262-04-11 23:47:16.854775807Z"), Ok(i64::MAX));
+        assert_eq!(parse_wall_time("1677-09-21 00:12:44Z"), Ok(-9_223_372_036 * NS_PER_SEC));
+    }
+
+    #[test]
+    fn formats_wall_times() {
+        assert_eq!(format_wall_time(0), "1970-01-01 00:00:00.000000000 UTC");
+        assert_eq!(format_wall_time(1_709_642_096_789_000_000), "2024-03-05 12:34:56.789000000 UTC");
+        assert_eq!(format_wall_time(-1), "1969-12-31 23:59:59.999999999 UTC");
+        assert_eq!(format_wall_time(i64::MAX), "2262-04-11 23:47:16.854775807 UTC");
+        assert_eq!(format_wall_time(i64::MIN), "1677-09-21 00:12:43.145224192 UTC");
+    }
+
+    #[test]
+    fn wall_times_round_trip() {
+        for ns in [0, 1, -1, 1_709_642_096_789_000_001, -5_000_000_000_123, i64::MAX, -9_223_372_036 * NS_PER_SEC] {
+            let text = format_wall_time(ns);
+            assert_eq!(parse_wall_time(text.trim_end_matches(" UTC")), Ok(ns), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Webhook notifications for catalog and session events.
+//!
+//! Configured in the config file's `[webhooks]` table (see `config_file`):
+//!
+//! ```toml
+//! [webhooks]
+//! base_url = "http://perf-box:9001"
+//!
+//! [[webhooks.hooks]]
+//! url = "https://chat.example.com/hooks/abc"
+//! events = ["trace_ingested"]
+//! template = '{"text": "New trace from device farm: {{trace_name}}, open here: {{link}}"}'
+//! ```
+//!
+//! `--webhooks FILE` reads a JSON file instead, as does `webhooks.json` in
+//! the data directory without the table, where earlier versions kept it; in
+//! JSON the list of hooks is named `webhooks`.
+//!
+//! `{{name}}` placeholders in the template are replaced with JSON-escaped
+//! values: `event`, `text`, `trace_id`, `trace_name`, `trace_path`, `link`,
+//! `session_url`, `check` and `message`. Without a template the payload is
+//! `{"event": ..., "text": ...}`. A webhook without `events` gets all of them.
+
+use crate::catalog::Trace;
+use crate::config_file;
+use crate::paths::data_dir;
+use log::warn;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// A new trace was added to the catalog
+    TraceIngested,
+    SessionOpened,
+    SessionClosed,
+    /// A doctor check failed
+    CheckFailed,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::TraceIngested => "trace_ingested",
+            Event::SessionOpened => "session_opened",
+            Event::SessionClosed => "session_closed",
+            Event::CheckFailed => "check_failed",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Webhook {
+    url: String,
+    #[serde(default)]
+    events: Vec<Event>,
+    template: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+#[derive(Default, Deserialize)]
+struct WebhooksFile {
+    /// Public address of the launcher for links, if localhost is not reachable
+    /// from where the notifications are read
+    base_url: Option<String>,
+    #[serde(default, alias = "hooks")]
+    webhooks: Vec<Webhook>,
+}
+
+static CONFIG: OnceLock<WebhooksFile> = OnceLock::new();
+/// Address of the running session, used for links when `base_url` is unset
+static SESSION_URL: Mutex<Option<String>> = Mutex::new(None);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn default_path() -> PathBuf {
+    data_dir().join("webhooks.json")
+}
+
+/// Load webhooks from `path`, the config file, or the data directory if the
+/// file exists there
+pub fn init(path: Option<&Path>) {
+    let (file, config) = match path {
+        Some(path) => (path.to_path_buf(), read(path)),
+        None => match config_file::section::<WebhooksFile>("webhooks") {
+            Some(section) => section,
+            None if default_path().is_file() => (default_path(), read(&default_path())),
+            None => return,
+        },
+    };
+    match config {
+        Ok(config) => {
+            let _ = CONFIG.set(config);
+        }
+        Err(e) => warn!("{}", t!("webhooks-invalid", path = file.display(), error = e)),
+    }
+}
+
+fn read(path: &Path) -> Result<WebhooksFile, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+pub fn set_session_url(url: &str) {
+    *SESSION_URL.lock().unwrap() = Some(url.trim_end_matches('/').to_string());
+}
+
+pub fn trace_ingested(trace: &Trace) {
+    let link = format!("{}/t/{}", base_url(), trace.id);
+    fire(
+        Event::TraceIngested,
+        t!("webhook-trace-ingested", name = trace.name, link = link),
+        &[
+            ("trace_id", trace.id.clone()),
+            ("trace_name", trace.name.clone()),
+            ("trace_path", trace.path.display().to_string()),
+            ("link", link),
+        ],
+    );
+}
+
+pub fn session_opened(url: &str) {
+    fire(Event::SessionOpened, t!("webhook-session-opened", url = url), &[("session_url", url.to_string())]);
+}
+
+pub fn session_closed(url: &str) {
+    fire(Event::SessionClosed, t!("webhook-session-closed", url = url), &[("session_url", url.to_string())]);
+}
+
+pub fn check_failed(check: &str, message: &str) {
+    fire(
+        Event::CheckFailed,
+        t!("webhook-check-failed", check = check, message = message),
+        &[("check", check.to_string()), ("message", message.to_string())],
+    );
+}
+
+/// Wait up to `timeout` for notifications still being sent
+pub fn flush(timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while IN_FLIGHT.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn base_url() -> String {
+    let configured = CONFIG.get().and_then(|c| c.base_url.clone());
+    configured
+        .map(|url| url.trim_end_matches('/').to_string())
+        .or_else(|| SESSION_URL.lock().unwrap().clone())
+        .unwrap_or_default()
+}
+
+fn fire(event: Event, text: String, vars: &[(&str, String)]) {
+    let Some(config) = CONFIG.get() else { return };
+    for webhook in config.webhooks.iter().filter(|w| w.events.is_empty() || w.events.contains(&event)) {
+        let body = match &webhook.template {
+            Some(template) => {
+                let mut values: HashMap<&str, &str> = vars.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                values.insert("event", event.name());
+                values.insert("text", &text);
+                render(template, &values)
+            }
+            None => serde_json::json!({ "event": event.name(), "text": text }).to_string(),
+        };
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        let url = webhook.url.clone();
+        let headers = webhook.headers.clone();
+        thread::spawn(move || {
+            let mut request = ureq::post(&url).timeout(SEND_TIMEOUT).set("Content-Type", "application/json");
+            for (name, value) in &headers {
+                request = request.set(name, value);
+            }
+            if let Err(e) = request.send_string(&body) {
+                warn!("{}", t!("webhook-failed", url = url, error = e));
+            }
+            IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Replace `{{name}}` with the JSON-escaped value; unknown names become empty
+fn render(template: &str, values: &HashMap<&str, &str>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        let value = values.get(name).copied().unwrap_or("");
+        let quoted = serde_json::to_string(value).unwrap();
+        out.push_str(&quoted[1..quoted.len() - 1]);
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,322 @@
+//! The workspace: the directories of the data directory the launcher stores
+//! traces in, and a scratch directory for each running launcher.
+//!
+//! Uploads, downloads, conversions, captures and the rest each have an area
+//! of their own. Features take theirs from `dir` rather than making up paths,
+//! and temporary files from `temp_path`, so the quota and `clean` see every
+//! file the launcher wrote. The scratch directory, `tmp/<pid>`, is removed at
+//! exit, and by the next start or `clean` if the launcher did not get to it.
+//! Each trace_processor session gets `<session id>/` in it for its temporary
+//! files, removed when the session stops.
+//!
+//! With `--workspace-quota-mb` the least recently used traces are deleted
+//! whenever a new one is stored, until the areas fit the quota again. A trace
+//! counts as used when it was registered or last opened; pinned traces and
+//! the one being served are never deleted.
+
+use crate::catalog::Catalog;
+use crate::ingest;
+use crate::instance;
+use crate::paths::data_dir;
+use log::info;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Numbers temporary files so names never collide within a run
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Area {
+    /// Traces dropped on the landing page or sent to `POST /api/traces`
+    Uploads,
+    /// `--fetch` downloads and the traces extracted from them
+    Downloads,
+    /// Output of converter plugins
+    Converted,
+    /// Copies with symbols or deobfuscation mappings added
+    Enriched,
+    /// Traces restored by `catalog import`
+    Imported,
+    /// `capture schedule` output
+    Captures,
+    /// `record` output
+    Recordings,
+    /// Traces pulled from Android devices, by serial
+    Android,
+}
+
+impl Area {
+    pub const ALL: [Area; 8] = [
+        Area::Uploads,
+        Area::Downloads,
+        Area::Converted,
+        Area::Enriched,
+        Area::Imported,
+        Area::Captures,
+        Area::Recordings,
+        Area::Android,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Area::Uploads => "uploads",
+            Area::Downloads => "downloads",
+            Area::Converted => "converted",
+            Area::Enriched => "enriched",
+            Area::Imported => "imported",
+            Area::Captures => "captures",
+            Area::Recordings => "recordings",
+            Area::Android => "android",
+        }
+    }
+}
+
+pub fn dir(area: Area) -> PathBuf {
+    data_dir().join(area.name())
+}
+
+/// Whether `path` is a file the launcher stored in one of the areas
+pub fn owns(path: &Path) -> bool {
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    Area::ALL.iter().filter_map(|area| dir(*area).canonicalize().ok()).any(|dir| path.starts_with(dir))
+}
+
+fn scratch_root() -> PathBuf {
+    data_dir().join("tmp")
+}
+
+/// This launcher's scratch directory
+pub fn scratch_dir() -> PathBuf {
+    scratch_root().join(process::id().to_string())
+}
+
+/// Scratch directory of session `id`, for its trace_processor's temporary files
+pub fn session_scratch_dir(id: &str) -> PathBuf {
+    scratch_dir().join(id)
+}
+
+/// A new path in the scratch directory for a temporary file named like `name`
+pub fn temp_path(name: &str) -> io::Result<PathBuf> {
+    let dir = scratch_dir();
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(format!("{}-{}", NEXT_TEMP.fetch_add(1, Ordering::Relaxed), name)))
+}
+
+/// Delete this launcher's scratch directory; called at exit
+pub fn remove_scratch() {
+    let _ = fs::remove_dir_all(scratch_dir());
+}
+
+/// Scratch directories of launchers that are no longer running
+fn stale_scratch() -> Vec<PathBuf> {
+    let mut system = System::new();
+    fs::read_dir(scratch_root())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse().ok()) else {
+                return true;
+            };
+            let pid = Pid::from_u32(pid);
+            system.refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), true, ProcessRefreshKind::nothing());
+            system.process(pid).is_none()
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Delete the scratch directories crashed or killed launchers left behind
+pub fn remove_stale_scratch() {
+    for dir in stale_scratch() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+/// A file in one of the areas
+struct Entry {
+    path: PathBuf,
+    size: u64,
+    /// Unix time the trace was last opened or registered, or the file modified
+    used: u64,
+    /// Catalog id, None for files no trace refers to
+    id: Option<String>,
+    pinned: bool,
+}
+
+/// Every file in the areas, least recently used first
+fn entries(catalog: &Catalog) -> Vec<Entry> {
+    let mut files = Vec::new();
+    for area in Area::ALL {
+        collect_files(&dir(area), &mut files);
+    }
+    let mut entries: Vec<Entry> = files
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let modified = metadata.modified().ok().and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+            let trace = path.canonicalize().ok().and_then(|real| catalog.traces().iter().find(|t| t.path == real));
+            Some(Entry {
+                size: metadata.len(),
+                used: match trace {
+                    Some(trace) => trace.opened.unwrap_or(trace.added),
+                    None => modified.map(|m| m.as_secs()).unwrap_or(0),
+                },
+                id: trace.map(|t| t.id.clone()),
+                pinned: trace.is_some_and(|t| t.pinned),
+                path,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.used);
+    entries
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok) {
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_files(&entry.path(), files),
+            Ok(kind) if kind.is_file() => files.push(entry.path()),
+            _ => {}
+        }
+    }
+}
+
+/// Files and bytes deleted
+#[derive(Default)]
+struct Cleaned {
+    files: usize,
+    bytes: u64,
+}
+
+/// Delete `entry`'s file and forget its trace; only report what would go if `dry_run`
+fn delete(catalog: &mut Catalog, entry: &Entry, dry_run: bool, cleaned: &mut Cleaned) {
+    if dry_run {
+        say!("{}", t!("clean-would-delete", path = entry.path.display(), size = megabytes(entry.size)));
+    } else {
+        if let Some(id) = &entry.id {
+            let _ = catalog.remove(id);
+            ingest::forget(id);
+        }
+        if fs::remove_file(&entry.path).is_err() {
+            return;
+        }
+        info!("{}", t!("clean-deleted", path = entry.path.display(), size = megabytes(entry.size)));
+    }
+    cleaned.files += 1;
+    cleaned.bytes += entry.size;
+}
+
+/// Delete the least recently used traces until the areas take at most
+/// `quota` bytes; pinned traces and `keep` stay whatever their size
+pub fn enforce_quota(catalog: &mut Catalog, quota: u64, keep: &[&Path]) {
+    let keep: Vec<PathBuf> = keep.iter().filter_map(|path| path.canonicalize().ok()).collect();
+    let entries = entries(catalog);
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let mut cleaned = Cleaned::default();
+    for entry in &entries {
+        if total <= quota {
+            break;
+        }
+        if entry.pinned || entry.path.canonicalize().is_ok_and(|path| keep.contains(&path)) {
+            continue;
+        }
+        delete(catalog, entry, false, &mut cleaned);
+        total = total.saturating_sub(entry.size);
+    }
+    if cleaned.files > 0 {
+        say!("{}", t!("workspace-quota-cleaned", count = cleaned.files, size = megabytes(cleaned.bytes)));
+    }
+}
+
+/// What `clean` deletes besides the scratch directories of launchers that
+/// are gone and files no trace refers to
+pub struct CleanOptions {
+    /// Every trace that is not pinned
+    pub all: bool,
+    /// Traces not used for this long
+    pub older_than: Option<Duration>,
+    /// Least recently used traces until the areas take at most this many bytes
+    pub quota: Option<u64>,
+    pub dry_run: bool,
+}
+
+/// `clean` subcommand
+pub fn clean(options: &CleanOptions) -> Result<(), String> {
+    // A running launcher keeps the catalog in memory and would write deleted
+    // traces back to it
+    if let Some(running) = instance::running() {
+        return Err(t!("clean-launcher-running", pid = running.pid));
+    }
+    let mut cleaned = Cleaned::default();
+    for dir in stale_scratch() {
+        let size = dir_size(&dir);
+        if options.dry_run {
+            say!("{}", t!("clean-would-delete", path = dir.display(), size = megabytes(size)));
+        } else if fs::remove_dir_all(&dir).is_err() {
+            continue;
+        }
+        cleaned.bytes += size;
+    }
+
+    let mut catalog = Catalog::open();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let entries = entries(&catalog);
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let mut deleted = vec![false; entries.len()];
+    for (index, entry) in entries.iter().enumerate() {
+        let expired = options.older_than.is_some_and(|age| now.saturating_sub(entry.used) > age.as_secs());
+        if entry.id.is_none() || (!entry.pinned && (options.all || expired)) {
+            delete(&mut catalog, entry, options.dry_run, &mut cleaned);
+            deleted[index] = true;
+            total = total.saturating_sub(entry.size);
+        }
+    }
+    if let Some(quota) = options.quota {
+        for (entry, _) in entries.iter().zip(&deleted).filter(|(entry, deleted)| !**deleted && !entry.pinned) {
+            if total <= quota {
+                break;
+            }
+            delete(&mut catalog, entry, options.dry_run, &mut cleaned);
+            total = total.saturating_sub(entry.size);
+        }
+    }
+    if !options.dry_run {
+        for area in Area::ALL {
+            remove_empty_dirs(&dir(area));
+        }
+    }
+
+    let (count, size) = (cleaned.files, megabytes(cleaned.bytes));
+    let key = if options.dry_run { "clean-would-free" } else { "clean-done" };
+    say!("{}", t!(key, count = count, size = size, remaining = megabytes(total)));
+    Ok(())
+}
+
+/// Remove the directories below `dir` that deleting files left empty
+fn remove_empty_dirs(dir: &Path) {
+    for entry in fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok) {
+        if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            remove_empty_dirs(&entry.path());
+            // Fails unless empty
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    files.iter().filter_map(|path| fs::metadata(path).ok()).map(|m| m.len()).sum()
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1}", bytes as f64 / (1024.0 * 1024.0))
+}